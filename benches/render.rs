@@ -0,0 +1,141 @@
+//! CPU cost of a 16-voice chord with every effect switched on, and of the heaviest
+//! master effects alone, rendered one sample at a time and by blocks.
+//! Run with `cargo bench --bench render`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use synthesizer_emulation::audio::controller::SynthController;
+use synthesizer_emulation::audio::engine::{AudioEngine, create_engine};
+use synthesizer_emulation::consts::constants::{MAX_BLOCK_SIZE, SAMPLE_RATE};
+use synthesizer_emulation::synths::manager::SynthType;
+use synthesizer_emulation::synths::modules::compressor::{Compressor, CompressorSettings};
+use synthesizer_emulation::synths::modules::modulated::ModEffectKind;
+use synthesizer_emulation::synths::modules::reverb::{Reverb, ReverbType};
+use synthesizer_emulation::synths::traits::{Module, StereoFrame};
+
+const VOICES: usize = 16;
+
+/// Engine playing a 16-note chord through every module and effect of the synthesizer
+fn chord_engine() -> (SynthController, AudioEngine) {
+    let mut synth = SynthType::n_sawtooth();
+    synth.set_filter_activation(true);
+    synth.set_lfo_activation(true);
+    synth.set_noise_activation(true);
+    synth.set_gain_activation(true);
+    synth.set_distortion_activation(true);
+    synth.set_compressor_activation(true);
+    for kind in ModEffectKind::ALL {
+        synth.set_mod_effect_activation(kind, true);
+    }
+    synth.set_delay_activation(true);
+    synth.set_reverb_activation(true);
+    synth.set_leslie_activation(true);
+    synth.set_limiter_activation(true);
+    for name in [
+        "MultiModeFilter",
+        "LFO",
+        "NoiseEffect",
+        "Gain",
+        "Distortion",
+        "SimpleRMSCompressor",
+        "Chorus",
+        "Flanger",
+        "Phaser",
+        "Delay",
+        "Reverb",
+        "Leslie",
+        "Limiter",
+    ] {
+        assert!(synth.has_module(name), "{name} is not in the chord");
+    }
+
+    let (controller, mut engine) = create_engine(synth, SAMPLE_RATE);
+    for index in 0..VOICES {
+        // Accord étalé sur trois octaves et demie à partir de La 1
+        let frequency = 55.0 * 2.0_f64.powf(index as f64 * 3.0 / 12.0);
+        controller.note_on_with_velocity(frequency, 0.8);
+    }
+    engine.process_commands();
+    (controller, engine)
+}
+
+fn render_chord(c: &mut Criterion) {
+    let mut group = c.benchmark_group("16_voice_chord");
+    group.throughput(Throughput::Elements(MAX_BLOCK_SIZE as u64));
+    let mut buffer = vec![[0.0, 0.0]; MAX_BLOCK_SIZE];
+
+    let (_controller, mut engine) = chord_engine();
+    group.bench_function(BenchmarkId::new("per_sample", MAX_BLOCK_SIZE), |b| {
+        b.iter(|| {
+            for frame in buffer.iter_mut() {
+                *frame = engine.next_frame();
+            }
+        })
+    });
+
+    let (_controller, mut engine) = chord_engine();
+    group.bench_function(BenchmarkId::new("block", MAX_BLOCK_SIZE), |b| {
+        b.iter(|| engine.render_block(&mut buffer))
+    });
+
+    group.finish();
+}
+
+/// Compressor and reverb on a loud stereo block, so the compressor is always reducing
+fn render_master_effects(c: &mut Criterion) {
+    let mut group = c.benchmark_group("master_effects");
+    group.throughput(Throughput::Elements(MAX_BLOCK_SIZE as u64));
+    let input: Vec<StereoFrame> = (0..MAX_BLOCK_SIZE)
+        .map(|n| {
+            let phase = n as f64 * 0.03;
+            [phase.sin(), phase.cos()]
+        })
+        .collect();
+    let mut buffer = input.clone();
+
+    let modules: [(&str, Box<dyn Module>); 2] = [
+        (
+            "compressor",
+            Box::new(Compressor::from_settings(
+                CompressorSettings::default(),
+                SAMPLE_RATE,
+            )),
+        ),
+        (
+            "reverb",
+            Box::new(Reverb::new(
+                SAMPLE_RATE,
+                ReverbType::Hall,
+                0.3,
+                0.9,
+                0.95,
+                20.0,
+            )),
+        ),
+    ];
+    for (name, mut module) in modules {
+        group.bench_function(
+            BenchmarkId::new(format!("{name}_per_sample"), MAX_BLOCK_SIZE),
+            |b| {
+                b.iter(|| {
+                    for (frame, &sample) in buffer.iter_mut().zip(&input) {
+                        *frame = module.process_frame(sample, 0.0);
+                    }
+                })
+            },
+        );
+        group.bench_function(
+            BenchmarkId::new(format!("{name}_block"), MAX_BLOCK_SIZE),
+            |b| {
+                b.iter(|| {
+                    buffer.copy_from_slice(&input);
+                    module.process_block(&mut buffer);
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, render_chord, render_master_effects);
+criterion_main!(benches);
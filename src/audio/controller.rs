@@ -0,0 +1,222 @@
+use crate::audio::engine::{Command, EngineStatus, Garbage};
+use crate::audio::note_manager::{self, ActiveNote};
+use crate::audio::voice_allocator::VoiceSettings;
+use crate::consts::constants;
+use crate::presets::Preset;
+use crate::synths::manager::SynthType;
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::oscillators::wavetable::Wavetable;
+use crate::synths::param::Param;
+use crate::synths::velocity::VelocitySettings;
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+/// Patch kept on the control side, used to build the voices sent to the audio thread
+struct ControlState {
+    synth: SynthType,
+    envelope: ADSR,
+    velocity: VelocitySettings,
+    voices: VoiceSettings,
+}
+
+/// Control-side handle of the audio engine, shared by the GUI and the terminal.
+/// Locks taken here only ever involve control threads: the audio thread just
+/// reads the command queue.
+#[derive(Clone)]
+pub struct SynthController {
+    commands: SyncSender<Command>,
+    garbage: Arc<Mutex<Receiver<Garbage>>>,
+    state: Arc<Mutex<ControlState>>,
+    status: Arc<EngineStatus>,
+}
+
+impl SynthController {
+    pub(crate) fn new(
+        commands: SyncSender<Command>,
+        garbage: Receiver<Garbage>,
+        synth: SynthType,
+        status: Arc<EngineStatus>,
+        sample_rate: f64,
+    ) -> Self {
+        let envelope = ADSR::new(
+            sample_rate,
+            constants::ADSR_ATTACK,
+            constants::ADSR_DECAY,
+            constants::ADSR_SUSTAIN,
+            constants::ADSR_RELEASE,
+        );
+        Self {
+            commands,
+            garbage: Arc::new(Mutex::new(garbage)),
+            state: Arc::new(Mutex::new(ControlState {
+                synth,
+                envelope,
+                velocity: VelocitySettings::default(),
+                voices: VoiceSettings::default(),
+            })),
+            status,
+        }
+    }
+
+    /// Sends a command. The garbage queue is emptied first, so it always has room.
+    fn send(&self, command: Command) {
+        self.collect_garbage();
+        match self.commands.try_send(command) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => eprintln!("Warning: audio command queue is full"),
+            Err(TrySendError::Disconnected(_)) => eprintln!("Warning: audio engine stopped"),
+        }
+    }
+
+    /// Starts a note at the fixed computer keyboard velocity
+    pub fn note_on(&self, frequency: f64) {
+        let velocity = self.state.lock().unwrap().velocity.keyboard_velocity;
+        self.note_on_with_velocity(frequency, velocity);
+    }
+
+    /// Starts a note; its voice chain is cloned and shaped by the velocity here, not on the audio thread
+    pub fn note_on_with_velocity(&self, frequency: f64, velocity: f64) {
+        let note = {
+            let state = self.state.lock().unwrap();
+            let mut voice = state.synth.voice();
+            let mut envelope = state.envelope;
+            state.velocity.apply(velocity, &mut voice, &mut envelope);
+
+            let mut note = ActiveNote::new(frequency, velocity, envelope, voice);
+            note.gain = state.velocity.amplitude_gain(velocity);
+            note
+        };
+        self.send(Command::NoteOn(note));
+    }
+
+    /// Releases a note (its ADSR enters the release stage)
+    pub fn note_off(&self, frequency: f64) {
+        self.send(Command::NoteOff(note_manager::note_key(frequency)));
+    }
+
+    pub fn all_notes_off(&self) {
+        self.send(Command::AllNotesOff);
+    }
+
+    /// Bends the pitch of every voice, `bend` goes from -1 to 1
+    pub fn pitch_bend(&self, bend: f64) {
+        self.send(Command::PitchBend(bend));
+    }
+
+    /// Moves the mod wheel, from 0 to 1, a source of the modulation matrix
+    pub fn mod_wheel(&self, value: f64) {
+        self.send(Command::ModWheel(value));
+    }
+
+    /// Channel pressure, from 0 to 1, a source of the modulation matrix
+    pub fn aftertouch(&self, value: f64) {
+        self.send(Command::Aftertouch(value));
+    }
+
+    /// Changes a parameter for the next notes, the playing voices and the master bus
+    pub fn set_param(&self, param: Param) {
+        let mut state = self.state.lock().unwrap();
+        if param.apply_to_velocity(&mut state.velocity) {
+            return;
+        }
+        if param.apply_to_voices(&mut state.voices) {
+            self.send(Command::SetVoiceSettings(state.voices));
+            return;
+        }
+        let rebuild = param.requires_rebuild(&state.synth);
+        state.synth.apply_param(param);
+        param.apply_to_envelope(&mut state.envelope);
+
+        if rebuild {
+            // Rebuilding reallocates buffers: do it here and swap the whole patch
+            self.send(Command::SetSynth(state.synth.clone()));
+        } else {
+            self.send(Command::SetParam(param));
+        }
+    }
+
+    /// Replaces the synthesizer used by the next notes and the master bus
+    pub fn set_synth(&self, mut synth: SynthType) {
+        let mut state = self.state.lock().unwrap();
+        synth.set_sample_rate(state.envelope.get_sample_rate());
+        state.synth = synth;
+        self.send(Command::SetSynth(state.synth.clone()));
+    }
+
+    /// Changes the table of the wavetable oscillator for the next notes.
+    /// Tables are loaded by the caller, the patch is swapped like for rebuilt parameters.
+    pub fn set_wavetable(&self, table: Arc<Wavetable>) {
+        let mut state = self.state.lock().unwrap();
+        state.synth.set_wavetable(table);
+        self.send(Command::SetSynth(state.synth.clone()));
+    }
+
+    /// Replaces the synthesizer and the envelope by the ones of a preset
+    pub fn load_preset(&self, preset: &Preset) {
+        let mut state = self.state.lock().unwrap();
+        let sample_rate = state.envelope.get_sample_rate();
+        state.synth = preset.to_synth();
+        state.synth.set_sample_rate(sample_rate);
+        state.envelope = preset.to_envelope(sample_rate);
+        state.velocity = preset.velocity;
+        state.voices = preset.voices;
+        self.send(Command::SetSynth(state.synth.clone()));
+        self.send(Command::SetVoiceSettings(state.voices));
+    }
+
+    /// Captures the current patch and envelope as a preset
+    pub fn capture_preset(&self, name: &str, octave: u8) -> Preset {
+        let state = self.state.lock().unwrap();
+        Preset::capture(
+            name,
+            &state.synth,
+            &state.envelope,
+            &state.velocity,
+            &state.voices,
+            octave,
+        )
+    }
+
+    /// Returns a copy of the current patch
+    pub fn get_synth(&self) -> SynthType {
+        self.state.lock().unwrap().synth.clone()
+    }
+
+    pub fn get_envelope(&self) -> ADSR {
+        self.state.lock().unwrap().envelope
+    }
+
+    pub fn get_velocity_settings(&self) -> VelocitySettings {
+        self.state.lock().unwrap().velocity
+    }
+
+    pub fn get_voice_settings(&self) -> VoiceSettings {
+        self.state.lock().unwrap().voices
+    }
+
+    pub fn get_active_voices(&self) -> usize {
+        self.status.get_active_voices()
+    }
+
+    /// Gain reduction of the compressor in dB, for the meter
+    pub fn get_gain_reduction(&self) -> f64 {
+        self.status.get_gain_reduction()
+    }
+
+    pub fn get_output_peak(&self) -> f64 {
+        self.status.get_output_peak()
+    }
+
+    /// Samples clipped at the output since the start, see `EngineStatus::get_clipped_samples`
+    pub fn get_clipped_samples(&self) -> u64 {
+        self.status.get_clipped_samples()
+    }
+
+    /// Drops the voices and patches the audio thread is done with.
+    /// Must be called regularly by the interface loop.
+    pub fn collect_garbage(&self) {
+        if let Ok(garbage) = self.garbage.lock() {
+            while garbage.try_recv().is_ok() {}
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use crate::audio::controller::SynthController;
+use crate::audio::note_manager::ActiveNote;
+use crate::audio::voice_allocator::{VoiceAllocator, VoiceSettings};
+use crate::consts::constants::{COMMAND_QUEUE_SIZE, GARBAGE_QUEUE_SIZE, MAX_BLOCK_SIZE};
+use crate::synths::manager::SynthType;
+use crate::synths::modulation::ModControllers;
+use crate::synths::param::Param;
+use crate::synths::traits::StereoFrame;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+
+// Amplification finale, appliquée avant le master bus pour que le limiteur reste le dernier étage
+const OUTPUT_GAIN: f64 = 1.5;
+
+/// Messages sent from the interfaces (GUI, terminal) to the audio thread
+pub enum Command {
+    NoteOn(ActiveNote),
+    NoteOff(u64),
+    AllNotesOff,
+    SetParam(Param),
+    SetSynth(SynthType),
+    SetVoiceSettings(VoiceSettings),
+    PitchBend(f64),  // -1..1
+    ModWheel(f64),   // 0..1
+    Aftertouch(f64), // 0..1
+}
+
+/// Objects handed back to the control side so they are never freed on the audio thread
+pub enum Garbage {
+    Note(ActiveNote),
+    Synth(SynthType),
+}
+
+/// Values published by the audio thread, readable without locking
+#[derive(Default)]
+pub struct EngineStatus {
+    active_voices: AtomicUsize,
+    gain_reduction: AtomicU64, // bits d'un f64, en dB
+    output_peak: AtomicU64,    // bits d'un f64, crête du dernier buffer
+    clipped_samples: AtomicU64,
+}
+
+impl EngineStatus {
+    pub fn get_active_voices(&self) -> usize {
+        self.active_voices.load(Ordering::Relaxed)
+    }
+
+    /// Gain reduction of the compressor at the end of the last buffer, in dB
+    pub fn get_gain_reduction(&self) -> f64 {
+        f64::from_bits(self.gain_reduction.load(Ordering::Relaxed))
+    }
+
+    /// Highest absolute sample of the last buffer, before the clip protection
+    pub fn get_output_peak(&self) -> f64 {
+        f64::from_bits(self.output_peak.load(Ordering::Relaxed))
+    }
+
+    /// Samples that went over full scale since the start, clipped to ±1 on output
+    pub fn get_clipped_samples(&self) -> u64 {
+        self.clipped_samples.load(Ordering::Relaxed)
+    }
+}
+
+/// Audio-thread-owned engine.
+/// It only talks to the rest of the program through the command queue, so rendering
+/// never locks, allocates or clones: voices are built on the control side and
+/// everything removed here is sent back to be dropped there.
+pub struct AudioEngine {
+    commands: Receiver<Command>,
+    garbage: SyncSender<Garbage>,
+    status: Arc<EngineStatus>,
+    synth: SynthType, // porte le master bus
+    voices: VoiceAllocator,
+    sample_rate: f64,
+    pitch_bend: f64,               // position de la molette, -1..1
+    pitch_ratio: f64,              // pitch bend appliqué à toutes les voix
+    controllers: ModControllers,   // molette et aftertouch, sources de la matrice de modulation
+    voice_block: Vec<StereoFrame>, // tampon d'une voix, alloué une fois pour MAX_BLOCK_SIZE
+    output_peak: f64,              // crête du buffer en cours, publiée avec le statut
+}
+
+/// Sends removed notes back to the control side, like `AudioEngine::discard`
+fn note_discarder(garbage: &SyncSender<Garbage>) -> impl FnMut(ActiveNote) + '_ {
+    move |note| {
+        let _ = garbage.try_send(Garbage::Note(note));
+    }
+}
+
+/// Creates an engine and the controller driving it
+/// The synth is rebuilt at `sample_rate`, like every synth the controller sends later.
+pub fn create_engine(mut synth: SynthType, sample_rate: f64) -> (SynthController, AudioEngine) {
+    synth.set_sample_rate(sample_rate);
+    let (command_sender, command_receiver) = mpsc::sync_channel(COMMAND_QUEUE_SIZE);
+    let (garbage_sender, garbage_receiver) = mpsc::sync_channel(GARBAGE_QUEUE_SIZE);
+    let status = Arc::new(EngineStatus::default());
+
+    let controller = SynthController::new(
+        command_sender,
+        garbage_receiver,
+        synth.clone(),
+        Arc::clone(&status),
+        sample_rate,
+    );
+    let engine = AudioEngine {
+        commands: command_receiver,
+        garbage: garbage_sender,
+        status,
+        synth,
+        voices: VoiceAllocator::new(VoiceSettings::default()),
+        sample_rate,
+        pitch_bend: 0.0,
+        pitch_ratio: 1.0,
+        controllers: ModControllers::default(),
+        voice_block: vec![[0.0, 0.0]; MAX_BLOCK_SIZE],
+        output_peak: 0.0,
+    };
+    (controller, engine)
+}
+
+impl AudioEngine {
+    pub fn get_sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Applies every pending command. Called once per audio buffer.
+    pub fn process_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            self.handle_command(command);
+        }
+    }
+
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::NoteOn(mut note) => {
+                // Les LFO libres de la voix reprennent la phase de ceux du master bus
+                note.voice.follow_free_lfos(self.synth.modulator());
+                self.voices.note_on(note, note_discarder(&self.garbage))
+            }
+            Command::NoteOff(key) => self.voices.note_off(key),
+            Command::AllNotesOff => self.voices.all_notes_off(),
+            Command::SetParam(param) => {
+                // Rien n'est construit ici : un module absent ne peut venir que d'un SetSynth
+                if !param.requires_rebuild(&self.synth) {
+                    self.synth.apply_param(param);
+                }
+                for voice in self.voices.voices_mut() {
+                    param.apply_to_envelope(&mut voice.adsr);
+                    // Les voix n'ont pas de master bus, et celles jouées avant le SetSynth qui
+                    // a ajouté un module gardent leur chaîne
+                    if !param.is_master() && !param.requires_rebuild(&voice.voice) {
+                        voice.voice.apply_param(param);
+                    }
+                }
+                self.update_pitch_ratio();
+            }
+            Command::SetSynth(synth) => {
+                let old = std::mem::replace(&mut self.synth, synth);
+                self.discard(Garbage::Synth(old));
+                self.update_pitch_ratio();
+            }
+            Command::SetVoiceSettings(settings) => self
+                .voices
+                .set_settings(settings, note_discarder(&self.garbage)),
+            Command::PitchBend(bend) => {
+                self.pitch_bend = bend.clamp(-1.0, 1.0);
+                self.update_pitch_ratio();
+            }
+            Command::ModWheel(value) => self.controllers.mod_wheel = value.clamp(0.0, 1.0),
+            Command::Aftertouch(value) => self.controllers.aftertouch = value.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Sends an object back to the control side.
+    /// `GARBAGE_QUEUE_SIZE` bounds everything the engine can hold, so the queue is never full.
+    fn discard(&mut self, garbage: Garbage) {
+        let _ = self.garbage.try_send(garbage);
+    }
+
+    /// Pitch bend ratio, from the bend range of the current patch
+    fn update_pitch_ratio(&mut self) {
+        self.pitch_ratio = self.synth.get_pitch().bend_ratio(self.pitch_bend);
+    }
+
+    /// Generates the next frame: every voice runs its own chain, the mix goes through the master bus.
+    /// Same result as `render_block`, one sample at a time.
+    pub fn next_frame(&mut self) -> StereoFrame {
+        let mut frame = [0.0, 0.0];
+        for voice in self.voices.voices_mut() {
+            let [left, right] =
+                voice.next_frame(self.sample_rate, self.pitch_ratio, &self.controllers);
+            frame[0] += left;
+            frame[1] += right;
+        }
+
+        // Normalize by number of active notes to prevent clipping but keep good volume
+        // Division par racine carrée pour préserver le volume
+        let scale = OUTPUT_GAIN / (self.voices.len() as f64).max(1.0).sqrt();
+        frame = frame.map(|sample| sample * scale);
+
+        // Global effects (compressor, reverb) run once on the mix, even without notes for the tails
+        frame = self.synth.process_master(frame, 0.0, &self.controllers);
+
+        let mut output = [frame];
+        self.protect_output(&mut output);
+        output[0]
+    }
+
+    /// Fills `output` with the next frames, by blocks of at most `MAX_BLOCK_SIZE`.
+    /// Each module then runs once per block instead of once per sample.
+    pub fn render_block(&mut self, output: &mut [StereoFrame]) {
+        for block in output.chunks_mut(MAX_BLOCK_SIZE) {
+            self.mix_block(block);
+        }
+    }
+
+    /// Same as `render_block`, with `key` as the sidechain signal of the compressor.
+    /// Frames past the end of `key` are heard as silence.
+    pub fn render_block_with_sidechain(&mut self, output: &mut [StereoFrame], key: &[StereoFrame]) {
+        for (index, block) in output.chunks_mut(MAX_BLOCK_SIZE).enumerate() {
+            let start = (index * MAX_BLOCK_SIZE).min(key.len());
+            let end = (start + block.len()).min(key.len());
+            self.synth.sidechain(&key[start..end]);
+            self.mix_block(block);
+        }
+    }
+
+    fn mix_block(&mut self, output: &mut [StereoFrame]) {
+        output.fill([0.0, 0.0]);
+        let voice_block = &mut self.voice_block[..output.len()];
+        for voice in self.voices.voices_mut() {
+            voice.render_block(
+                voice_block,
+                self.sample_rate,
+                self.pitch_ratio,
+                &self.controllers,
+            );
+            for (frame, [left, right]) in output.iter_mut().zip(voice_block.iter()) {
+                frame[0] += left;
+                frame[1] += right;
+            }
+        }
+
+        // Même normalisation que next_frame, le nombre de voix ne change pas pendant un bloc
+        let scale = OUTPUT_GAIN / (self.voices.len() as f64).max(1.0).sqrt();
+        for frame in output.iter_mut() {
+            *frame = frame.map(|sample| sample * scale);
+        }
+
+        self.synth.process_master_block(output, &self.controllers);
+        self.protect_output(output);
+    }
+
+    /// Measures the peak and clips to full scale, so the converter never receives more than ±1.
+    /// With the limiter on, nothing reaches this point above its ceiling.
+    fn protect_output(&mut self, output: &mut [StereoFrame]) {
+        let mut clipped = 0;
+        for sample in output.iter_mut().flatten() {
+            let level = sample.abs();
+            self.output_peak = self.output_peak.max(level);
+            if level > 1.0 {
+                clipped += 1;
+                *sample = sample.clamp(-1.0, 1.0);
+            }
+        }
+        if clipped > 0 {
+            self.status
+                .clipped_samples
+                .fetch_add(clipped, Ordering::Relaxed);
+        }
+    }
+
+    /// Removes voices whose release is over. Called once per audio buffer.
+    pub fn remove_finished_voices(&mut self) {
+        self.voices.remove_finished(note_discarder(&self.garbage));
+        self.status
+            .active_voices
+            .store(self.voices.len(), Ordering::Relaxed);
+        self.status
+            .gain_reduction
+            .store(self.synth.get_gain_reduction().to_bits(), Ordering::Relaxed);
+        self.status
+            .output_peak
+            .store(self.output_peak.to_bits(), Ordering::Relaxed);
+        self.output_peak = 0.0;
+    }
+
+    /// Renders a stereo buffer: commands first, then frames, then cleanup
+    pub fn render(&mut self, output: &mut [StereoFrame]) {
+        self.process_commands();
+        self.render_block(output);
+        self.remove_finished_voices();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::modules::limiter::LimiterSettings;
+
+    /// Peak before the output clip of a loud 8-note chord, limited to -1 dBTP or not
+    fn hot_chord_peak(limited: bool) -> f64 {
+        let mut synth = SynthType::n_sawtooth();
+        synth.apply_param(Param::Limiter(LimiterSettings {
+            ceiling_db: -1.0,
+            release_ms: 50.0,
+        }));
+        synth.apply_param(Param::LimiterActive(limited));
+        let (controller, mut engine) = create_engine(synth, 48000.0);
+        for index in 0..8 {
+            controller.note_on_with_velocity(110.0 * 2.0_f64.powf(index as f64 / 4.0), 1.0);
+        }
+        engine.process_commands();
+        let mut block = vec![[0.0, 0.0]; MAX_BLOCK_SIZE];
+        let mut peak: f64 = 0.0;
+        for _ in 0..40 {
+            engine.render_block(&mut block);
+            engine.remove_finished_voices();
+            peak = peak.max(controller.get_output_peak());
+        }
+        peak
+    }
+
+    #[test]
+    fn limiter_keeps_a_hot_chord_under_its_ceiling() {
+        let ceiling = 10f64.powf(-1.0 / 20.0);
+        let unlimited = hot_chord_peak(false);
+        assert!(unlimited > 1.0, "the chord only peaks at {unlimited}");
+        let limited = hot_chord_peak(true);
+        assert!(
+            limited <= ceiling,
+            "peak {limited} over the ceiling {ceiling}"
+        );
+    }
+
+    #[test]
+    fn switching_on_a_missing_effect_swaps_the_patch_instead_of_building_it_here() {
+        let mut synth = SynthType::n_sine();
+        if let SynthType::Sine(ref mut sine) = synth {
+            sine.master
+                .modules
+                .retain(|module| module.name() != "Delay");
+        }
+        let (controller, mut engine) = create_engine(synth, 44100.0);
+        controller.note_on(440.0);
+        engine.process_commands();
+
+        controller.set_param(Param::DelayActive(true));
+        assert!(matches!(
+            engine.commands.try_recv(),
+            Ok(Command::SetSynth(ref synth)) if synth.has_module("Delay")
+        ));
+
+        // Un effet du master bus déjà présent ne touche pas aux voix
+        controller.set_param(Param::ReverbActive(false));
+        engine.process_commands();
+        assert!(!engine.synth.is_reverb_active());
+        for voice in engine.voices.voices_mut() {
+            assert!(!voice.voice.has_module("Reverb"));
+            assert!(!voice.voice.has_module("Delay"));
+        }
+    }
+}
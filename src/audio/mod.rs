@@ -1,3 +1,7 @@
-pub mod setup_realtime_audio;
+pub mod controller;
+pub mod engine;
+pub mod frequency_manager;
 pub mod note_manager;
-pub mod frequency_manager;
\ No newline at end of file
+pub mod offline_render;
+pub mod setup_realtime_audio;
+pub mod voice_allocator;
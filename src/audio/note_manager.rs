@@ -1,39 +1,173 @@
 use crate::consts::constants;
+use crate::synths::manager::SynthType;
+use crate::synths::modulation::ModControllers;
 use crate::synths::modules::adsr::ADSR;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use crate::synths::traits::StereoFrame;
 
-/// Struct that represents an active note with its frequency and ADSR envelope
+/// Integer key identifying a note by its frequency
+pub fn note_key(frequency: f64) -> u64 {
+    (frequency * 1000.0) as u64 // Convertir en clé entière
+}
+
+/// Struct that represents an active note with its frequency, ADSR envelope
+/// and its own signal chain (oscillator + per-voice modules)
 #[derive(Clone)]
 pub struct ActiveNote {
+    pub key: u64,
     pub frequency: f64,
+    pub velocity: f64, // 0..1
+    pub gain: f64,     // multiplicateur d'amplitude tiré de la vélocité
     pub adsr: ADSR,
     pub is_released: bool, // true quand la touche est relâchée mais l'ADSR est en release
+    pub is_stolen: bool,   // voix volée qui s'éteint en quelques ms, sa place est déjà reprise
+    pub voice: SynthType,  // chaîne propre à la note (filtre, LFO, bruit...)
+    pub phase: f64,        // phase de l'oscillateur, 0..1
 }
 
 impl ActiveNote {
-    pub fn new(frequency: f64, sample_rate: f64) -> Self {
-        let mut adsr = ADSR::new(
-            sample_rate,
-            constants::ADSR_ATTACK,
-            constants::ADSR_DECAY,
-            constants::ADSR_SUSTAIN,
-            constants::ADSR_RELEASE,
-        );
+    /// Creates a started note from a copy of the current envelope and voice chain
+    pub fn new(frequency: f64, velocity: f64, envelope: ADSR, mut voice: SynthType) -> Self {
+        let mut adsr = envelope;
         adsr.note_on();
+        voice.set_note_frequency(frequency);
+        voice.set_note_velocity(velocity);
+        voice.prepare_voice();
+        voice.note_on(); // après la pile d'unisson, pour que chaque oscillateur tire son état
 
         Self {
+            key: note_key(frequency),
             frequency,
+            velocity: velocity.clamp(0.0, 1.0),
+            gain: 1.0,
             adsr,
             is_released: false,
+            is_stolen: false,
+            voice,
+            phase: 0.0,
+        }
+    }
+
+    /// Generates the next frame of this voice (chain output scaled by the ADSR and velocity gain).
+    /// `pitch_ratio` multiplies the frequency, e.g. for pitch bend.
+    pub fn next_frame(
+        &mut self,
+        sample_rate: f64,
+        pitch_ratio: f64,
+        controllers: &ModControllers,
+    ) -> StereoFrame {
+        self.voice.modulate(controllers, 1);
+        let frequency = self.modulated_frequency(pitch_ratio);
+
+        // Convertir la phase [0,1] vers [0,2π] pour les oscillateurs
+        let phase_radians = self.phase * 2.0 * std::f64::consts::PI;
+        let increment = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+        let [left, right] = self
+            .voice
+            .generate_frame(phase_radians, increment, frequency);
+
+        self.phase += frequency / sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+
+        let gain = self.adsr.get_amplitude() * self.gain;
+        [left * gain, right * gain]
+    }
+
+    /// Block version of `next_frame`: the oscillator fills the block sample by sample,
+    /// then the voice chain processes it as a whole before the envelope is applied.
+    /// The block is split where the modulation matrix updates its amounts.
+    pub fn render_block(
+        &mut self,
+        block: &mut [StereoFrame],
+        sample_rate: f64,
+        pitch_ratio: f64,
+        controllers: &ModControllers,
+    ) {
+        let mut start = 0;
+        while start < block.len() {
+            let span = self.voice.modulate(controllers, block.len() - start);
+            let part = &mut block[start..start + span];
+            start += span;
+
+            let frequency = self.modulated_frequency(pitch_ratio);
+            let increment = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+
+            for frame in part.iter_mut() {
+                *frame = self
+                    .voice
+                    .oscillate(self.phase * 2.0 * std::f64::consts::PI, increment);
+
+                self.phase += frequency / sample_rate;
+                if self.phase >= 1.0 {
+                    self.phase -= 1.0;
+                }
+            }
+
+            self.voice.process_block(part);
+        }
+
+        for frame in block.iter_mut() {
+            let gain = self.adsr.get_amplitude() * self.gain;
+            *frame = [frame[0] * gain, frame[1] * gain];
+        }
+    }
+
+    /// Frequency played by the voice, with pitch bend and the pitch modulation of the matrix,
+    /// the vibrato and the glide
+    fn modulated_frequency(&self, pitch_ratio: f64) -> f64 {
+        let semitones = self.voice.pitch_offset();
+        if semitones == 0.0 {
+            self.frequency * pitch_ratio
+        } else {
+            self.frequency * pitch_ratio * 2.0_f64.powf(semitones / 12.0)
+        }
+    }
+
+    /// Continues the sound of a previous voice: same phase, attack restarted from its current level
+    pub fn take_over(&mut self, previous: &ActiveNote) {
+        self.phase = previous.phase;
+        self.adsr.note_on_from(previous.adsr.get_level());
+    }
+
+    /// Slides from the pitch `from` (Hz) to the note if the glide mode of the patch allows it.
+    /// `legato` tells whether the previous note is still held.
+    pub fn glide_from(&mut self, from: f64, legato: bool) {
+        if self.voice.get_pitch().glides(legato) && from > 0.0 {
+            self.voice.glide_from(12.0 * (from / self.frequency).log2());
         }
     }
 
+    /// Pitch reached by the voice in Hz: its note, or where its glide is, without bend nor vibrato
+    pub fn current_frequency(&self) -> f64 {
+        self.frequency * 2.0_f64.powf(self.voice.glide_offset() / 12.0)
+    }
+
+    /// Moves the voice to another pitch without touching its envelope (legato)
+    pub fn set_frequency(&mut self, frequency: f64) {
+        self.key = note_key(frequency);
+        self.frequency = frequency;
+        self.voice.set_note_frequency(frequency);
+    }
+
+    /// Current loudness of the voice, without advancing its envelope
+    pub fn get_level(&self) -> f64 {
+        self.adsr.get_level() * self.gain
+    }
+
     pub fn note_off(&mut self) {
         self.adsr.note_off();
+        self.voice.note_off();
         self.is_released = true;
     }
 
+    /// Releases the voice over `STEAL_FADE` whatever its envelope, so stealing it does not click
+    pub fn fade_out(&mut self) {
+        self.adsr.set_release(constants::STEAL_FADE);
+        self.note_off();
+        self.is_stolen = true;
+    }
+
     pub fn is_finished(&self) -> bool {
         use crate::synths::modules::adsr::EnvelopeStage;
         matches!(self.adsr.get_stage(), EnvelopeStage::Idle)
@@ -76,60 +210,91 @@ impl ActiveNote {
     }
 }
 
-/// Gestionnaire des notes actives avec ADSR individuels
-pub type ActiveNoteManager = Arc<Mutex<HashMap<u64, ActiveNote>>>;
-
-pub fn create_note_manager() -> ActiveNoteManager {
-    Arc::new(Mutex::new(HashMap::new()))
-}
-
-/// Ajoute une nouvelle note avec son ADSR
-pub fn add_note(manager: &ActiveNoteManager, frequency: f64, sample_rate: f64) {
-    if let Ok(mut notes) = manager.lock() {
-        let frequency_key = (frequency * 1000.0) as u64; // Convertir en clé entière
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::param::Param;
+    use crate::synths::pitch::GlideMode;
 
-        // Créer une nouvelle note
-        let note = ActiveNote::new(frequency, sample_rate);
-        notes.insert(frequency_key, note);
-        println!("Note ajoutée: {:.2} Hz", frequency);
-    } else {
-        eprintln!("Warning: Failed to lock note manager to add note");
+    /// A4 on a sine voice with the given pitch settings
+    fn note(params: &[Param]) -> ActiveNote {
+        let mut voice = SynthType::n_sine();
+        for &param in params {
+            voice.apply_param(param);
+        }
+        let envelope = ADSR::new(voice.get_sample_rate(), 0.0, 0.0, 1.0, 0.1);
+        ActiveNote::new(440.0, 1.0, envelope, voice)
     }
-}
 
-pub fn release_note(manager: &ActiveNoteManager, frequency: f64) {
-    if let Ok(mut notes) = manager.lock() {
-        let frequency_key = (frequency * 1000.0) as u64;
+    /// Semitones between the pitch the voice plays and its note
+    fn semitones(note: &ActiveNote, pitch_ratio: f64) -> f64 {
+        12.0 * (note.modulated_frequency(pitch_ratio) / note.frequency).log2()
+    }
 
-        if let Some(note) = notes.get_mut(&frequency_key) {
-            if !note.is_released {
-                note.note_off();
-                println!("Note relâchée: {:.2} Hz", frequency);
-            }
+    #[test]
+    fn full_bend_moves_the_pitch_by_the_bend_range() {
+        for range in [2.0, 7.0, 12.0, 24.0] {
+            let note = note(&[Param::PitchBendRange(range), Param::Vibrato(0.0)]);
+            let pitch = note.voice.get_pitch();
+            assert!((semitones(&note, pitch.bend_ratio(1.0)) - range).abs() < 1e-9);
+            assert!((semitones(&note, pitch.bend_ratio(-1.0)) + range).abs() < 1e-9);
+            assert!((semitones(&note, pitch.bend_ratio(0.5)) - range / 2.0).abs() < 1e-9);
+            assert_eq!(semitones(&note, pitch.bend_ratio(0.0)), 0.0);
         }
-    } else {
-        eprintln!("Warning: Failed to lock note manager to release note");
     }
-}
 
-/// Nettoie les notes finies (ADSR en Idle)
-pub fn cleanup_finished_notes(manager: &ActiveNoteManager) {
-    if let Ok(mut notes) = manager.lock() {
-        notes.retain(|_key, note| !note.is_finished());
-    } else {
-        eprintln!("Warning: Failed to lock note manager for cleanup");
+    #[test]
+    fn glide_reaches_the_note_in_the_glide_time() {
+        let glide_time = 0.1;
+        let mut note = note(&[
+            Param::Vibrato(0.0),
+            Param::GlideMode(GlideMode::Always),
+            Param::GlideTime(glide_time),
+        ]);
+        let sample_rate = note.voice.get_sample_rate();
+        let controllers = ModControllers::default();
+        // D'une octave plus bas, en ligne droite sur les demi-tons
+        note.glide_from(220.0, false);
+
+        let mut pitch = Vec::new();
+        for _ in 0..(glide_time * 1.5 * sample_rate) as usize {
+            note.next_frame(sample_rate, 1.0, &controllers);
+            pitch.push(semitones(&note, 1.0));
+        }
+        let at = |seconds: f64| pitch[(seconds * sample_rate) as usize];
+        // Les montants avancent par pas de contrôle : un pas de tolérance
+        let tolerance = 12.0 * constants::MOD_CONTROL_INTERVAL as f64 / (glide_time * sample_rate);
+        assert!((at(0.0) + 12.0).abs() <= tolerance, "starts at {}", at(0.0));
+        assert!(
+            (at(glide_time / 2.0) + 6.0).abs() <= tolerance,
+            "halfway at {}",
+            at(glide_time / 2.0)
+        );
+        assert!(at(glide_time * 0.9) < 0.0, "arrived too early");
+        assert_eq!(
+            at(glide_time + constants::MOD_CONTROL_INTERVAL as f64 / sample_rate),
+            0.0
+        );
+        assert_eq!(note.current_frequency(), 440.0);
     }
-}
 
-/// Arrête toutes les notes
-pub fn stop_all_notes(manager: &ActiveNoteManager) {
-    if let Ok(mut notes) = manager.lock() {
-        for note in notes.values_mut() {
-            if !note.is_released {
-                note.note_off();
-            }
+    #[test]
+    fn vibrato_swings_by_its_depth_around_the_note() {
+        let depth = 0.5;
+        let mut note = note(&[Param::Vibrato(depth)]);
+        let sample_rate = note.voice.get_sample_rate();
+        let controllers = ModControllers::default();
+        let (mut low, mut high) = (0.0f64, 0.0f64);
+        for _ in 0..sample_rate as usize {
+            note.next_frame(sample_rate, 1.0, &controllers);
+            let offset = semitones(&note, 1.0);
+            low = low.min(offset);
+            high = high.max(offset);
         }
-    } else {
-        eprintln!("Warning: Failed to lock note manager to stop notes");
+        assert!(
+            high <= depth + 1e-9 && low >= -depth - 1e-9,
+            "{low}..{high}"
+        );
+        assert!(high > depth * 0.95 && low < -depth * 0.95, "{low}..{high}");
     }
 }
@@ -0,0 +1,254 @@
+use crate::audio::controller::SynthController;
+use crate::audio::engine::{self, AudioEngine};
+use crate::consts::constants::MAX_BLOCK_SIZE;
+use crate::presets::Preset;
+use crate::synths::manager::SynthType;
+use crate::synths::traits::StereoFrame;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A note played during an offline render, times in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct NoteEvent {
+    pub start: f64,
+    pub duration: f64,
+    pub frequency: f64,
+    #[serde(default = "full_velocity")]
+    pub velocity: f64, // 0..1
+}
+
+fn full_velocity() -> f64 {
+    1.0
+}
+
+impl NoteEvent {
+    pub fn new(start: f64, duration: f64, frequency: f64) -> Self {
+        Self {
+            start,
+            duration,
+            frequency,
+            velocity: full_velocity(),
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: f64) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn end(&self) -> f64 {
+        self.start + self.duration
+    }
+}
+
+/// Sample format of the written WAV file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WavFormat {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl WavFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "16" | "int16" => Some(WavFormat::Int16),
+            "24" | "int24" => Some(WavFormat::Int24),
+            "float" | "f32" | "32f" => Some(WavFormat::Float32),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub sample_rate: f64,
+    pub format: WavFormat,
+    pub tail: f64, // secondes rendues après la dernière note (release, réverb)
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate: crate::consts::constants::SAMPLE_RATE,
+            format: WavFormat::Int16,
+            tail: 2.0,
+        }
+    }
+}
+
+/// Renders the events through the same engine as the real-time callback, without any audio device
+pub fn render(
+    synth: SynthType,
+    events: &[NoteEvent],
+    settings: &RenderSettings,
+) -> Vec<StereoFrame> {
+    render_with_sidechain(synth, events, &[], settings)
+}
+
+/// Same as `render`, with `key` heard by the compressor when its sidechain is on.
+/// `key` starts with the render, frames past its end are silence.
+pub fn render_with_sidechain(
+    synth: SynthType,
+    events: &[NoteEvent],
+    key: &[StereoFrame],
+    settings: &RenderSettings,
+) -> Vec<StereoFrame> {
+    let (controller, mut engine) = engine::create_engine(synth, settings.sample_rate);
+    render_events(&controller, &mut engine, events, key, settings)
+}
+
+/// Renders the events with the synthesizer and envelope of a preset
+pub fn render_preset(
+    preset: &Preset,
+    events: &[NoteEvent],
+    settings: &RenderSettings,
+) -> Vec<StereoFrame> {
+    render_preset_with_sidechain(preset, events, &[], settings)
+}
+
+/// Same as `render_preset`, with `key` heard by the compressor when its sidechain is on
+pub fn render_preset_with_sidechain(
+    preset: &Preset,
+    events: &[NoteEvent],
+    key: &[StereoFrame],
+    settings: &RenderSettings,
+) -> Vec<StereoFrame> {
+    let (controller, mut engine) = engine::create_engine(preset.to_synth(), settings.sample_rate);
+    controller.load_preset(preset);
+    engine.process_commands();
+    render_events(&controller, &mut engine, events, key, settings)
+}
+
+fn render_events(
+    controller: &SynthController,
+    engine: &mut AudioEngine,
+    events: &[NoteEvent],
+    key: &[StereoFrame],
+    settings: &RenderSettings,
+) -> Vec<StereoFrame> {
+    let sample_rate = settings.sample_rate;
+    let to_index = |seconds: f64| (seconds.max(0.0) * sample_rate).round() as usize;
+
+    // (instant, note on ?, note), les note off passent avant les note on au même instant
+    let mut timeline: Vec<(usize, bool, &NoteEvent)> = Vec::with_capacity(events.len() * 2);
+    for event in events {
+        timeline.push((to_index(event.start), true, event));
+        timeline.push((to_index(event.end()), false, event));
+    }
+    timeline.sort_by_key(|&(index, is_on, _)| (index, is_on));
+
+    let end = events.iter().map(NoteEvent::end).fold(0.0, f64::max);
+    let length = to_index(end + settings.tail);
+
+    let mut output = Vec::with_capacity(length);
+    let mut pending = timeline.iter().peekable();
+
+    while output.len() < length {
+        // Envoyer les événements arrivés à échéance
+        while let Some(&&(index, is_on, event)) = pending.peek() {
+            if index > output.len() {
+                break;
+            }
+            if is_on {
+                controller.note_on_with_velocity(event.frequency, event.velocity);
+            } else {
+                controller.note_off(event.frequency);
+            }
+            engine.process_commands();
+            pending.next();
+        }
+
+        // Render up to the next event or the end of the block, cleanups happen between blocks
+        let next_event = pending.peek().map_or(length, |&&(index, _, _)| index);
+        let block_end = next_event.min(output.len() + MAX_BLOCK_SIZE).min(length);
+        let block_start = output.len();
+        output.resize(block_end, [0.0, 0.0]);
+        let key = &key[block_start.min(key.len())..block_end.min(key.len())];
+        engine.render_block_with_sidechain(&mut output[block_start..], key);
+
+        engine.remove_finished_voices();
+        controller.collect_garbage();
+    }
+    output
+}
+
+/// Writes stereo frames to a WAV file. Integer formats are clipped to [-1, 1].
+pub fn write_wav<P: AsRef<Path>>(
+    path: P,
+    frames: &[StereoFrame],
+    sample_rate: f64,
+    format: WavFormat,
+) -> Result<(), hound::Error> {
+    let (bits_per_sample, sample_format) = match format {
+        WavFormat::Int16 => (16, hound::SampleFormat::Int),
+        WavFormat::Int24 => (24, hound::SampleFormat::Int),
+        WavFormat::Float32 => (32, hound::SampleFormat::Float),
+    };
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: sample_rate as u32,
+        bits_per_sample,
+        sample_format,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in frames.iter().flatten() {
+        let clipped = sample.clamp(-1.0, 1.0);
+        match format {
+            WavFormat::Int16 => writer.write_sample((clipped * i16::MAX as f64) as i16)?,
+            WavFormat::Int24 => writer.write_sample((clipped * 8_388_607.0) as i32)?,
+            WavFormat::Float32 => writer.write_sample(sample as f32)?,
+        }
+    }
+    writer.finalize()
+}
+
+/// Reads a WAV file as stereo frames and its sample rate.
+/// Mono files play on both channels, channels past the second are ignored.
+pub fn read_wav<P: AsRef<Path>>(path: P) -> Result<(Vec<StereoFrame>, f64), hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples: Vec<f64> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|sample| sample.map(f64::from))
+            .collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let full_scale = (1i64 << (spec.bits_per_sample - 1)) as f64;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|value| value as f64 / full_scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let frames = samples
+        .chunks_exact(channels)
+        .map(|frame| [frame[0], frame[1.min(channels - 1)]])
+        .collect();
+    Ok((frames, spec.sample_rate as f64))
+}
+
+/// Renders the events and writes the result to a WAV file
+pub fn render_to_wav<P: AsRef<Path>>(
+    path: P,
+    synth: SynthType,
+    events: &[NoteEvent],
+    settings: &RenderSettings,
+) -> Result<(), hound::Error> {
+    let frames = render(synth, events, settings);
+    write_wav(path, &frames, settings.sample_rate, settings.format)
+}
+
+/// Renders the events with a preset and writes the result to a WAV file
+pub fn render_preset_to_wav<P: AsRef<Path>>(
+    path: P,
+    preset: &Preset,
+    events: &[NoteEvent],
+    settings: &RenderSettings,
+) -> Result<(), hound::Error> {
+    let frames = render_preset(preset, events, settings);
+    write_wav(path, &frames, settings.sample_rate, settings.format)
+}
@@ -0,0 +1,453 @@
+use crate::audio::note_manager::ActiveNote;
+use crate::consts::constants::{self, MAX_VOICES, STEAL_SLOTS};
+use serde::{Deserialize, Serialize};
+
+/// Voice dropped when a new note needs a slot and the polyphony limit is reached
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StealPolicy {
+    Oldest,   // la plus ancienne, en commençant par les notes relâchées
+    Quietest, // celle dont le niveau actuel est le plus faible
+}
+
+/// What happens when a pitch that is still sounding is played again
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RetriggerMode {
+    SameVoice, // la voix existante repart de son niveau actuel
+    NewVoice,  // l'ancienne voix finit son release, une nouvelle démarre
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VoiceMode {
+    Poly,
+    Mono,   // une seule voix, l'enveloppe repart à chaque note
+    Legato, // une seule voix, l'enveloppe ne repart pas tant qu'une touche est tenue
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceSettings {
+    pub max_voices: usize,
+    pub steal_policy: StealPolicy,
+    pub retrigger: RetriggerMode,
+    pub mode: VoiceMode,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            max_voices: constants::CURRENT_MAX_VOICES,
+            steal_policy: StealPolicy::Oldest,
+            retrigger: RetriggerMode::SameVoice,
+            mode: VoiceMode::Poly,
+        }
+    }
+}
+
+/// Voices owned by the audio thread.
+/// Both vectors are preallocated and never grow, and removed voices are handed to `discard`
+/// so the caller decides where they are freed. Stolen voices fade out in `STEAL_SLOTS`
+/// extra slots and no longer count against the polyphony.
+/// Voices are kept from the oldest to the most recent.
+pub struct VoiceAllocator {
+    voices: Vec<ActiveNote>,
+    held: Vec<(u64, f64)>, // touches tenues en mode mono (clé, fréquence), la dernière sonne
+    settings: VoiceSettings,
+}
+
+impl VoiceAllocator {
+    pub fn new(settings: VoiceSettings) -> Self {
+        let mut allocator = Self {
+            voices: Vec::with_capacity(MAX_VOICES + STEAL_SLOTS),
+            held: Vec::with_capacity(MAX_VOICES),
+            settings: VoiceSettings::default(),
+        };
+        allocator.set_settings(settings, |_| {});
+        allocator
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: VoiceSettings, mut discard: impl FnMut(ActiveNote)) {
+        let mode_changed = settings.mode != self.settings.mode;
+        self.settings = VoiceSettings {
+            max_voices: settings.max_voices.clamp(1, MAX_VOICES),
+            ..settings
+        };
+
+        if mode_changed {
+            self.held.clear();
+            self.release_all();
+        }
+        while self.playing() > self.settings.max_voices {
+            self.steal(&mut discard);
+        }
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> VoiceSettings {
+        self.settings
+    }
+
+    pub fn len(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    pub fn voices_mut(&mut self) -> &mut [ActiveNote] {
+        &mut self.voices
+    }
+
+    /// Voices counted against the polyphony, the stolen ones fading out excluded
+    fn playing(&self) -> usize {
+        self.voices.iter().filter(|voice| !voice.is_stolen).count()
+    }
+
+    pub fn note_on(&mut self, note: ActiveNote, mut discard: impl FnMut(ActiveNote)) {
+        match self.settings.mode {
+            VoiceMode::Poly => self.poly_note_on(note, &mut discard),
+            VoiceMode::Mono | VoiceMode::Legato => self.mono_note_on(note, &mut discard),
+        }
+    }
+
+    pub fn note_off(&mut self, key: u64) {
+        match self.settings.mode {
+            VoiceMode::Poly => {
+                for voice in self.voices.iter_mut() {
+                    if voice.key == key && !voice.is_released {
+                        voice.note_off();
+                    }
+                }
+            }
+            VoiceMode::Mono | VoiceMode::Legato => self.mono_note_off(key),
+        }
+    }
+
+    pub fn all_notes_off(&mut self) {
+        self.held.clear();
+        self.release_all();
+    }
+
+    /// Removes the voices whose release is over
+    pub fn remove_finished(&mut self, mut discard: impl FnMut(ActiveNote)) {
+        let mut index = 0;
+        while index < self.voices.len() {
+            if self.voices[index].is_finished() {
+                discard(self.voices.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    fn poly_note_on(&mut self, mut note: ActiveNote, discard: &mut impl FnMut(ActiveNote)) {
+        // Le glide part de la dernière note jouée, en legato si une touche est encore tenue
+        if let Some(last) = self.voices.last() {
+            let held = self.voices.iter().any(|voice| !voice.is_released);
+            note.glide_from(last.current_frequency(), held);
+        }
+
+        match self.settings.retrigger {
+            RetriggerMode::SameVoice => {
+                // La voix la plus récente de cette hauteur devient la nouvelle note
+                if let Some(index) = self
+                    .voices
+                    .iter()
+                    .rposition(|v| v.key == note.key && !v.is_stolen)
+                {
+                    note.take_over(&self.voices[index]);
+                    discard(std::mem::replace(&mut self.voices[index], note));
+                    self.voices[index..].rotate_left(1);
+                    return;
+                }
+            }
+            RetriggerMode::NewVoice => {
+                for voice in self.voices.iter_mut() {
+                    if voice.key == note.key && !voice.is_released {
+                        voice.note_off();
+                    }
+                }
+            }
+        }
+
+        if self.playing() >= self.settings.max_voices {
+            self.steal(discard);
+        }
+        self.voices.push(note);
+    }
+
+    fn mono_note_on(&mut self, mut note: ActiveNote, discard: &mut impl FnMut(ActiveNote)) {
+        self.held.retain(|&(key, _)| key != note.key);
+        if self.held.len() == self.held.capacity() {
+            self.held.remove(0);
+        }
+        self.held.push((note.key, note.frequency));
+
+        let Some(current) = self.voices.last_mut().filter(|v| !v.is_stolen) else {
+            self.voices.push(note);
+            return;
+        };
+
+        let from = current.current_frequency();
+        if self.settings.mode == VoiceMode::Legato && !current.is_released {
+            current.set_frequency(note.frequency);
+            current.glide_from(from, true);
+            discard(note);
+        } else {
+            note.take_over(current);
+            note.glide_from(from, !current.is_released);
+            let previous = std::mem::replace(current, note);
+            discard(previous);
+        }
+    }
+
+    fn mono_note_off(&mut self, key: u64) {
+        self.held.retain(|&(held, _)| held != key);
+        let mode = self.settings.mode;
+        let Some(current) = self.voices.last_mut() else {
+            return;
+        };
+        if current.key != key || current.is_released {
+            return;
+        }
+
+        // Revenir à la dernière touche encore tenue
+        match self.held.last() {
+            Some(&(_, frequency)) => {
+                let from = current.current_frequency();
+                current.set_frequency(frequency);
+                current.glide_from(from, true);
+                if mode == VoiceMode::Mono {
+                    let level = current.adsr.get_level();
+                    current.adsr.note_on_from(level);
+                }
+            }
+            None => current.note_off(),
+        }
+    }
+
+    fn release_all(&mut self) {
+        for voice in self.voices.iter_mut() {
+            if !voice.is_released {
+                voice.note_off();
+            }
+        }
+    }
+
+    /// Frees one slot according to the steal policy.
+    /// The stolen voice fades out in a reserved slot; when they are all taken,
+    /// the oldest fading voice is cut, it is already almost silent.
+    fn steal(&mut self, discard: &mut impl FnMut(ActiveNote)) {
+        if self.voices.iter().filter(|v| v.is_stolen).count() >= STEAL_SLOTS
+            && let Some(oldest) = self.voices.iter().position(|v| v.is_stolen)
+        {
+            discard(self.voices.remove(oldest));
+        }
+
+        let candidates = || self.voices.iter().enumerate().filter(|(_, v)| !v.is_stolen);
+        let index = match self.settings.steal_policy {
+            StealPolicy::Oldest => candidates()
+                .find(|(_, v)| v.is_released)
+                .or_else(|| candidates().next()),
+            StealPolicy::Quietest => {
+                candidates().min_by(|(_, a), (_, b)| a.get_level().total_cmp(&b.get_level()))
+            }
+        };
+        if let Some((index, _)) = index {
+            self.voices[index].fade_out();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::note_manager::note_key;
+    use crate::synths::manager::SynthType;
+    use crate::synths::modules::adsr::{ADSR, EnvelopeStage};
+
+    const A: f64 = 220.0;
+    const B: f64 = 330.0;
+    const C: f64 = 440.0;
+
+    fn note(frequency: f64) -> ActiveNote {
+        let envelope = ADSR::new(44100.0, 0.01, 0.1, 0.8, 0.5);
+        ActiveNote::new(frequency, 1.0, envelope, SynthType::n_sine().voice())
+    }
+
+    fn poly(max_voices: usize, steal_policy: StealPolicy) -> VoiceAllocator {
+        VoiceAllocator::new(VoiceSettings {
+            max_voices,
+            steal_policy,
+            ..VoiceSettings::default()
+        })
+    }
+
+    fn mono(mode: VoiceMode) -> VoiceAllocator {
+        VoiceAllocator::new(VoiceSettings {
+            mode,
+            ..VoiceSettings::default()
+        })
+    }
+
+    /// Frequencies of the voices still counted against the polyphony
+    fn playing(voices: &mut VoiceAllocator) -> Vec<f64> {
+        let voices = voices.voices_mut().iter();
+        voices
+            .filter(|v| !v.is_stolen)
+            .map(|v| v.frequency)
+            .collect()
+    }
+
+    fn stolen(voices: &mut VoiceAllocator) -> Vec<f64> {
+        let voices = voices.voices_mut().iter();
+        voices
+            .filter(|v| v.is_stolen)
+            .map(|v| v.frequency)
+            .collect()
+    }
+
+    #[test]
+    fn oldest_policy_steals_the_oldest_voice() {
+        let mut voices = poly(2, StealPolicy::Oldest);
+        for frequency in [A, B, C] {
+            voices.note_on(note(frequency), drop);
+        }
+        assert_eq!(playing(&mut voices), [B, C]);
+        assert_eq!(stolen(&mut voices), [A]);
+    }
+
+    #[test]
+    fn oldest_policy_prefers_released_voices() {
+        let mut voices = poly(2, StealPolicy::Oldest);
+        voices.note_on(note(A), drop);
+        voices.note_on(note(B), drop);
+        voices.note_off(note_key(B));
+        voices.note_on(note(C), drop);
+        assert_eq!(playing(&mut voices), [A, C]);
+        assert_eq!(stolen(&mut voices), [B]);
+    }
+
+    #[test]
+    fn quietest_policy_steals_the_quietest_voice() {
+        let mut voices = poly(2, StealPolicy::Quietest);
+        voices.note_on(note(A), drop);
+        let mut quiet = note(B);
+        quiet.gain = 0.1;
+        voices.note_on(quiet, drop);
+        for voice in voices.voices_mut() {
+            for _ in 0..100 {
+                voice.get_amplitude();
+            }
+        }
+        voices.note_on(note(C), drop);
+        assert_eq!(playing(&mut voices), [A, C]);
+        assert_eq!(stolen(&mut voices), [B]);
+    }
+
+    #[test]
+    fn stolen_voice_fades_out_before_being_removed() {
+        let mut voices = poly(1, StealPolicy::Oldest);
+        voices.note_on(note(A), drop);
+        for _ in 0..1000 {
+            voices.voices_mut()[0].get_amplitude();
+        }
+        voices.note_on(note(B), drop);
+
+        let mut removed = Vec::new();
+        voices.remove_finished(|voice| removed.push(voice.frequency));
+        assert!(removed.is_empty(), "the stolen voice was cut");
+
+        // Le fondu dure STEAL_FADE, bien plus court que le release de 0.5 s
+        let stolen = &mut voices.voices_mut()[0];
+        let mut previous = stolen.get_level();
+        for _ in 0..(constants::STEAL_FADE * 44100.0) as usize + 1 {
+            stolen.get_amplitude();
+            assert!(stolen.get_level() <= previous, "the fade is not monotonic");
+            previous = stolen.get_level();
+        }
+        voices.remove_finished(|voice| removed.push(voice.frequency));
+        assert_eq!(removed, [A]);
+        assert_eq!(playing(&mut voices), [B]);
+    }
+
+    #[test]
+    fn fading_voices_stay_within_the_reserved_slots() {
+        let mut voices = poly(1, StealPolicy::Oldest);
+        let mut cut = 0;
+        // La première note ne vole rien, les STEAL_SLOTS suivantes remplissent la réserve
+        for index in 0..STEAL_SLOTS + 5 {
+            voices.note_on(note(A + index as f64), |_| cut += 1);
+        }
+        assert_eq!(voices.len(), 1 + STEAL_SLOTS);
+        assert_eq!(cut, 4);
+    }
+
+    #[test]
+    fn same_voice_retrigger_reuses_the_voice() {
+        let mut voices = poly(4, StealPolicy::Oldest);
+        voices.note_on(note(A), drop);
+        voices.note_on(note(B), drop);
+        let mut replaced = Vec::new();
+        voices.note_on(note(A), |voice| replaced.push(voice.frequency));
+        assert_eq!(replaced, [A]);
+        // La note rejouée passe en dernière position, comme une nouvelle voix
+        assert_eq!(playing(&mut voices), [B, A]);
+        assert!(voices.voices_mut().iter().all(|v| !v.is_released));
+    }
+
+    #[test]
+    fn new_voice_retrigger_releases_the_previous_voice() {
+        let mut voices = VoiceAllocator::new(VoiceSettings {
+            retrigger: RetriggerMode::NewVoice,
+            ..VoiceSettings::default()
+        });
+        voices.note_on(note(A), drop);
+        voices.note_on(note(A), drop);
+        let released: Vec<bool> = voices.voices_mut().iter().map(|v| v.is_released).collect();
+        assert_eq!(released, [true, false]);
+    }
+
+    #[test]
+    fn mono_returns_to_the_last_held_key() {
+        let mut voices = mono(VoiceMode::Mono);
+        voices.note_on(note(A), drop);
+        voices.note_on(note(B), drop);
+        voices.note_on(note(C), drop);
+        assert_eq!(playing(&mut voices), [C]);
+
+        voices.note_off(note_key(C));
+        assert_eq!(playing(&mut voices), [B]);
+        // Relâcher une touche qui ne sonne pas ne change rien
+        voices.note_off(note_key(A));
+        assert_eq!(playing(&mut voices), [B]);
+        assert!(!voices.voices_mut()[0].is_released);
+
+        voices.note_off(note_key(B));
+        assert!(voices.voices_mut()[0].is_released);
+    }
+
+    #[test]
+    fn mono_restarts_the_envelope_and_legato_keeps_it() {
+        for (mode, restarted) in [(VoiceMode::Mono, true), (VoiceMode::Legato, false)] {
+            let mut voices = mono(mode);
+            voices.note_on(note(A), drop);
+            for _ in 0..2000 {
+                voices.voices_mut()[0].get_amplitude();
+            }
+            let mut discarded = Vec::new();
+            voices.note_on(note(B), |voice| discarded.push(voice.frequency));
+
+            // Mono remplace la voix, legato garde la sienne et jette la nouvelle note
+            assert_eq!(discarded, [if restarted { A } else { B }], "{mode:?}");
+            assert_eq!(playing(&mut voices), [B], "{mode:?}");
+            let stage = voices.voices_mut()[0].adsr.get_stage();
+            assert_eq!(
+                matches!(stage, EnvelopeStage::Attack),
+                restarted,
+                "{mode:?}"
+            );
+        }
+    }
+}
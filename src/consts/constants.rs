@@ -1,4 +1,9 @@
+use crate::synths::modules::distortion::DistortionCurve;
+use crate::synths::modules::filter::{FilterMode, FilterSlope};
+use crate::synths::modules::leslie::LeslieSpeed;
 use crate::synths::modules::lfo::LfoWaveform;
+use crate::synths::modules::modulated::ModEffectSettings;
+use crate::synths::modules::noise::NoiseColor;
 use crate::synths::modules::reverb::ReverbType;
 use egui::Color32;
 use std::sync::atomic::AtomicUsize;
@@ -7,6 +12,21 @@ use std::sync::atomic::AtomicUsize;
 pub const VECTEUR_NOTES: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9]; // The 9 octaves
 pub const SAMPLE_RATE: f64 = 44100.0;
 pub const PROJECT_NAME: &str = "Synthétiseur Rust";
+pub const MAX_VOICES: usize = 64; // Voices preallocated for the audio thread
+pub const STEAL_SLOTS: usize = 8; // Extra slots where stolen voices fade out
+pub const STEAL_FADE: f64 = 0.005; // Fade of a stolen voice in seconds, short enough not to be heard
+pub const MAX_UNISON: usize = 8; // Oscillators stacked on a note at most
+pub const COMMAND_QUEUE_SIZE: usize = 1024; // Capacity of the interface -> audio thread queue
+// Capacity of the audio thread -> interface queue. Everything the engine throws away is a voice or
+// synth it holds, or came with a queued command, and the controller empties this queue before each
+// command: it holds at most one object per queued command, every voice slot and two synths.
+pub const GARBAGE_QUEUE_SIZE: usize = COMMAND_QUEUE_SIZE + MAX_VOICES + STEAL_SLOTS + 2;
+pub const MAX_BLOCK_SIZE: usize = 512; // Frames rendered per block, longer buffers are split
+pub const DEFAULT_TEMPO: f64 = 120.0; // Tempo in BPM at startup, for the synced LFOs
+pub const MAX_PITCH_BEND_RANGE: f64 = 24.0; // Widest pitch bend range in semitones
+pub const USER_PRESETS_DIR: &str = "presets"; // Directory of the user presets
+pub const USER_WAVETABLES_DIR: &str = "wavetables"; // Directory of the user wavetables (WAV files)
+pub const MOD_CONTROL_INTERVAL: usize = 32; // Frames between two updates of the modulation matrix
 
 // Keys colors
 pub const USED_KEYS: (u8, u8, u8) = (100, 150, 255); // Color of used keys
@@ -17,7 +37,7 @@ pub const BLACK_KEYS: (u8, u8, u8) = (50, 50, 50); // Color of black keys
 // NOISE
 pub const KNOB_NOISE_COLOR: (Color32, Color32, Color32) =
     (Color32::DARK_GRAY, Color32::WHITE, Color32::WHITE);
-    
+
 // GAIN
 pub const KNOB_GAIN_COLOR: (Color32, Color32, Color32) =
     (Color32::DARK_GRAY, Color32::RED, Color32::WHITE);
@@ -33,7 +53,23 @@ pub const KNOB_ADSR_R_COLOR: (Color32, Color32, Color32) =
     (Color32::DARK_GRAY, Color32::PURPLE, Color32::WHITE);
 
 /// Variables
-pub static CURRENT_OCTAVE_INDEX: AtomicUsize = AtomicUsize::new(4); // Current index in VECTEUR_NOTES (thread-safe)
+pub const DEFAULT_OCTAVE_INDEX: usize = 4; // Octave index at startup
+pub static CURRENT_OCTAVE_INDEX: AtomicUsize = AtomicUsize::new(DEFAULT_OCTAVE_INDEX); // Current index in VECTEUR_NOTES (thread-safe)
+
+/// OSCILLATORS
+pub static BAND_LIMITED_OSCILLATORS: bool = true; // true for PolyBLEP square and sawtooth, false for the naive waveforms
+
+/// HAMMOND
+pub static HAMMOND_DRAWBARS: [u8; 9] = [7, 6, 8, 6, 5, 4, 4, 3, 2]; // Drawbars from 16' to 1' (0 to 8)
+pub static HAMMOND_PERCUSSION: bool = false; // true to add the percussion at the start of the notes
+pub static HAMMOND_KEY_CLICK: f64 = 0.3; // Key click level (0.0 to 1.0)
+
+/// FM
+pub static FM_MOD_INDEX: f64 = 3.5; // Modulation index of the two-operator FM
+pub static FM_MOD_RATIO: f64 = 1.414; // Modulator to carrier frequency ratio
+
+/// WAVETABLE
+pub static WAVETABLE_POSITION: f64 = 0.0; // Morph position between the first (0.0) and the last (1.0) frame
 
 /// ADSR
 pub static ADSR_ATTACK: f64 = 0.1; // Attack time in seconds
@@ -41,6 +77,25 @@ pub static ADSR_DECAY: f64 = 0.3; // Decay time in seconds
 pub static ADSR_SUSTAIN: f64 = 0.6; // Sustain level (0.0 to 1.0)
 pub static ADSR_RELEASE: f64 = 1.0; // Release time in seconds
 
+/// VELOCITY
+pub static VELOCITY_AMPLITUDE: f64 = 1.0; // Velocity sensitivity of the amplitude (0.0 to 1.0)
+pub static VELOCITY_CUTOFF: f64 = 0.0; // Octaves of cutoff removed at zero velocity
+pub static VELOCITY_ENVELOPE: f64 = 0.0; // Velocity sensitivity of the attack and decay times (0.0 to 1.0)
+pub static KEYBOARD_VELOCITY: f64 = 0.8; // Fixed velocity of the computer keyboard
+
+/// VOICES
+pub static CURRENT_MAX_VOICES: usize = 32; // Polyphony limit (1 to MAX_VOICES)
+
+/// PITCH
+pub static PITCH_BEND_RANGE: f64 = 2.0; // Default pitch bend range in semitones (up and down)
+pub static VIBRATO_DEPTH: f64 = 0.0; // Semitones of vibrato at the peak of the LFO
+pub static GLIDE_TIME: f64 = 0.1; // Seconds to slide to a new note when the glide is on
+
+/// UNISON
+pub static CURRENT_UNISON_VOICES: usize = 1; // Oscillators stacked on each note (1 to MAX_UNISON)
+pub static CURRENT_UNISON_DETUNE: f64 = 15.0; // Detune of the outer oscillators in cents
+pub static CURRENT_UNISON_SPREAD: f64 = 0.5; // Stereo width of the stack (0.0 to 1.0)
+
 /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 /// ACTIVATION EFFECT
 pub static ACTIVATION_GAIN: bool = true; // true to activate the gain effect
@@ -48,10 +103,18 @@ pub static ACTIVATION_LFO: bool = true; // true to activate the LFO effect
 pub static ACTIVATION_COMPRESSOR: bool = true; // true to activate the compressor effect
 pub static ACTIVATION_NOISE: bool = true; // true to activate the noise effect
 pub static ACTIVATION_FILTER: bool = true; // true to activate the filter effect
+pub static ACTIVATION_DISTORTION: bool = false; // true to activate the distortion effect
 pub static ACTIVATION_REVERB: bool = true; // true to activate the reverb effect
+pub static ACTIVATION_LESLIE: bool = false; // true to activate the rotary speaker (always on for the Hammond)
+pub static ACTIVATION_DELAY: bool = false; // true to activate the delay effect
+pub static ACTIVATION_CHORUS: bool = false; // true to activate the chorus effect
+pub static ACTIVATION_FLANGER: bool = false; // true to activate the flanger effect
+pub static ACTIVATION_PHASER: bool = false; // true to activate the phaser effect
+pub static ACTIVATION_LIMITER: bool = true; // true to activate the master limiter
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////
 // GAIN
 pub static CURRENT_GAIN: f64 = 0.0; // Current gain value in dB (0 dB = unity gain)
+pub static CURRENT_PAN: f64 = 0.0; // Balance between left (-1) and right (1)
 // LFO
 pub static CURRENT_LFO_FREQ: f64 = 725.0; // Current LFO freq
 pub static CURRENT_LFO_WAVEFORM: LfoWaveform = LfoWaveform::Sine; // Current LFO waveform
@@ -61,14 +124,69 @@ pub static CURRENT_RATIO: f64 = 4.0; // ratio très doux : 1.2:1 - compression t
 pub static CURRENT_ATTACK: f64 = 1.01; // attaque encore plus lente : 200 ms
 pub static CURRENT_RELEASE: f64 = 0.2; // release très lente : 1 seconde
 pub static CURRENT_MAKEUP_GAIN: f64 = 0.0; // gain neutre
+pub static CURRENT_KNEE: f64 = 0.0; // en dB : coude franc
+pub static CURRENT_LOOKAHEAD_MS: f64 = 0.0; // pas de retard du signal
 // NOISE
 pub static CURRENT_NOISE: f64 = 0.0; // niveau de bruit à ajouter
+pub static CURRENT_NOISE_COLOR: NoiseColor = NoiseColor::White; // couleur du bruit ajouté et de l'oscillateur de bruit
 // LOW PASS
 pub static CURRENT_FILTER_CUTOFF: f64 = 8000.0; // fréquence de coupure en Hz (était 100Hz - trop bas!)
 pub static CURRENT_FILTER_RESONANCE: f64 = 1.2; // résonance (Q)
+pub static CURRENT_FILTER_MODE: FilterMode = FilterMode::LowPass; // Filter response
+pub static CURRENT_FILTER_SLOPE: FilterSlope = FilterSlope::Db12; // 12 or 24 dB per octave
+pub static CURRENT_FILTER_GAIN: f64 = 6.0; // Boost of the peak and shelving modes in dB
+pub static FILTER_ENV_ATTACK: f64 = 0.01; // Filter envelope attack in seconds
+pub static FILTER_ENV_DECAY: f64 = 0.4; // Filter envelope decay in seconds
+pub static FILTER_ENV_SUSTAIN: f64 = 0.3; // Filter envelope sustain level (0.0 to 1.0)
+pub static FILTER_ENV_RELEASE: f64 = 0.5; // Filter envelope release in seconds
+pub static FILTER_ENV_AMOUNT: f64 = 0.0; // Octaves added to the cutoff at the envelope peak (negative closes it)
+pub static FILTER_KEY_TRACKING: f64 = 0.0; // 1 = the cutoff follows the note exactly
+// DISTORTION
+pub static CURRENT_DISTORTION_CURVE: DistortionCurve = DistortionCurve::Tanh; // Courbe du waveshaper
+pub static CURRENT_DISTORTION_DRIVE: f64 = 12.0; // Gain avant la courbe en dB
+pub static CURRENT_DISTORTION_LOW_CUT: f64 = 40.0; // Passe-haut avant la courbe en Hz
+pub static CURRENT_DISTORTION_HIGH_CUT: f64 = 12000.0; // Passe-bas après la courbe en Hz
+pub static CURRENT_DISTORTION_OUTPUT: f64 = -6.0; // Niveau de sortie en dB
+// MODULATION MATRIX
+pub static MOD_LFO_FREQUENCIES: [f64; 2] = [5.0, 0.5]; // Rates of the two modulation LFOs in Hz
+pub static MOD_LFO_WAVEFORM: LfoWaveform = LfoWaveform::Sine; // Waveform of the modulation LFOs
+pub static MOD_ENV_ATTACK: f64 = 0.01; // Modulation envelope attack in seconds
+pub static MOD_ENV_DECAY: f64 = 0.5; // Modulation envelope decay in seconds
+pub static MOD_ENV_SUSTAIN: f64 = 0.0; // Modulation envelope sustain level (0.0 to 1.0)
+pub static MOD_ENV_RELEASE: f64 = 0.3; // Modulation envelope release in seconds
 // REVERB
 pub static CURRENT_DRY_WET: f64 = 0.7; // niveau de réverbération à ajouter
 pub static CURRENT_REVERB_TYPE: ReverbType = ReverbType::Plate; // Type de réverbération par défaut
 pub static CURRENT_REVERB_EARLY_GAIN: f64 = 0.9; // Gain des premières réflexions
 pub static CURRENT_REVERB_TAIL_GAIN: f64 = 0.9; // Gain de la queue
 pub static CURRENT_REVERB_PRE_DELAY_MS: f64 = 100.0; // Pré-délai en millisecondes
+// LESLIE
+pub static CURRENT_LESLIE_SPEED: LeslieSpeed = LeslieSpeed::Slow; // Vitesse des rotors au démarrage
+// DELAY
+pub static CURRENT_DELAY_TIME_MS: f64 = 375.0; // Temps entre deux échos en millisecondes
+pub static CURRENT_DELAY_FEEDBACK: f64 = 0.4; // Part de l'écho réinjectée
+pub static CURRENT_DELAY_LOW_CUT: f64 = 150.0; // Passe-haut des répétitions en Hz
+pub static CURRENT_DELAY_HIGH_CUT: f64 = 5000.0; // Passe-bas des répétitions en Hz
+pub static CURRENT_DELAY_DRY_WET: f64 = 0.3; // niveau des échos
+// LIMITER
+pub static CURRENT_LIMITER_CEILING_DB: f64 = -0.3; // crête maximale en sortie, sous le 0 dBFS
+pub static CURRENT_LIMITER_RELEASE_MS: f64 = 50.0; // remontée du gain après un pic
+// CHORUS, FLANGER, PHASER
+pub static CURRENT_CHORUS: ModEffectSettings = ModEffectSettings {
+    rate: 0.8, // balayage lent, léger désaccord
+    depth: 0.5,
+    feedback: 0.0,
+    mix: 0.5,
+};
+pub static CURRENT_FLANGER: ModEffectSettings = ModEffectSettings {
+    rate: 0.2,
+    depth: 0.7,
+    feedback: 0.6, // peigne marqué
+    mix: 0.5,
+};
+pub static CURRENT_PHASER: ModEffectSettings = ModEffectSettings {
+    rate: 0.4,
+    depth: 0.6,
+    feedback: 0.3,
+    mix: 0.5, // creux les plus profonds à mi-chemin
+};
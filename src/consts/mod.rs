@@ -1 +1 @@
-pub mod constants;
\ No newline at end of file
+pub mod constants;
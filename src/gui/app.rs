@@ -1,26 +1,52 @@
-use crate::audio::note_manager;
+use crate::audio::controller::SynthController;
+use crate::audio::voice_allocator::{RetriggerMode, StealPolicy, VoiceMode, VoiceSettings};
 use crate::consts::constants::{
-    self, BLACK_KEYS, KNOB_GAIN_COLOR, KNOB_NOISE_COLOR, USED_KEYS, WHITE_KEYS, KNOB_ADSR_A_COLOR,
-    KNOB_ADSR_D_COLOR, KNOB_ADSR_S_COLOR, KNOB_ADSR_R_COLOR
+    self, BLACK_KEYS, KNOB_ADSR_A_COLOR, KNOB_ADSR_D_COLOR, KNOB_ADSR_R_COLOR, KNOB_ADSR_S_COLOR,
+    KNOB_GAIN_COLOR, KNOB_NOISE_COLOR, USED_KEYS, WHITE_KEYS,
 };
 use crate::input::key_handlers::NOTES;
+use crate::presets::{Preset, PresetBank};
 use crate::synths::manager::SynthType;
+use crate::synths::modulation::{MOD_LFOS, MOD_ROUTES, ModDestination, ModMatrix, ModSource};
+use crate::synths::modules::compressor::{
+    CompressorDetector, CompressorSettings, MAX_LOOKAHEAD_MS,
+};
+use crate::synths::modules::delay::{DelaySettings, MAX_DELAY_FEEDBACK, MAX_DELAY_MS};
+use crate::synths::modules::distortion::{
+    DistortionCurve, DistortionSettings, MAX_DRIVE_DB, Oversampling,
+};
+use crate::synths::modules::filter::{
+    FilterMode, FilterModulation, FilterSlope, MAX_FILTER_ENV_AMOUNT,
+};
+use crate::synths::modules::leslie::LeslieSpeed;
 use crate::synths::modules::lfo::LfoWaveform;
+use crate::synths::modules::limiter::{LimiterSettings, MIN_CEILING_DB};
+use crate::synths::modules::modulated::{ModEffectKind, ModEffectSettings};
+use crate::synths::modules::noise::NoiseColor;
 use crate::synths::modules::reverb::ReverbType;
+use crate::synths::oscillators::hammond::{
+    DRAWBAR_NAMES, HammondRegistration, MAX_DRAWBAR, PercussionDecay, PercussionHarmonic,
+};
+use crate::synths::oscillators::multi_fm::{ALGORITHM_COUNT, FmPatch, OPERATOR_COUNT};
+use crate::synths::oscillators::wavetable::{Wavetable, WavetableSettings, WavetableSource};
+use crate::synths::param::Param;
+use crate::synths::pitch::{GlideMode, PitchSettings};
+use crate::synths::tempo::{NoteFeel, NoteLength};
+use crate::synths::unison::UnisonSettings;
+use crate::synths::velocity::VelocitySettings;
 use eframe::egui;
 use egui::RichText;
 use egui_knob::{Knob, KnobStyle, LabelPosition};
 use std::collections::HashSet;
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex};
 
 pub struct SynthesizerApp {
     // État du synthétiseur
     current_synth_type: SynthType,
+    band_limited: bool, // anti-aliasing des oscillateurs carré et dent de scie
 
     // Interface audio
-    notes: Option<note_manager::ActiveNoteManager>,
-    synth_control: Option<Arc<Mutex<SynthType>>>,
+    controller: Option<SynthController>,
 
     // Suivi des notes actuellement pressées
     pressed_notes: HashSet<String>,         // Pour le clavier virtuel
@@ -32,11 +58,14 @@ pub struct SynthesizerApp {
     noise_activation: bool,
     noise: f64,
     noise_knob: f32,
+    noise_color: NoiseColor,
+    noise_source_color: NoiseColor, // couleur de l'oscillateur de bruit
 
     // GAIN
     gain_activation: bool,
     gain: f64,
     gain_knob: f32,
+    pan: f64,
 
     // ADSR
     attack: f64,
@@ -48,18 +77,47 @@ pub struct SynthesizerApp {
     release: f64,
     release_knob: f32,
 
+    // VELOCITY
+    velocity: VelocitySettings,
+
+    // VOICES
+    voices: VoiceSettings,
+
+    // UNISON
+    unison: UnisonSettings,
+    pitch: PitchSettings,
+    pitch_bend: f64, // molette de pitch bend à la souris, revient au centre au relâchement
+
+    // HAMMOND
+    hammond: HammondRegistration,
+
+    // FM
+    fm_mod_index: f64,
+    fm_mod_ratio: f64,
+    fm_patch: FmPatch,
+
+    // WAVETABLE
+    wavetable_source: WavetableSource,
+    wavetable: WavetableSettings,
+    wavetable_status: String, // erreur de chargement de la dernière table
+
     // FILTER
     filter_activation: bool,
     cutoff: f64,
     resonance: f64,
+    filter_mode: FilterMode,
+    filter_slope: FilterSlope,
+    filter_gain: f64,
+    filter_modulation: FilterModulation,
+
+    // MODULATION MATRIX
+    mod_matrix: ModMatrix,
+    mod_wheel: f64, // molette à la souris, pour jouer sans contrôleur MIDI
+    tempo: f64,     // noires par minute
 
     // COMPRESSOR
     compressor_activation: bool,
-    threshold: f64,
-    ratio: f64,
-    attack_comp: f64,
-    release_comp: f64,
-    make_up_gain: f64,
+    compressor: CompressorSettings,
 
     // LFO
     lfo_activation: bool,
@@ -74,9 +132,37 @@ pub struct SynthesizerApp {
     tail_gain: f64,
     predelay: f64,
 
+    // LESLIE
+    leslie_activation: bool,
+    leslie_speed: LeslieSpeed,
+
+    // DISTORTION
+    distortion_activation: bool,
+    distortion: DistortionSettings,
+
+    // DELAY
+    delay_activation: bool,
+    delay: DelaySettings,
+
+    // LIMITER
+    limiter_activation: bool,
+    limiter: LimiterSettings,
+    clipped_samples: u64, // dernier compte lu, pour repérer les nouveaux clips
+    clip_until: f64,      // instant (s) jusqu'auquel le voyant CLIP reste allumé
+
+    // CHORUS, FLANGER, PHASER (dans l'ordre de ModEffectKind::ALL)
+    mod_effect_activation: [bool; 3],
+    mod_effects: [ModEffectSettings; 3],
+
     // OCTAVE
     current_octave: usize,
 
+    // PRESETS
+    preset_bank: PresetBank,
+    preset_name: String,     // nom utilisé pour sauvegarder
+    preset_new_name: String, // nouveau nom pour renommer
+    preset_status: String,   // dernier message affiché
+
     show_keyboard: bool,
     show_effects: bool,
 
@@ -84,10 +170,24 @@ pub struct SynthesizerApp {
     expanded_noise: bool,
     expanded_gain: bool,
     expanded_adsr: bool,
+    expanded_velocity: bool,
+    expanded_voices: bool,
+    expanded_unison: bool,
+    expanded_pitch: bool,
     expanded_filter: bool,
+    expanded_modulation: bool,
     expanded_compressor: bool,
     expanded_lfo: bool,
     expanded_reverb: bool,
+    expanded_hammond: bool,
+    expanded_fm: bool,
+    expanded_wavetable: bool,
+    expanded_noise_source: bool,
+    expanded_leslie: bool,
+    expanded_delay: bool,
+    expanded_limiter: bool,
+    expanded_distortion: bool,
+    expanded_mod_effects: bool,
 }
 
 impl SynthesizerApp {
@@ -96,8 +196,8 @@ impl SynthesizerApp {
         configure_fonts(&cc.egui_ctx);
         Self {
             current_synth_type: SynthType::n_sine(),
-            notes: None,
-            synth_control: None,
+            band_limited: constants::BAND_LIMITED_OSCILLATORS,
+            controller: None,
             pressed_notes: HashSet::new(),
             pressed_physical_keys: HashSet::new(),
             active_notes: HashSet::new(),
@@ -105,10 +205,13 @@ impl SynthesizerApp {
             noise_activation: constants::ACTIVATION_NOISE,
             noise: constants::CURRENT_NOISE,
             noise_knob: constants::CURRENT_NOISE as f32,
+            noise_color: constants::CURRENT_NOISE_COLOR,
+            noise_source_color: constants::CURRENT_NOISE_COLOR,
 
             gain_activation: constants::ACTIVATION_GAIN,
             gain: constants::CURRENT_GAIN,
             gain_knob: constants::CURRENT_GAIN as f32,
+            pan: constants::CURRENT_PAN,
 
             attack: constants::ADSR_ATTACK,
             attack_knob: constants::ADSR_ATTACK as f32,
@@ -119,6 +222,24 @@ impl SynthesizerApp {
             release: constants::ADSR_RELEASE,
             release_knob: constants::ADSR_RELEASE as f32,
 
+            velocity: VelocitySettings::default(),
+
+            voices: VoiceSettings::default(),
+
+            unison: UnisonSettings::default(),
+            pitch: PitchSettings::default(),
+            pitch_bend: 0.0,
+
+            hammond: HammondRegistration::default(),
+
+            fm_mod_index: constants::FM_MOD_INDEX,
+            fm_mod_ratio: constants::FM_MOD_RATIO,
+            fm_patch: FmPatch::default(),
+
+            wavetable_source: WavetableSource::Basic,
+            wavetable: WavetableSettings::default(),
+            wavetable_status: String::new(),
+
             lfo_activation: constants::ACTIVATION_LFO,
             freq: constants::CURRENT_LFO_FREQ,
             waveform: constants::CURRENT_LFO_WAVEFORM,
@@ -130,21 +251,43 @@ impl SynthesizerApp {
             tail_gain: constants::CURRENT_REVERB_TAIL_GAIN,
             predelay: constants::CURRENT_REVERB_PRE_DELAY_MS,
 
+            leslie_activation: constants::ACTIVATION_LESLIE,
+            leslie_speed: constants::CURRENT_LESLIE_SPEED,
+            distortion_activation: constants::ACTIVATION_DISTORTION,
+            distortion: DistortionSettings::default(),
+            delay_activation: constants::ACTIVATION_DELAY,
+            delay: DelaySettings::default(),
+            limiter_activation: constants::ACTIVATION_LIMITER,
+            limiter: LimiterSettings::default(),
+            clipped_samples: 0,
+            clip_until: 0.0,
+            mod_effect_activation: ModEffectKind::ALL.map(|kind| kind.is_active_by_default()),
+            mod_effects: ModEffectKind::ALL.map(|kind| kind.default_settings()),
+
             filter_activation: constants::ACTIVATION_FILTER,
             cutoff: constants::CURRENT_FILTER_CUTOFF,
             resonance: constants::CURRENT_FILTER_RESONANCE,
+            filter_mode: constants::CURRENT_FILTER_MODE,
+            filter_slope: constants::CURRENT_FILTER_SLOPE,
+            filter_gain: constants::CURRENT_FILTER_GAIN,
+            filter_modulation: FilterModulation::default(),
+
+            mod_matrix: ModMatrix::default(),
+            mod_wheel: 0.0,
+            tempo: constants::DEFAULT_TEMPO,
 
             compressor_activation: constants::ACTIVATION_COMPRESSOR,
-            threshold: constants::CURRENT_THRESHOLD,
-            ratio: constants::CURRENT_RATIO,
-            attack_comp: constants::CURRENT_ATTACK,
-            release_comp: constants::CURRENT_RELEASE,
-            make_up_gain: constants::CURRENT_MAKEUP_GAIN,
+            compressor: CompressorSettings::default(),
 
             current_octave: constants::VECTEUR_NOTES
                 [constants::CURRENT_OCTAVE_INDEX.load(Ordering::Relaxed)]
                 as usize,
 
+            preset_bank: PresetBank::default(),
+            preset_name: String::new(),
+            preset_new_name: String::new(),
+            preset_status: String::new(),
+
             show_keyboard: true,
             show_effects: true,
 
@@ -152,20 +295,40 @@ impl SynthesizerApp {
             expanded_noise: true,
             expanded_gain: true,
             expanded_adsr: true,
+            expanded_velocity: true,
+            expanded_voices: true,
+            expanded_unison: true,
+            expanded_pitch: true,
             expanded_filter: true,
+            expanded_modulation: true,
             expanded_compressor: true,
             expanded_lfo: true,
             expanded_reverb: true,
+            expanded_hammond: true,
+            expanded_fm: true,
+            expanded_wavetable: true,
+            expanded_noise_source: true,
+            expanded_leslie: true,
+            expanded_delay: true,
+            expanded_limiter: true,
+            expanded_distortion: true,
+            expanded_mod_effects: true,
         }
     }
 
-    pub fn with_audio(mut self, notes: note_manager::ActiveNoteManager) -> Self {
-        self.notes = Some(notes);
-        self
-    }
-
-    pub fn with_synth_control(mut self, synth_control: Arc<Mutex<SynthType>>) -> Self {
-        self.synth_control = Some(synth_control);
+    pub fn with_controller(mut self, controller: SynthController) -> Self {
+        self.current_synth_type = controller.get_synth();
+        let envelope = controller.get_envelope();
+        self.velocity = controller.get_velocity_settings();
+        self.voices = controller.get_voice_settings();
+        self.controller = Some(controller);
+        self.sync_values_from_synth();
+        self.sync_envelope(
+            envelope.get_attack(),
+            envelope.get_decay(),
+            envelope.get_sustain(),
+            envelope.get_release(),
+        );
         self
     }
 }
@@ -174,6 +337,10 @@ impl eframe::App for SynthesizerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Pour que la fenêtre recoive les événements clavier
         ctx.request_repaint();
+        // Libérer les notes terminées par le thread audio
+        if let Some(ref controller) = self.controller {
+            controller.collect_garbage();
+        }
         // Gérer les événements clavier (comme dans le terminal)
         self.handle_keyboard_input(ctx);
 
@@ -196,6 +363,19 @@ impl eframe::App for SynthesizerApp {
                     if ui.button("Hammond").clicked() {
                         self.change_synth_type_preserving_params(SynthType::n_hammond());
                     }
+                    if ui.button("Multi FM").clicked() {
+                        self.change_synth_type_preserving_params(SynthType::n_multi_fm());
+                    }
+                    if ui.button("Wavetable").clicked() {
+                        self.change_synth_type_preserving_params(SynthType::n_wavetable());
+                    }
+                    if ui.button("Noise").clicked() {
+                        self.change_synth_type_preserving_params(SynthType::n_noise());
+                    }
+                });
+
+                ui.menu_button("Presets", |ui| {
+                    self.draw_preset_menu(ui);
                 });
 
                 ui.menu_button("Affichage", |ui| {
@@ -223,6 +403,13 @@ impl eframe::App for SynthesizerApp {
                         .show(ui, |ui| {
                             ui.heading("Contrôles");
                             ui.separator();
+                            if self.current_synth_type.get_band_limited().is_some()
+                                && ui
+                                    .checkbox(&mut self.band_limited, "Anti-aliasing (PolyBLEP)")
+                                    .changed()
+                            {
+                                self.send_param(Param::BandLimited(self.band_limited));
+                            }
                             ui.horizontal(|ui| {
                                 let expand_icon = if self.expanded_adsr { "v" } else { ">" };
                                 if ui.button(format!("{} ⏱ ADSR", expand_icon)).clicked() {
@@ -350,283 +537,991 @@ impl eframe::App for SynthesizerApp {
                             }
                             ui.separator();
 
-                            // Noise
+                            // Velocity
                             ui.horizontal(|ui| {
-                                // Bouton d'expansion avec icône
-                                let expand_icon = if self.expanded_noise { "v" } else { ">" };
-                                if ui.button(format!("{} 📻 Noise", expand_icon)).clicked() {
-                                    self.expanded_noise = !self.expanded_noise;
-                                }
-                                ui.add_space(10.0);
-                                if ui.checkbox(&mut self.noise_activation, "ON").changed() {
-                                    self.update_noise_activation();
+                                let expand_icon = if self.expanded_velocity { "v" } else { ">" };
+                                if ui.button(format!("{} 🎚 Vélocité", expand_icon)).clicked() {
+                                    self.expanded_velocity = !self.expanded_velocity;
                                 }
                             });
-                            ui.add_space(10.0);
-                            if self.expanded_noise {
+
+                            if self.expanded_velocity {
                                 ui.horizontal(|ui| {
-                                    self.noise_knob = self.noise as f32;
-                                    ui.label("Noise");
+                                    ui.label("Amplitude:");
+                                    if ui
+                                        .add(egui::Slider::new(
+                                            &mut self.velocity.amplitude,
+                                            0.0..=1.0,
+                                        ))
+                                        .changed()
+                                    {
+                                        self.send_param(Param::VelocityAmplitude(
+                                            self.velocity.amplitude,
+                                        ));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Cutoff:");
                                     if ui
                                         .add(
-                                            Knob::new(
-                                                &mut self.noise_knob,
-                                                0.0_f32,
-                                                1.0_f32,
-                                                KnobStyle::Wiper,
-                                            )
-                                            .with_size(50.0)
-                                            .with_font_size(20.0)
-                                            .with_stroke_width(3.0)
-                                            .with_colors(
-                                                KNOB_NOISE_COLOR.0,
-                                                KNOB_NOISE_COLOR.1,
-                                                KNOB_NOISE_COLOR.2,
-                                            )
-                                            .with_label("", LabelPosition::Bottom),
+                                            egui::Slider::new(&mut self.velocity.cutoff, 0.0..=8.0)
+                                                .text("octaves"),
                                         )
                                         .changed()
                                     {
-                                        self.noise = self.noise_knob as f64;
-                                        self.update_synth_noise();
+                                        self.send_param(Param::VelocityCutoff(
+                                            self.velocity.cutoff,
+                                        ));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Enveloppe:");
+                                    if ui
+                                        .add(egui::Slider::new(
+                                            &mut self.velocity.envelope,
+                                            0.0..=1.0,
+                                        ))
+                                        .changed()
+                                    {
+                                        self.send_param(Param::VelocityEnvelope(
+                                            self.velocity.envelope,
+                                        ));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Clavier:");
+                                    if ui
+                                        .add(egui::Slider::new(
+                                            &mut self.velocity.keyboard_velocity,
+                                            0.0..=1.0,
+                                        ))
+                                        .changed()
+                                    {
+                                        self.send_param(Param::KeyboardVelocity(
+                                            self.velocity.keyboard_velocity,
+                                        ));
                                     }
                                 });
-                                ui.add_space(5.0);
                             }
                             ui.separator();
+
+                            // Voices
                             ui.horizontal(|ui| {
-                                let expand_icon = if self.expanded_lfo { "v" } else { ">" };
-                                if ui.button(format!("{} 🔄 LFO", expand_icon)).clicked() {
-                                    self.expanded_lfo = !self.expanded_lfo;
-                                }
-                                ui.add_space(10.0);
-                                if ui.checkbox(&mut self.lfo_activation, "ON").changed() {
-                                    self.update_lfo_activation();
+                                let expand_icon = if self.expanded_voices { "v" } else { ">" };
+                                if ui.button(format!("{} 🎼 Voix", expand_icon)).clicked() {
+                                    self.expanded_voices = !self.expanded_voices;
                                 }
                             });
 
-                            if self.expanded_lfo {
+                            if self.expanded_voices {
                                 ui.horizontal(|ui| {
-                                    ui.label("Fréquence:");
+                                    ui.label("Mode:");
+                                    let old_mode = self.voices.mode;
+                                    egui::ComboBox::from_id_salt("voice_mode")
+                                        .selected_text(format!("{:?}", self.voices.mode))
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut self.voices.mode,
+                                                VoiceMode::Poly,
+                                                "Poly",
+                                            );
+                                            ui.selectable_value(
+                                                &mut self.voices.mode,
+                                                VoiceMode::Mono,
+                                                "Mono",
+                                            );
+                                            ui.selectable_value(
+                                                &mut self.voices.mode,
+                                                VoiceMode::Legato,
+                                                "Legato",
+                                            );
+                                        });
+                                    if old_mode != self.voices.mode {
+                                        self.send_param(Param::VoiceMode(self.voices.mode));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Polyphonie:");
                                     if ui
-                                        .add(egui::Slider::new(&mut self.freq, 0.01..=1000.0))
+                                        .add(egui::Slider::new(
+                                            &mut self.voices.max_voices,
+                                            1..=constants::MAX_VOICES,
+                                        ))
                                         .changed()
                                     {
-                                        self.update_synth_lfo();
-                                    };
+                                        self.send_param(Param::MaxVoices(self.voices.max_voices));
+                                    }
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label("Forme d'onde:");
-                                    let old_waveform = self.waveform;
-                                    egui::ComboBox::from_id_salt("lfo_waveform")
-                                        .selected_text(format!("{:?}", self.waveform))
+                                    ui.label("Vol de voix:");
+                                    let old_policy = self.voices.steal_policy;
+                                    egui::ComboBox::from_id_salt("steal_policy")
+                                        .selected_text(format!("{:?}", self.voices.steal_policy))
                                         .show_ui(ui, |ui| {
                                             ui.selectable_value(
-                                                &mut self.waveform,
-                                                LfoWaveform::Sine,
-                                                "Sine",
-                                            );
-                                            ui.selectable_value(
-                                                &mut self.waveform,
-                                                LfoWaveform::Triangle,
-                                                "Triangle",
+                                                &mut self.voices.steal_policy,
+                                                StealPolicy::Oldest,
+                                                "Oldest",
                                             );
                                             ui.selectable_value(
-                                                &mut self.waveform,
-                                                LfoWaveform::Square,
-                                                "Square",
+                                                &mut self.voices.steal_policy,
+                                                StealPolicy::Quietest,
+                                                "Quietest",
                                             );
+                                        });
+                                    if old_policy != self.voices.steal_policy {
+                                        self.send_param(Param::StealPolicy(
+                                            self.voices.steal_policy,
+                                        ));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Note rejouée:");
+                                    let old_retrigger = self.voices.retrigger;
+                                    egui::ComboBox::from_id_salt("retrigger_mode")
+                                        .selected_text(format!("{:?}", self.voices.retrigger))
+                                        .show_ui(ui, |ui| {
                                             ui.selectable_value(
-                                                &mut self.waveform,
-                                                LfoWaveform::SawUp,
-                                                "Sawtooth Up",
+                                                &mut self.voices.retrigger,
+                                                RetriggerMode::SameVoice,
+                                                "SameVoice",
                                             );
                                             ui.selectable_value(
-                                                &mut self.waveform,
-                                                LfoWaveform::SawDown,
-                                                "Sawtooth Down",
+                                                &mut self.voices.retrigger,
+                                                RetriggerMode::NewVoice,
+                                                "NewVoice",
                                             );
                                         });
-                                    if old_waveform != self.waveform {
-                                        self.update_synth_lfo_waveform();
+                                    if old_retrigger != self.voices.retrigger {
+                                        self.send_param(Param::Retrigger(self.voices.retrigger));
                                     }
                                 });
                             }
-
                             ui.separator();
-                            // Filter
+
+                            // Unison
                             ui.horizontal(|ui| {
-                                let expand_icon = if self.expanded_filter { "v" } else { ">" };
-                                if ui.button(format!("{} ⬇ Filter", expand_icon)).clicked() {
-                                    self.expanded_filter = !self.expanded_filter;
-                                }
-                                ui.add_space(10.0);
-                                if ui.checkbox(&mut self.filter_activation, "ON").changed() {
-                                    self.update_filter_activation();
+                                let expand_icon = if self.expanded_unison { "v" } else { ">" };
+                                if ui.button(format!("{} 👥 Unisson", expand_icon)).clicked() {
+                                    self.expanded_unison = !self.expanded_unison;
                                 }
                             });
 
-                            if self.expanded_filter {
+                            if self.expanded_unison {
                                 ui.horizontal(|ui| {
-                                    ui.label("Cutoff:");
+                                    ui.label("Oscillateurs:");
                                     if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.cutoff, 20.0..=20000.0)
-                                                .text("Hz"),
-                                        )
+                                        .add(egui::Slider::new(
+                                            &mut self.unison.voices,
+                                            1..=constants::MAX_UNISON,
+                                        ))
                                         .changed()
                                     {
-                                        self.update_synth_cutoff();
+                                        self.send_param(Param::UnisonVoices(self.unison.voices));
                                     }
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label("Resonance:");
+                                    ui.label("Désaccord:");
                                     if ui
                                         .add(
-                                            egui::Slider::new(&mut self.resonance, 0.0..=20.0)
-                                                .text("Q Factor"),
+                                            egui::Slider::new(&mut self.unison.detune, 0.0..=100.0)
+                                                .suffix(" ct"),
                                         )
                                         .changed()
                                     {
-                                        self.update_synth_resonance();
+                                        self.send_param(Param::UnisonDetune(self.unison.detune));
                                     }
                                 });
-                            }
-
-                            ui.separator();
-
-                            // Gain général
-                            ui.horizontal(|ui| {
-                                let expand_icon = if self.expanded_gain { "v" } else { ">" };
-                                if ui.button(format!("{} 🔊 Gain", expand_icon)).clicked() {
-                                    self.expanded_gain = !self.expanded_gain;
-                                }
-                                ui.add_space(10.0);
-                                if ui.checkbox(&mut self.gain_activation, "ON").changed() {
-                                    self.update_gain_activation();
-                                }
-                            });
-                            ui.add_space(10.0);
-
-                            if self.expanded_gain {
                                 ui.horizontal(|ui| {
-                                    // Synchroniser la valeur f32 avec la valeur f64
-                                    self.gain_knob = self.gain as f32;
-                                    ui.label("Gain:");
+                                    ui.label("Largeur stéréo:");
                                     if ui
-                                        .add(
-                                            Knob::new(
-                                                &mut self.gain_knob,
-                                                -12.0_f32,
-                                                6.0_f32,
-                                                KnobStyle::Wiper,
-                                            )
-                                            .with_size(50.0)
-                                            .with_font_size(20.0)
-                                            .with_stroke_width(3.0)
-                                            .with_colors(
-                                                KNOB_GAIN_COLOR.0,
-                                                KNOB_GAIN_COLOR.1,
-                                                KNOB_GAIN_COLOR.2,
-                                            )
-                                            .with_label("", LabelPosition::Bottom),
-                                        )
+                                        .add(egui::Slider::new(&mut self.unison.spread, 0.0..=1.0))
                                         .changed()
                                     {
-                                        // Changement en f64
-                                        self.gain = self.gain_knob as f64;
-                                        self.update_synth_gain();
+                                        self.send_param(Param::UnisonSpread(self.unison.spread));
                                     }
                                 });
-                                ui.add_space(5.0);
                             }
                             ui.separator();
-                            // Compressor
+
+                            // Hauteur : pitch bend, vibrato et glide
                             ui.horizontal(|ui| {
-                                let expand_icon = if self.expanded_compressor { "v" } else { ">" };
-                                if ui
-                                    .button(format!("{} 🤏 Compressor", expand_icon))
-                                    .clicked()
-                                {
-                                    self.expanded_compressor = !self.expanded_compressor;
-                                }
-                                ui.add_space(10.0);
-                                if ui.checkbox(&mut self.compressor_activation, "ON").changed() {
-                                    self.update_compressor_activation();
+                                let expand_icon = if self.expanded_pitch { "v" } else { ">" };
+                                if ui.button(format!("{} 🎚 Hauteur", expand_icon)).clicked() {
+                                    self.expanded_pitch = !self.expanded_pitch;
                                 }
                             });
 
-                            if self.expanded_compressor {
+                            if self.expanded_pitch {
+                                self.draw_pitch_settings(ui);
+                            }
+                            ui.separator();
+
+                            // Hammond
+                            if self.current_synth_type.get_hammond_registration().is_some() {
                                 ui.horizontal(|ui| {
-                                    ui.label("Threshold:");
-                                    if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.threshold, -50.0..=0.0)
-                                                .text("dB"),
-                                        )
-                                        .changed()
+                                    let expand_icon = if self.expanded_hammond { "v" } else { ">" };
+                                    if ui.button(format!("{} 🎹 Tirettes", expand_icon)).clicked()
                                     {
-                                        self.update_synth_threshold();
+                                        self.expanded_hammond = !self.expanded_hammond;
                                     }
                                 });
+
+                                if self.expanded_hammond {
+                                    ui.horizontal(|ui| {
+                                        for (index, name) in DRAWBAR_NAMES.iter().enumerate() {
+                                            ui.vertical(|ui| {
+                                                if ui
+                                                    .add(
+                                                        egui::Slider::new(
+                                                            &mut self.hammond.drawbars[index],
+                                                            0..=MAX_DRAWBAR,
+                                                        )
+                                                        .vertical(),
+                                                    )
+                                                    .changed()
+                                                {
+                                                    self.send_param(Param::Drawbar(
+                                                        index,
+                                                        self.hammond.drawbars[index],
+                                                    ));
+                                                }
+                                                ui.label(*name);
+                                            });
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .checkbox(&mut self.hammond.percussion, "Percussion")
+                                            .changed()
+                                        {
+                                            self.send_param(Param::Percussion(
+                                                self.hammond.percussion,
+                                            ));
+                                        }
+                                        let old_harmonic = self.hammond.percussion_harmonic;
+                                        egui::ComboBox::from_id_salt("percussion_harmonic")
+                                            .selected_text(format!(
+                                                "{:?}",
+                                                self.hammond.percussion_harmonic
+                                            ))
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(
+                                                    &mut self.hammond.percussion_harmonic,
+                                                    PercussionHarmonic::Second,
+                                                    "Second",
+                                                );
+                                                ui.selectable_value(
+                                                    &mut self.hammond.percussion_harmonic,
+                                                    PercussionHarmonic::Third,
+                                                    "Third",
+                                                );
+                                            });
+                                        if old_harmonic != self.hammond.percussion_harmonic {
+                                            self.send_param(Param::PercussionHarmonic(
+                                                self.hammond.percussion_harmonic,
+                                            ));
+                                        }
+                                        let old_decay = self.hammond.percussion_decay;
+                                        egui::ComboBox::from_id_salt("percussion_decay")
+                                            .selected_text(format!(
+                                                "{:?}",
+                                                self.hammond.percussion_decay
+                                            ))
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(
+                                                    &mut self.hammond.percussion_decay,
+                                                    PercussionDecay::Fast,
+                                                    "Fast",
+                                                );
+                                                ui.selectable_value(
+                                                    &mut self.hammond.percussion_decay,
+                                                    PercussionDecay::Slow,
+                                                    "Slow",
+                                                );
+                                            });
+                                        if old_decay != self.hammond.percussion_decay {
+                                            self.send_param(Param::PercussionDecay(
+                                                self.hammond.percussion_decay,
+                                            ));
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Clic de touche:");
+                                        if ui
+                                            .add(egui::Slider::new(
+                                                &mut self.hammond.key_click,
+                                                0.0..=1.0,
+                                            ))
+                                            .changed()
+                                        {
+                                            self.send_param(Param::KeyClick(
+                                                self.hammond.key_click,
+                                            ));
+                                        }
+                                    });
+                                }
+                                ui.separator();
+                            }
+
+                            // FM
+                            let is_fm = self.current_synth_type.get_fm_modulation().is_some();
+                            let is_multi_fm = self.current_synth_type.get_fm_patch().is_some();
+                            if is_fm || is_multi_fm {
                                 ui.horizontal(|ui| {
-                                    ui.label("Ratio:");
-                                    if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.ratio, 1.0..=20.0)
-                                                .text(":1"),
-                                        )
-                                        .changed()
-                                    {
-                                        //TODO: self.update_synth_ratio();
+                                    let expand_icon = if self.expanded_fm { "v" } else { ">" };
+                                    if ui.button(format!("{} 📡 FM", expand_icon)).clicked() {
+                                        self.expanded_fm = !self.expanded_fm;
                                     }
                                 });
+                            }
+
+                            if is_fm && self.expanded_fm {
                                 ui.horizontal(|ui| {
-                                    ui.label("Attack:");
+                                    ui.label("Indice:");
                                     if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.attack_comp, 0.0..=100.0)
-                                                .text("ms"),
-                                        )
+                                        .add(egui::Slider::new(&mut self.fm_mod_index, 0.0..=10.0))
                                         .changed()
                                     {
-                                        //TODO: self.update_synth_attack();
+                                        self.send_param(Param::FmModIndex(self.fm_mod_index));
                                     }
                                 });
-
                                 ui.horizontal(|ui| {
-                                    ui.label("Release:");
+                                    ui.label("Ratio:");
                                     if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.release_comp, 0.0..=600.0)
-                                                .text("ms"),
-                                        )
+                                        .add(egui::Slider::new(&mut self.fm_mod_ratio, 0.25..=8.0))
                                         .changed()
                                     {
-                                        //TODO: self.update_synth_release();
+                                        self.send_param(Param::FmModRatio(self.fm_mod_ratio));
                                     }
                                 });
+                            }
 
+                            if is_multi_fm && self.expanded_fm {
                                 ui.horizontal(|ui| {
-                                    ui.label("Makeup Gain:");
+                                    ui.label("Algorithme:");
                                     if ui
-                                        .add(
-                                            egui::Slider::new(&mut self.make_up_gain, -20.0..=20.0)
-                                                .text("dB"),
-                                        )
+                                        .add(egui::Slider::new(
+                                            &mut self.fm_patch.algorithm,
+                                            1..=ALGORITHM_COUNT,
+                                        ))
                                         .changed()
                                     {
-                                        //TODO: self.update_synth_makeup_gain();
+                                        self.send_param(Param::FmAlgorithm(
+                                            self.fm_patch.algorithm,
+                                        ));
                                     }
                                 });
-                            }
-
-                            ui.separator();
-
-                            // Section Reverb
-                            ui.horizontal(|ui| {
-                                let expand_icon = if self.expanded_reverb { "v" } else { ">" };
-                                if ui.button(format!("{} 🌊 Reverb", expand_icon)).clicked() {
+                                for index in (0..OPERATOR_COUNT).rev() {
+                                    let mut changed = false;
+                                    let operator = &mut self.fm_patch.operators[index];
+                                    ui.label(format!("Opérateur {}", index + 1));
+                                    ui.horizontal(|ui| {
+                                        ui.label("Ratio:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(&mut operator.ratio, 0.5..=16.0))
+                                            .changed();
+                                        ui.label("Désaccord:");
+                                        changed |= ui
+                                            .add(
+                                                egui::Slider::new(
+                                                    &mut operator.detune,
+                                                    -50.0..=50.0,
+                                                )
+                                                .suffix(" ct"),
+                                            )
+                                            .changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Niveau:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(&mut operator.level, 0.0..=1.0))
+                                            .changed();
+                                        ui.label("Feedback:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(
+                                                &mut operator.feedback,
+                                                0.0..=1.0,
+                                            ))
+                                            .changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("A:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(
+                                                &mut operator.attack,
+                                                0.001..=2.0,
+                                            ))
+                                            .changed();
+                                        ui.label("D:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(
+                                                &mut operator.decay,
+                                                0.001..=5.0,
+                                            ))
+                                            .changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("S:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(
+                                                &mut operator.sustain,
+                                                0.0..=1.0,
+                                            ))
+                                            .changed();
+                                        ui.label("R:");
+                                        changed |= ui
+                                            .add(egui::Slider::new(
+                                                &mut operator.release,
+                                                0.001..=5.0,
+                                            ))
+                                            .changed();
+                                    });
+                                    if changed {
+                                        let settings = *operator;
+                                        self.send_param(Param::FmOperator(index, settings));
+                                    }
+                                }
+                            }
+                            if is_fm || is_multi_fm {
+                                ui.separator();
+                            }
+
+                            // Wavetable
+                            if self.current_synth_type.get_wavetable().is_some() {
+                                ui.horizontal(|ui| {
+                                    let expand_icon =
+                                        if self.expanded_wavetable { "v" } else { ">" };
+                                    if ui.button(format!("{} 🌊 Wavetable", expand_icon)).clicked()
+                                    {
+                                        self.expanded_wavetable = !self.expanded_wavetable;
+                                    }
+                                });
+
+                                if self.expanded_wavetable {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Table:");
+                                        let old_source = self.wavetable_source.clone();
+                                        egui::ComboBox::from_id_salt("wavetable_source")
+                                            .selected_text(self.wavetable_source.name())
+                                            .show_ui(ui, |ui| {
+                                                for source in WavetableSource::BUILTIN
+                                                    .into_iter()
+                                                    .chain(user_wavetables())
+                                                {
+                                                    let name = source.name().to_string();
+                                                    ui.selectable_value(
+                                                        &mut self.wavetable_source,
+                                                        source,
+                                                        name,
+                                                    );
+                                                }
+                                            });
+                                        if old_source != self.wavetable_source {
+                                            self.load_wavetable(old_source);
+                                        }
+                                    });
+                                    if !self.wavetable_status.is_empty() {
+                                        ui.label(&self.wavetable_status);
+                                    }
+                                    ui.horizontal(|ui| {
+                                        ui.label("Position:");
+                                        if ui
+                                            .add(egui::Slider::new(
+                                                &mut self.wavetable.position,
+                                                0.0..=1.0,
+                                            ))
+                                            .changed()
+                                        {
+                                            self.send_param(Param::WavetablePosition(
+                                                self.wavetable.position,
+                                            ));
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Balayage:");
+                                        if ui
+                                            .add(
+                                                egui::Slider::new(
+                                                    &mut self.wavetable.scan_rate,
+                                                    0.01..=10.0,
+                                                )
+                                                .logarithmic(true)
+                                                .suffix(" Hz"),
+                                            )
+                                            .changed()
+                                        {
+                                            self.send_param(Param::WavetableScanRate(
+                                                self.wavetable.scan_rate,
+                                            ));
+                                        }
+                                        ui.label("Profondeur:");
+                                        if ui
+                                            .add(egui::Slider::new(
+                                                &mut self.wavetable.scan_depth,
+                                                0.0..=1.0,
+                                            ))
+                                            .changed()
+                                        {
+                                            self.send_param(Param::WavetableScanDepth(
+                                                self.wavetable.scan_depth,
+                                            ));
+                                        }
+                                    });
+                                }
+                                ui.separator();
+                            }
+
+                            // Oscillateur de bruit
+                            if self.current_synth_type.get_noise_source_color().is_some() {
+                                ui.horizontal(|ui| {
+                                    let expand_icon =
+                                        if self.expanded_noise_source { "v" } else { ">" };
+                                    if ui
+                                        .button(format!("{} 🌬 Source de bruit", expand_icon))
+                                        .clicked()
+                                    {
+                                        self.expanded_noise_source = !self.expanded_noise_source;
+                                    }
+                                });
+
+                                if self.expanded_noise_source {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Couleur:");
+                                        let old_color = self.noise_source_color;
+                                        egui::ComboBox::from_id_salt("noise_source_color")
+                                            .selected_text(self.noise_source_color.name())
+                                            .show_ui(ui, |ui| {
+                                                for color in NoiseColor::ALL {
+                                                    ui.selectable_value(
+                                                        &mut self.noise_source_color,
+                                                        color,
+                                                        color.name(),
+                                                    );
+                                                }
+                                            });
+                                        if old_color != self.noise_source_color {
+                                            self.send_param(Param::NoiseSourceColor(
+                                                self.noise_source_color,
+                                            ));
+                                        }
+                                    });
+                                }
+                                ui.separator();
+                            }
+
+                            // Noise
+                            ui.horizontal(|ui| {
+                                // Bouton d'expansion avec icône
+                                let expand_icon = if self.expanded_noise { "v" } else { ">" };
+                                if ui.button(format!("{} 📻 Noise", expand_icon)).clicked() {
+                                    self.expanded_noise = !self.expanded_noise;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.noise_activation, "ON").changed() {
+                                    self.update_noise_activation();
+                                }
+                            });
+                            ui.add_space(10.0);
+                            if self.expanded_noise {
+                                ui.horizontal(|ui| {
+                                    self.noise_knob = self.noise as f32;
+                                    ui.label("Noise");
+                                    if ui
+                                        .add(
+                                            Knob::new(
+                                                &mut self.noise_knob,
+                                                0.0_f32,
+                                                1.0_f32,
+                                                KnobStyle::Wiper,
+                                            )
+                                            .with_size(50.0)
+                                            .with_font_size(20.0)
+                                            .with_stroke_width(3.0)
+                                            .with_colors(
+                                                KNOB_NOISE_COLOR.0,
+                                                KNOB_NOISE_COLOR.1,
+                                                KNOB_NOISE_COLOR.2,
+                                            )
+                                            .with_label("", LabelPosition::Bottom),
+                                        )
+                                        .changed()
+                                    {
+                                        self.noise = self.noise_knob as f64;
+                                        self.update_synth_noise();
+                                    }
+                                    ui.label("Couleur:");
+                                    let old_color = self.noise_color;
+                                    egui::ComboBox::from_id_salt("noise_color")
+                                        .selected_text(self.noise_color.name())
+                                        .show_ui(ui, |ui| {
+                                            for color in NoiseColor::ALL {
+                                                ui.selectable_value(
+                                                    &mut self.noise_color,
+                                                    color,
+                                                    color.name(),
+                                                );
+                                            }
+                                        });
+                                    if old_color != self.noise_color {
+                                        self.send_param(Param::NoiseColor(self.noise_color));
+                                    }
+                                });
+                                ui.add_space(5.0);
+                            }
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_lfo { "v" } else { ">" };
+                                if ui.button(format!("{} 🔄 LFO", expand_icon)).clicked() {
+                                    self.expanded_lfo = !self.expanded_lfo;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.lfo_activation, "ON").changed() {
+                                    self.update_lfo_activation();
+                                }
+                            });
+
+                            if self.expanded_lfo {
+                                ui.horizontal(|ui| {
+                                    ui.label("Fréquence:");
+                                    if ui
+                                        .add(egui::Slider::new(&mut self.freq, 0.01..=1000.0))
+                                        .changed()
+                                    {
+                                        self.update_synth_lfo();
+                                    };
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Forme d'onde:");
+                                    let old_waveform = self.waveform;
+                                    egui::ComboBox::from_id_salt("lfo_waveform")
+                                        .selected_text(self.waveform.name())
+                                        .show_ui(ui, |ui| {
+                                            for waveform in LfoWaveform::ALL {
+                                                ui.selectable_value(
+                                                    &mut self.waveform,
+                                                    waveform,
+                                                    waveform.name(),
+                                                );
+                                            }
+                                        });
+                                    if old_waveform != self.waveform {
+                                        self.update_synth_lfo_waveform();
+                                    }
+                                });
+                            }
+
+                            ui.separator();
+                            // Filter
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_filter { "v" } else { ">" };
+                                if ui.button(format!("{} ⬇ Filter", expand_icon)).clicked() {
+                                    self.expanded_filter = !self.expanded_filter;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.filter_activation, "ON").changed() {
+                                    self.update_filter_activation();
+                                }
+                            });
+
+                            if self.expanded_filter {
+                                ui.horizontal(|ui| {
+                                    ui.label("Mode:");
+                                    let old_mode = self.filter_mode;
+                                    egui::ComboBox::from_id_salt("filter_mode")
+                                        .selected_text(self.filter_mode.name())
+                                        .show_ui(ui, |ui| {
+                                            for mode in FilterMode::ALL {
+                                                ui.selectable_value(
+                                                    &mut self.filter_mode,
+                                                    mode,
+                                                    mode.name(),
+                                                );
+                                            }
+                                        });
+                                    if old_mode != self.filter_mode {
+                                        self.send_param(Param::FilterMode(self.filter_mode));
+                                    }
+
+                                    let old_slope = self.filter_slope;
+                                    for slope in [FilterSlope::Db12, FilterSlope::Db24] {
+                                        ui.radio_value(&mut self.filter_slope, slope, slope.name());
+                                    }
+                                    if old_slope != self.filter_slope {
+                                        self.send_param(Param::FilterSlope(self.filter_slope));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Cutoff:");
+                                    if ui
+                                        .add(
+                                            egui::Slider::new(&mut self.cutoff, 20.0..=20000.0)
+                                                .text("Hz"),
+                                        )
+                                        .changed()
+                                    {
+                                        self.update_synth_cutoff();
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Resonance:");
+                                    if ui
+                                        .add(
+                                            egui::Slider::new(&mut self.resonance, 0.0..=20.0)
+                                                .text("Q Factor"),
+                                        )
+                                        .changed()
+                                    {
+                                        self.update_synth_resonance();
+                                    }
+                                });
+                                if self.filter_mode.uses_gain() {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Gain:");
+                                        if ui
+                                            .add(
+                                                egui::Slider::new(
+                                                    &mut self.filter_gain,
+                                                    -24.0..=24.0,
+                                                )
+                                                .text("dB"),
+                                            )
+                                            .changed()
+                                        {
+                                            self.send_param(Param::FilterGain(self.filter_gain));
+                                        }
+                                    });
+                                }
+
+                                // Enveloppe et suivi de clavier, propres à chaque voix
+                                let modulation = &mut self.filter_modulation;
+                                let mut changed = false;
+                                ui.label("Envelope:");
+                                ui.horizontal(|ui| {
+                                    ui.label("Amount:");
+                                    changed |= ui
+                                        .add(
+                                            egui::Slider::new(
+                                                &mut modulation.amount,
+                                                -MAX_FILTER_ENV_AMOUNT..=MAX_FILTER_ENV_AMOUNT,
+                                            )
+                                            .text("oct"),
+                                        )
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("A:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut modulation.attack, 0.001..=2.0))
+                                        .changed();
+                                    ui.label("D:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut modulation.decay, 0.001..=5.0))
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("S:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(&mut modulation.sustain, 0.0..=1.0))
+                                        .changed();
+                                    ui.label("R:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(
+                                            &mut modulation.release,
+                                            0.001..=5.0,
+                                        ))
+                                        .changed();
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Key tracking:");
+                                    changed |= ui
+                                        .add(egui::Slider::new(
+                                            &mut modulation.key_tracking,
+                                            0.0..=1.0,
+                                        ))
+                                        .changed();
+                                });
+                                if changed {
+                                    self.send_param(Param::FilterModulation(
+                                        self.filter_modulation,
+                                    ));
+                                }
+                            }
+
+                            ui.separator();
+
+                            // Distortion
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_distortion { "v" } else { ">" };
+                                if ui
+                                    .button(format!("{} 🔥 Distortion", expand_icon))
+                                    .clicked()
+                                {
+                                    self.expanded_distortion = !self.expanded_distortion;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.distortion_activation, "ON").changed() {
+                                    self.send_param(Param::DistortionActive(
+                                        self.distortion_activation,
+                                    ));
+                                }
+                            });
+
+                            if self.expanded_distortion {
+                                self.draw_distortion_settings(ui);
+                            }
+
+                            ui.separator();
+
+                            // Matrice de modulation
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_modulation { "v" } else { ">" };
+                                if ui
+                                    .button(format!("{} 🔀 Modulation", expand_icon))
+                                    .clicked()
+                                {
+                                    self.expanded_modulation = !self.expanded_modulation;
+                                }
+                            });
+
+                            if self.expanded_modulation {
+                                self.draw_modulation_matrix(ui);
+                            }
+
+                            ui.separator();
+
+                            // Tempo global des LFO synchronisés
+                            ui.horizontal(|ui| {
+                                ui.label("⏱ Tempo:");
+                                if ui
+                                    .add(
+                                        egui::Slider::new(&mut self.tempo, 30.0..=300.0)
+                                            .text("BPM"),
+                                    )
+                                    .changed()
+                                {
+                                    self.send_param(Param::Tempo(self.tempo));
+                                }
+                            });
+
+                            ui.separator();
+
+                            // Gain général
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_gain { "v" } else { ">" };
+                                if ui.button(format!("{} 🔊 Gain", expand_icon)).clicked() {
+                                    self.expanded_gain = !self.expanded_gain;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.gain_activation, "ON").changed() {
+                                    self.update_gain_activation();
+                                }
+                            });
+                            ui.add_space(10.0);
+
+                            if self.expanded_gain {
+                                ui.horizontal(|ui| {
+                                    // Synchroniser la valeur f32 avec la valeur f64
+                                    self.gain_knob = self.gain as f32;
+                                    ui.label("Gain:");
+                                    if ui
+                                        .add(
+                                            Knob::new(
+                                                &mut self.gain_knob,
+                                                -12.0_f32,
+                                                6.0_f32,
+                                                KnobStyle::Wiper,
+                                            )
+                                            .with_size(50.0)
+                                            .with_font_size(20.0)
+                                            .with_stroke_width(3.0)
+                                            .with_colors(
+                                                KNOB_GAIN_COLOR.0,
+                                                KNOB_GAIN_COLOR.1,
+                                                KNOB_GAIN_COLOR.2,
+                                            )
+                                            .with_label("", LabelPosition::Bottom),
+                                        )
+                                        .changed()
+                                    {
+                                        // Changement en f64
+                                        self.gain = self.gain_knob as f64;
+                                        self.update_synth_gain();
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Pan:");
+                                    if ui
+                                        .add(
+                                            egui::Slider::new(&mut self.pan, -1.0..=1.0)
+                                                .text("L / R"),
+                                        )
+                                        .changed()
+                                    {
+                                        self.send_param(Param::Pan(self.pan));
+                                    }
+                                });
+                                ui.add_space(5.0);
+                            }
+                            ui.separator();
+                            // Compressor
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_compressor { "v" } else { ">" };
+                                if ui
+                                    .button(format!("{} 🤏 Compressor", expand_icon))
+                                    .clicked()
+                                {
+                                    self.expanded_compressor = !self.expanded_compressor;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.compressor_activation, "ON").changed() {
+                                    self.update_compressor_activation();
+                                }
+                            });
+
+                            if self.expanded_compressor {
+                                self.draw_compressor_settings(ui);
+                            }
+
+                            ui.separator();
+
+                            // Chorus, flanger, phaser
+                            let expand_icon = if self.expanded_mod_effects { "v" } else { ">" };
+                            if ui
+                                .button(format!("{} 〰 Chorus / Flanger / Phaser", expand_icon))
+                                .clicked()
+                            {
+                                self.expanded_mod_effects = !self.expanded_mod_effects;
+                            }
+
+                            if self.expanded_mod_effects {
+                                self.draw_mod_effects(ui);
+                            }
+
+                            ui.separator();
+
+                            // Delay
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_delay { "v" } else { ">" };
+                                if ui.button(format!("{} 🔁 Delay", expand_icon)).clicked() {
+                                    self.expanded_delay = !self.expanded_delay;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.delay_activation, "ON").changed() {
+                                    self.send_param(Param::DelayActive(self.delay_activation));
+                                }
+                            });
+
+                            if self.expanded_delay {
+                                self.draw_delay_settings(ui);
+                            }
+
+                            ui.separator();
+
+                            // Section Reverb
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_reverb { "v" } else { ">" };
+                                if ui.button(format!("{} 🌊 Reverb", expand_icon)).clicked() {
                                     self.expanded_reverb = !self.expanded_reverb;
                                 }
                                 ui.add_space(10.0);
@@ -732,6 +1627,58 @@ impl eframe::App for SynthesizerApp {
 
                             ui.separator();
 
+                            // Leslie
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_leslie { "v" } else { ">" };
+                                if ui.button(format!("{} 🌀 Leslie", expand_icon)).clicked() {
+                                    self.expanded_leslie = !self.expanded_leslie;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.leslie_activation, "ON").changed() {
+                                    self.send_param(Param::LeslieActive(self.leslie_activation));
+                                }
+                            });
+
+                            if self.expanded_leslie {
+                                ui.horizontal(|ui| {
+                                    ui.label("Vitesse:");
+                                    let old_speed = self.leslie_speed;
+                                    ui.selectable_value(
+                                        &mut self.leslie_speed,
+                                        LeslieSpeed::Slow,
+                                        "Lent",
+                                    );
+                                    ui.selectable_value(
+                                        &mut self.leslie_speed,
+                                        LeslieSpeed::Fast,
+                                        "Rapide",
+                                    );
+                                    if old_speed != self.leslie_speed {
+                                        self.send_param(Param::LeslieSpeed(self.leslie_speed));
+                                    }
+                                });
+                            }
+
+                            ui.separator();
+
+                            // Limiter, dernier étage du master bus
+                            ui.horizontal(|ui| {
+                                let expand_icon = if self.expanded_limiter { "v" } else { ">" };
+                                if ui.button(format!("{} 🧱 Limiter", expand_icon)).clicked() {
+                                    self.expanded_limiter = !self.expanded_limiter;
+                                }
+                                ui.add_space(10.0);
+                                if ui.checkbox(&mut self.limiter_activation, "ON").changed() {
+                                    self.send_param(Param::LimiterActive(self.limiter_activation));
+                                }
+                            });
+
+                            if self.expanded_limiter {
+                                self.draw_limiter_settings(ui);
+                            }
+
+                            ui.separator();
+
                             // Octave (correspondant au système JSON 1-9)
                             ui.heading("🎵 Octave");
                             ui.horizontal(|ui| {
@@ -747,13 +1694,12 @@ impl eframe::App for SynthesizerApp {
 
                             // Informations
                             ui.heading("ℹ Info");
-                            if let Some(ref notes) = self.notes {
-                                let notes_guard = notes.lock().unwrap();
-                                let released_notes_count = notes_guard
-                                    .values()
-                                    .filter(|note| note.adsr.is_released())
-                                    .count();
-                                ui.label(format!("Notes actives: {}", released_notes_count));
+                            if let Some(ref controller) = self.controller {
+                                ui.label(format!(
+                                    "Notes actives: {}",
+                                    controller.get_active_voices()
+                                ));
+                                self.draw_clip_indicator(ui);
                             } else {
                                 ui.label("Audio non connecté");
                             }
@@ -845,6 +1791,18 @@ impl SynthesizerApp {
                 self.current_synth_type = SynthType::n_hammond();
                 self.update_synth_type();
             }
+            if i.key_pressed(Key::M) {
+                self.current_synth_type = SynthType::n_multi_fm();
+                self.update_synth_type();
+            }
+            if i.key_pressed(Key::T) {
+                self.current_synth_type = SynthType::n_wavetable();
+                self.update_synth_type();
+            }
+            if i.key_pressed(Key::N) {
+                self.current_synth_type = SynthType::n_noise();
+                self.update_synth_type();
+            }
 
             // Arrêter toutes les notes
             if i.key_pressed(Key::Space) {
@@ -853,117 +1811,585 @@ impl SynthesizerApp {
         });
     }
 
-    /// Gère une touche de note (press/release)
-    fn handle_note_key(&mut self, input: &egui::InputState, key: egui::Key, note: &str) {
-        let key_string = format!("physical_{}", note);
-
-        // Note pressée
-        if input.key_pressed(key) && !self.pressed_physical_keys.contains(&key_string) {
-            self.pressed_physical_keys.insert(key_string.clone());
-            self.play_note(note);
+    /// Gère une touche de note (press/release)
+    fn handle_note_key(&mut self, input: &egui::InputState, key: egui::Key, note: &str) {
+        let key_string = format!("physical_{}", note);
+
+        // Note pressée
+        if input.key_pressed(key) && !self.pressed_physical_keys.contains(&key_string) {
+            self.pressed_physical_keys.insert(key_string.clone());
+            self.play_note(note);
+        }
+
+        // Note relâchée
+        if input.key_released(key) && self.pressed_physical_keys.contains(&key_string) {
+            self.pressed_physical_keys.remove(&key_string);
+            self.stop_note(note);
+        }
+    }
+
+    /// Arrête toutes les notes en cours
+    fn stop_all_notes(&mut self) {
+        if let Some(ref controller) = self.controller {
+            controller.all_notes_off();
+        }
+        // Vider tous les sets de notes pressées
+        self.pressed_notes.clear();
+        self.pressed_physical_keys.clear();
+        self.active_notes.clear();
+    }
+
+    fn draw_virtual_keyboard(&mut self, ui: &mut egui::Ui) {
+        ui.heading("🎹 Clavier virtuel");
+
+        // Touches blanches (notes naturelles)
+        ui.horizontal(|ui| {
+            let white_keys = ["C", "D", "E", "F", "G", "A", "B"];
+            for key in &white_keys {
+                let key_string = key.to_string();
+                let is_active = self.pressed_notes.contains(&key_string);
+
+                let button = egui::Button::new(*key)
+                    .min_size(egui::vec2(120.0, 180.0))
+                    .fill(if is_active {
+                        egui::Color32::from_rgb(USED_KEYS.0, USED_KEYS.1, USED_KEYS.2)
+                    } else {
+                        egui::Color32::from_rgb(WHITE_KEYS.0, WHITE_KEYS.1, WHITE_KEYS.2)
+                    });
+
+                let response = ui.add(button);
+
+                // Approche simple: utiliser clicked pour démarrer/arrêter en toggle
+                if response.clicked() {
+                    if self.pressed_notes.contains(&key_string) {
+                        // Si déjà active, l'arrêter
+                        self.pressed_notes.remove(&key_string);
+                        self.stop_note(key);
+                    } else {
+                        // Si pas active, la démarrer
+                        self.pressed_notes.insert(key_string.clone());
+                        self.play_note_with_velocity(key, click_velocity(&response));
+                    }
+                }
+            }
+        });
+
+        // Touches noires (dièses)
+        ui.horizontal(|ui| {
+            let black_keys = ["", "C#", "", "D#", "", "", "F#", "", "G#", "", "A#", ""];
+            for key in black_keys.iter() {
+                if key.is_empty() {
+                    ui.add_space(67.0); // Espace pour alignement
+                } else {
+                    let key_string = key.to_string();
+                    let is_active = self.pressed_notes.contains(&key_string);
+
+                    let button = egui::Button::new(*key)
+                        .min_size(egui::vec2(80.0, 150.0))
+                        .fill(if is_active {
+                            egui::Color32::from_rgb(USED_KEYS.0, USED_KEYS.1, USED_KEYS.2)
+                        } else {
+                            egui::Color32::from_rgb(BLACK_KEYS.0, BLACK_KEYS.1, BLACK_KEYS.2)
+                        });
+
+                    let response = ui.add(button);
+
+                    // Utiliser clicked pour démarrer/arrêter en toggle
+                    if response.clicked() {
+                        if self.pressed_notes.contains(&key_string) {
+                            // Si déjà active, l'arrêter
+                            self.pressed_notes.remove(&key_string);
+                            self.stop_note(key);
+                        } else {
+                            // Si pas active, démarrer la note
+                            self.pressed_notes.insert(key_string.clone());
+                            self.play_note_with_velocity(key, click_velocity(&response));
+                        }
+                    }
+                }
+            }
+        });
+
+        ui.label(format!("Octave actuelle: {}", self.current_octave));
+    }
+
+    /// Plage du pitch bend, vibrato et glide
+    fn draw_pitch_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Pitch bend:");
+            let response = ui.add(egui::Slider::new(&mut self.pitch_bend, -1.0..=1.0));
+            if response.drag_stopped() {
+                self.pitch_bend = 0.0;
+            }
+            if (response.changed() || response.drag_stopped())
+                && let Some(ref controller) = self.controller
+            {
+                controller.pitch_bend(self.pitch_bend);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Plage:");
+            if ui
+                .add(
+                    egui::Slider::new(
+                        &mut self.pitch.bend_range,
+                        0.0..=constants::MAX_PITCH_BEND_RANGE,
+                    )
+                    .step_by(1.0)
+                    .suffix(" st"),
+                )
+                .changed()
+            {
+                self.send_param(Param::PitchBendRange(self.pitch.bend_range));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Vibrato:");
+            if ui
+                .add(egui::Slider::new(&mut self.pitch.vibrato, 0.0..=2.0).suffix(" st"))
+                .changed()
+            {
+                self.send_param(Param::Vibrato(self.pitch.vibrato));
+            }
+            let old_lfo = self.pitch.vibrato_lfo;
+            egui::ComboBox::from_id_salt("vibrato_lfo")
+                .selected_text(format!("LFO {}", self.pitch.vibrato_lfo + 1))
+                .show_ui(ui, |ui| {
+                    for index in 0..MOD_LFOS {
+                        ui.selectable_value(
+                            &mut self.pitch.vibrato_lfo,
+                            index,
+                            format!("LFO {}", index + 1),
+                        );
+                    }
+                });
+            if old_lfo != self.pitch.vibrato_lfo {
+                self.send_param(Param::VibratoLfo(self.pitch.vibrato_lfo));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Glide:");
+            let old_mode = self.pitch.glide_mode;
+            egui::ComboBox::from_id_salt("glide_mode")
+                .selected_text(self.pitch.glide_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in GlideMode::ALL {
+                        ui.selectable_value(&mut self.pitch.glide_mode, mode, mode.name());
+                    }
+                });
+            if old_mode != self.pitch.glide_mode {
+                self.send_param(Param::GlideMode(self.pitch.glide_mode));
+            }
+            if ui
+                .add(
+                    egui::Slider::new(&mut self.pitch.glide, 0.0..=2.0)
+                        .logarithmic(true)
+                        .suffix(" s"),
+                )
+                .changed()
+            {
+                self.send_param(Param::GlideTime(self.pitch.glide));
+            }
+        });
+    }
+
+    /// Courbe, drive, filtres de tonalité et suréchantillonnage de la distorsion
+    fn draw_distortion_settings(&mut self, ui: &mut egui::Ui) {
+        let mut distortion = self.distortion;
+        ui.horizontal(|ui| {
+            ui.label("Courbe:");
+            egui::ComboBox::from_id_salt("distortion_curve")
+                .selected_text(distortion.curve.name())
+                .show_ui(ui, |ui| {
+                    for curve in DistortionCurve::ALL {
+                        ui.selectable_value(&mut distortion.curve, curve, curve.name());
+                    }
+                });
+            if distortion.curve == DistortionCurve::Bitcrush {
+                ui.add(egui::Slider::new(&mut distortion.bits, 2..=16).text("bits"));
+            }
+            ui.label("Oversampling:");
+            for oversampling in Oversampling::ALL {
+                ui.selectable_value(
+                    &mut distortion.oversampling,
+                    oversampling,
+                    oversampling.name(),
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Drive:");
+            ui.add(egui::Slider::new(&mut distortion.drive, 0.0..=MAX_DRIVE_DB).suffix(" dB"));
+            ui.label("Output:");
+            ui.add(egui::Slider::new(&mut distortion.output, -48.0..=12.0).suffix(" dB"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Low cut:");
+            ui.add(
+                egui::Slider::new(&mut distortion.low_cut, 20.0..=2000.0)
+                    .logarithmic(true)
+                    .suffix(" Hz"),
+            );
+            ui.label("High cut:");
+            ui.add(
+                egui::Slider::new(&mut distortion.high_cut, 500.0..=20000.0)
+                    .logarithmic(true)
+                    .suffix(" Hz"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Dry Wet:");
+            ui.add(egui::Slider::new(&mut distortion.mix, 0.0..=1.0));
+        });
+        if distortion != self.distortion {
+            self.distortion = distortion;
+            self.send_param(Param::Distortion(distortion));
+        }
+    }
+
+    /// Vitesse, profondeur, feedback et mix du chorus, du flanger et du phaser
+    fn draw_mod_effects(&mut self, ui: &mut egui::Ui) {
+        for (index, kind) in ModEffectKind::ALL.into_iter().enumerate() {
+            let mut effect = self.mod_effects[index];
+            ui.horizontal(|ui| {
+                ui.label(format!("{}:", kind.name()));
+                if ui
+                    .checkbox(&mut self.mod_effect_activation[index], "ON")
+                    .changed()
+                {
+                    self.send_param(Param::ModEffectActive(
+                        kind,
+                        self.mod_effect_activation[index],
+                    ));
+                }
+                ui.add(
+                    egui::Slider::new(&mut effect.rate, 0.01..=ModEffectSettings::MAX_RATE)
+                        .logarithmic(true)
+                        .text("Hz"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Depth:");
+                ui.add(egui::Slider::new(&mut effect.depth, 0.0..=1.0));
+                ui.label("Feedback:");
+                ui.add(egui::Slider::new(
+                    &mut effect.feedback,
+                    -ModEffectSettings::MAX_FEEDBACK..=ModEffectSettings::MAX_FEEDBACK,
+                ));
+                ui.label("Mix:");
+                ui.add(egui::Slider::new(&mut effect.mix, 0.0..=1.0));
+            });
+            if effect != self.mod_effects[index] {
+                self.mod_effects[index] = effect;
+                self.send_param(Param::ModEffect(kind, effect));
+            }
+        }
+    }
+
+    /// Temps (libre ou synchronisé), répétitions filtrées, ping-pong et mix du delay
+    fn draw_delay_settings(&mut self, ui: &mut egui::Ui) {
+        let mut delay = self.delay;
+        ui.horizontal(|ui| {
+            ui.label("Temps:");
+            if delay.sync {
+                egui::ComboBox::from_id_salt("delay_length")
+                    .selected_text(delay.division.length.name())
+                    .show_ui(ui, |ui| {
+                        for length in NoteLength::ALL {
+                            ui.selectable_value(&mut delay.division.length, length, length.name());
+                        }
+                    });
+                egui::ComboBox::from_id_salt("delay_feel")
+                    .selected_text(delay.division.feel.name())
+                    .show_ui(ui, |ui| {
+                        for feel in NoteFeel::ALL {
+                            ui.selectable_value(&mut delay.division.feel, feel, feel.name());
+                        }
+                    });
+            } else {
+                ui.add(
+                    egui::Slider::new(&mut delay.time_ms, 1.0..=MAX_DELAY_MS)
+                        .logarithmic(true)
+                        .suffix(" ms"),
+                );
+            }
+            ui.checkbox(&mut delay.sync, "Sync");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Feedback:");
+            ui.add(egui::Slider::new(
+                &mut delay.feedback,
+                0.0..=MAX_DELAY_FEEDBACK,
+            ));
+            ui.checkbox(&mut delay.ping_pong, "Ping-pong");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Low cut:");
+            ui.add(
+                egui::Slider::new(&mut delay.low_cut, 20.0..=2000.0)
+                    .logarithmic(true)
+                    .suffix(" Hz"),
+            );
+            ui.label("High cut:");
+            ui.add(
+                egui::Slider::new(&mut delay.high_cut, 500.0..=20000.0)
+                    .logarithmic(true)
+                    .suffix(" Hz"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Dry Wet:");
+            ui.add(egui::Slider::new(&mut delay.dry_wet, 0.0..=1.0));
+        });
+        if delay != self.delay {
+            self.delay = delay;
+            self.send_param(Param::Delay(delay));
+        }
+    }
+
+    fn draw_compressor_settings(&mut self, ui: &mut egui::Ui) {
+        let mut compressor = self.compressor;
+        ui.horizontal(|ui| {
+            ui.label("Threshold:");
+            ui.add(egui::Slider::new(&mut compressor.threshold_db, -50.0..=0.0).suffix(" dB"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Ratio:");
+            ui.add(
+                egui::Slider::new(&mut compressor.ratio, 1.0..=20.0)
+                    .logarithmic(true)
+                    .suffix(":1"),
+            );
+            ui.label("Knee:");
+            ui.add(egui::Slider::new(&mut compressor.knee_db, 0.0..=24.0).suffix(" dB"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Attack:");
+            ui.add(
+                egui::Slider::new(&mut compressor.attack, 0.0001..=1.0)
+                    .logarithmic(true)
+                    .suffix(" s"),
+            );
+            ui.label("Release:");
+            ui.add(
+                egui::Slider::new(&mut compressor.release, 0.005..=3.0)
+                    .logarithmic(true)
+                    .suffix(" s"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Makeup Gain:");
+            ui.add(egui::Slider::new(&mut compressor.makeup_gain_db, -20.0..=20.0).suffix(" dB"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Detector:");
+            for detector in CompressorDetector::ALL {
+                ui.selectable_value(&mut compressor.detector, detector, detector.name());
+            }
+            ui.label("Lookahead:");
+            ui.add(
+                egui::Slider::new(&mut compressor.lookahead_ms, 0.0..=MAX_LOOKAHEAD_MS)
+                    .suffix(" ms"),
+            );
+        });
+        if compressor != self.compressor {
+            self.compressor = compressor;
+            self.send_param(Param::Compressor(compressor));
         }
 
-        // Note relâchée
-        if input.key_released(key) && self.pressed_physical_keys.contains(&key_string) {
-            self.pressed_physical_keys.remove(&key_string);
-            self.stop_note(note);
+        // Réduction de gain publiée par le thread audio
+        if let Some(ref controller) = self.controller {
+            let reduction = controller.get_gain_reduction();
+            ui.horizontal(|ui| {
+                ui.label("GR:");
+                ui.add(
+                    egui::ProgressBar::new((reduction / 24.0) as f32)
+                        .desired_width(200.0)
+                        .text(format!("-{:.1} dB", reduction)),
+                );
+            });
         }
     }
 
-    /// Arrête toutes les notes en cours
-    fn stop_all_notes(&mut self) {
-        if let Some(ref notes) = self.notes {
-            if let Ok(mut notes_guard) = notes.lock() {
-                for note in notes_guard.values_mut() {
-                    note.adsr.note_off();
-                }
-            }
+    fn draw_limiter_settings(&mut self, ui: &mut egui::Ui) {
+        let mut limiter = self.limiter;
+        ui.horizontal(|ui| {
+            ui.label("Ceiling:");
+            ui.add(
+                egui::Slider::new(&mut limiter.ceiling_db, MIN_CEILING_DB..=0.0).suffix(" dBTP"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Release:");
+            ui.add(
+                egui::Slider::new(&mut limiter.release_ms, 1.0..=1000.0)
+                    .logarithmic(true)
+                    .suffix(" ms"),
+            );
+        });
+        if limiter != self.limiter {
+            self.limiter = limiter;
+            self.send_param(Param::Limiter(limiter));
         }
-        // Vider tous les sets de notes pressées
-        self.pressed_notes.clear();
-        self.pressed_physical_keys.clear();
-        self.active_notes.clear();
     }
 
-    fn draw_virtual_keyboard(&mut self, ui: &mut egui::Ui) {
-        ui.heading("🎹 Clavier virtuel");
-
-        // Touches blanches (notes naturelles)
+    /// Output peak, and a CLIP light that stays on for a second after a sample went over full scale
+    fn draw_clip_indicator(&mut self, ui: &mut egui::Ui) {
+        let Some(ref controller) = self.controller else {
+            return;
+        };
+        let now = ui.input(|input| input.time);
+        let clipped_samples = controller.get_clipped_samples();
+        if clipped_samples > self.clipped_samples {
+            self.clipped_samples = clipped_samples;
+            self.clip_until = now + 1.0;
+        }
+        let peak_db = 20.0 * controller.get_output_peak().max(1e-6).log10();
         ui.horizontal(|ui| {
-            let white_keys = ["C", "D", "E", "F", "G", "A", "B"];
-            for key in &white_keys {
-                let key_string = key.to_string();
-                let is_active = self.pressed_notes.contains(&key_string);
+            ui.label(format!("Crête: {:.1} dBFS", peak_db));
+            let clip = RichText::new("CLIP").strong();
+            if now < self.clip_until {
+                ui.label(clip.color(egui::Color32::RED));
+            } else {
+                ui.label(clip.weak());
+            }
+        });
+    }
 
-                let button = egui::Button::new(*key)
-                    .min_size(egui::vec2(120.0, 180.0))
-                    .fill(if is_active {
-                        egui::Color32::from_rgb(USED_KEYS.0, USED_KEYS.1, USED_KEYS.2)
-                    } else {
-                        egui::Color32::from_rgb(WHITE_KEYS.0, WHITE_KEYS.1, WHITE_KEYS.2)
+    /// Sources (LFOs, envelope, molette) et emplacements de la matrice de modulation
+    fn draw_modulation_matrix(&mut self, ui: &mut egui::Ui) {
+        for index in 0..MOD_LFOS {
+            let mut lfo = self.mod_matrix.lfos[index];
+            ui.horizontal(|ui| {
+                ui.label(format!("LFO {}:", index + 1));
+                egui::ComboBox::from_id_salt(("mod_lfo_waveform", index))
+                    .selected_text(lfo.waveform.name())
+                    .show_ui(ui, |ui| {
+                        for waveform in LfoWaveform::ALL {
+                            ui.selectable_value(&mut lfo.waveform, waveform, waveform.name());
+                        }
                     });
-
-                let response = ui.add(button);
-
-                // Approche simple: utiliser clicked pour démarrer/arrêter en toggle
-                if response.clicked() {
-                    if self.pressed_notes.contains(&key_string) {
-                        // Si déjà active, l'arrêter
-                        self.pressed_notes.remove(&key_string);
-                        self.stop_note(key);
-                    } else {
-                        // Si pas active, la démarrer
-                        self.pressed_notes.insert(key_string.clone());
-                        self.play_note(key);
-                    }
+                if lfo.sync {
+                    egui::ComboBox::from_id_salt(("mod_lfo_length", index))
+                        .selected_text(lfo.division.length.name())
+                        .show_ui(ui, |ui| {
+                            for length in NoteLength::ALL {
+                                ui.selectable_value(
+                                    &mut lfo.division.length,
+                                    length,
+                                    length.name(),
+                                );
+                            }
+                        });
+                    egui::ComboBox::from_id_salt(("mod_lfo_feel", index))
+                        .selected_text(lfo.division.feel.name())
+                        .show_ui(ui, |ui| {
+                            for feel in NoteFeel::ALL {
+                                ui.selectable_value(&mut lfo.division.feel, feel, feel.name());
+                            }
+                        });
+                } else {
+                    ui.add(
+                        egui::Slider::new(&mut lfo.frequency, 0.01..=20.0)
+                            .logarithmic(true)
+                            .text("Hz"),
+                    );
                 }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut lfo.sync, "Sync");
+                ui.checkbox(&mut lfo.retrigger, "Retrigger");
+                ui.label("Delay:");
+                ui.add(egui::Slider::new(&mut lfo.delay, 0.0..=5.0).suffix(" s"));
+                ui.label("Fade:");
+                ui.add(egui::Slider::new(&mut lfo.fade_in, 0.0..=5.0).suffix(" s"));
+            });
+            if lfo != self.mod_matrix.lfos[index] {
+                self.mod_matrix.lfos[index] = lfo;
+                self.send_param(Param::ModLfo(index, lfo));
             }
-        });
+        }
 
-        // Touches noires (dièses)
+        let envelope = &mut self.mod_matrix.envelope;
+        let mut changed = false;
         ui.horizontal(|ui| {
-            let black_keys = ["", "C#", "", "D#", "", "", "F#", "", "G#", "", "A#", ""];
-            for key in black_keys.iter() {
-                if key.is_empty() {
-                    ui.add_space(67.0); // Espace pour alignement
-                } else {
-                    let key_string = key.to_string();
-                    let is_active = self.pressed_notes.contains(&key_string);
-
-                    let button = egui::Button::new(*key)
-                        .min_size(egui::vec2(80.0, 150.0))
-                        .fill(if is_active {
-                            egui::Color32::from_rgb(USED_KEYS.0, USED_KEYS.1, USED_KEYS.2)
-                        } else {
-                            egui::Color32::from_rgb(BLACK_KEYS.0, BLACK_KEYS.1, BLACK_KEYS.2)
-                        });
-
-                    let response = ui.add(button);
+            ui.label("Envelope A:");
+            changed |= ui
+                .add(egui::Slider::new(&mut envelope.attack, 0.001..=2.0))
+                .changed();
+            ui.label("D:");
+            changed |= ui
+                .add(egui::Slider::new(&mut envelope.decay, 0.001..=5.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("S:");
+            changed |= ui
+                .add(egui::Slider::new(&mut envelope.sustain, 0.0..=1.0))
+                .changed();
+            ui.label("R:");
+            changed |= ui
+                .add(egui::Slider::new(&mut envelope.release, 0.001..=5.0))
+                .changed();
+        });
+        if changed {
+            self.send_param(Param::ModEnvelope(self.mod_matrix.envelope));
+        }
 
-                    // Utiliser clicked pour démarrer/arrêter en toggle
-                    if response.clicked() {
-                        if self.pressed_notes.contains(&key_string) {
-                            // Si déjà active, l'arrêter
-                            self.pressed_notes.remove(&key_string);
-                            self.stop_note(key);
-                        } else {
-                            // Si pas active, démarrer la note
-                            self.pressed_notes.insert(key_string.clone());
-                            self.play_note(key);
+        ui.label("Routes:");
+        for index in 0..MOD_ROUTES {
+            let mut route = self.mod_matrix.routes[index];
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut route.active, "");
+                egui::ComboBox::from_id_salt(("mod_source", index))
+                    .selected_text(route.source.name())
+                    .show_ui(ui, |ui| {
+                        for source in ModSource::ALL {
+                            ui.selectable_value(&mut route.source, source, source.name());
                         }
-                    }
-                }
+                    });
+                ui.label("→");
+                egui::ComboBox::from_id_salt(("mod_destination", index))
+                    .selected_text(route.destination.name())
+                    .show_ui(ui, |ui| {
+                        for destination in ModDestination::ALL {
+                            ui.selectable_value(
+                                &mut route.destination,
+                                destination,
+                                destination.name(),
+                            );
+                        }
+                    });
+                let destination = route.destination;
+                ui.add(
+                    egui::Slider::new(&mut route.depth, -1.0..=1.0).custom_formatter(
+                        move |depth, _| {
+                            format!("{:+.2} {}", depth * destination.range(), destination.unit())
+                        },
+                    ),
+                );
+            });
+            if route != self.mod_matrix.routes[index] {
+                self.mod_matrix.routes[index] = route;
+                self.send_param(Param::ModRoute(index, route));
             }
-        });
+        }
 
-        ui.label(format!("Octave actuelle: {}", self.current_octave));
+        ui.horizontal(|ui| {
+            ui.label("Mod wheel:");
+            if ui
+                .add(egui::Slider::new(&mut self.mod_wheel, 0.0..=1.0))
+                .changed()
+                && let Some(ref controller) = self.controller
+            {
+                controller.mod_wheel(self.mod_wheel);
+            }
+        });
     }
 
     fn update_synth_type(&mut self) {
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                *synth = self.current_synth_type.clone();
-            }
+        if let Some(ref controller) = self.controller {
+            controller.set_synth(self.current_synth_type.clone());
         }
         self.sync_values_from_synth();
     }
@@ -971,10 +2397,13 @@ impl SynthesizerApp {
     fn change_synth_type_preserving_params(&mut self, mut new_synth_type: SynthType) {
         // Appliquer les valeurs actuelles de l'interface au nouveau synthé
         new_synth_type.set_current_gain(self.gain);
+        new_synth_type.set_current_pan(self.pan);
         new_synth_type.set_gain_activation(self.gain_activation);
-        
+
         new_synth_type.set_current_noise(self.noise);
         new_synth_type.set_noise_activation(self.noise_activation);
+        new_synth_type.set_noise_color(self.noise_color);
+        new_synth_type.set_noise_source_color(self.noise_source_color);
 
         new_synth_type.set_lfo_activation(self.lfo_activation);
         new_synth_type.set_current_lfo_waveform(self.waveform);
@@ -983,25 +2412,63 @@ impl SynthesizerApp {
         new_synth_type.set_filter_activation(self.filter_activation);
         new_synth_type.set_current_cutoff(self.cutoff);
         new_synth_type.set_current_resonance(self.resonance);
+        new_synth_type.set_filter_mode(self.filter_mode);
+        new_synth_type.set_filter_slope(self.filter_slope);
+        new_synth_type.set_filter_gain(self.filter_gain);
+        new_synth_type.set_filter_modulation(self.filter_modulation);
+        new_synth_type.set_mod_matrix(self.mod_matrix);
+        new_synth_type.set_tempo(self.tempo);
+
+        new_synth_type.set_distortion_activation(self.distortion_activation);
+        new_synth_type.set_distortion(self.distortion);
 
         new_synth_type.set_compressor_activation(self.compressor_activation);
-        new_synth_type.set_current_threshold(self.threshold);
+        new_synth_type.set_compressor(self.compressor);
 
         new_synth_type.set_reverb_activation(self.reverb_activation);
-        
+
+        new_synth_type.set_delay_activation(self.delay_activation);
+        new_synth_type.set_delay(self.delay);
+        new_synth_type.set_limiter_activation(self.limiter_activation);
+        new_synth_type.set_limiter(self.limiter);
+        for (index, kind) in ModEffectKind::ALL.into_iter().enumerate() {
+            new_synth_type.set_mod_effect_activation(kind, self.mod_effect_activation[index]);
+            new_synth_type.set_mod_effect(kind, self.mod_effects[index]);
+        }
+
+        new_synth_type.set_band_limited(self.band_limited);
+        new_synth_type.set_hammond_registration(self.hammond);
+        new_synth_type.set_fm_mod_index(self.fm_mod_index);
+        new_synth_type.set_fm_mod_ratio(self.fm_mod_ratio);
+        new_synth_type.set_fm_patch(self.fm_patch);
+        new_synth_type.set_unison(self.unison);
+        new_synth_type.set_pitch(self.pitch);
+        if let Ok(table) = Wavetable::load(&self.wavetable_source) {
+            new_synth_type.set_wavetable(table);
+        }
+        new_synth_type.set_wavetable_settings(self.wavetable);
+        new_synth_type.set_leslie_speed(self.leslie_speed);
+        self.leslie_activation = new_synth_type.is_leslie_active();
+
         self.current_synth_type = new_synth_type;
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                *synth = self.current_synth_type.clone();
-            }
+        if let Some(ref controller) = self.controller {
+            controller.set_synth(self.current_synth_type.clone());
         }
     }
 
     /// Synchronise les valeurs de l'interface avec le synthétiseur actuel
     fn sync_values_from_synth(&mut self) {
         // TODO: finir les autres valeurs
+        // OSCILLATOR
+        if let Some(band_limited) = self.current_synth_type.get_band_limited() {
+            self.band_limited = band_limited;
+        }
+        self.unison = self.current_synth_type.get_unison();
+        self.pitch = self.current_synth_type.get_pitch();
+
         // GAIN
         self.gain = self.current_synth_type.get_current_gain();
+        self.pan = self.current_synth_type.get_current_pan();
         self.gain_activation = self.current_synth_type.is_gain_active();
 
         // NOISE
@@ -1017,217 +2484,278 @@ impl SynthesizerApp {
         self.filter_activation = self.current_synth_type.is_filter_active();
         self.cutoff = self.current_synth_type.get_current_cutoff();
         self.resonance = self.current_synth_type.get_current_resonance();
+        self.filter_mode = self.current_synth_type.get_filter_mode();
+        self.filter_slope = self.current_synth_type.get_filter_slope();
+        self.filter_gain = self.current_synth_type.get_filter_gain();
+        self.filter_modulation = self.current_synth_type.get_filter_modulation();
+
+        // Modulation
+        self.mod_matrix = self.current_synth_type.get_mod_matrix();
+        self.tempo = self.current_synth_type.get_tempo();
+
+        // Distortion
+        self.distortion_activation = self.current_synth_type.is_distortion_active();
+        self.distortion = self.current_synth_type.get_distortion();
 
         // Compressor
         self.compressor_activation = self.current_synth_type.is_compressor_active();
-        self.threshold = self.current_synth_type.get_current_threshold();
+        self.compressor = self.current_synth_type.get_compressor();
 
         // Reverb
         self.reverb_activation = self.current_synth_type.is_reverb_active();
+        self.reverb_type = self.current_synth_type.get_current_reverb_type();
+
+        // Delay
+        self.delay_activation = self.current_synth_type.is_delay_active();
+        self.delay = self.current_synth_type.get_delay();
+
+        // Limiter
+        self.limiter_activation = self.current_synth_type.is_limiter_active();
+        self.limiter = self.current_synth_type.get_limiter();
+
+        // Chorus, flanger, phaser
+        for (index, kind) in ModEffectKind::ALL.into_iter().enumerate() {
+            self.mod_effect_activation[index] = self.current_synth_type.is_mod_effect_active(kind);
+            self.mod_effects[index] = self.current_synth_type.get_mod_effect(kind);
+        }
+
+        // Hammond & Leslie
+        if let Some(registration) = self.current_synth_type.get_hammond_registration() {
+            self.hammond = registration;
+        }
+        self.leslie_activation = self.current_synth_type.is_leslie_active();
+        self.leslie_speed = self.current_synth_type.get_current_leslie_speed();
+
+        // FM
+        if let Some((mod_index, mod_ratio)) = self.current_synth_type.get_fm_modulation() {
+            self.fm_mod_index = mod_index;
+            self.fm_mod_ratio = mod_ratio;
+        }
+        if let Some(patch) = self.current_synth_type.get_fm_patch() {
+            self.fm_patch = patch;
+        }
+
+        // Wavetable
+        if let Some((source, settings)) = self.current_synth_type.get_wavetable() {
+            self.wavetable_source = source;
+            self.wavetable = settings;
+        }
+
+        // Noise
+        self.noise_color = self.current_synth_type.get_noise_color();
+        if let Some(color) = self.current_synth_type.get_noise_source_color() {
+            self.noise_source_color = color;
+        }
+
+        // Knobs
+        self.gain_knob = self.gain as f32;
+        self.noise_knob = self.noise as f32;
     }
 
-    fn update_synth_cutoff(&mut self) {
-        // Mettre à jour la fréquence de coupure dans le synthétiseur local
-        self.current_synth_type.set_current_cutoff(self.cutoff);
+    /// Met à jour les valeurs et knobs de l'ADSR affichés
+    fn sync_envelope(&mut self, attack: f64, decay: f64, sustain: f64, release: f64) {
+        self.attack = attack;
+        self.attack_knob = attack as f32;
+        self.decay = decay;
+        self.decay_knob = decay as f32;
+        self.sustain = sustain;
+        self.sustain_knob = sustain as f32;
+        self.release = release;
+        self.release_knob = release as f32;
+    }
 
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_cutoff(self.cutoff);
+    fn draw_preset_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label("Usine");
+        for preset in PresetBank::factory_presets() {
+            if ui.button(&preset.name).clicked() {
+                self.apply_preset(&preset);
             }
         }
-    }
 
-    fn update_synth_resonance(&mut self) {
-        // Mettre à jour la résonance dans le synthétiseur local
-        self.current_synth_type
-            .set_current_resonance(self.resonance);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_resonance(self.resonance);
+        ui.separator();
+        ui.label("Utilisateur");
+        for name in self.preset_bank.user_preset_names() {
+            ui.horizontal(|ui| {
+                if ui.button(&name).clicked() {
+                    self.load_preset(&name);
+                }
+                if ui.small_button("Renommer").clicked() {
+                    let result = self.preset_bank.rename(&name, &self.preset_new_name);
+                    self.preset_status = match result {
+                        Ok(()) => format!("Preset renommé : {}", self.preset_new_name),
+                        Err(err) => err.to_string(),
+                    };
+                }
+                if ui.small_button("Supprimer").clicked() {
+                    self.preset_status = match self.preset_bank.delete(&name) {
+                        Ok(()) => format!("Preset supprimé : {name}"),
+                        Err(err) => err.to_string(),
+                    };
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Nouveau nom :");
+            ui.text_edit_singleline(&mut self.preset_new_name);
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.preset_name);
+            if ui.button("Sauvegarder").clicked() {
+                self.save_preset();
             }
+        });
+
+        if !self.preset_status.is_empty() {
+            ui.label(&self.preset_status);
         }
     }
 
-    /// Met à jour le gain dans le synthétiseur actuel
-    fn update_synth_gain(&mut self) {
-        // Mettre à jour le gain dans le synthétiseur local
-        self.current_synth_type.set_current_gain(self.gain);
+    fn load_preset(&mut self, name: &str) {
+        match self.preset_bank.load(name) {
+            Ok(preset) => self.apply_preset(&preset),
+            Err(err) => self.preset_status = err.to_string(),
+        }
+    }
 
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_gain(self.gain);
-            }
+    /// Remplace le synthé, l'ADSR et l'octave par ceux du preset
+    fn apply_preset(&mut self, preset: &Preset) {
+        // Pas de signal de sidechain en direct
+        let preset = &preset.with_sidechain(false);
+        self.current_synth_type = preset.to_synth();
+        if let Some(ref controller) = self.controller {
+            controller.load_preset(preset);
         }
+        self.sync_values_from_synth();
+        self.velocity = preset.velocity;
+        self.voices = preset.voices;
+
+        let envelope = &preset.envelope;
+        self.sync_envelope(
+            envelope.attack,
+            envelope.decay,
+            envelope.sustain,
+            envelope.release,
+        );
+
+        self.current_octave = preset.octave as usize;
+        self.update_global_octave();
+
+        self.preset_name = preset.name.clone();
+        self.preset_status = format!("Preset chargé : {}", preset.name);
     }
 
-    fn update_synth_threshold(&mut self) {
-        self.current_synth_type
-            .set_current_threshold(self.threshold);
+    fn save_preset(&mut self) {
+        let name = self.preset_name.trim().to_string();
+        let preset = match self.controller {
+            Some(ref controller) => controller.capture_preset(&name, self.current_octave as u8),
+            None => Preset::capture(
+                &name,
+                &self.current_synth_type,
+                &crate::synths::modules::adsr::ADSR::new(
+                    constants::SAMPLE_RATE,
+                    self.attack,
+                    self.decay,
+                    self.sustain,
+                    self.release,
+                ),
+                &self.velocity,
+                &self.voices,
+                self.current_octave as u8,
+            ),
+        };
+        self.preset_status = match self.preset_bank.save(&preset) {
+            Ok(()) => format!("Preset sauvegardé : {name}"),
+            Err(err) => err.to_string(),
+        };
+    }
 
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_threshold(self.threshold);
+    /// Applique un paramètre au synthé local puis l'envoie au thread audio
+    /// Loads the selected table, going back to the previous one when the file can't be read
+    fn load_wavetable(&mut self, previous: WavetableSource) {
+        match Wavetable::load(&self.wavetable_source) {
+            Ok(table) => {
+                self.wavetable_status.clear();
+                self.current_synth_type.set_wavetable(table.clone());
+                if let Some(ref controller) = self.controller {
+                    controller.set_wavetable(table);
+                }
+            }
+            Err(err) => {
+                self.wavetable_status = format!("{} : {err}", self.wavetable_source.name());
+                self.wavetable_source = previous;
             }
         }
     }
 
-    fn update_synth_noise(&mut self) {
-        // Mettre à jour UNIQUEMENT le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_noise(self.noise);
-            }
+    fn send_param(&mut self, param: Param) {
+        self.current_synth_type.apply_param(param);
+        if let Some(ref controller) = self.controller {
+            controller.set_param(param);
         }
     }
 
-    fn update_synth_lfo(&mut self) {
-        // Mettre à jour la fréquence dans le synthétiseur local
-        self.current_synth_type.set_current_lfo_frequency(self.freq);
+    fn update_synth_cutoff(&mut self) {
+        self.send_param(Param::Cutoff(self.cutoff));
+    }
 
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_lfo_frequency(self.freq);
-            }
-        }
+    fn update_synth_resonance(&mut self) {
+        self.send_param(Param::Resonance(self.resonance));
+    }
+
+    /// Met à jour le gain dans le synthétiseur actuel
+    fn update_synth_gain(&mut self) {
+        self.send_param(Param::Gain(self.gain));
+    }
+
+    fn update_synth_noise(&mut self) {
+        self.send_param(Param::Noise(self.noise));
+    }
+
+    fn update_synth_lfo(&mut self) {
+        self.send_param(Param::LfoFrequency(self.freq));
     }
 
     fn update_synth_attack(&mut self) {
-        // Mettre à jour toutes les notes actives existantes
-        if let Some(ref notes) = self.notes {
-            if let Ok(mut notes_guard) = notes.lock() {
-                for note in notes_guard.values_mut() {
-                    note.set_current_attack(self.attack);
-                }
-            }
-        }
+        self.send_param(Param::Attack(self.attack));
     }
 
     fn update_synth_decay(&mut self) {
-        // Mettre à jour toutes les notes actives existantes
-        if let Some(ref notes) = self.notes {
-            if let Ok(mut notes_guard) = notes.lock() {
-                for note in notes_guard.values_mut() {
-                    note.set_current_decay(self.decay);
-                }
-            }
-        }
+        self.send_param(Param::Decay(self.decay));
     }
 
     fn update_synth_sustain(&mut self) {
-        // Mettre à jour toutes les notes actives existantes
-        if let Some(ref notes) = self.notes {
-            if let Ok(mut notes_guard) = notes.lock() {
-                for note in notes_guard.values_mut() {
-                    note.set_current_sustain(self.sustain);
-                }
-            }
-        }
+        self.send_param(Param::Sustain(self.sustain));
     }
 
     fn update_synth_release(&mut self) {
-        // Mettre à jour toutes les notes actives existantes
-        if let Some(ref notes) = self.notes {
-            if let Ok(mut notes_guard) = notes.lock() {
-                for note in notes_guard.values_mut() {
-                    note.set_current_release(self.release);
-                }
-            }
-        }
+        self.send_param(Param::Release(self.release));
     }
 
     fn update_filter_activation(&mut self) {
-        self.current_synth_type
-            .set_filter_activation(self.filter_activation);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_filter_activation(self.filter_activation);
-            }
-        }
+        self.send_param(Param::FilterActive(self.filter_activation));
     }
 
     /// Met à jour l'activation du gain
     fn update_gain_activation(&mut self) {
-        self.current_synth_type
-            .set_gain_activation(self.gain_activation);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_gain_activation(self.gain_activation);
-            }
-        }
+        self.send_param(Param::GainActive(self.gain_activation));
     }
 
     fn update_compressor_activation(&mut self) {
-        println!(
-            "Activation du compresseur changée: {}",
-            self.compressor_activation
-        );
-
-        self.current_synth_type
-            .set_compressor_activation(self.compressor_activation);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_compressor_activation(self.compressor_activation);
-                println!(
-                    "Activation du compresseur mise à jour dans le contrôleur audio: {}",
-                    self.compressor_activation
-                );
-            }
-        }
+        self.send_param(Param::CompressorActive(self.compressor_activation));
     }
 
     fn update_reverb_activation(&mut self) {
-        println!(
-            "Activation de la réverbération changée: {}",
-            self.reverb_activation
-        );
-
-        self.current_synth_type
-            .set_reverb_activation(self.reverb_activation);
-
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_reverb_activation(self.reverb_activation);
-                println!(
-                    "Activation de la réverbération mise à jour dans le contrôleur audio: {}",
-                    self.reverb_activation
-                );
-            }
-        }
+        self.send_param(Param::ReverbActive(self.reverb_activation));
     }
 
     fn update_noise_activation(&mut self) {
-        self.current_synth_type
-            .set_noise_activation(self.noise_activation);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_noise_activation(self.noise_activation);
-            }
-        }
+        self.send_param(Param::NoiseActive(self.noise_activation));
     }
 
     fn update_lfo_activation(&mut self) {
-        self.current_synth_type
-            .set_lfo_activation(self.lfo_activation);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_lfo_activation(self.lfo_activation);
-            }
-        }
+        self.send_param(Param::LfoActive(self.lfo_activation));
 
         // Mettre à jour la fréquence du LFO si l'activation change
         if self.lfo_activation {
@@ -1237,31 +2765,11 @@ impl SynthesizerApp {
 
     /// Met à jour la forme d'onde du LFO
     fn update_synth_lfo_waveform(&mut self) {
-        // Mettre à jour la forme d'onde dans le synthétiseur local
-        self.current_synth_type
-            .set_current_lfo_waveform(self.waveform);
-
-        // Mettre à jour aussi le synthétiseur dans le contrôleur audio
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_lfo_waveform(self.waveform);
-            }
-        }
+        self.send_param(Param::LfoWaveform(self.waveform));
     }
 
     fn update_synth_reverb_type(&mut self) {
-        self.current_synth_type
-            .set_current_reverb_type(self.reverb_type);
-
-        if let Some(ref synth_control) = self.synth_control {
-            if let Ok(mut synth) = synth_control.lock() {
-                synth.set_current_reverb_type(self.reverb_type);
-                println!(
-                    "Type de réverbération mis à jour dans le contrôleur audio: {:?}",
-                    self.reverb_type
-                );
-            }
-        }
+        self.send_param(Param::ReverbType(self.reverb_type));
     }
 
     /// Met à jour l'octave globale
@@ -1271,7 +2779,12 @@ impl SynthesizerApp {
         constants::CURRENT_OCTAVE_INDEX.store(octave_index, Ordering::Relaxed);
     }
 
+    /// Joue une note avec la vélocité fixe du clavier d'ordinateur
     fn play_note(&mut self, note_name: &str) {
+        self.play_note_with_velocity(note_name, self.velocity.keyboard_velocity);
+    }
+
+    fn play_note_with_velocity(&mut self, note_name: &str, velocity: f64) {
         // Créer une clé unique pour la note basée sur la note + octave
         let note_key = format!("{}_{}", note_name, self.current_octave);
 
@@ -1279,9 +2792,8 @@ impl SynthesizerApp {
         if !self.active_notes.contains(&note_key) {
             self.active_notes.insert(note_key.clone());
 
-            if let Some(ref notes) = self.notes {
-                let frequency = self.note_to_frequency(note_name);
-                self.add_note(notes, frequency);
+            if let Some(ref controller) = self.controller {
+                controller.note_on_with_velocity(self.note_to_frequency(note_name), velocity);
             }
         }
     }
@@ -1298,13 +2810,12 @@ impl SynthesizerApp {
         let still_pressed_virtual = self.pressed_notes.contains(&virtual_key);
 
         // Si aucun des deux claviers ne presse la note, l'arrêter
-        if !still_pressed_physical && !still_pressed_virtual {
-            if self.active_notes.remove(&note_key) {
-                if let Some(ref notes) = self.notes {
-                    let frequency = self.note_to_frequency(note_name);
-                    self.remove_note(notes, frequency);
-                }
-            }
+        if !still_pressed_physical
+            && !still_pressed_virtual
+            && self.active_notes.remove(&note_key)
+            && let Some(ref controller) = self.controller
+        {
+            controller.note_off(self.note_to_frequency(note_name));
         }
     }
 
@@ -1331,45 +2842,23 @@ impl SynthesizerApp {
         let octave = self.current_octave as u8;
 
         // Chercher la fréquence dans le système JSON
-        if let Some(octave_notes) = NOTES.0.get(&octave) {
-            if let Some(&frequency) = octave_notes.get(json_note) {
-                return frequency;
-            }
+        if let Some(octave_notes) = NOTES.0.get(&octave)
+            && let Some(&frequency) = octave_notes.get(json_note)
+        {
+            return frequency;
         }
         440.0 // If not found, return A4
     }
+}
 
-    /// Ajoute une note au système audio
-    fn add_note(&self, notes: &crate::audio::note_manager::ActiveNoteManager, frequency: f64) {
-        use crate::audio::note_manager::ActiveNote;
-        use crate::consts::constants::SAMPLE_RATE;
-
-        let frequency_key = (frequency * 100.0) as u64;
-        let mut active_note = ActiveNote::new(frequency, SAMPLE_RATE);
-
-        active_note.set_current_attack(self.attack);
-        active_note.set_current_decay(self.decay);
-        active_note.set_current_sustain(self.sustain);
-        active_note.set_current_release(self.release);
-
-        // CRUCIAL: Redémarrer note_on() APRÈS avoir mis à jour les paramètres
-        active_note.adsr.note_on();
-
-        if let Ok(mut notes_guard) = notes.lock() {
-            notes_guard.insert(frequency_key, active_note);
-        }
-    }
-
-    /// Supprime une note du système audio
-    fn remove_note(&self, notes: &crate::audio::note_manager::ActiveNoteManager, frequency: f64) {
-        let frequency_key = (frequency * 100.0) as u64;
-
-        if let Ok(mut notes_guard) = notes.lock() {
-            if let Some(note) = notes_guard.get_mut(&frequency_key) {
-                // Déclencher le release de l'ADSR au lieu de supprimer directement
-                note.adsr.note_off();
-            }
+/// Vélocité d'un clic sur une touche : plus le clic est bas, plus la note est forte
+fn click_velocity(response: &egui::Response) -> f64 {
+    let rect = response.rect;
+    match response.interact_pointer_pos() {
+        Some(pos) if rect.height() > 0.0 => {
+            (((pos.y - rect.top()) / rect.height()) as f64).clamp(0.1, 1.0)
         }
+        _ => constants::KEYBOARD_VELOCITY,
     }
 }
 
@@ -1377,3 +2866,20 @@ fn configure_fonts(ctx: &egui::Context) {
     let fonts = egui::FontDefinitions::default();
     ctx.set_fonts(fonts);
 }
+
+/// WAV files of the user wavetable directory, sorted by name
+fn user_wavetables() -> Vec<WavetableSource> {
+    let Ok(entries) = std::fs::read_dir(constants::USER_WAVETABLES_DIR) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+        })
+        .filter_map(|path| path.to_str().map(str::to_string))
+        .collect();
+    paths.sort();
+    paths.into_iter().map(WavetableSource::File).collect()
+}
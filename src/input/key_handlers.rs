@@ -1,12 +1,11 @@
-use crate::audio::note_manager;
-use crate::consts::constants::{CURRENT_OCTAVE_INDEX, VECTEUR_NOTES, SAMPLE_RATE};
+use crate::audio::controller::SynthController;
+use crate::consts::constants::{CURRENT_OCTAVE_INDEX, VECTEUR_NOTES};
 use crate::synths;
 use device_query::Keycode;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Deserialize)]
 pub struct Notes(pub HashMap<u8, HashMap<String, f64>>);
@@ -16,11 +15,7 @@ pub static NOTES: Lazy<Notes> = Lazy::new(|| {
     serde_json::from_str(json_str).expect("JSON invalide")
 });
 
-pub fn matching_key_pressed(
-    key: Keycode,
-    current_synth_type: &Arc<Mutex<synths::manager::SynthType>>,
-    note_manager: &note_manager::ActiveNoteManager,
-) {
+pub fn matching_key_pressed(key: Keycode, controller: &SynthController) {
     // Catch the octave and note to get the frequency in NOTES
     let get_frequency =
         |octave: u8, note: &str| -> Option<f64> { NOTES.0.get(&octave)?.get(note).copied() };
@@ -35,7 +30,7 @@ pub fn matching_key_pressed(
                     "Touche Q pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::B => {
@@ -44,7 +39,7 @@ pub fn matching_key_pressed(
                     "Touche B pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::C => {
@@ -53,7 +48,7 @@ pub fn matching_key_pressed(
                     "Touche C pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::D => {
@@ -62,7 +57,7 @@ pub fn matching_key_pressed(
                     "Touche D pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::E => {
@@ -71,7 +66,7 @@ pub fn matching_key_pressed(
                     "Touche E pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::F => {
@@ -80,7 +75,7 @@ pub fn matching_key_pressed(
                     "Touche F pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::G => {
@@ -89,7 +84,7 @@ pub fn matching_key_pressed(
                     "Touche G pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Key1 => {
@@ -98,7 +93,7 @@ pub fn matching_key_pressed(
                     "Touche 1 pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Key2 => {
@@ -107,7 +102,7 @@ pub fn matching_key_pressed(
                     "Touche 2 pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Key3 => {
@@ -116,7 +111,7 @@ pub fn matching_key_pressed(
                     "Touche 3 pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Key4 => {
@@ -125,7 +120,7 @@ pub fn matching_key_pressed(
                     "Touche 4 pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Key5 => {
@@ -134,33 +129,45 @@ pub fn matching_key_pressed(
                     "Touche 5 pressée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::add_note(note_manager, freq, SAMPLE_RATE);
+                controller.note_on(freq);
             }
         }
         Keycode::Space => {
             println!("Espace pressé - arrêt de toutes les notes");
-            note_manager::stop_all_notes(note_manager);
+            controller.all_notes_off();
         }
         Keycode::Z => {
-            *current_synth_type.lock().unwrap() = synths::manager::SynthType::n_sine();
+            controller.set_synth(synths::manager::SynthType::n_sine());
             println!("Synthétiseur changé: Modular Sine");
         }
         Keycode::X => {
-            *current_synth_type.lock().unwrap() = synths::manager::SynthType::n_square();
+            controller.set_synth(synths::manager::SynthType::n_square());
             println!("Synthétiseur changé: Modular Square");
         }
         Keycode::S => {
-            *current_synth_type.lock().unwrap() = synths::manager::SynthType::n_sawtooth();
+            controller.set_synth(synths::manager::SynthType::n_sawtooth());
             println!("Synthétiseur changé: Modular Sawtooth");
         }
         Keycode::K => {
-            *current_synth_type.lock().unwrap() = synths::manager::SynthType::n_fm();
+            controller.set_synth(synths::manager::SynthType::n_fm());
             println!("Synthétiseur changé: FM");
         }
         Keycode::H => {
-            *current_synth_type.lock().unwrap() = synths::manager::SynthType::n_hammond();
+            controller.set_synth(synths::manager::SynthType::n_hammond());
             println!("Synthétiseur changé: Hammond Organ");
         }
+        Keycode::M => {
+            controller.set_synth(synths::manager::SynthType::n_multi_fm());
+            println!("Synthétiseur changé: Multi FM");
+        }
+        Keycode::T => {
+            controller.set_synth(synths::manager::SynthType::n_wavetable());
+            println!("Synthétiseur changé: Wavetable");
+        }
+        Keycode::N => {
+            controller.set_synth(synths::manager::SynthType::n_noise());
+            println!("Synthétiseur changé: Noise");
+        }
         Keycode::Escape => {
             println!("\rAu revoir !");
             std::process::exit(0);
@@ -194,11 +201,7 @@ pub fn matching_key_pressed(
     }
 }
 
-pub fn matching_key_released(
-    key: Keycode,
-    _current_synth_type: &Arc<Mutex<synths::manager::SynthType>>,
-    note_manager: &note_manager::ActiveNoteManager,
-) {
+pub fn matching_key_released(key: Keycode, controller: &SynthController) {
     let get_frequency =
         |octave: u8, note: &str| -> Option<f64> { NOTES.0.get(&octave)?.get(note).copied() };
 
@@ -212,7 +215,7 @@ pub fn matching_key_released(
                     "Touche Q relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::B => {
@@ -221,7 +224,7 @@ pub fn matching_key_released(
                     "Touche B relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::C => {
@@ -230,7 +233,7 @@ pub fn matching_key_released(
                     "Touche C relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::D => {
@@ -239,7 +242,7 @@ pub fn matching_key_released(
                     "Touche D relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::E => {
@@ -248,7 +251,7 @@ pub fn matching_key_released(
                     "Touche E relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::F => {
@@ -257,7 +260,7 @@ pub fn matching_key_released(
                     "Touche F relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::G => {
@@ -266,7 +269,7 @@ pub fn matching_key_released(
                     "Touche G relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::Key1 => {
@@ -275,7 +278,7 @@ pub fn matching_key_released(
                     "Touche 1 relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::Key2 => {
@@ -284,7 +287,7 @@ pub fn matching_key_released(
                     "Touche 2 relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::Key3 => {
@@ -293,7 +296,7 @@ pub fn matching_key_released(
                     "Touche 3 relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::Key4 => {
@@ -302,7 +305,7 @@ pub fn matching_key_released(
                     "Touche 4 relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         Keycode::Key5 => {
@@ -311,7 +314,7 @@ pub fn matching_key_released(
                     "Touche 5 relâchée - octave: {} - fréquence: {}",
                     current_octave, freq
                 );
-                note_manager::release_note(note_manager, freq);
+                controller.note_off(freq);
             }
         }
         _ => {}
@@ -1,32 +1,29 @@
-use crate::audio::note_manager;
+use crate::audio::controller::SynthController;
 use crate::input::key_handlers;
-use crate::synths;
 use device_query::{DeviceQuery, DeviceState, Keycode};
 use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 /// Manages key inputs and synthesizer actions based on key events.
 pub fn key_management(
     device_state: &DeviceState,
     previous_keys: &mut HashSet<Keycode>,
-    current_synth_type: &Arc<Mutex<synths::manager::SynthType>>,
-    note_manager: &note_manager::ActiveNoteManager,
+    controller: &SynthController,
 ) {
     let keys: HashSet<Keycode> = device_state.get_keys().into_iter().collect();
 
     // Check for pressed keys
     for key in keys.difference(previous_keys) {
-        key_handlers::matching_key_pressed(*key, current_synth_type, note_manager);
+        key_handlers::matching_key_pressed(*key, controller);
     }
 
     // Check for released keys
     for key in previous_keys.difference(&keys) {
-        key_handlers::matching_key_released(*key, current_synth_type, note_manager);
+        key_handlers::matching_key_released(*key, controller);
     }
 
-    // Clean up finished notes
-    note_manager::cleanup_finished_notes(note_manager);
+    // Free the notes and synthesizers the audio thread is done with
+    controller.collect_garbage();
     *previous_keys = keys;
 
     // Small sleep to avoid busy-waiting
@@ -0,0 +1,310 @@
+use crate::audio::controller::SynthController;
+use crate::consts::constants::PROJECT_NAME;
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use std::collections::HashSet;
+
+// Control change numbers
+const CC_MOD_WHEEL: u8 = 1;
+const CC_SUSTAIN: u8 = 64;
+const CC_ALL_NOTES_OFF: u8 = 123;
+const SUSTAIN_THRESHOLD: u8 = 64; // la pédale est enfoncée à partir de cette valeur
+
+/// Brings a 7-bit data byte to 0..1
+fn normalize(value: u8) -> f64 {
+    value.min(127) as f64 / 127.0
+}
+
+/// Channel messages understood by the synthesizer (the channel is ignored)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiMessage {
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+    ControlChange { controller: u8, value: u8 },
+    PitchBend(f64),  // -1..1
+    Aftertouch(f64), // pression du canal, 0..1
+}
+
+impl MidiMessage {
+    /// Decodes a raw MIDI message, returns None for the unsupported ones
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (&status, data) = bytes.split_first()?;
+        match (status & 0xF0, data) {
+            // Un note on de vélocité nulle est un note off
+            (0x90, &[note, 0, ..]) | (0x80, &[note, _, ..]) => Some(MidiMessage::NoteOff { note }),
+            (0x90, &[note, velocity, ..]) => Some(MidiMessage::NoteOn { note, velocity }),
+            (0xB0, &[controller, value, ..]) => {
+                Some(MidiMessage::ControlChange { controller, value })
+            }
+            (0xE0, &[lsb, msb, ..]) => {
+                // 14 bits centrés sur 8192, chaque côté ramené à une amplitude de 1
+                let value = (((msb & 0x7F) as i32) << 7 | (lsb & 0x7F) as i32) - 8192;
+                let range = if value > 0 { 8191.0 } else { 8192.0 };
+                Some(MidiMessage::PitchBend(value as f64 / range))
+            }
+            (0xD0, &[pressure, ..]) => Some(MidiMessage::Aftertouch(normalize(pressure))),
+            _ => None,
+        }
+    }
+}
+
+/// Equal-tempered frequency of a MIDI note (69 = A4 = 440 Hz)
+pub fn note_to_frequency(note: u8) -> f64 {
+    440.0 * 2.0_f64.powf((note as f64 - 69.0) / 12.0)
+}
+
+/// Turns MIDI messages into controller calls and keeps the sustain pedal state
+pub struct MidiHandler {
+    controller: SynthController,
+    sustain: bool,
+    sustained: HashSet<u8>, // notes relâchées pendant que la pédale est enfoncée
+}
+
+impl MidiHandler {
+    pub fn new(controller: SynthController) -> Self {
+        Self {
+            controller,
+            sustain: false,
+            sustained: HashSet::new(),
+        }
+    }
+
+    pub fn handle(&mut self, message: MidiMessage) {
+        match message {
+            MidiMessage::NoteOn { note, velocity } => {
+                self.sustained.remove(&note);
+                self.controller
+                    .note_on_with_velocity(note_to_frequency(note), normalize(velocity));
+            }
+            MidiMessage::NoteOff { note } => {
+                if self.sustain {
+                    self.sustained.insert(note);
+                } else {
+                    self.controller.note_off(note_to_frequency(note));
+                }
+            }
+            MidiMessage::ControlChange { controller, value } => {
+                self.handle_control_change(controller, value)
+            }
+            MidiMessage::PitchBend(bend) => self.controller.pitch_bend(bend),
+            MidiMessage::Aftertouch(pressure) => self.controller.aftertouch(pressure),
+        }
+        self.controller.collect_garbage();
+    }
+
+    fn handle_control_change(&mut self, controller: u8, value: u8) {
+        match controller {
+            CC_MOD_WHEEL => self.controller.mod_wheel(normalize(value)),
+            CC_SUSTAIN => {
+                self.sustain = value >= SUSTAIN_THRESHOLD;
+                if !self.sustain {
+                    for note in self.sustained.drain() {
+                        self.controller.note_off(note_to_frequency(note));
+                    }
+                }
+            }
+            CC_ALL_NOTES_OFF => {
+                self.sustained.clear();
+                self.controller.all_notes_off();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Open MIDI connections, closed when dropped
+pub struct MidiInputs {
+    connections: Vec<MidiInputConnection<MidiHandler>>,
+}
+
+impl MidiInputs {
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+}
+
+/// Connects every available MIDI input port to the synthesizer.
+/// On Unix a virtual input port named after the project is also created, so other
+/// programs (virtual keyboards, sequencers, `aconnect`) can play the synthesizer.
+pub fn connect_midi_inputs(
+    controller: &SynthController,
+) -> Result<MidiInputs, Box<dyn std::error::Error>> {
+    let mut connections = Vec::new();
+
+    let ports = new_midi_input()?.ports();
+    for port in &ports {
+        let input = new_midi_input()?;
+        let port_name = input.port_name(port).unwrap_or_default();
+        // Ne pas se connecter à notre propre port virtuel
+        if port_name.starts_with(PROJECT_NAME) {
+            continue;
+        }
+        match input.connect(
+            port,
+            PROJECT_NAME,
+            on_midi_message,
+            MidiHandler::new(controller.clone()),
+        ) {
+            Ok(connection) => {
+                println!("Entrée MIDI connectée : {port_name}");
+                connections.push(connection);
+            }
+            Err(err) => eprintln!("Warning: failed to connect MIDI input {port_name}: {err}"),
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use midir::os::unix::VirtualInput;
+
+        match new_midi_input()?.create_virtual(
+            PROJECT_NAME,
+            on_midi_message,
+            MidiHandler::new(controller.clone()),
+        ) {
+            Ok(connection) => {
+                println!("Port MIDI virtuel créé : {PROJECT_NAME}");
+                connections.push(connection);
+            }
+            Err(err) => eprintln!("Warning: failed to create virtual MIDI port: {err}"),
+        }
+    }
+
+    Ok(MidiInputs { connections })
+}
+
+fn new_midi_input() -> Result<MidiInput, midir::InitError> {
+    let mut input = MidiInput::new(PROJECT_NAME)?;
+    input.ignore(Ignore::All);
+    Ok(input)
+}
+
+fn on_midi_message(_timestamp: u64, bytes: &[u8], handler: &mut MidiHandler) {
+    if let Some(message) = MidiMessage::parse(bytes) {
+        handler.handle(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::engine;
+    use crate::synths::manager::SynthType;
+
+    #[test]
+    fn note_on_with_zero_velocity_is_a_note_off() {
+        assert_eq!(
+            MidiMessage::parse(&[0x90, 60, 0]),
+            Some(MidiMessage::NoteOff { note: 60 })
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0x80, 60, 64]),
+            Some(MidiMessage::NoteOff { note: 60 })
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0x90, 60, 100]),
+            Some(MidiMessage::NoteOn {
+                note: 60,
+                velocity: 100
+            })
+        );
+    }
+
+    #[test]
+    fn channel_bits_are_ignored() {
+        for channel in 0..16 {
+            assert_eq!(
+                MidiMessage::parse(&[0x90 | channel, 64, 90]),
+                Some(MidiMessage::NoteOn {
+                    note: 64,
+                    velocity: 90
+                })
+            );
+            assert_eq!(
+                MidiMessage::parse(&[0xB0 | channel, CC_SUSTAIN, 127]),
+                Some(MidiMessage::ControlChange {
+                    controller: CC_SUSTAIN,
+                    value: 127
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn pitch_bend_spans_minus_one_to_one() {
+        let bend = |lsb, msb| match MidiMessage::parse(&[0xE3, lsb, msb]) {
+            Some(MidiMessage::PitchBend(bend)) => bend,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(bend(0x00, 0x00), -1.0);
+        assert_eq!(bend(0x00, 0x40), 0.0);
+        assert_eq!(bend(0x7F, 0x7F), 1.0);
+        // Le LSB compte : un pas de 14 bits sous le centre
+        assert_eq!(bend(0x7F, 0x3F), -1.0 / 8192.0);
+    }
+
+    #[test]
+    fn channel_pressure_and_mod_wheel_scale_to_one() {
+        assert_eq!(
+            MidiMessage::parse(&[0xD0, 127]),
+            Some(MidiMessage::Aftertouch(1.0))
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0xD5, 0]),
+            Some(MidiMessage::Aftertouch(0.0))
+        );
+        assert_eq!(normalize(0), 0.0);
+        assert_eq!(normalize(127), 1.0);
+        assert!((normalize(64) - 0.504).abs() < 1e-3);
+    }
+
+    #[test]
+    fn truncated_or_unsupported_messages_are_ignored() {
+        for bytes in [
+            &[][..],
+            &[0x90],
+            &[0x90, 60],
+            &[0x80, 60],
+            &[0xB0, 1],
+            &[0xE0, 0],
+            &[0xD0],
+            &[0xA0, 60, 10], // pression polyphonique
+            &[0xF8],         // horloge
+        ] {
+            assert_eq!(MidiMessage::parse(bytes), None, "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn sustain_pedal_holds_released_notes_from_64() {
+        let (controller, _engine) = engine::create_engine(SynthType::n_sine(), 44100.0);
+        let mut handler = MidiHandler::new(controller);
+        handler.handle(MidiMessage::NoteOn {
+            note: 60,
+            velocity: 100,
+        });
+
+        handler.handle(MidiMessage::ControlChange {
+            controller: CC_SUSTAIN,
+            value: SUSTAIN_THRESHOLD - 1,
+        });
+        assert!(!handler.sustain);
+        handler.handle(MidiMessage::ControlChange {
+            controller: CC_SUSTAIN,
+            value: SUSTAIN_THRESHOLD,
+        });
+        assert!(handler.sustain);
+
+        handler.handle(MidiMessage::NoteOff { note: 60 });
+        assert!(handler.sustained.contains(&60));
+        handler.handle(MidiMessage::ControlChange {
+            controller: CC_SUSTAIN,
+            value: 0,
+        });
+        assert!(!handler.sustain);
+        assert!(handler.sustained.is_empty());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod key_handlers;
-pub mod key_logic;
\ No newline at end of file
+pub mod key_logic;
+pub mod midi;
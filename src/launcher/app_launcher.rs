@@ -1,37 +1,154 @@
-use crate::audio::{note_manager, setup_realtime_audio};
-use crate::consts::constants::PROJECT_NAME;
+use crate::audio::controller::SynthController;
+use crate::audio::offline_render::{self, NoteEvent, RenderSettings, WavFormat};
+use crate::audio::setup_realtime_audio;
+use crate::consts::constants::{self, PROJECT_NAME};
 use crate::gui::SynthesizerApp;
-use crate::input::key_logic;
+use crate::input::key_handlers::NOTES;
+use crate::input::{key_logic, midi};
+use crate::presets::{Preset, PresetError, bank};
+use crate::synths::modules::compressor::CompressorSettings;
+use crate::synths::param::Param;
 use crate::{prints, synths};
 use device_query::DeviceState;
 use display_info::DisplayInfo;
 use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
 
 /// Launch the terminal application
 pub fn launch_terminal_application() -> Result<(), Box<dyn std::error::Error>> {
-
-    let (note_manager, current_synth_type) = prepare_audio();
+    let preset = preset_from_args()?;
+    let controller = prepare_audio(preset.as_ref());
+    let _midi = connect_midi(&controller);
     prints::printfn::print_intro();
 
     let device_state = DeviceState::new();
     let mut previous_keys = HashSet::new();
+    let mut clipped_samples = 0;
 
     loop {
-        key_logic::key_management(
-            &device_state,
-            &mut previous_keys,
-            &current_synth_type,
-            &note_manager,
-        );
+        key_logic::key_management(&device_state, &mut previous_keys, &controller);
+
+        // Signale les échantillons écrêtés en sortie depuis le dernier tour
+        let clipped = controller.get_clipped_samples();
+        if clipped > clipped_samples {
+            prints::printfn::print_clip(clipped - clipped_samples, controller.get_output_peak());
+            clipped_samples = clipped;
+        }
+    }
+}
+
+/// Launch the offline renderer: plays a list of timed notes into a WAV file, no audio device needed
+pub fn launch_render_application() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let mut output = None;
+    let mut synth = synths::manager::SynthType::n_sine();
+    let mut preset = None;
+    let mut events_path = None;
+    let mut sidechain_path = None;
+    let mut settings = RenderSettings::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {arg}"));
+        match arg.as_str() {
+            "--synth" => {
+                let name = value()?;
+                synth = synths::manager::SynthType::from_name(&name)
+                    .ok_or(format!("unknown synthesizer: {name}"))?;
+            }
+            "--preset" => preset = Some(bank::load_preset_argument(&value()?)?),
+            "--format" => {
+                let name = value()?;
+                settings.format =
+                    WavFormat::from_name(&name).ok_or(format!("unknown WAV format: {name}"))?;
+            }
+            "--sample-rate" => settings.sample_rate = value()?.parse()?,
+            "--tail" => settings.tail = value()?.parse()?,
+            "--events" => events_path = Some(value()?),
+            "--sidechain" => sidechain_path = Some(value()?),
+            "-h" | "--help" => {
+                prints::printfn::print_render_usage();
+                return Ok(());
+            }
+            _ if output.is_none() && !arg.starts_with('-') => output = Some(arg),
+            _ => return Err(format!("unexpected argument: {arg}").into()),
+        }
     }
+
+    let Some(output) = output else {
+        prints::printfn::print_render_usage();
+        return Err("missing output file".into());
+    };
+
+    let events: Vec<NoteEvent> = match events_path {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => demo_events(),
+    };
+
+    // Le compresseur écoute le fichier de sidechain s'il y en a un, sinon son entrée
+    let key = match sidechain_path {
+        Some(path) => {
+            let (key, sample_rate) = offline_render::read_wav(&path)?;
+            if sample_rate != settings.sample_rate {
+                return Err(format!(
+                    "sidechain {path} is at {sample_rate} Hz, the render at {} Hz",
+                    settings.sample_rate
+                )
+                .into());
+            }
+            key
+        }
+        None => Vec::new(),
+    };
+    let sidechain = !key.is_empty();
+
+    let frames = match preset {
+        Some(preset) => offline_render::render_preset_with_sidechain(
+            &preset.with_sidechain(sidechain),
+            &events,
+            &key,
+            &settings,
+        ),
+        None => {
+            synth.apply_param(Param::Compressor(CompressorSettings {
+                sidechain,
+                ..synth.get_compressor()
+            }));
+            offline_render::render_with_sidechain(synth, &events, &key, &settings)
+        }
+    };
+    offline_render::write_wav(&output, &frames, settings.sample_rate, settings.format)?;
+    println!("Rendu écrit dans {output}");
+    Ok(())
+}
+
+/// Accord de do majeur puis arpège, joué quand aucun fichier d'événements n'est donné
+fn demo_events() -> Vec<NoteEvent> {
+    let frequency = |note: &str| NOTES.0.get(&4).and_then(|octave| octave.get(note).copied());
+    let chord = ["C", "E", "G"].map(|note| frequency(note).unwrap_or(440.0));
+
+    let mut events: Vec<NoteEvent> = chord
+        .iter()
+        .map(|&freq| NoteEvent::new(0.0, 1.0, freq))
+        .collect();
+    events.extend(
+        chord
+            .iter()
+            .enumerate()
+            .map(|(i, &freq)| NoteEvent::new(1.5 + i as f64 * 0.25, 0.25, freq)),
+    );
+    events
 }
 
 /// Launch the GUI application
 pub fn launch_gui_application() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
-    let (note_manager, current_synth_type) = prepare_audio();
+    let preset = preset_from_args().unwrap_or_else(|err| {
+        eprintln!("Warning: {err}, starting with the default synthesizer");
+        None
+    });
+    let controller = prepare_audio(preset.as_ref());
+    let _midi = connect_midi(&controller);
 
     // Détection multiplateforme de la taille d'écran principale
     let display = DisplayInfo::all()
@@ -96,29 +213,46 @@ pub fn launch_gui_application() -> eframe::Result<()> {
             cc.egui_ctx.set_style(style);
 
             Ok(Box::new(
-                SynthesizerApp::new(cc)
-                    .with_audio(note_manager)
-                    .with_synth_control(current_synth_type),
+                SynthesizerApp::new(cc).with_controller(controller),
             ))
         }),
     )?;
     Ok(())
 }
 
-fn prepare_audio() -> (
-    note_manager::ActiveNoteManager,
-    Arc<Mutex<synths::manager::SynthType>>,
-) {
-    let note_manager = note_manager::create_note_manager();
-    let current_synth_type: Arc<Mutex<synths::manager::SynthType>> =
-        Arc::new(Mutex::new(synths::manager::SynthType::n_sine()));
+fn prepare_audio(preset: Option<&Preset>) -> SynthController {
+    // Run the audio output in a separate thread, driven through the returned controller
+    let controller =
+        setup_realtime_audio::run_output_polyphonic_realtime(synths::manager::SynthType::n_sine());
 
-    // Clone for the audio thread
-    let notes_clone = Arc::clone(&note_manager);
-    let synth_type_clone = Arc::clone(&current_synth_type);
+    if let Some(preset) = preset {
+        // Pas de signal de sidechain en direct
+        controller.load_preset(&preset.with_sidechain(false));
+        constants::CURRENT_OCTAVE_INDEX.store(preset.octave_index(), Ordering::Relaxed);
+        println!("Preset chargé : {}", preset.name);
+    }
+    controller
+}
 
-    // Run the audio output in a separate thread
-    setup_realtime_audio::run_output_polyphonic_realtime(notes_clone, synth_type_clone);
+/// Reads the `--preset <name|file.json>` flag shared by every binary
+fn preset_from_args() -> Result<Option<Preset>, PresetError> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--preset" {
+            let value = args.next().ok_or(PresetError::InvalidName(String::new()))?;
+            return bank::load_preset_argument(&value).map(Some);
+        }
+    }
+    Ok(None)
+}
 
-    (note_manager, current_synth_type)
-}
\ No newline at end of file
+/// MIDI is optional: without a MIDI system the synthesizer is still played from the keyboard
+fn connect_midi(controller: &SynthController) -> Option<midi::MidiInputs> {
+    match midi::connect_midi_inputs(controller) {
+        Ok(inputs) => Some(inputs),
+        Err(err) => {
+            eprintln!("Warning: MIDI unavailable: {err}");
+            None
+        }
+    }
+}
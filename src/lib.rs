@@ -1,7 +1,8 @@
-pub mod synths;
+pub mod audio;
 pub mod consts;
-pub mod prints;
+pub mod gui;
 pub mod input;
-pub mod audio;
 pub mod launcher;
-pub mod gui;
\ No newline at end of file
+pub mod presets;
+pub mod prints;
+pub mod synths;
@@ -0,0 +1,222 @@
+use crate::consts::constants::USER_PRESETS_DIR;
+use crate::presets::preset::{Preset, PresetError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Factory presets, bundled in the binary
+const FACTORY_PRESETS: [&str; 7] = [
+    include_str!("../../res/presets/init.json"),
+    include_str!("../../res/presets/warm_pad.json"),
+    include_str!("../../res/presets/square_lead.json"),
+    include_str!("../../res/presets/fm_bell.json"),
+    include_str!("../../res/presets/fm_piano.json"),
+    include_str!("../../res/presets/organ.json"),
+    include_str!("../../res/presets/wind.json"),
+];
+
+/// Factory presets followed by the user presets stored as JSON files in a directory
+pub struct PresetBank {
+    directory: PathBuf,
+}
+
+impl Default for PresetBank {
+    fn default() -> Self {
+        Self::new(USER_PRESETS_DIR)
+    }
+}
+
+impl PresetBank {
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Factory presets, parsed on first use only
+    fn factory() -> &'static [Preset] {
+        static FACTORY: OnceLock<Vec<Preset>> = OnceLock::new();
+        FACTORY.get_or_init(|| {
+            FACTORY_PRESETS
+                .iter()
+                .map(|json| Preset::from_json(json).expect("invalid factory preset"))
+                .collect()
+        })
+    }
+
+    pub fn factory_presets() -> Vec<Preset> {
+        Self::factory().to_vec()
+    }
+
+    pub fn is_factory(name: &str) -> bool {
+        Self::factory().iter().any(|preset| preset.name == name)
+    }
+
+    /// Names of the user presets, sorted
+    pub fn user_preset_names(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return Vec::new(); // Pas encore de dossier : aucun preset utilisateur
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Loads a preset by name, user presets first then the factory bank
+    pub fn load(&self, name: &str) -> Result<Preset, PresetError> {
+        if let Ok(path) = self.path_of(name)
+            && path.exists()
+        {
+            return load_file(&path);
+        }
+        Self::factory()
+            .iter()
+            .find(|preset| preset.name == name)
+            .cloned()
+            .ok_or_else(|| PresetError::NotFound(name.to_string()))
+    }
+
+    /// Writes a preset under its normalized name, replacing a user preset with the same name
+    pub fn save(&self, preset: &Preset) -> Result<(), PresetError> {
+        let name = normalize(&preset.name)?;
+        if Self::is_factory(name) {
+            return Err(PresetError::ReadOnly(name.to_string()));
+        }
+        let path = self.path_of(name)?;
+        let mut preset = preset.clone();
+        preset.name = name.to_string();
+        fs::create_dir_all(&self.directory)?;
+        fs::write(path, preset.to_json()?)?;
+        Ok(())
+    }
+
+    /// Renames a user preset, without overwriting another user preset
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<(), PresetError> {
+        if Self::is_factory(name) {
+            return Err(PresetError::ReadOnly(name.to_string()));
+        }
+        let path = self.path_of(name)?;
+        if !path.exists() {
+            return Err(PresetError::NotFound(name.to_string()));
+        }
+        let new_path = self.path_of(new_name)?;
+        if new_path != path && new_path.exists() {
+            return Err(PresetError::AlreadyExists(normalize(new_name)?.to_string()));
+        }
+
+        let mut preset = load_file(&path)?;
+        preset.name = new_name.to_string();
+        self.save(&preset)?;
+        if path != new_path {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), PresetError> {
+        if Self::is_factory(name) {
+            return Err(PresetError::ReadOnly(name.to_string()));
+        }
+        let path = self.path_of(name)?;
+        if !path.exists() {
+            return Err(PresetError::NotFound(name.to_string()));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// File of a user preset
+    fn path_of(&self, name: &str) -> Result<PathBuf, PresetError> {
+        Ok(self.directory.join(format!("{}.json", normalize(name)?)))
+    }
+}
+
+/// Name a user preset is stored under: trimmed, without path separators
+fn normalize(name: &str) -> Result<&str, PresetError> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(PresetError::InvalidName(name.to_string()));
+    }
+    Ok(name)
+}
+
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Preset, PresetError> {
+    Preset::from_json(&fs::read_to_string(path)?)
+}
+
+/// Resolves the value of a `--preset` flag: a JSON file path or a preset name
+pub fn load_preset_argument(argument: &str) -> Result<Preset, PresetError> {
+    let path = Path::new(argument);
+    if path.extension().is_some_and(|ext| ext == "json") || path.is_file() {
+        return load_file(path);
+    }
+    PresetBank::default().load(argument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_factory_preset_parses_and_builds() {
+        let presets = PresetBank::factory_presets();
+        assert_eq!(presets.len(), FACTORY_PRESETS.len());
+        for preset in &presets {
+            preset.to_synth();
+            assert!(PresetBank::is_factory(&preset.name));
+        }
+        let mut names: Vec<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(
+            names.len(),
+            presets.len(),
+            "two factory presets share a name"
+        );
+    }
+
+    #[test]
+    fn factory_presets_are_read_only() {
+        let bank = PresetBank::new(std::env::temp_dir().join("preset_bank_read_only"));
+        let name = PresetBank::factory_presets()[0].name.clone();
+        assert!(matches!(bank.delete(&name), Err(PresetError::ReadOnly(_))));
+        assert!(matches!(
+            bank.rename(&name, "Other"),
+            Err(PresetError::ReadOnly(_))
+        ));
+        assert!(!PresetBank::is_factory("Not a factory preset"));
+    }
+
+    #[test]
+    fn user_presets_are_saved_trimmed_and_never_renamed_over_another() {
+        let directory = std::env::temp_dir().join("preset_bank_rename");
+        let _ = fs::remove_dir_all(&directory);
+        let bank = PresetBank::new(&directory);
+        let mut preset = PresetBank::factory_presets()[0].clone();
+        preset.name = "  Lead  ".to_string();
+        bank.save(&preset).unwrap();
+        preset.name = "Pad".to_string();
+        bank.save(&preset).unwrap();
+        assert_eq!(bank.user_preset_names(), ["Lead", "Pad"]);
+        assert_eq!(bank.load("Lead").unwrap().name, "Lead");
+
+        assert!(matches!(
+            bank.rename("Lead", " Pad "),
+            Err(PresetError::AlreadyExists(name)) if name == "Pad"
+        ));
+        assert_eq!(bank.user_preset_names(), ["Lead", "Pad"]);
+        assert_eq!(bank.load("Pad").unwrap().name, "Pad");
+
+        bank.rename("Lead", " Bass ").unwrap();
+        assert_eq!(bank.user_preset_names(), ["Bass", "Pad"]);
+        assert_eq!(bank.load("Bass").unwrap().name, "Bass");
+        bank.rename("Bass", "Bass").unwrap();
+        assert_eq!(bank.user_preset_names(), ["Bass", "Pad"]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
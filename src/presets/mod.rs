@@ -0,0 +1,5 @@
+pub mod bank;
+pub mod preset;
+
+pub use bank::PresetBank;
+pub use preset::{Preset, PresetError};
@@ -0,0 +1,717 @@
+use crate::audio::voice_allocator::VoiceSettings;
+use crate::consts::constants;
+use crate::synths::manager::SynthType;
+use crate::synths::modular::{ModularSynth, ModuleSlot};
+use crate::synths::modulation::ModMatrix;
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::modules::compressor::{CompressorDetector, CompressorSettings};
+use crate::synths::modules::delay::DelaySettings;
+use crate::synths::modules::distortion::DistortionSettings;
+use crate::synths::modules::filter::{FilterMode, FilterModulation, FilterSlope};
+use crate::synths::modules::leslie::LeslieSpeed;
+use crate::synths::modules::lfo::LfoWaveform;
+use crate::synths::modules::limiter::LimiterSettings;
+use crate::synths::modules::modulated::{ModEffectKind, ModEffectSettings};
+use crate::synths::modules::noise::NoiseColor;
+use crate::synths::modules::reverb::ReverbType;
+use crate::synths::modules::{
+    Compressor, Delay, Distortion, Gain, LFO, Leslie, Limiter, MultiModeFilter, Noise, Reverb,
+};
+use crate::synths::oscillators::hammond::HammondRegistration;
+use crate::synths::oscillators::multi_fm::FmPatch;
+use crate::synths::oscillators::wavetable::{Wavetable, WavetableSettings, WavetableSource};
+use crate::synths::oscillators::{
+    FmOscillator, HammondOscillator, MultiFmOscillator, NoiseOscillator, SawtoothOscillator,
+    SineOscillator, SquareOscillator, WavetableOscillator,
+};
+use crate::synths::pitch::PitchSettings;
+use crate::synths::traits::{Module, Oscillator};
+use crate::synths::unison::UnisonSettings;
+use crate::synths::velocity::VelocitySettings;
+use serde::{Deserialize, Serialize};
+
+/// Version written in every preset. Bump it when a change cannot be read by older builds;
+/// new optional fields only need `#[serde(default)]`.
+/// 2: module kinds added after the first release (multi-mode filter, distortion, delay,
+/// chorus/flanger/phaser, limiter).
+pub const PRESET_VERSION: u32 = 2;
+
+/// Complete synthesizer state: oscillator, both module chains, envelope and octave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub version: u32,
+    pub name: String,
+    pub oscillator: OscillatorPreset,
+    #[serde(default = "default_amplitude")]
+    pub amplitude: f64,
+    #[serde(default)]
+    pub unison: UnisonSettings,
+    /// Per-voice chain, in processing order
+    pub modules: Vec<ModuleSlotPreset>,
+    /// Master bus, in processing order
+    #[serde(default)]
+    pub master: Vec<ModuleSlotPreset>,
+    #[serde(default)]
+    pub modulation: ModMatrix,
+    #[serde(default)]
+    pub pitch: PitchSettings,
+    #[serde(default = "default_tempo")]
+    pub tempo: f64,
+    pub envelope: EnvelopePreset,
+    #[serde(default)]
+    pub velocity: VelocitySettings,
+    #[serde(default)]
+    pub voices: VoiceSettings,
+    #[serde(default = "default_octave")]
+    pub octave: u8,
+}
+
+fn default_amplitude() -> f64 {
+    1.0
+}
+
+fn default_tempo() -> f64 {
+    constants::DEFAULT_TEMPO
+}
+
+fn default_octave() -> u8 {
+    constants::VECTEUR_NOTES[constants::DEFAULT_OCTAVE_INDEX]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_noise_color() -> NoiseColor {
+    constants::CURRENT_NOISE_COLOR
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OscillatorPreset {
+    Sine,
+    Square {
+        #[serde(default = "default_true")]
+        band_limited: bool,
+    },
+    Sawtooth {
+        #[serde(default = "default_true")]
+        band_limited: bool,
+    },
+    FM {
+        mod_index: f64,
+        mod_ratio: f64,
+    },
+    Hammond {
+        #[serde(default)]
+        registration: HammondRegistration,
+    },
+    MultiFM {
+        #[serde(default)]
+        patch: Box<FmPatch>,
+    },
+    Wavetable {
+        source: WavetableSource,
+        #[serde(default)]
+        settings: WavetableSettings,
+    },
+    Noise {
+        #[serde(default = "default_noise_color")]
+        color: NoiseColor,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopePreset {
+    pub attack: f64,
+    pub decay: f64,
+    pub sustain: f64,
+    pub release: f64,
+}
+
+/// A module of a chain together with its activation state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleSlotPreset {
+    #[serde(default = "default_true")]
+    pub active: bool,
+    #[serde(flatten)]
+    pub module: ModulePreset,
+}
+
+/// Parameters of every module that can be saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ModulePreset {
+    Gain {
+        gain_db: f64,
+        #[serde(default)]
+        pan: f64,
+    },
+    Noise {
+        amount: f64,
+        #[serde(default = "default_noise_color")]
+        color: NoiseColor,
+    },
+    LFO {
+        waveform: LfoWaveform,
+        frequency: f64,
+        amplitude: f64,
+        offset: f64,
+        bipolar: bool,
+    },
+    /// Filter of the presets written before the multi-mode filter, loaded as a 12 dB low-pass
+    LowPassFilter {
+        cutoff: f64,
+        resonance: f64,
+    },
+    Filter {
+        mode: FilterMode,
+        slope: FilterSlope,
+        cutoff: f64,
+        resonance: f64,
+        #[serde(default)]
+        gain_db: f64,
+        #[serde(default)]
+        modulation: FilterModulation,
+    },
+    Distortion {
+        settings: DistortionSettings,
+    },
+    Compressor {
+        threshold_db: f64,
+        ratio: f64,
+        attack: f64,
+        release: f64,
+        makeup_gain_db: f64,
+        #[serde(default)]
+        knee_db: f64,
+        #[serde(default)]
+        detector: CompressorDetector,
+        #[serde(default)]
+        lookahead_ms: f64,
+        #[serde(default)]
+        sidechain: bool,
+    },
+    Reverb {
+        reverb_type: ReverbType,
+        dry_wet: f64,
+        early_gain: f64,
+        tail_gain: f64,
+        pre_delay_ms: f64,
+    },
+    Leslie {
+        speed: LeslieSpeed,
+    },
+    Delay {
+        settings: DelaySettings,
+    },
+    Limiter {
+        settings: LimiterSettings,
+    },
+    /// Chorus, flanger or phaser
+    ModEffect {
+        kind: ModEffectKind,
+        settings: ModEffectSettings,
+    },
+}
+
+impl ModulePreset {
+    /// Reads the parameters of a module, None if this module cannot be saved
+    pub fn from_module(module: &dyn Module) -> Option<Self> {
+        let any = module.as_any();
+        if let Some(gain) = any.downcast_ref::<Gain>() {
+            return Some(ModulePreset::Gain {
+                gain_db: gain.get_gain(),
+                pan: gain.get_pan(),
+            });
+        }
+        if let Some(noise) = any.downcast_ref::<Noise>() {
+            return Some(ModulePreset::Noise {
+                amount: noise.get_amount(),
+                color: noise.get_color(),
+            });
+        }
+        if let Some(lfo) = any.downcast_ref::<LFO>() {
+            return Some(ModulePreset::LFO {
+                waveform: lfo.get_waveform(),
+                frequency: lfo.get_freq(),
+                amplitude: lfo.get_amplitude(),
+                offset: lfo.get_offset(),
+                bipolar: lfo.get_bipolar(),
+            });
+        }
+        if let Some(filter) = any.downcast_ref::<MultiModeFilter>() {
+            return Some(ModulePreset::Filter {
+                mode: filter.get_mode(),
+                slope: filter.get_slope(),
+                cutoff: filter.get_cutoff_freq(),
+                resonance: filter.get_resonance(),
+                gain_db: filter.get_gain(),
+                modulation: filter.get_modulation(),
+            });
+        }
+        if let Some(distortion) = any.downcast_ref::<Distortion>() {
+            return Some(ModulePreset::Distortion {
+                settings: distortion.get_settings(),
+            });
+        }
+        if let Some(compressor) = any.downcast_ref::<Compressor>() {
+            return Some(ModulePreset::Compressor {
+                threshold_db: compressor.get_threshold(),
+                ratio: compressor.get_ratio(),
+                attack: compressor.get_attack(),
+                release: compressor.get_release(),
+                makeup_gain_db: compressor.get_makeup_gain(),
+                knee_db: compressor.get_knee(),
+                detector: compressor.get_detector(),
+                lookahead_ms: compressor.get_lookahead(),
+                sidechain: compressor.get_sidechain(),
+            });
+        }
+        if let Some(reverb) = any.downcast_ref::<Reverb>() {
+            return Some(ModulePreset::Reverb {
+                reverb_type: reverb.reverb_type(),
+                dry_wet: reverb.get_dry_wet(),
+                early_gain: reverb.get_early_gain(),
+                tail_gain: reverb.get_tail_gain(),
+                pre_delay_ms: reverb.get_pre_delay_ms(),
+            });
+        }
+        if let Some(leslie) = any.downcast_ref::<Leslie>() {
+            return Some(ModulePreset::Leslie {
+                speed: leslie.get_speed(),
+            });
+        }
+        if let Some(delay) = any.downcast_ref::<Delay>() {
+            return Some(ModulePreset::Delay {
+                settings: delay.get_settings(),
+            });
+        }
+        if let Some(limiter) = any.downcast_ref::<Limiter>() {
+            return Some(ModulePreset::Limiter {
+                settings: limiter.get_settings(),
+            });
+        }
+        for kind in ModEffectKind::ALL {
+            if let Some(settings) = kind.settings_of(module) {
+                return Some(ModulePreset::ModEffect { kind, settings });
+            }
+        }
+        None
+    }
+
+    /// Creates the module described by this preset
+    pub fn build(&self, sample_rate: f64) -> Box<dyn Module> {
+        match *self {
+            ModulePreset::Gain { gain_db, pan } => {
+                let mut gain = Gain::new(gain_db);
+                gain.set_pan(pan);
+                Box::new(gain)
+            }
+            ModulePreset::Noise { amount, color } => Box::new(Noise::with_color(amount, color)),
+            ModulePreset::LFO {
+                waveform,
+                frequency,
+                amplitude,
+                offset,
+                bipolar,
+            } => {
+                let mut lfo = LFO::new(waveform, frequency, sample_rate);
+                lfo.set_amplitude(amplitude);
+                lfo.set_offset(offset);
+                lfo.set_bipolar(bipolar);
+                Box::new(lfo)
+            }
+            ModulePreset::LowPassFilter { cutoff, resonance } => {
+                Box::new(MultiModeFilter::low_pass(sample_rate, cutoff, resonance))
+            }
+            ModulePreset::Filter {
+                mode,
+                slope,
+                cutoff,
+                resonance,
+                gain_db,
+                modulation,
+            } => {
+                let mut filter =
+                    MultiModeFilter::new(sample_rate, mode, slope, cutoff, resonance, gain_db);
+                filter.set_modulation(modulation);
+                Box::new(filter)
+            }
+            ModulePreset::Distortion { settings } => {
+                Box::new(Distortion::new(sample_rate, settings))
+            }
+            ModulePreset::Compressor {
+                threshold_db,
+                ratio,
+                attack,
+                release,
+                makeup_gain_db,
+                knee_db,
+                detector,
+                lookahead_ms,
+                sidechain,
+            } => Box::new(Compressor::from_settings(
+                CompressorSettings {
+                    threshold_db,
+                    ratio,
+                    attack,
+                    release,
+                    makeup_gain_db,
+                    knee_db,
+                    detector,
+                    lookahead_ms,
+                    sidechain,
+                },
+                sample_rate,
+            )),
+            ModulePreset::Reverb {
+                reverb_type,
+                dry_wet,
+                early_gain,
+                tail_gain,
+                pre_delay_ms,
+            } => {
+                // Le type impose ses propres valeurs, on les écrase ensuite
+                let mut reverb = Reverb::new(
+                    sample_rate,
+                    reverb_type,
+                    dry_wet,
+                    early_gain,
+                    tail_gain,
+                    pre_delay_ms,
+                );
+                reverb.set_dry_wet(dry_wet);
+                reverb.set_early_gain(early_gain);
+                reverb.set_tail_gain(tail_gain);
+                reverb.set_pre_delay_ms(pre_delay_ms);
+                Box::new(reverb)
+            }
+            ModulePreset::Leslie { speed } => Box::new(Leslie::new(sample_rate, speed)),
+            ModulePreset::Delay { settings } => Box::new(Delay::new(sample_rate, settings)),
+            ModulePreset::Limiter { settings } => Box::new(Limiter::new(sample_rate, settings)),
+            ModulePreset::ModEffect { kind, settings } => kind.build(sample_rate, settings),
+        }
+    }
+}
+
+fn chain_to_presets(slots: &[ModuleSlot]) -> Vec<ModuleSlotPreset> {
+    slots
+        .iter()
+        .filter_map(|slot| {
+            ModulePreset::from_module(slot.module.as_ref()).map(|module| ModuleSlotPreset {
+                active: slot.active,
+                module,
+            })
+        })
+        .collect()
+}
+
+fn presets_to_chain(presets: &[ModuleSlotPreset], sample_rate: f64) -> Vec<ModuleSlot> {
+    presets
+        .iter()
+        .map(|preset| ModuleSlot::new(preset.module.build(sample_rate), preset.active))
+        .collect()
+}
+
+impl Preset {
+    /// Captures the state of a synthesizer, its envelope, velocity and voice settings and the octave
+    pub fn capture(
+        name: &str,
+        synth: &SynthType,
+        envelope: &ADSR,
+        velocity: &VelocitySettings,
+        voices: &VoiceSettings,
+        octave: u8,
+    ) -> Self {
+        let (oscillator, (amplitude, modules, master)) = match synth {
+            SynthType::Sine(s) => (OscillatorPreset::Sine, Self::capture_chains(s)),
+            SynthType::Square(s) => (
+                OscillatorPreset::Square {
+                    band_limited: s.oscillator.is_band_limited(),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::Sawtooth(s) => (
+                OscillatorPreset::Sawtooth {
+                    band_limited: s.oscillator.is_band_limited(),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::FM(s) => (
+                OscillatorPreset::FM {
+                    mod_index: s.oscillator.get_mod_index(),
+                    mod_ratio: s.oscillator.get_mod_ratio(),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::Hammond(s) => (
+                OscillatorPreset::Hammond {
+                    registration: s.oscillator.get_registration(),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::MultiFM(s) => (
+                OscillatorPreset::MultiFM {
+                    patch: Box::new(s.oscillator.get_patch()),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::Wavetable(s) => (
+                OscillatorPreset::Wavetable {
+                    source: s.oscillator.get_table().source().clone(),
+                    settings: s.oscillator.get_settings(),
+                },
+                Self::capture_chains(s),
+            ),
+            SynthType::Noise(s) => (
+                OscillatorPreset::Noise {
+                    color: s.oscillator.get_color(),
+                },
+                Self::capture_chains(s),
+            ),
+        };
+
+        Self {
+            version: PRESET_VERSION,
+            name: name.to_string(),
+            oscillator,
+            amplitude,
+            unison: synth.get_unison(),
+            modules,
+            master,
+            modulation: synth.get_mod_matrix(),
+            pitch: synth.get_pitch(),
+            tempo: synth.get_tempo(),
+            envelope: EnvelopePreset {
+                attack: envelope.get_attack(),
+                decay: envelope.get_decay(),
+                sustain: envelope.get_sustain(),
+                release: envelope.get_release(),
+            },
+            velocity: *velocity,
+            voices: *voices,
+            octave,
+        }
+    }
+
+    fn capture_chains<O: Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> (f64, Vec<ModuleSlotPreset>, Vec<ModuleSlotPreset>) {
+        (
+            synth.amplitude,
+            chain_to_presets(&synth.modules),
+            chain_to_presets(&synth.master.modules),
+        )
+    }
+
+    /// Rebuilds the synthesizer described by this preset
+    pub fn to_synth(&self) -> SynthType {
+        match self.oscillator {
+            OscillatorPreset::Sine => SynthType::Sine(self.build_chains(SineOscillator)),
+            OscillatorPreset::Square { band_limited } => {
+                SynthType::Square(self.build_chains(SquareOscillator::new(band_limited)))
+            }
+            OscillatorPreset::Sawtooth { band_limited } => {
+                SynthType::Sawtooth(self.build_chains(SawtoothOscillator::new(band_limited)))
+            }
+            OscillatorPreset::FM {
+                mod_index,
+                mod_ratio,
+            } => SynthType::FM(self.build_chains(FmOscillator::new(mod_index, mod_ratio))),
+            OscillatorPreset::Hammond { registration } => SynthType::Hammond(
+                self.build_chains(HammondOscillator::new(registration, constants::SAMPLE_RATE)),
+            ),
+            OscillatorPreset::MultiFM { ref patch } => SynthType::MultiFM(Box::new(
+                self.build_chains(MultiFmOscillator::new(**patch, constants::SAMPLE_RATE)),
+            )),
+            OscillatorPreset::Wavetable {
+                ref source,
+                settings,
+            } => {
+                // Un fichier manquant ne doit pas empêcher de charger le reste du patch
+                let table = Wavetable::load(source).unwrap_or_else(|err| {
+                    eprintln!("Warning: wavetable {} not loaded: {err}", source.name());
+                    Wavetable::load(&WavetableSource::Basic).expect("built-in wavetable")
+                });
+                SynthType::Wavetable(self.build_chains(WavetableOscillator::new(
+                    table,
+                    settings,
+                    constants::SAMPLE_RATE,
+                )))
+            }
+            OscillatorPreset::Noise { color } => {
+                SynthType::Noise(self.build_chains(NoiseOscillator::new(color)))
+            }
+        }
+    }
+
+    fn build_chains<O: Oscillator>(&self, oscillator: O) -> ModularSynth<O> {
+        let mut synth = ModularSynth::new(oscillator);
+        synth.amplitude = self.amplitude;
+        synth.set_unison(self.unison);
+        synth.modules = presets_to_chain(&self.modules, constants::SAMPLE_RATE);
+        synth.master.modules = presets_to_chain(&self.master, constants::SAMPLE_RATE);
+        // Les presets d'avant le limiteur le reçoivent en fin de chaîne
+        if synth.find_slot("Limiter").is_none() {
+            let limiter = Limiter::new(constants::SAMPLE_RATE, LimiterSettings::default());
+            synth.master.modules.push(ModuleSlot::new(
+                Box::new(limiter),
+                constants::ACTIVATION_LIMITER,
+            ));
+        }
+        synth.set_mod_matrix(self.modulation);
+        synth.set_pitch(self.pitch);
+        synth.set_tempo(self.tempo);
+        synth
+    }
+
+    /// Same preset with the sidechain of its compressors switched on or off.
+    /// Live playing has no key signal: a sidechained compressor would never compress there.
+    pub fn with_sidechain(&self, sidechain: bool) -> Preset {
+        let mut preset = self.clone();
+        for slot in preset.modules.iter_mut().chain(preset.master.iter_mut()) {
+            if let ModulePreset::Compressor {
+                sidechain: ref mut enabled,
+                ..
+            } = slot.module
+            {
+                *enabled = sidechain;
+            }
+        }
+        preset
+    }
+
+    /// Returns the envelope described by this preset
+    pub fn to_envelope(&self, sample_rate: f64) -> ADSR {
+        ADSR::new(
+            sample_rate,
+            self.envelope.attack,
+            self.envelope.decay,
+            self.envelope.sustain,
+            self.envelope.release,
+        )
+    }
+
+    /// Index of the octave in `VECTEUR_NOTES`
+    pub fn octave_index(&self) -> usize {
+        constants::VECTEUR_NOTES
+            .iter()
+            .position(|&octave| octave == self.octave)
+            .unwrap_or(constants::DEFAULT_OCTAVE_INDEX)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Parses a preset, refusing files written by a newer format version
+    pub fn from_json(json: &str) -> Result<Self, PresetError> {
+        let preset: Preset = serde_json::from_str(json)?;
+        if preset.version > PRESET_VERSION {
+            return Err(PresetError::UnsupportedVersion(preset.version));
+        }
+        Ok(preset)
+    }
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    NotFound(String),
+    ReadOnly(String),
+    InvalidName(String),
+    AlreadyExists(String),
+}
+
+impl std::fmt::Display for PresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetError::Io(err) => write!(f, "preset file error: {err}"),
+            PresetError::Json(err) => write!(f, "invalid preset: {err}"),
+            PresetError::UnsupportedVersion(version) => write!(
+                f,
+                "preset version {version} is newer than the supported version {PRESET_VERSION}"
+            ),
+            PresetError::NotFound(name) => write!(f, "preset not found: {name}"),
+            PresetError::ReadOnly(name) => write!(f, "factory preset cannot be modified: {name}"),
+            PresetError::InvalidName(name) => write!(f, "invalid preset name: {name:?}"),
+            PresetError::AlreadyExists(name) => write!(f, "preset already exists: {name}"),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<std::io::Error> for PresetError {
+    fn from(err: std::io::Error) -> Self {
+        PresetError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(err: serde_json::Error) -> Self {
+        PresetError::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::param::Param;
+
+    fn edited_patch() -> Preset {
+        let mut synth = SynthType::n_fm();
+        for param in [
+            Param::Cutoff(1234.0),
+            Param::DelayActive(true),
+            Param::LimiterActive(false),
+            Param::Tempo(96.0),
+        ] {
+            synth.apply_param(param);
+        }
+        let envelope = ADSR::new(constants::SAMPLE_RATE, 0.02, 0.3, 0.6, 0.8);
+        Preset::capture(
+            "Edited",
+            &synth,
+            &envelope,
+            &VelocitySettings::default(),
+            &VoiceSettings::default(),
+            5,
+        )
+    }
+
+    #[test]
+    fn round_trip_keeps_the_patch() {
+        let preset = edited_patch();
+        let parsed = Preset::from_json(&preset.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, preset);
+
+        // Le synthé reconstruit redonne le même preset
+        let rebuilt = Preset::capture(
+            &parsed.name,
+            &parsed.to_synth(),
+            &parsed.to_envelope(constants::SAMPLE_RATE),
+            &parsed.velocity,
+            &parsed.voices,
+            parsed.octave,
+        );
+        assert_eq!(rebuilt, preset);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let mut preset = edited_patch();
+        for version in [1, PRESET_VERSION] {
+            preset.version = version;
+            assert!(Preset::from_json(&preset.to_json().unwrap()).is_ok());
+        }
+        preset.version = PRESET_VERSION + 1;
+        assert!(matches!(
+            Preset::from_json(&preset.to_json().unwrap()),
+            Err(PresetError::UnsupportedVersion(version)) if version == PRESET_VERSION + 1
+        ));
+    }
+}
@@ -20,7 +20,8 @@ pub fn print_intro() {
     println!("Synthétiseurs :");
     println!("W - Modular Sine        X - Modular Square");
     println!("S - Modular Sawtooth    H - Modular Hammond");
-    println!("K - Modular FM");
+    println!("K - Modular FM          M - Modular Multi FM");
+    println!("T - Modular Wavetable   N - Modular Noise");
     println!();
     println!("Contrôles :");
     println!("FLÈCHE DROITE - Octave supérieure");
@@ -28,3 +29,29 @@ pub fn print_intro() {
     println!("ESPACE - Arrêter toutes les notes");
     println!("ESC - Quitter");
 }
+
+pub fn print_render_usage() {
+    println!("Usage : render <sortie.wav> [options]");
+    println!("  --synth <nom>               Synthétiseur (défaut : sine)");
+    println!(
+        "                              sine, square, sawtooth, fm, hammond, multifm, wavetable, noise"
+    );
+    println!("  --preset <nom|fichier.json> Preset (remplace --synth)");
+    println!("  --format <16|24|float>      Format WAV (défaut : 16)");
+    println!("  --sample-rate <Hz>          Fréquence d'échantillonnage");
+    println!("  --tail <secondes>           Durée rendue après la dernière note");
+    println!(
+        "  --events <fichier.json>     Notes : [{{\"start\", \"duration\", \"frequency\", \"velocity\"?}}]"
+    );
+    println!(
+        "  --sidechain <clé.wav>       Signal écouté par le compresseur, à la fréquence du rendu"
+    );
+}
+
+pub fn print_clip(samples: u64, peak: f64) {
+    println!(
+        "CLIP : {} échantillons écrêtés (crête {:.1} dBFS), activez le limiteur ou baissez le gain",
+        samples,
+        20.0 * peak.max(1e-6).log10()
+    );
+}
@@ -1,15 +1,50 @@
 use crate::consts::constants;
-use crate::synths::modular::ModularSynth;
-use crate::synths::modules::compressor::Compressor;
-use crate::synths::modules::filter::LowPassFilter;
+use crate::synths::modular::{ModularSynth, ModuleSlot};
+use crate::synths::modulation::{
+    MOD_LFOS, MOD_ROUTES, ModControllers, ModEnvelopeSettings, ModLfoSettings, ModMatrix, ModRoute,
+    Modulator,
+};
+use crate::synths::modules::compressor::{Compressor, CompressorSettings};
+use crate::synths::modules::delay::{Delay, DelaySettings};
+use crate::synths::modules::distortion::{Distortion, DistortionSettings};
+use crate::synths::modules::filter::{FilterMode, FilterModulation, FilterSlope, MultiModeFilter};
 use crate::synths::modules::gain::Gain;
+use crate::synths::modules::leslie::{Leslie, LeslieSpeed};
 use crate::synths::modules::lfo::{LFO, LfoWaveform};
-use crate::synths::modules::noise::Noise;
+use crate::synths::modules::limiter::{Limiter, LimiterSettings};
+use crate::synths::modules::modulated::{ModEffectKind, ModEffectSettings};
+use crate::synths::modules::noise::{Noise, NoiseColor};
 use crate::synths::modules::reverb::Reverb;
 use crate::synths::modules::reverb::ReverbType;
+use crate::synths::oscillators::hammond::{
+    HammondRegistration, PercussionDecay, PercussionHarmonic,
+};
+use crate::synths::oscillators::multi_fm::{FmOperatorSettings, FmPatch};
+use crate::synths::oscillators::wavetable::{Wavetable, WavetableSettings, WavetableSource};
 use crate::synths::oscillators::{
-    FmOscillator, HammondOscillator, SawtoothOscillator, SineOscillator, SquareOscillator,
+    FmOscillator, HammondOscillator, MultiFmOscillator, NoiseOscillator, SawtoothOscillator,
+    SineOscillator, SquareOscillator, WavetableOscillator,
 };
+use crate::synths::pitch::PitchSettings;
+use crate::synths::traits::{Module, StereoFrame};
+use crate::synths::unison::UnisonSettings;
+use std::sync::Arc;
+
+/// Applies the same expression to the modular synth of whichever variant is active
+macro_rules! with_synth {
+    ($self:expr, $synth:ident => $body:expr) => {
+        match $self {
+            SynthType::Sine($synth) => $body,
+            SynthType::Square($synth) => $body,
+            SynthType::Sawtooth($synth) => $body,
+            SynthType::FM($synth) => $body,
+            SynthType::Hammond($synth) => $body,
+            SynthType::MultiFM($synth) => $body,
+            SynthType::Wavetable($synth) => $body,
+            SynthType::Noise($synth) => $body,
+        }
+    };
+}
 
 #[derive(Clone)]
 /// Enum representing different types of modular synthesizers
@@ -19,19 +54,161 @@ pub enum SynthType {
     Sawtooth(ModularSynth<SawtoothOscillator>),
     FM(ModularSynth<FmOscillator>),
     Hammond(ModularSynth<HammondOscillator>),
+    MultiFM(Box<ModularSynth<MultiFmOscillator>>), // les quatre enveloppes alourdissent la variante
+    Wavetable(ModularSynth<WavetableOscillator>),
+    Noise(ModularSynth<NoiseOscillator>),
 }
 
 impl SynthType {
-    pub fn generate_sample(&mut self, phase: f64, frequency: f64) -> f64 {
+    pub fn generate_frame(&mut self, phase: f64, increment: f64, frequency: f64) -> StereoFrame {
+        with_synth!(self, synth => synth.generate_frame(phase, increment, frequency))
+    }
+
+    pub fn oscillate(&mut self, phase: f64, increment: f64) -> StereoFrame {
+        with_synth!(self, synth => synth.oscillate(phase, increment))
+    }
+
+    /// Runs a block through the per-voice chain
+    pub fn process_block(&mut self, block: &mut [StereoFrame]) {
+        with_synth!(self, synth => synth.process_block(block))
+    }
+
+    /// Builds the unison stack of a voice, see `ModularSynth::prepare_voice`
+    pub fn prepare_voice(&mut self) {
+        with_synth!(self, synth => synth.prepare_voice())
+    }
+
+    pub fn get_unison(&self) -> UnisonSettings {
+        with_synth!(self, synth => synth.unison)
+    }
+
+    pub fn set_unison(&mut self, settings: UnisonSettings) {
+        with_synth!(self, synth => synth.set_unison(settings))
+    }
+
+    /// Returns a copy of the per-voice chain (oscillator + voice modules, empty master bus)
+    pub fn voice(&self) -> SynthType {
         match self {
-            SynthType::Sine(synth) => synth.generate_sample(phase, frequency),
-            SynthType::Square(synth) => synth.generate_sample(phase, frequency),
-            SynthType::Sawtooth(synth) => synth.generate_sample(phase, frequency),
-            SynthType::FM(synth) => synth.generate_sample(phase, frequency),
-            SynthType::Hammond(synth) => synth.generate_sample(phase, frequency),
+            SynthType::Sine(synth) => SynthType::Sine(synth.voice()),
+            SynthType::Square(synth) => SynthType::Square(synth.voice()),
+            SynthType::Sawtooth(synth) => SynthType::Sawtooth(synth.voice()),
+            SynthType::FM(synth) => SynthType::FM(synth.voice()),
+            SynthType::Hammond(synth) => SynthType::Hammond(synth.voice()),
+            SynthType::MultiFM(synth) => SynthType::MultiFM(Box::new(synth.voice())),
+            SynthType::Wavetable(synth) => SynthType::Wavetable(synth.voice()),
+            SynthType::Noise(synth) => SynthType::Noise(synth.voice()),
         }
     }
 
+    /// Processes the mix of all voices through the global effects
+    pub fn process_master(
+        &mut self,
+        frame: StereoFrame,
+        time: f64,
+        controllers: &ModControllers,
+    ) -> StereoFrame {
+        with_synth!(self, synth => synth.process_master(frame, time, controllers))
+    }
+
+    pub fn process_master_block(
+        &mut self,
+        block: &mut [StereoFrame],
+        controllers: &ModControllers,
+    ) {
+        with_synth!(self, synth => synth.process_master_block(block, controllers))
+    }
+
+    pub fn sidechain(&mut self, key: &[StereoFrame]) {
+        with_synth!(self, synth => synth.sidechain(key))
+    }
+
+    /// Updates the modulation of a voice, see `ModularSynth::modulate`
+    pub fn modulate(&mut self, controllers: &ModControllers, frames: usize) -> usize {
+        with_synth!(self, synth => synth.modulate(controllers, frames))
+    }
+
+    /// Pitch offset of the voice in semitones, from the modulation matrix
+    pub fn pitch_offset(&self) -> f64 {
+        with_synth!(self, synth => synth.pitch_offset())
+    }
+
+    pub fn get_mod_matrix(&self) -> ModMatrix {
+        with_synth!(self, synth => synth.get_mod_matrix())
+    }
+
+    pub fn set_mod_matrix(&mut self, matrix: ModMatrix) {
+        with_synth!(self, synth => synth.set_mod_matrix(matrix))
+    }
+
+    pub fn set_mod_route(&mut self, index: usize, route: ModRoute) {
+        if index < MOD_ROUTES {
+            let mut matrix = self.get_mod_matrix();
+            matrix.routes[index] = route;
+            self.set_mod_matrix(matrix);
+        }
+    }
+
+    pub fn set_mod_lfo(&mut self, index: usize, settings: ModLfoSettings) {
+        if index < MOD_LFOS {
+            let mut matrix = self.get_mod_matrix();
+            matrix.lfos[index] = settings;
+            self.set_mod_matrix(matrix);
+        }
+    }
+
+    pub fn set_mod_envelope(&mut self, settings: ModEnvelopeSettings) {
+        let mut matrix = self.get_mod_matrix();
+        matrix.envelope = settings;
+        self.set_mod_matrix(matrix);
+    }
+
+    pub fn get_pitch(&self) -> PitchSettings {
+        with_synth!(self, synth => synth.get_pitch())
+    }
+
+    pub fn set_pitch(&mut self, settings: PitchSettings) {
+        with_synth!(self, synth => synth.set_pitch(settings))
+    }
+
+    pub fn glide_from(&mut self, semitones: f64) {
+        with_synth!(self, synth => synth.glide_from(semitones))
+    }
+
+    pub fn glide_offset(&self) -> f64 {
+        with_synth!(self, synth => synth.glide_offset())
+    }
+
+    pub fn modulator(&self) -> &Modulator {
+        with_synth!(self, synth => synth.modulator())
+    }
+
+    /// Lets a new voice continue the free-running LFOs of `master`, see `Modulator::follow_free_lfos`
+    pub fn follow_free_lfos(&mut self, master: &Modulator) {
+        with_synth!(self, synth => synth.follow_free_lfos(master))
+    }
+
+    pub fn get_tempo(&self) -> f64 {
+        with_synth!(self, synth => synth.get_tempo())
+    }
+
+    pub fn set_tempo(&mut self, bpm: f64) {
+        with_synth!(self, synth => synth.set_tempo(bpm))
+    }
+
+    pub fn get_sample_rate(&self) -> f64 {
+        with_synth!(self, synth => synth.get_sample_rate())
+    }
+
+    /// True when the voice chain or the master bus holds a module of this name, on or off
+    pub fn has_module(&self, name: &str) -> bool {
+        with_synth!(self, synth => synth.find_slot(name).is_some())
+    }
+
+    /// Rebuilds the synth for the sample rate of the engine, see `ModularSynth::set_sample_rate`
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        with_synth!(self, synth => synth.set_sample_rate(sample_rate))
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             SynthType::Sine(_) => "Modular Sine",
@@ -39,176 +216,398 @@ impl SynthType {
             SynthType::Sawtooth(_) => "Modular Sawtooth",
             SynthType::FM(_) => "Modular FM",
             SynthType::Hammond(_) => "Modular Hammond",
+            SynthType::MultiFM(_) => "Modular Multi FM",
+            SynthType::Wavetable(_) => "Modular Wavetable",
+            SynthType::Noise(_) => "Modular Noise",
         }
     }
 
-    pub fn note_on(&mut self) {
-        match self {
-            SynthType::Sine(synth) => synth.note_on(),
-            SynthType::Square(synth) => synth.note_on(),
-            SynthType::Sawtooth(synth) => synth.note_on(),
-            SynthType::FM(synth) => synth.note_on(),
-            SynthType::Hammond(synth) => synth.note_on(),
+    /// Builds a default synthesizer from its short name
+    /// (sine, square, sawtooth, fm, hammond, multifm, wavetable, noise)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "sine" => Some(SynthType::n_sine()),
+            "square" => Some(SynthType::n_square()),
+            "sawtooth" | "saw" => Some(SynthType::n_sawtooth()),
+            "fm" => Some(SynthType::n_fm()),
+            "hammond" => Some(SynthType::n_hammond()),
+            "multifm" | "fm4" => Some(SynthType::n_multi_fm()),
+            "wavetable" | "wt" => Some(SynthType::n_wavetable()),
+            "noise" => Some(SynthType::n_noise()),
+            _ => None,
         }
     }
 
+    pub fn note_on(&mut self) {
+        with_synth!(self, synth => synth.note_on())
+    }
+
     pub fn note_off(&mut self) {
-        match self {
-            SynthType::Sine(synth) => synth.note_off(),
-            SynthType::Square(synth) => synth.note_off(),
-            SynthType::Sawtooth(synth) => synth.note_off(),
-            SynthType::FM(synth) => synth.note_off(),
-            SynthType::Hammond(synth) => synth.note_off(),
-        }
+        with_synth!(self, synth => synth.note_off())
+    }
+
+    pub fn set_note_frequency(&mut self, frequency: f64) {
+        with_synth!(self, synth => synth.set_note_frequency(frequency))
+    }
+
+    pub fn set_note_velocity(&mut self, velocity: f64) {
+        with_synth!(self, synth => synth.set_note_velocity(velocity))
     }
 
     pub fn get_current_gain(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => self.get_gain_from_synth(synth),
-            SynthType::Square(synth) => self.get_gain_from_synth(synth),
-            SynthType::Sawtooth(synth) => self.get_gain_from_synth(synth),
-            SynthType::FM(synth) => self.get_gain_from_synth(synth),
-            SynthType::Hammond(synth) => self.get_gain_from_synth(synth),
-        }
+        with_synth!(self, synth => self.get_gain_from_synth(synth))
     }
 
     pub fn get_current_noise(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => self.get_noise_from_synth(synth),
-            SynthType::Square(synth) => self.get_noise_from_synth(synth),
-            SynthType::Sawtooth(synth) => self.get_noise_from_synth(synth),
-            SynthType::FM(synth) => self.get_noise_from_synth(synth),
-            SynthType::Hammond(synth) => self.get_noise_from_synth(synth),
-        }
+        with_synth!(self, synth => self.get_noise_from_synth(synth))
+    }
+
+    pub fn get_current_pan(&self) -> f64 {
+        with_synth!(self, synth => Self::get_pan_from_synth(synth))
     }
 
     pub fn set_current_cutoff(&mut self, new_cutoff: f64) {
-        match self {
-            SynthType::Sine(synth) => {
-                Self::set_cutoff_in_synth_static(synth, new_cutoff);
-            }
-            SynthType::Square(synth) => {
-                Self::set_cutoff_in_synth_static(synth, new_cutoff);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_cutoff_in_synth_static(synth, new_cutoff);
-            }
-            SynthType::FM(synth) => {
-                Self::set_cutoff_in_synth_static(synth, new_cutoff);
-            }
-            SynthType::Hammond(synth) => {
-                Self::set_cutoff_in_synth_static(synth, new_cutoff);
-            }
-        }
+        with_synth!(self, synth => Self::set_cutoff_in_synth_static(synth, new_cutoff))
     }
 
     pub fn set_current_resonance(&mut self, new_resonance: f64) {
-        match self {
-            SynthType::Sine(synth) => {
-                Self::set_resonance_in_synth_static(synth, new_resonance);
-            }
-            SynthType::Square(synth) => {
-                Self::set_resonance_in_synth_static(synth, new_resonance);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_resonance_in_synth_static(synth, new_resonance);
-            }
-            SynthType::FM(synth) => {
-                Self::set_resonance_in_synth_static(synth, new_resonance);
-            }
-            SynthType::Hammond(synth) => {
-                Self::set_resonance_in_synth_static(synth, new_resonance);
-            }
-        }
+        with_synth!(self, synth => Self::set_resonance_in_synth_static(synth, new_resonance))
+    }
+
+    pub fn set_filter_mode(&mut self, mode: FilterMode) {
+        with_synth!(self, synth => Self::change_filter_in_synth_static(synth, |filter| filter.set_mode(mode)))
+    }
+
+    pub fn set_filter_slope(&mut self, slope: FilterSlope) {
+        with_synth!(self, synth => Self::change_filter_in_synth_static(synth, |filter| filter.set_slope(slope)))
+    }
+
+    /// Boost or cut of the peak and shelving modes, in dB
+    pub fn set_filter_gain(&mut self, gain_db: f64) {
+        with_synth!(self, synth => Self::change_filter_in_synth_static(synth, |filter| filter.set_gain(gain_db)))
+    }
+
+    /// Filter envelope and key tracking, playing voices keep their envelope stage
+    pub fn set_filter_modulation(&mut self, modulation: FilterModulation) {
+        with_synth!(self, synth => Self::change_filter_in_synth_static(synth, |filter| filter.set_modulation(modulation)))
     }
 
     pub fn set_current_gain(&mut self, new_gain: f64) {
-        match self {
-            SynthType::Sine(synth) => {
-                Self::set_gain_in_synth_static(synth, new_gain);
-            }
-            SynthType::Square(synth) => {
-                Self::set_gain_in_synth_static(synth, new_gain);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_gain_in_synth_static(synth, new_gain);
-            }
-            SynthType::FM(synth) => {
-                Self::set_gain_in_synth_static(synth, new_gain);
-            }
-            SynthType::Hammond(synth) => {
-                Self::set_gain_in_synth_static(synth, new_gain);
-            }
-        }
+        with_synth!(self, synth => Self::set_gain_in_synth_static(synth, new_gain))
+    }
+
+    pub fn set_current_pan(&mut self, pan: f64) {
+        with_synth!(self, synth => Self::set_pan_in_synth_static(synth, pan))
     }
 
     pub fn set_current_threshold(&mut self, new_threshold: f64) {
+        with_synth!(self, synth => Self::set_threshold_in_synth_static(synth, new_threshold))
+    }
+
+    pub fn set_current_noise(&mut self, new_noise: f64) {
+        with_synth!(self, synth => Self::set_noise_in_synth_static(synth, new_noise))
+    }
+
+    pub fn set_current_lfo_frequency(&mut self, frequency: f64) {
+        with_synth!(self, synth => Self::set_lfo_frequency_in_synth_static(synth, frequency))
+    }
+
+    pub fn set_current_lfo_amplitude(&mut self, amplitude: f64) {
+        with_synth!(self, synth => Self::set_lfo_amplitude_in_synth_static(synth, amplitude))
+    }
+
+    pub fn set_current_lfo_waveform(&mut self, waveform: LfoWaveform) {
+        with_synth!(self, synth => Self::set_lfo_waveform_in_synth_static(synth, waveform))
+    }
+
+    pub fn set_current_reverb_type(&mut self, new_reverb_type: ReverbType) {
+        with_synth!(self, synth => Self::set_reverb_type_in_synth_static(synth, new_reverb_type))
+    }
+
+    /// Switches the PolyBLEP correction of the square and sawtooth oscillators, ignored by the others
+    pub fn set_band_limited(&mut self, band_limited: bool) {
         match self {
-            SynthType::Sine(synth) => {
-                Self::set_threshold_in_synth_static(synth, new_threshold);
-            }
-            SynthType::Square(synth) => {
-                Self::set_threshold_in_synth_static(synth, new_threshold);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_threshold_in_synth_static(synth, new_threshold);
-            }
-            SynthType::FM(synth) => {
-                Self::set_threshold_in_synth_static(synth, new_threshold);
+            SynthType::Square(synth) => synth
+                .oscillators_mut()
+                .for_each(|oscillator| oscillator.set_band_limited(band_limited)),
+            SynthType::Sawtooth(synth) => synth
+                .oscillators_mut()
+                .for_each(|oscillator| oscillator.set_band_limited(band_limited)),
+            _ => {}
+        }
+    }
+
+    pub fn set_leslie_speed(&mut self, speed: LeslieSpeed) {
+        with_synth!(self, synth => Self::set_leslie_speed_in_synth_static(synth, speed))
+    }
+
+    pub fn set_leslie_activation(&mut self, active: bool) {
+        with_synth!(self, synth => Self::set_leslie_activation_static(synth, active))
+    }
+
+    pub fn is_leslie_active(&self) -> bool {
+        with_synth!(self, synth => synth.is_module_active("Leslie"))
+    }
+
+    pub fn get_current_leslie_speed(&self) -> LeslieSpeed {
+        with_synth!(self, synth => Self::get_leslie_speed_from_synth(synth))
+    }
+
+    pub fn set_delay(&mut self, settings: DelaySettings) {
+        with_synth!(self, synth => Self::set_delay_in_synth_static(synth, settings))
+    }
+
+    pub fn set_delay_activation(&mut self, active: bool) {
+        with_synth!(self, synth => Self::set_delay_activation_static(synth, active))
+    }
+
+    pub fn is_delay_active(&self) -> bool {
+        with_synth!(self, synth => synth.is_module_active("Delay"))
+    }
+
+    pub fn get_delay(&self) -> DelaySettings {
+        with_synth!(self, synth => Self::get_delay_from_synth(synth))
+    }
+
+    pub fn set_limiter(&mut self, settings: LimiterSettings) {
+        with_synth!(self, synth => Self::set_limiter_in_synth_static(synth, settings))
+    }
+
+    pub fn set_limiter_activation(&mut self, active: bool) {
+        with_synth!(self, synth => Self::set_limiter_activation_static(synth, active))
+    }
+
+    pub fn is_limiter_active(&self) -> bool {
+        with_synth!(self, synth => synth.is_module_active("Limiter"))
+    }
+
+    pub fn get_limiter(&self) -> LimiterSettings {
+        with_synth!(self, synth => Self::get_limiter_from_synth(synth))
+    }
+
+    pub fn set_distortion(&mut self, settings: DistortionSettings) {
+        with_synth!(self, synth => {
+            if let Some(slot) = synth.find_slot_mut("Distortion")
+                && let Some(distortion) = slot.module.as_any_mut().downcast_mut::<Distortion>()
+            {
+                distortion.set_settings(settings);
             }
-            SynthType::Hammond(synth) => {
-                Self::set_threshold_in_synth_static(synth, new_threshold);
+        })
+    }
+
+    pub fn set_distortion_activation(&mut self, active: bool) {
+        with_synth!(self, synth => Self::set_distortion_activation_static(synth, active))
+    }
+
+    pub fn is_distortion_active(&self) -> bool {
+        with_synth!(self, synth => synth.is_module_active("Distortion"))
+    }
+
+    pub fn get_distortion(&self) -> DistortionSettings {
+        with_synth!(self, synth => synth
+            .find_slot("Distortion")
+            .and_then(|slot| slot.as_any().downcast_ref::<Distortion>())
+            .map(|distortion| distortion.get_settings())
+            .unwrap_or_default())
+    }
+
+    /// Sets the rate, depth, feedback and mix of the chorus, flanger or phaser
+    pub fn set_mod_effect(&mut self, kind: ModEffectKind, settings: ModEffectSettings) {
+        with_synth!(self, synth => {
+            if let Some(slot) = synth.find_slot_mut(kind.name()) {
+                kind.apply(slot.module.as_mut(), settings);
             }
+        })
+    }
+
+    pub fn set_mod_effect_activation(&mut self, kind: ModEffectKind, active: bool) {
+        with_synth!(self, synth => Self::set_mod_effect_activation_static(synth, kind, active))
+    }
+
+    pub fn is_mod_effect_active(&self, kind: ModEffectKind) -> bool {
+        with_synth!(self, synth => synth.is_module_active(kind.name()))
+    }
+
+    pub fn get_mod_effect(&self, kind: ModEffectKind) -> ModEffectSettings {
+        with_synth!(self, synth => synth
+            .find_slot(kind.name())
+            .and_then(|slot| kind.settings_of(slot.module.as_ref()))
+            .unwrap_or_else(|| kind.default_settings()))
+    }
+
+    /// Drawbars and percussion of the organ, None for the other synthesizers
+    pub fn get_hammond_registration(&self) -> Option<HammondRegistration> {
+        match self {
+            SynthType::Hammond(synth) => Some(synth.oscillator.get_registration()),
+            _ => None,
         }
     }
 
-    pub fn set_current_noise(&mut self, new_noise: f64) {
+    /// Gives access to the organ oscillator, ignored by the other synthesizers
+    fn with_hammond(&mut self, change: impl FnMut(&mut HammondOscillator)) {
+        if let SynthType::Hammond(synth) = self {
+            synth.oscillators_mut().for_each(change);
+        }
+    }
+
+    pub fn set_hammond_registration(&mut self, registration: HammondRegistration) {
+        self.with_hammond(|organ| organ.set_registration(registration));
+    }
+
+    pub fn set_drawbar(&mut self, index: usize, level: u8) {
+        self.with_hammond(|organ| organ.set_drawbar(index, level));
+    }
+
+    pub fn set_percussion(&mut self, percussion: bool) {
+        self.with_hammond(|organ| organ.set_percussion(percussion));
+    }
+
+    pub fn set_percussion_harmonic(&mut self, harmonic: PercussionHarmonic) {
+        self.with_hammond(|organ| organ.set_percussion_harmonic(harmonic));
+    }
+
+    pub fn set_percussion_decay(&mut self, decay: PercussionDecay) {
+        self.with_hammond(|organ| organ.set_percussion_decay(decay));
+    }
+
+    pub fn set_key_click(&mut self, key_click: f64) {
+        self.with_hammond(|organ| organ.set_key_click(key_click));
+    }
+
+    /// Modulation index and ratio of the two-operator FM, None for the other synthesizers
+    pub fn get_fm_modulation(&self) -> Option<(f64, f64)> {
         match self {
-            SynthType::Sine(synth) => Self::set_noise_in_synth_static(synth, new_noise),
-            SynthType::Square(synth) => Self::set_noise_in_synth_static(synth, new_noise),
-            SynthType::Sawtooth(synth) => Self::set_noise_in_synth_static(synth, new_noise),
-            SynthType::FM(synth) => Self::set_noise_in_synth_static(synth, new_noise),
-            SynthType::Hammond(synth) => Self::set_noise_in_synth_static(synth, new_noise),
+            SynthType::FM(synth) => Some((
+                synth.oscillator.get_mod_index(),
+                synth.oscillator.get_mod_ratio(),
+            )),
+            _ => None,
         }
     }
 
-    pub fn set_current_lfo_frequency(&mut self, frequency: f64) {
+    pub fn set_fm_mod_index(&mut self, mod_index: f64) {
+        if let SynthType::FM(synth) = self {
+            synth
+                .oscillators_mut()
+                .for_each(|fm| fm.set_mod_index(mod_index));
+        }
+    }
+
+    pub fn set_fm_mod_ratio(&mut self, mod_ratio: f64) {
+        if let SynthType::FM(synth) = self {
+            synth
+                .oscillators_mut()
+                .for_each(|fm| fm.set_mod_ratio(mod_ratio));
+        }
+    }
+
+    /// Algorithm and operators of the multi-operator FM, None for the other synthesizers
+    pub fn get_fm_patch(&self) -> Option<FmPatch> {
         match self {
-            SynthType::Sine(synth) => Self::set_lfo_frequency_in_synth_static(synth, frequency),
-            SynthType::Square(synth) => Self::set_lfo_frequency_in_synth_static(synth, frequency),
-            SynthType::Sawtooth(synth) => Self::set_lfo_frequency_in_synth_static(synth, frequency),
-            SynthType::FM(synth) => Self::set_lfo_frequency_in_synth_static(synth, frequency),
-            SynthType::Hammond(synth) => Self::set_lfo_frequency_in_synth_static(synth, frequency),
+            SynthType::MultiFM(synth) => Some(synth.oscillator.get_patch()),
+            _ => None,
         }
     }
 
-    pub fn set_current_lfo_waveform(&mut self, waveform: LfoWaveform) {
+    /// Gives access to the multi-operator FM oscillator, ignored by the other synthesizers
+    fn with_multi_fm(&mut self, change: impl FnMut(&mut MultiFmOscillator)) {
+        if let SynthType::MultiFM(synth) = self {
+            synth.oscillators_mut().for_each(change);
+        }
+    }
+
+    pub fn set_fm_patch(&mut self, patch: FmPatch) {
+        self.with_multi_fm(|fm| fm.set_patch(patch));
+    }
+
+    pub fn set_fm_algorithm(&mut self, algorithm: u8) {
+        self.with_multi_fm(|fm| fm.set_algorithm(algorithm));
+    }
+
+    pub fn set_fm_operator(&mut self, index: usize, settings: FmOperatorSettings) {
+        self.with_multi_fm(|fm| fm.set_operator(index, settings));
+    }
+
+    /// Table and morph settings of the wavetable oscillator, None for the other synthesizers
+    pub fn get_wavetable(&self) -> Option<(WavetableSource, WavetableSettings)> {
         match self {
-            SynthType::Sine(synth) => Self::set_lfo_waveform_in_synth_static(synth, waveform),
-            SynthType::Square(synth) => Self::set_lfo_waveform_in_synth_static(synth, waveform),
-            SynthType::Sawtooth(synth) => Self::set_lfo_waveform_in_synth_static(synth, waveform),
-            SynthType::FM(synth) => Self::set_lfo_waveform_in_synth_static(synth, waveform),
-            SynthType::Hammond(synth) => Self::set_lfo_waveform_in_synth_static(synth, waveform),
+            SynthType::Wavetable(synth) => Some((
+                synth.oscillator.get_table().source().clone(),
+                synth.oscillator.get_settings(),
+            )),
+            _ => None,
         }
     }
 
-    pub fn set_current_reverb_type(&mut self, new_reverb_type: ReverbType) {
+    /// Gives access to the wavetable oscillator, ignored by the other synthesizers
+    fn with_wavetable(&mut self, change: impl FnMut(&mut WavetableOscillator)) {
+        if let SynthType::Wavetable(synth) = self {
+            synth.oscillators_mut().for_each(change);
+        }
+    }
+
+    pub fn set_wavetable(&mut self, table: Arc<Wavetable>) {
+        self.with_wavetable(|oscillator| oscillator.set_table(Arc::clone(&table)));
+    }
+
+    pub fn set_wavetable_settings(&mut self, settings: WavetableSettings) {
+        self.with_wavetable(|oscillator| oscillator.set_settings(settings));
+    }
+
+    pub fn set_wavetable_position(&mut self, position: f64) {
+        self.with_wavetable(|oscillator| oscillator.set_position(position));
+    }
+
+    pub fn set_wavetable_scan_rate(&mut self, rate: f64) {
+        self.with_wavetable(|oscillator| oscillator.set_scan_rate(rate));
+    }
+
+    pub fn set_wavetable_scan_depth(&mut self, depth: f64) {
+        self.with_wavetable(|oscillator| oscillator.set_scan_depth(depth));
+    }
+
+    /// Color of the noise oscillator, None for the other synthesizers
+    pub fn get_noise_source_color(&self) -> Option<NoiseColor> {
         match self {
-            SynthType::Sine(synth) => {
-                Self::set_reverb_type_in_synth_static(synth, new_reverb_type);
-            }
-            SynthType::Square(synth) => {
-                Self::set_reverb_type_in_synth_static(synth, new_reverb_type);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_reverb_type_in_synth_static(synth, new_reverb_type);
-            }
-            SynthType::FM(synth) => {
-                Self::set_reverb_type_in_synth_static(synth, new_reverb_type);
-            }
-            SynthType::Hammond(synth) => {
-                Self::set_reverb_type_in_synth_static(synth, new_reverb_type);
+            SynthType::Noise(synth) => Some(synth.oscillator.get_color()),
+            _ => None,
+        }
+    }
+
+    /// Changes the color of the noise oscillator and of its unison copies
+    pub fn set_noise_source_color(&mut self, color: NoiseColor) {
+        if let SynthType::Noise(synth) = self {
+            synth
+                .oscillators_mut()
+                .for_each(|oscillator| oscillator.set_color(color));
+        }
+    }
+
+    /// Color of the noise added by the noise module
+    pub fn get_noise_color(&self) -> NoiseColor {
+        with_synth!(self, synth => synth
+            .modules
+            .iter()
+            .find_map(|module| module.as_any().downcast_ref::<Noise>())
+            .map_or(constants::CURRENT_NOISE_COLOR, Noise::get_color))
+    }
+
+    pub fn set_noise_color(&mut self, color: NoiseColor) {
+        with_synth!(self, synth => {
+            for module in &mut synth.modules {
+                if let Some(noise) = module.as_any_mut().downcast_mut::<Noise>() {
+                    noise.set_color(color);
+                }
             }
+        })
+    }
+
+    /// None when the oscillator has no discontinuity to band-limit
+    pub fn get_band_limited(&self) -> Option<bool> {
+        match self {
+            SynthType::Square(synth) => Some(synth.oscillator.is_band_limited()),
+            SynthType::Sawtooth(synth) => Some(synth.oscillator.is_band_limited()),
+            _ => None,
         }
     }
 
@@ -238,7 +637,6 @@ impl SynthType {
             if module.name() == "NoiseEffect" {
                 // Utiliser le downcasting en lecture seule
                 if let Some(noise_module) = module.as_any().downcast_ref::<Noise>() {
-                    println!("Noise module found: {}", noise_module.get_amount());
                     return noise_module.get_amount();
                 }
             }
@@ -251,14 +649,13 @@ impl SynthType {
         new_cutoff: f64,
     ) {
         for module in &mut synth.modules {
-            if module.name() == "LowPassFilter" {
-                if let Some(filter_module) = module.as_any_mut().downcast_mut::<LowPassFilter>() {
-                    filter_module.set_cutoff_freq(new_cutoff);
-                    return;
-                }
+            if module.name() == "MultiModeFilter"
+                && let Some(filter_module) = module.as_any_mut().downcast_mut::<MultiModeFilter>()
+            {
+                filter_module.set_cutoff_freq(new_cutoff);
+                return;
             }
         }
-        println!("Module Filter non trouvé pour mise à jour");
     }
 
     fn set_resonance_in_synth_static<O: crate::synths::traits::Oscillator>(
@@ -266,14 +663,13 @@ impl SynthType {
         new_resonance: f64,
     ) {
         for module in &mut synth.modules {
-            if module.name() == "LowPassFilter" {
-                if let Some(filter_module) = module.as_any_mut().downcast_mut::<LowPassFilter>() {
-                    filter_module.set_resonance(new_resonance);
-                    return;
-                }
+            if module.name() == "MultiModeFilter"
+                && let Some(filter_module) = module.as_any_mut().downcast_mut::<MultiModeFilter>()
+            {
+                filter_module.set_resonance(new_resonance);
+                return;
             }
         }
-        println!("Module Filter non trouvé pour mise à jour");
     }
 
     /// Helper pour mettre à jour le gain d'un synthétiseur modulaire
@@ -291,35 +687,81 @@ impl SynthType {
                 }
             }
         }
-        println!("Module Gain non trouvé pour mise à jour");
+    }
+
+    fn set_pan_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        pan: f64,
+    ) {
+        for module in &mut synth.modules {
+            if module.name() == "Gain"
+                && let Some(gain_module) = module.as_any_mut().downcast_mut::<Gain>()
+            {
+                gain_module.set_pan(pan);
+                return;
+            }
+        }
+    }
+
+    fn get_pan_from_synth<O: crate::synths::traits::Oscillator>(synth: &ModularSynth<O>) -> f64 {
+        match synth.find_slot("Gain") {
+            Some(slot) => match slot.as_any().downcast_ref::<Gain>() {
+                Some(gain) => gain.get_pan(),
+                None => constants::CURRENT_PAN,
+            },
+            None => constants::CURRENT_PAN,
+        }
     }
 
     fn set_threshold_in_synth_static<O: crate::synths::traits::Oscillator>(
         synth: &mut ModularSynth<O>,
         new_threshold: f64,
     ) {
-        for module in &mut synth.modules {
-            if module.name() == "SimpleRMSCompressor" {
-                if let Some(compressor_module) =
-                    module.as_any_mut().downcast_mut::<Compressor>()
-                {
-                    compressor_module.set_threshold(new_threshold);
-                    return;
-                }
+        for module in &mut synth.master.modules {
+            if module.name() == "SimpleRMSCompressor"
+                && let Some(compressor_module) = module.as_any_mut().downcast_mut::<Compressor>()
+            {
+                compressor_module.set_threshold(new_threshold);
+                return;
             }
         }
     }
 
+    fn set_compressor_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        settings: CompressorSettings,
+    ) {
+        if let Some(slot) = synth.find_slot_mut("SimpleRMSCompressor")
+            && let Some(compressor) = slot.module.as_any_mut().downcast_mut::<Compressor>()
+        {
+            compressor.set_settings(settings);
+        }
+    }
+
     fn set_lfo_frequency_in_synth_static<O: crate::synths::traits::Oscillator>(
         synth: &mut ModularSynth<O>,
         new_frequency: f64,
     ) {
         for module in &mut synth.modules {
-            if module.name() == "LFO" {
-                if let Some(lfo_module) = module.as_any_mut().downcast_mut::<LFO>() {
-                    lfo_module.set_freq(new_frequency);
-                    return;
-                }
+            if module.name() == "LFO"
+                && let Some(lfo_module) = module.as_any_mut().downcast_mut::<LFO>()
+            {
+                lfo_module.set_freq(new_frequency);
+                return;
+            }
+        }
+    }
+
+    fn set_lfo_amplitude_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        new_amplitude: f64,
+    ) {
+        for module in &mut synth.modules {
+            if module.name() == "LFO"
+                && let Some(lfo_module) = module.as_any_mut().downcast_mut::<LFO>()
+            {
+                lfo_module.set_amplitude(new_amplitude);
+                return;
             }
         }
     }
@@ -329,11 +771,11 @@ impl SynthType {
         new_waveform: LfoWaveform,
     ) {
         for module in &mut synth.modules {
-            if module.name() == "LFO" {
-                if let Some(lfo_module) = module.as_any_mut().downcast_mut::<LFO>() {
-                    lfo_module.set_waveform(new_waveform);
-                    return;
-                }
+            if module.name() == "LFO"
+                && let Some(lfo_module) = module.as_any_mut().downcast_mut::<LFO>()
+            {
+                lfo_module.set_waveform(new_waveform);
+                return;
             }
         }
     }
@@ -342,12 +784,12 @@ impl SynthType {
         synth: &mut ModularSynth<O>,
         new_reverb_type: ReverbType,
     ) {
-        for module in &mut synth.modules {
-            if module.name() == "Reverb" {
-                if let Some(reverb_module) = module.as_any_mut().downcast_mut::<Reverb>() {
-                    reverb_module.set_type(new_reverb_type);
-                    return;
-                }
+        for module in &mut synth.master.modules {
+            if module.name() == "Reverb"
+                && let Some(reverb_module) = module.as_any_mut().downcast_mut::<Reverb>()
+            {
+                reverb_module.set_type(new_reverb_type);
+                return;
             }
         }
     }
@@ -369,203 +811,136 @@ impl SynthType {
     }
 
     pub fn set_filter_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => Self::set_filter_activation_static(synth, active),
-            SynthType::Square(synth) => Self::set_filter_activation_static(synth, active),
-            SynthType::Sawtooth(synth) => Self::set_filter_activation_static(synth, active),
-            SynthType::FM(synth) => Self::set_filter_activation_static(synth, active),
-            SynthType::Hammond(synth) => Self::set_filter_activation_static(synth, active),
-        }
+        with_synth!(self, synth => Self::set_filter_activation_static(synth, active))
     }
 
     /// Active ou désactive le module gain
     pub fn set_gain_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => {
-                Self::set_gain_activation_static(synth, active);
-            }
-            SynthType::Square(synth) => {
-                Self::set_gain_activation_static(synth, active);
-            }
-            SynthType::Sawtooth(synth) => {
-                Self::set_gain_activation_static(synth, active);
-            }
-            SynthType::FM(synth) => {
-                Self::set_gain_activation_static(synth, active);
-            }
-            SynthType::Hammond(synth) => {
-                Self::set_gain_activation_static(synth, active);
-            }
-        }
+        with_synth!(self, synth => Self::set_gain_activation_static(synth, active))
     }
 
     pub fn set_compressor_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => Self::set_compressor_activation_static(synth, active),
-            SynthType::Square(synth) => Self::set_compressor_activation_static(synth, active),
-            SynthType::Sawtooth(synth) => Self::set_compressor_activation_static(synth, active),
-            SynthType::FM(synth) => Self::set_compressor_activation_static(synth, active),
-            SynthType::Hammond(synth) => Self::set_compressor_activation_static(synth, active),
-        }
+        with_synth!(self, synth => Self::set_compressor_activation_static(synth, active))
     }
 
     pub fn set_reverb_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => Self::set_reverb_activation_static(synth, active),
-            SynthType::Square(synth) => Self::set_reverb_activation_static(synth, active),
-            SynthType::Sawtooth(synth) => Self::set_reverb_activation_static(synth, active),
-            SynthType::FM(synth) => Self::set_reverb_activation_static(synth, active),
-            SynthType::Hammond(synth) => Self::set_reverb_activation_static(synth, active),
-        }
+        with_synth!(self, synth => Self::set_reverb_activation_static(synth, active))
     }
 
     pub fn set_noise_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => Self::set_noise_activation_static(synth, active),
-            SynthType::Square(synth) => Self::set_noise_activation_static(synth, active),
-            SynthType::Sawtooth(synth) => Self::set_noise_activation_static(synth, active),
-            SynthType::FM(synth) => Self::set_noise_activation_static(synth, active),
-            SynthType::Hammond(synth) => Self::set_noise_activation_static(synth, active),
-        }
+        with_synth!(self, synth => Self::set_noise_activation_static(synth, active))
     }
 
     pub fn set_lfo_activation(&mut self, active: bool) {
-        match self {
-            SynthType::Sine(synth) => Self::set_lfo_activation_static(synth, active),
-            SynthType::Square(synth) => Self::set_lfo_activation_static(synth, active),
-            SynthType::Sawtooth(synth) => Self::set_lfo_activation_static(synth, active),
-            SynthType::FM(synth) => Self::set_lfo_activation_static(synth, active),
-            SynthType::Hammond(synth) => Self::set_lfo_activation_static(synth, active),
-        }
+        with_synth!(self, synth => Self::set_lfo_activation_static(synth, active))
     }
 
     /// Vérifie si le module gain est actif
     pub fn is_gain_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_gain_active_static(synth),
-            SynthType::Square(synth) => Self::is_gain_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_gain_active_static(synth),
-            SynthType::FM(synth) => Self::is_gain_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_gain_active_static(synth),
-        }
+        with_synth!(self, synth => Self::is_gain_active_static(synth))
     }
 
     pub fn is_noise_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_noise_active_static(synth),
-            SynthType::Square(synth) => Self::is_noise_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_noise_active_static(synth),
-            SynthType::FM(synth) => Self::is_noise_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_noise_active_static(synth),
-        }
+        with_synth!(self, synth => Self::is_noise_active_static(synth))
     }
 
     pub fn is_lfo_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_lfo_active_static(synth),
-            SynthType::Square(synth) => Self::is_lfo_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_lfo_active_static(synth),
-            SynthType::FM(synth) => Self::is_lfo_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_lfo_active_static(synth),
-        }
+        with_synth!(self, synth => Self::is_lfo_active_static(synth))
     }
 
     pub fn is_filter_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_filter_active_static(synth),
-            SynthType::Square(synth) => Self::is_filter_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_filter_active_static(synth),
-            SynthType::FM(synth) => Self::is_filter_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_filter_active_static(synth),
-        }
+        with_synth!(self, synth => Self::is_filter_active_static(synth))
+    }
+
+    pub fn is_compressor_active(&self) -> bool {
+        with_synth!(self, synth => Self::is_compressor_active_static(synth))
+    }
+
+    pub fn is_reverb_active(&self) -> bool {
+        with_synth!(self, synth => Self::is_reverb_active_static(synth))
+    }
+
+    /// Obtient la forme d'onde actuelle du LFO
+    pub fn get_current_lfo_waveform(&self) -> LfoWaveform {
+        with_synth!(self, synth => Self::get_lfo_waveform_from_synth(synth))
+    }
+
+    pub fn get_current_reverb_type(&self) -> ReverbType {
+        with_synth!(self, synth => Self::get_reverb_type_from_synth(synth))
+    }
+
+    pub fn get_current_lfo_frequency(&self) -> f64 {
+        with_synth!(self, synth => Self::get_lfo_frequency_from_synth(synth))
+    }
+
+    pub fn get_current_cutoff(&self) -> f64 {
+        with_synth!(self, synth => Self::get_filter_cutoff_from_synth(synth))
+    }
+
+    pub fn get_current_resonance(&self) -> f64 {
+        with_synth!(self, synth => Self::get_filter_resonance_from_synth(synth))
+    }
+
+    pub fn get_filter_mode(&self) -> FilterMode {
+        with_synth!(self, synth => Self::get_filter_from_synth(synth)
+            .map_or(constants::CURRENT_FILTER_MODE, |filter| filter.get_mode()))
     }
 
-    pub fn is_compressor_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_compressor_active_static(synth),
-            SynthType::Square(synth) => Self::is_compressor_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_compressor_active_static(synth),
-            SynthType::FM(synth) => Self::is_compressor_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_compressor_active_static(synth),
-        }
+    pub fn get_filter_slope(&self) -> FilterSlope {
+        with_synth!(self, synth => Self::get_filter_from_synth(synth)
+            .map_or(constants::CURRENT_FILTER_SLOPE, |filter| filter.get_slope()))
     }
 
-    pub fn is_reverb_active(&self) -> bool {
-        match self {
-            SynthType::Sine(synth) => Self::is_reverb_active_static(synth),
-            SynthType::Square(synth) => Self::is_reverb_active_static(synth),
-            SynthType::Sawtooth(synth) => Self::is_reverb_active_static(synth),
-            SynthType::FM(synth) => Self::is_reverb_active_static(synth),
-            SynthType::Hammond(synth) => Self::is_reverb_active_static(synth),
-        }
+    pub fn get_filter_gain(&self) -> f64 {
+        with_synth!(self, synth => Self::get_filter_from_synth(synth)
+            .map_or(constants::CURRENT_FILTER_GAIN, |filter| filter.get_gain()))
     }
 
-    /// Obtient la forme d'onde actuelle du LFO
-    pub fn get_current_lfo_waveform(&self) -> LfoWaveform {
-        match self {
-            SynthType::Sine(synth) => Self::get_lfo_waveform_from_synth(synth),
-            SynthType::Square(synth) => Self::get_lfo_waveform_from_synth(synth),
-            SynthType::Sawtooth(synth) => Self::get_lfo_waveform_from_synth(synth),
-            SynthType::FM(synth) => Self::get_lfo_waveform_from_synth(synth),
-            SynthType::Hammond(synth) => Self::get_lfo_waveform_from_synth(synth),
-        }
+    pub fn get_filter_modulation(&self) -> FilterModulation {
+        with_synth!(self, synth => Self::get_filter_from_synth(synth)
+            .map_or(FilterModulation::default(), |filter| filter.get_modulation()))
     }
 
-    pub fn get_current_lfo_frequency(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => Self::get_lfo_frequency_from_synth(synth),
-            SynthType::Square(synth) => Self::get_lfo_frequency_from_synth(synth),
-            SynthType::Sawtooth(synth) => Self::get_lfo_frequency_from_synth(synth),
-            SynthType::FM(synth) => Self::get_lfo_frequency_from_synth(synth),
-            SynthType::Hammond(synth) => Self::get_lfo_frequency_from_synth(synth),
-        }
+    pub fn get_current_threshold(&self) -> f64 {
+        with_synth!(self, synth => Self::get_compressor_threshold_from_synth(synth))
     }
 
-    pub fn get_current_cutoff(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => Self::get_filter_cutoff_from_synth(synth),
-            SynthType::Square(synth) => Self::get_filter_cutoff_from_synth(synth),
-            SynthType::Sawtooth(synth) => Self::get_filter_cutoff_from_synth(synth),
-            SynthType::FM(synth) => Self::get_filter_cutoff_from_synth(synth),
-            SynthType::Hammond(synth) => Self::get_filter_cutoff_from_synth(synth),
-        }
+    pub fn set_compressor(&mut self, settings: CompressorSettings) {
+        with_synth!(self, synth => Self::set_compressor_in_synth_static(synth, settings))
     }
 
-    pub fn get_current_resonance(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => Self::get_filter_resonance_from_synth(synth),
-            SynthType::Square(synth) => Self::get_filter_resonance_from_synth(synth),
-            SynthType::Sawtooth(synth) => Self::get_filter_resonance_from_synth(synth),
-            SynthType::FM(synth) => Self::get_filter_resonance_from_synth(synth),
-            SynthType::Hammond(synth) => Self::get_filter_resonance_from_synth(synth),
-        }
+    pub fn get_compressor(&self) -> CompressorSettings {
+        with_synth!(self, synth => Self::get_compressor_from_synth(synth)
+            .map_or(CompressorSettings::default(), |compressor| compressor.get_settings()))
     }
 
-    pub fn get_current_threshold(&self) -> f64 {
-        match self {
-            SynthType::Sine(synth) => Self::get_compressor_threshold_from_synth(synth),
-            SynthType::Square(synth) => Self::get_compressor_threshold_from_synth(synth),
-            SynthType::Sawtooth(synth) => Self::get_compressor_threshold_from_synth(synth),
-            SynthType::FM(synth) => Self::get_compressor_threshold_from_synth(synth),
-            SynthType::Hammond(synth) => Self::get_compressor_threshold_from_synth(synth),
-        }
+    /// Gain reduction of the compressor in dB, 0 when it is off
+    pub fn get_gain_reduction(&self) -> f64 {
+        with_synth!(self, synth => {
+            if synth.is_module_active("SimpleRMSCompressor") {
+                Self::get_compressor_from_synth(synth)
+                    .map_or(0.0, |compressor| compressor.get_gain_reduction())
+            } else {
+                0.0
+            }
+        })
     }
 
     fn set_filter_activation_static<O: crate::synths::traits::Oscillator>(
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_filter = synth.modules.iter().any(|m| m.name() == "LowPassFilter");
-
-        if active && !has_filter {
-            let filter = LowPassFilter::new(
-                constants::SAMPLE_RATE,
+        // Le module reste dans la chaîne : on ne fait que basculer son activation
+        if !synth.set_module_active("MultiModeFilter", active) && active {
+            let filter = MultiModeFilter::new(
+                synth.get_sample_rate(),
+                constants::CURRENT_FILTER_MODE,
+                constants::CURRENT_FILTER_SLOPE,
                 constants::CURRENT_FILTER_CUTOFF,
                 constants::CURRENT_FILTER_RESONANCE,
+                constants::CURRENT_FILTER_GAIN,
             );
             synth.add_module(filter);
-        } else if !active && has_filter {
-            synth.modules.retain(|m| m.name() != "LowPassFilter");
         }
     }
 
@@ -574,13 +949,9 @@ impl SynthType {
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_gain = synth.modules.iter().any(|m| m.name() == "Gain");
-
-        if active && !has_gain {
+        if !synth.set_module_active("Gain", active) && active {
             let gain = Gain::new(constants::CURRENT_GAIN);
             synth.add_module(gain);
-        } else if !active && has_gain {
-            synth.modules.retain(|m| m.name() != "Gain");
         }
     }
 
@@ -588,44 +959,196 @@ impl SynthType {
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_compressor = synth
-            .modules
-            .iter()
-            .any(|m| m.name() == "SimpleRMSCompressor");
-
-        if active && !has_compressor {
+        if !synth.set_module_active("SimpleRMSCompressor", active) && active {
             let compressor = Compressor::new(
                 constants::CURRENT_THRESHOLD,
                 constants::CURRENT_RATIO,
                 constants::CURRENT_ATTACK,
                 constants::CURRENT_RELEASE,
                 constants::CURRENT_MAKEUP_GAIN,
-                constants::SAMPLE_RATE,
+                synth.get_sample_rate(),
             );
-            synth.add_module(compressor);
-        } else if !active && has_compressor {
-            synth.modules.retain(|m| m.name() != "SimpleRMSCompressor");
+            synth.add_master_module(compressor);
         }
     }
 
-    fn set_reverb_activation_static<O: crate::synths::traits::Oscillator>(
+    fn set_leslie_speed_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        speed: LeslieSpeed,
+    ) {
+        for module in &mut synth.master.modules {
+            if module.name() == "Leslie"
+                && let Some(leslie) = module.as_any_mut().downcast_mut::<Leslie>()
+            {
+                leslie.set_speed(speed);
+                return;
+            }
+        }
+    }
+
+    fn set_leslie_activation_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        active: bool,
+    ) {
+        if !synth.set_module_active("Leslie", active) && active {
+            // Le haut-parleur tournant se place avant le delay et la réverbe
+            let leslie = Leslie::new(synth.get_sample_rate(), constants::CURRENT_LESLIE_SPEED);
+            let index = synth
+                .master
+                .modules
+                .iter()
+                .position(|m| matches!(m.name(), "Delay" | "Reverb" | "Limiter"))
+                .unwrap_or(synth.master.modules.len());
+            synth
+                .master
+                .modules
+                .insert(index, ModuleSlot::new(Box::new(leslie), true));
+        }
+    }
+
+    fn set_distortion_activation_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        active: bool,
+    ) {
+        if !synth.set_module_active("Distortion", active) && active {
+            // Chaque voix sature après son filtre, avant le gain
+            let distortion =
+                Distortion::new(synth.get_sample_rate(), DistortionSettings::default());
+            let index = synth
+                .modules
+                .iter()
+                .position(|m| m.name() == "Gain")
+                .unwrap_or(synth.modules.len());
+            synth
+                .modules
+                .insert(index, ModuleSlot::new(Box::new(distortion), true));
+        }
+    }
+
+    fn set_mod_effect_activation_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        kind: ModEffectKind,
+        active: bool,
+    ) {
+        if !synth.set_module_active(kind.name(), active) && active {
+            // Chorus, flanger et phaser passent avant la cabine, le delay et la réverbe
+            let effect = kind.build(synth.get_sample_rate(), kind.default_settings());
+            let index = synth
+                .master
+                .modules
+                .iter()
+                .position(|m| matches!(m.name(), "Leslie" | "Delay" | "Reverb" | "Limiter"))
+                .unwrap_or(synth.master.modules.len());
+            synth
+                .master
+                .modules
+                .insert(index, ModuleSlot::new(effect, true));
+        }
+    }
+
+    fn get_leslie_speed_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> LeslieSpeed {
+        match synth.find_slot("Leslie") {
+            Some(slot) => match slot.as_any().downcast_ref::<Leslie>() {
+                Some(leslie) => leslie.get_speed(),
+                None => constants::CURRENT_LESLIE_SPEED,
+            },
+            None => constants::CURRENT_LESLIE_SPEED,
+        }
+    }
+
+    fn set_delay_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        settings: DelaySettings,
+    ) {
+        if let Some(slot) = synth.find_slot_mut("Delay")
+            && let Some(delay) = slot.module.as_any_mut().downcast_mut::<Delay>()
+        {
+            delay.set_settings(settings);
+        }
+    }
+
+    fn set_delay_activation_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        active: bool,
+    ) {
+        if !synth.set_module_active("Delay", active) && active {
+            // Les échos passent ensuite dans la réverbe
+            let mut delay = Delay::new(synth.get_sample_rate(), DelaySettings::default());
+            delay.set_tempo(synth.get_tempo());
+            let index = synth
+                .master
+                .modules
+                .iter()
+                .position(|m| matches!(m.name(), "Reverb" | "Limiter"))
+                .unwrap_or(synth.master.modules.len());
+            synth
+                .master
+                .modules
+                .insert(index, ModuleSlot::new(Box::new(delay), true));
+        }
+    }
+
+    fn get_delay_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> DelaySettings {
+        synth
+            .find_slot("Delay")
+            .and_then(|slot| slot.as_any().downcast_ref::<Delay>())
+            .map(|delay| delay.get_settings())
+            .unwrap_or_default()
+    }
+
+    fn set_limiter_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        settings: LimiterSettings,
+    ) {
+        if let Some(slot) = synth.find_slot_mut("Limiter")
+            && let Some(limiter) = slot.module.as_any_mut().downcast_mut::<Limiter>()
+        {
+            limiter.set_settings(settings);
+        }
+    }
+
+    fn set_limiter_activation_static<O: crate::synths::traits::Oscillator>(
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_reverb = synth.modules.iter().any(|m| m.name() == "Reverb");
+        if !synth.set_module_active("Limiter", active) && active {
+            // Toujours le dernier étage du master bus
+            let limiter = Limiter::new(synth.get_sample_rate(), LimiterSettings::default());
+            synth
+                .master
+                .modules
+                .push(ModuleSlot::new(Box::new(limiter), true));
+        }
+    }
 
-        if active && !has_reverb {
+    fn get_limiter_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> LimiterSettings {
+        synth
+            .find_slot("Limiter")
+            .and_then(|slot| slot.as_any().downcast_ref::<Limiter>())
+            .map(|limiter| limiter.get_settings())
+            .unwrap_or_default()
+    }
+
+    fn set_reverb_activation_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        active: bool,
+    ) {
+        if !synth.set_module_active("Reverb", active) && active {
             let reverb = Reverb::new(
-                constants::SAMPLE_RATE,
+                synth.get_sample_rate(),
                 constants::CURRENT_REVERB_TYPE,
                 constants::CURRENT_DRY_WET,
                 constants::CURRENT_REVERB_EARLY_GAIN,
                 constants::CURRENT_REVERB_TAIL_GAIN,
                 constants::CURRENT_REVERB_PRE_DELAY_MS,
             );
-            synth.add_module(reverb);
-        } else if !active && has_reverb {
-            synth.modules.retain(|m| m.name() != "Reverb");
+            synth.add_master_module(reverb);
         }
     }
 
@@ -633,13 +1156,9 @@ impl SynthType {
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_noise = synth.modules.iter().any(|m| m.name() == "NoiseEffect");
-
-        if active && !has_noise {
+        if !synth.set_module_active("NoiseEffect", active) && active {
             let noise = Noise::new(constants::CURRENT_NOISE);
             synth.add_module(noise);
-        } else if !active && has_noise {
-            synth.modules.retain(|m| m.name() != "NoiseEffect");
         }
     }
 
@@ -647,17 +1166,13 @@ impl SynthType {
         synth: &mut ModularSynth<O>,
         active: bool,
     ) {
-        let has_lfo = synth.modules.iter().any(|m| m.name() == "LFO");
-
-        if active && !has_lfo {
+        if !synth.set_module_active("LFO", active) && active {
             let lfo = LFO::new(
                 constants::CURRENT_LFO_WAVEFORM,
                 constants::CURRENT_LFO_FREQ,
-                constants::SAMPLE_RATE,
+                synth.get_sample_rate(),
             );
             synth.add_module(lfo);
-        } else if !active && has_lfo {
-            synth.modules.retain(|m| m.name() != "LFO");
         }
     }
 
@@ -665,38 +1180,35 @@ impl SynthType {
     fn is_gain_active_static<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> bool {
-        synth.modules.iter().any(|m| m.name() == "Gain")
+        synth.is_module_active("Gain")
     }
 
     fn is_noise_active_static<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> bool {
-        synth.modules.iter().any(|m| m.name() == "NoiseEffect")
+        synth.is_module_active("NoiseEffect")
     }
 
     fn is_lfo_active_static<O: crate::synths::traits::Oscillator>(synth: &ModularSynth<O>) -> bool {
-        synth.modules.iter().any(|m| m.name() == "LFO")
+        synth.is_module_active("LFO")
     }
 
     fn is_filter_active_static<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> bool {
-        synth.modules.iter().any(|m| m.name() == "LowPassFilter")
+        synth.is_module_active("MultiModeFilter")
     }
 
     fn is_compressor_active_static<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> bool {
-        synth
-            .modules
-            .iter()
-            .any(|m| m.name() == "SimpleRMSCompressor")
+        synth.is_module_active("SimpleRMSCompressor")
     }
 
     fn is_reverb_active_static<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> bool {
-        synth.modules.iter().any(|m| m.name() == "Reverb")
+        synth.is_module_active("Reverb")
     }
 
     /// Helper pour récupérer la forme d'onde du LFO
@@ -712,6 +1224,18 @@ impl SynthType {
         constants::CURRENT_LFO_WAVEFORM
     }
 
+    fn get_reverb_type_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> ReverbType {
+        match synth.find_slot("Reverb") {
+            Some(slot) => match slot.as_any().downcast_ref::<Reverb>() {
+                Some(reverb) => reverb.reverb_type(),
+                None => constants::CURRENT_REVERB_TYPE,
+            },
+            None => constants::CURRENT_REVERB_TYPE,
+        }
+    }
+
     fn get_lfo_frequency_from_synth<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> f64 {
@@ -723,11 +1247,33 @@ impl SynthType {
         constants::CURRENT_LFO_FREQ
     }
 
+    fn get_filter_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> Option<&MultiModeFilter> {
+        synth
+            .modules
+            .iter()
+            .find_map(|module| module.as_any().downcast_ref::<MultiModeFilter>())
+    }
+
+    fn change_filter_in_synth_static<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        change: impl FnOnce(&mut MultiModeFilter),
+    ) {
+        if let Some(filter) = synth
+            .modules
+            .iter_mut()
+            .find_map(|module| module.as_any_mut().downcast_mut::<MultiModeFilter>())
+        {
+            change(filter);
+        }
+    }
+
     fn get_filter_cutoff_from_synth<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> f64 {
         for module in &synth.modules {
-            if let Some(filter) = module.as_any().downcast_ref::<LowPassFilter>() {
+            if let Some(filter) = module.as_any().downcast_ref::<MultiModeFilter>() {
                 return filter.get_cutoff_freq();
             }
         }
@@ -738,17 +1284,25 @@ impl SynthType {
         synth: &ModularSynth<O>,
     ) -> f64 {
         for module in &synth.modules {
-            if let Some(filter) = module.as_any().downcast_ref::<LowPassFilter>() {
+            if let Some(filter) = module.as_any().downcast_ref::<MultiModeFilter>() {
                 return filter.get_resonance();
             }
         }
         constants::CURRENT_FILTER_RESONANCE
     }
 
+    fn get_compressor_from_synth<O: crate::synths::traits::Oscillator>(
+        synth: &ModularSynth<O>,
+    ) -> Option<&Compressor> {
+        synth
+            .find_slot("SimpleRMSCompressor")
+            .and_then(|slot| slot.as_any().downcast_ref::<Compressor>())
+    }
+
     fn get_compressor_threshold_from_synth<O: crate::synths::traits::Oscillator>(
         synth: &ModularSynth<O>,
     ) -> f64 {
-        for module in &synth.modules {
+        for module in &synth.master.modules {
             if let Some(compressor) = module.as_any().downcast_ref::<Compressor>() {
                 return compressor.get_threshold();
             }
@@ -765,11 +1319,57 @@ impl std::fmt::Debug for SynthType {
             SynthType::Sawtooth(_) => write!(f, "Sawtooth"),
             SynthType::FM(_) => write!(f, "FM"),
             SynthType::Hammond(_) => write!(f, "Hammond"),
+            SynthType::MultiFM(_) => write!(f, "Multi FM"),
+            SynthType::Wavetable(_) => write!(f, "Wavetable"),
+            SynthType::Noise(_) => write!(f, "Noise"),
         }
     }
 }
 
 impl SynthType {
+    /// Builds the default chain shared by every synthesizer.
+    /// Every module is always present; the ACTIVATION_* constants only decide
+    /// whether it starts switched on.
+    fn add_default_modules<O: crate::synths::traits::Oscillator>(
+        synth: &mut ModularSynth<O>,
+        noise: Noise,
+        lfo: LFO,
+        filter: MultiModeFilter,
+        gain: Gain,
+        compressor: Compressor,
+        reverb: Reverb,
+    ) {
+        synth.add_module_with_activation(noise, constants::ACTIVATION_NOISE);
+        synth.add_module_with_activation(lfo, constants::ACTIVATION_LFO);
+        synth.add_module_with_activation(filter, constants::ACTIVATION_FILTER);
+        synth.add_module_with_activation(
+            Distortion::new(constants::SAMPLE_RATE, DistortionSettings::default()),
+            constants::ACTIVATION_DISTORTION,
+        );
+        synth.add_module_with_activation(gain, constants::ACTIVATION_GAIN);
+
+        synth.add_master_module_with_activation(compressor, constants::ACTIVATION_COMPRESSOR);
+        for kind in ModEffectKind::ALL {
+            synth.master.modules.push(ModuleSlot::new(
+                kind.build(constants::SAMPLE_RATE, kind.default_settings()),
+                kind.is_active_by_default(),
+            ));
+        }
+        synth.add_master_module_with_activation(
+            Leslie::new(constants::SAMPLE_RATE, constants::CURRENT_LESLIE_SPEED),
+            constants::ACTIVATION_LESLIE,
+        );
+        synth.add_master_module_with_activation(
+            Delay::new(constants::SAMPLE_RATE, DelaySettings::default()),
+            constants::ACTIVATION_DELAY,
+        );
+        synth.add_master_module_with_activation(reverb, constants::ACTIVATION_REVERB);
+        synth.add_master_module_with_activation(
+            Limiter::new(constants::SAMPLE_RATE, LimiterSettings::default()),
+            constants::ACTIVATION_LIMITER,
+        );
+    }
+
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
     pub fn n_sine() -> Self {
         let oscillator: SineOscillator = SineOscillator;
@@ -779,7 +1379,7 @@ impl SynthType {
             constants::CURRENT_RATIO,
             constants::CURRENT_ATTACK,
             constants::CURRENT_RELEASE,
-            constants::CURRENT_GAIN,
+            constants::CURRENT_MAKEUP_GAIN,
             constants::SAMPLE_RATE,
         );
 
@@ -793,10 +1393,13 @@ impl SynthType {
             constants::SAMPLE_RATE,
         );
 
-        let filter = LowPassFilter::new(
+        let filter = MultiModeFilter::new(
             constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
             constants::CURRENT_FILTER_CUTOFF,
             constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
         );
 
         let reverb = Reverb::new(
@@ -809,25 +1412,13 @@ impl SynthType {
         );
 
         let mut synth: ModularSynth<SineOscillator> = ModularSynth::new(oscillator);
-        synth.add_module(noise);
-
-        synth.add_module(lfo);
-
-        if constants::ACTIVATION_FILTER {
-            synth.add_module(filter);
-        }
-        synth.add_module(gain);
-
-        if constants::ACTIVATION_COMPRESSOR {
-            synth.add_module(compressor);
-        }
-        synth.add_module(reverb);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
 
         SynthType::Sine(synth)
     }
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
     pub fn n_square() -> Self {
-        let oscillator = SquareOscillator;
+        let oscillator = SquareOscillator::new(constants::BAND_LIMITED_OSCILLATORS);
 
         let gain = Gain::new(constants::CURRENT_GAIN);
 
@@ -838,7 +1429,7 @@ impl SynthType {
             constants::CURRENT_RATIO,
             constants::CURRENT_ATTACK,
             constants::CURRENT_RELEASE,
-            constants::CURRENT_GAIN,
+            constants::CURRENT_MAKEUP_GAIN,
             constants::SAMPLE_RATE,
         );
 
@@ -848,10 +1439,13 @@ impl SynthType {
             constants::SAMPLE_RATE,
         );
 
-        let filter = LowPassFilter::new(
+        let filter = MultiModeFilter::new(
             constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
             constants::CURRENT_FILTER_CUTOFF,
             constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
         );
 
         let reverb = Reverb::new(
@@ -864,25 +1458,13 @@ impl SynthType {
         );
 
         let mut synth = ModularSynth::new(oscillator);
-        synth.add_module(noise);
-
-        synth.add_module(lfo);
-
-        if constants::ACTIVATION_FILTER {
-            synth.add_module(filter);
-        }
-        synth.add_module(gain);
-
-        if constants::ACTIVATION_COMPRESSOR {
-            synth.add_module(compressor);
-        }
-        synth.add_module(reverb);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
 
         SynthType::Square(synth)
     }
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
     pub fn n_sawtooth() -> Self {
-        let oscillator = SawtoothOscillator;
+        let oscillator = SawtoothOscillator::new(constants::BAND_LIMITED_OSCILLATORS);
 
         let gain = Gain::new(constants::CURRENT_GAIN);
 
@@ -893,14 +1475,17 @@ impl SynthType {
             constants::CURRENT_RATIO,
             constants::CURRENT_ATTACK,
             constants::CURRENT_RELEASE,
-            constants::CURRENT_GAIN,
+            constants::CURRENT_MAKEUP_GAIN,
             constants::SAMPLE_RATE,
         );
 
-        let filter = LowPassFilter::new(
+        let filter = MultiModeFilter::new(
             constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
             constants::CURRENT_FILTER_CUTOFF,
             constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
         );
 
         let lfo = LFO::new(
@@ -919,25 +1504,59 @@ impl SynthType {
         );
 
         let mut synth = ModularSynth::new(oscillator);
-        synth.add_module(noise);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
 
-        synth.add_module(lfo);
+        SynthType::Sawtooth(synth)
+    }
+    ////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    pub fn n_fm() -> Self {
+        let oscillator = FmOscillator::new(constants::FM_MOD_INDEX, constants::FM_MOD_RATIO);
 
-        if constants::ACTIVATION_FILTER {
-            synth.add_module(filter);
-        }
-        synth.add_module(gain);
+        let gain = Gain::new(constants::CURRENT_GAIN);
 
-        if constants::ACTIVATION_COMPRESSOR {
-            synth.add_module(compressor);
-        }
-        synth.add_module(reverb);
+        let noise = Noise::new(constants::CURRENT_NOISE);
 
-        SynthType::Sawtooth(synth)
+        let compressor = Compressor::new(
+            constants::CURRENT_THRESHOLD,
+            constants::CURRENT_RATIO,
+            constants::CURRENT_ATTACK,
+            constants::CURRENT_RELEASE,
+            constants::CURRENT_MAKEUP_GAIN,
+            constants::SAMPLE_RATE,
+        );
+
+        let filter = MultiModeFilter::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
+            constants::CURRENT_FILTER_CUTOFF,
+            constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
+        );
+
+        let lfo = LFO::new(
+            constants::CURRENT_LFO_WAVEFORM,
+            constants::CURRENT_LFO_FREQ,
+            constants::SAMPLE_RATE,
+        );
+
+        let reverb = Reverb::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_REVERB_TYPE,
+            constants::CURRENT_DRY_WET,
+            constants::CURRENT_REVERB_EARLY_GAIN,
+            constants::CURRENT_REVERB_TAIL_GAIN,
+            constants::CURRENT_REVERB_PRE_DELAY_MS,
+        );
+
+        let mut synth = ModularSynth::new(oscillator);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
+
+        SynthType::FM(synth)
     }
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    pub fn n_fm() -> Self {
-        let oscillator = FmOscillator::new(3.5, 1.414);
+    pub fn n_hammond() -> Self {
+        let oscillator = HammondOscillator::default();
 
         let gain = Gain::new(constants::CURRENT_GAIN);
 
@@ -948,14 +1567,17 @@ impl SynthType {
             constants::CURRENT_RATIO,
             constants::CURRENT_ATTACK,
             constants::CURRENT_RELEASE,
-            constants::CURRENT_GAIN,
+            constants::CURRENT_MAKEUP_GAIN,
             constants::SAMPLE_RATE,
         );
 
-        let filter = LowPassFilter::new(
+        let filter = MultiModeFilter::new(
             constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
             constants::CURRENT_FILTER_CUTOFF,
             constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
         );
 
         let lfo = LFO::new(
@@ -974,25 +1596,60 @@ impl SynthType {
         );
 
         let mut synth = ModularSynth::new(oscillator);
-        synth.add_module(noise);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
+        synth.set_module_active("Leslie", true); // un orgue sonne à travers sa cabine
 
-        synth.add_module(lfo);
+        SynthType::Hammond(synth)
+    }
+    ////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    pub fn n_multi_fm() -> Self {
+        let oscillator = MultiFmOscillator::default();
 
-        if constants::ACTIVATION_FILTER {
-            synth.add_module(filter);
-        }
-        synth.add_module(gain);
+        let gain = Gain::new(constants::CURRENT_GAIN);
 
-        if constants::ACTIVATION_COMPRESSOR {
-            synth.add_module(compressor);
-        }
-        synth.add_module(reverb);
+        let noise = Noise::new(constants::CURRENT_NOISE);
 
-        SynthType::FM(synth)
+        let compressor = Compressor::new(
+            constants::CURRENT_THRESHOLD,
+            constants::CURRENT_RATIO,
+            constants::CURRENT_ATTACK,
+            constants::CURRENT_RELEASE,
+            constants::CURRENT_MAKEUP_GAIN,
+            constants::SAMPLE_RATE,
+        );
+
+        let filter = MultiModeFilter::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
+            constants::CURRENT_FILTER_CUTOFF,
+            constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
+        );
+
+        let lfo = LFO::new(
+            constants::CURRENT_LFO_WAVEFORM,
+            constants::CURRENT_LFO_FREQ,
+            constants::SAMPLE_RATE,
+        );
+
+        let reverb = Reverb::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_REVERB_TYPE,
+            constants::CURRENT_DRY_WET,
+            constants::CURRENT_REVERB_EARLY_GAIN,
+            constants::CURRENT_REVERB_TAIL_GAIN,
+            constants::CURRENT_REVERB_PRE_DELAY_MS,
+        );
+
+        let mut synth = ModularSynth::new(oscillator);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
+
+        SynthType::MultiFM(Box::new(synth))
     }
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
-    pub fn n_hammond() -> Self {
-        let oscillator = HammondOscillator;
+    pub fn n_wavetable() -> Self {
+        let oscillator = WavetableOscillator::default();
 
         let gain = Gain::new(constants::CURRENT_GAIN);
 
@@ -1003,14 +1660,17 @@ impl SynthType {
             constants::CURRENT_RATIO,
             constants::CURRENT_ATTACK,
             constants::CURRENT_RELEASE,
-            constants::CURRENT_GAIN,
+            constants::CURRENT_MAKEUP_GAIN,
             constants::SAMPLE_RATE,
         );
 
-        let filter = LowPassFilter::new(
+        let filter = MultiModeFilter::new(
             constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
             constants::CURRENT_FILTER_CUTOFF,
             constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
         );
 
         let lfo = LFO::new(
@@ -1029,21 +1689,55 @@ impl SynthType {
         );
 
         let mut synth = ModularSynth::new(oscillator);
-        synth.add_module(noise);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
 
-        synth.add_module(lfo);
+        SynthType::Wavetable(synth)
+    }
+    ////////////////////////////////////////////////////////////////////////////////////////////////////////////
+    pub fn n_noise() -> Self {
+        let oscillator = NoiseOscillator::new(constants::CURRENT_NOISE_COLOR);
 
-        if constants::ACTIVATION_FILTER {
-            synth.add_module(filter);
-        }
-        synth.add_module(gain);
+        let gain = Gain::new(constants::CURRENT_GAIN);
 
-        if constants::ACTIVATION_COMPRESSOR {
-            synth.add_module(compressor);
-        }
-        synth.add_module(reverb);
+        let noise = Noise::new(constants::CURRENT_NOISE);
 
-        SynthType::Hammond(synth)
+        let compressor = Compressor::new(
+            constants::CURRENT_THRESHOLD,
+            constants::CURRENT_RATIO,
+            constants::CURRENT_ATTACK,
+            constants::CURRENT_RELEASE,
+            constants::CURRENT_MAKEUP_GAIN,
+            constants::SAMPLE_RATE,
+        );
+
+        let filter = MultiModeFilter::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_FILTER_MODE,
+            constants::CURRENT_FILTER_SLOPE,
+            constants::CURRENT_FILTER_CUTOFF,
+            constants::CURRENT_FILTER_RESONANCE,
+            constants::CURRENT_FILTER_GAIN,
+        );
+
+        let lfo = LFO::new(
+            constants::CURRENT_LFO_WAVEFORM,
+            constants::CURRENT_LFO_FREQ,
+            constants::SAMPLE_RATE,
+        );
+
+        let reverb = Reverb::new(
+            constants::SAMPLE_RATE,
+            constants::CURRENT_REVERB_TYPE,
+            constants::CURRENT_DRY_WET,
+            constants::CURRENT_REVERB_EARLY_GAIN,
+            constants::CURRENT_REVERB_TAIL_GAIN,
+            constants::CURRENT_REVERB_PRE_DELAY_MS,
+        );
+
+        let mut synth = ModularSynth::new(oscillator);
+        Self::add_default_modules(&mut synth, noise, lfo, filter, gain, compressor, reverb);
+
+        SynthType::Noise(synth)
     }
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////
 }
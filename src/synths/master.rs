@@ -0,0 +1,75 @@
+use crate::synths::modular::ModuleSlot;
+use crate::synths::traits::{Module, StereoFrame};
+
+/// Master bus shared by every voice.
+/// Holds the truly global effects (compressor, reverb) which must see the
+/// summed signal of all notes instead of running once per voice.
+#[derive(Clone)]
+pub struct MasterBus {
+    pub modules: Vec<ModuleSlot>,
+}
+
+impl Default for MasterBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MasterBus {
+    pub fn new() -> Self {
+        Self {
+            modules: Vec::new(),
+        }
+    }
+
+    pub fn add_module<M: Module + 'static>(&mut self, module: M) {
+        self.add_module_with_activation(module, true);
+    }
+
+    /// Adds a module at the end of the chain, before the limiter which stays the last stage
+    pub fn add_module_with_activation<M: Module + 'static>(&mut self, module: M, active: bool) {
+        let index = self
+            .modules
+            .iter()
+            .position(|m| m.name() == "Limiter")
+            .unwrap_or(self.modules.len());
+        self.modules
+            .insert(index, ModuleSlot::new(Box::new(module), active));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Processes the mixed signal of all voices through the global effects
+    pub fn process(&mut self, input: f64, time: f64) -> f64 {
+        let mut sample = input;
+        for module in &mut self.modules {
+            sample = module.process(sample, time);
+        }
+        sample
+    }
+
+    /// Stereo version of `process`
+    pub fn process_frame(&mut self, frame: StereoFrame, time: f64) -> StereoFrame {
+        let mut frame = frame;
+        for module in &mut self.modules {
+            frame = module.process_frame(frame, time);
+        }
+        frame
+    }
+
+    /// Block version of `process_frame`
+    pub fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for module in &mut self.modules {
+            module.process_block(block);
+        }
+    }
+
+    /// Gives the key signal of the next block to the modules with a sidechain input
+    pub fn sidechain(&mut self, key: &[StereoFrame]) {
+        for module in &mut self.modules {
+            module.sidechain(key);
+        }
+    }
+}
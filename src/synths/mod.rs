@@ -1,9 +1,17 @@
-pub mod traits;
-pub mod oscillators;
-pub mod modules;
-pub mod modular;
 pub mod manager;
+pub mod master;
+pub mod modular;
+pub mod modulation;
+pub mod modules;
+pub mod oscillators;
+pub mod param;
+pub mod pitch;
+pub mod tempo;
+pub mod traits;
+pub mod unison;
+pub mod velocity;
 
-pub use traits::*;
-pub use modular::ModularSynth;
 pub use manager::SynthType;
+pub use master::MasterBus;
+pub use modular::ModularSynth;
+pub use traits::*;
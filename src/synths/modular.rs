@@ -1,17 +1,100 @@
-use crate::synths::traits::{Oscillator, Module};
+use crate::consts::constants::{DEFAULT_TEMPO, MAX_UNISON, SAMPLE_RATE};
+use crate::synths::master::MasterBus;
+use crate::synths::modulation::{ModControllers, ModDestination, ModMatrix, Modulator};
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::modules::filter::MultiModeFilter;
+use crate::synths::modules::lfo::LFO;
+use crate::synths::modules::noise::Noise;
+use crate::synths::pitch::PitchSettings;
+use crate::synths::traits::{Module, Oscillator, StereoFrame};
+use crate::synths::unison::{UnisonSettings, UnisonStack};
+use std::ops::{Deref, DerefMut};
+
+/// A module placed in a chain together with its activation flag.
+/// Switching a module off keeps it (and its parameters) in the chain, so toggling
+/// never allocates and can safely happen on the audio thread.
+pub struct ModuleSlot {
+    pub module: Box<dyn Module>,
+    pub active: bool,
+}
+
+impl ModuleSlot {
+    pub fn new(module: Box<dyn Module>, active: bool) -> Self {
+        Self { module, active }
+    }
+
+    /// Runs the module, or passes the signal through untouched when it is switched off
+    pub fn process(&mut self, input: f64, time: f64) -> f64 {
+        if self.active {
+            self.module.process(input, time)
+        } else {
+            input
+        }
+    }
+
+    /// Stereo version of `process`
+    pub fn process_frame(&mut self, frame: StereoFrame, time: f64) -> StereoFrame {
+        if self.active {
+            self.module.process_frame(frame, time)
+        } else {
+            frame
+        }
+    }
+
+    pub fn process_block(&mut self, block: &mut [StereoFrame]) {
+        if self.active {
+            self.module.process_block(block);
+        }
+    }
+}
+
+impl Clone for ModuleSlot {
+    fn clone(&self) -> Self {
+        Self {
+            module: self.module.clone_box(),
+            active: self.active,
+        }
+    }
+}
+
+impl Deref for ModuleSlot {
+    type Target = dyn Module;
+
+    fn deref(&self) -> &Self::Target {
+        self.module.as_ref()
+    }
+}
+
+impl DerefMut for ModuleSlot {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.module.as_mut()
+    }
+}
 
 pub struct ModularSynth<O: Oscillator> {
     pub oscillator: O,
-    pub modules: Vec<Box<dyn Module>>,
+    pub modules: Vec<ModuleSlot>, // Chaîne propre à chaque voix (filtre, LFO, bruit, gain)
+    pub master: MasterBus,        // Effets globaux (compresseur, reverb)
     pub amplitude: f64,
+    pub unison: UnisonSettings,
+    stack: UnisonStack<O>, // oscillateurs empilés d'une note, vide hors unisson
+    modulation: Modulator, // sources de la matrice : celles de la note, ou du master bus
+    tempo: f64,            // noires par minute, pour les LFO et effets synchronisés
+    sample_rate: f64,      // fréquence des modules, celle du moteur qui joue le synthé
 }
 
 impl<O: Oscillator> Clone for ModularSynth<O> {
     fn clone(&self) -> Self {
         Self {
-            oscillator: self.oscillator,
-            modules: self.modules.iter().map(|m| m.clone_box()).collect(),
+            oscillator: self.oscillator.clone(),
+            modules: self.modules.clone(),
+            master: self.master.clone(),
             amplitude: self.amplitude,
+            unison: self.unison,
+            stack: self.stack.clone(),
+            modulation: self.modulation,
+            tempo: self.tempo,
+            sample_rate: self.sample_rate,
         }
     }
 }
@@ -21,44 +104,332 @@ impl<O: Oscillator> ModularSynth<O> {
         Self {
             oscillator,
             modules: Vec::new(),
+            master: MasterBus::new(),
             amplitude: 1.0,
+            unison: UnisonSettings::default(),
+            stack: UnisonStack::new(),
+            modulation: Modulator::new(SAMPLE_RATE),
+            tempo: DEFAULT_TEMPO,
+            sample_rate: SAMPLE_RATE,
         }
     }
 
     pub fn add_module<M: Module + 'static>(&mut self, module: M) {
-        self.modules.push(Box::new(module));
+        self.add_module_with_activation(module, true);
+    }
+
+    /// Adds a module that starts switched on or off, at the tempo of the synth
+    pub fn add_module_with_activation<M: Module + 'static>(&mut self, mut module: M, active: bool) {
+        module.set_tempo(self.tempo);
+        self.modules.push(ModuleSlot::new(Box::new(module), active));
+    }
+
+    /// Adds a module to the master bus instead of the per-voice chain
+    pub fn add_master_module<M: Module + 'static>(&mut self, module: M) {
+        self.add_master_module_with_activation(module, true);
+    }
+
+    /// Adds a master bus module that starts switched on or off
+    pub fn add_master_module_with_activation<M: Module + 'static>(
+        &mut self,
+        mut module: M,
+        active: bool,
+    ) {
+        module.set_tempo(self.tempo);
+        self.master.add_module_with_activation(module, active);
     }
 
     pub fn get_module(&self, index: usize) -> Option<&dyn Module> {
-        self.modules.get(index).map(|m| m.as_ref())
+        self.modules.get(index).map(|m| m.module.as_ref())
+    }
+
+    /// Looks a module up by name in the voice chain, then on the master bus
+    pub fn find_slot(&self, name: &str) -> Option<&ModuleSlot> {
+        self.modules
+            .iter()
+            .chain(self.master.modules.iter())
+            .find(|m| m.name() == name)
+    }
+
+    pub fn find_slot_mut(&mut self, name: &str) -> Option<&mut ModuleSlot> {
+        self.modules
+            .iter_mut()
+            .chain(self.master.modules.iter_mut())
+            .find(|m| m.name() == name)
+    }
+
+    pub fn is_module_active(&self, name: &str) -> bool {
+        self.find_slot(name).is_some_and(|slot| slot.active)
+    }
+
+    /// Switches a module on or off. Returns false when the chain has no such module.
+    pub fn set_module_active(&mut self, name: &str, active: bool) -> bool {
+        match self.find_slot_mut(name) {
+            Some(slot) => {
+                slot.active = active;
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn generate_sample(&mut self, phase: f64, time: f64) -> f64 {
-        let mut sample = self.oscillator.sample(phase) * self.amplitude;
+    /// Clones the oscillator and the per-voice modules only.
+    /// Each note owns such a copy so filter/LFO states are never shared between voices.
+    pub fn voice(&self) -> Self {
+        Self {
+            oscillator: self.oscillator.clone(),
+            modules: self.modules.clone(),
+            master: MasterBus::new(),
+            amplitude: self.amplitude,
+            unison: self.unison,
+            stack: UnisonStack::new(),
+            modulation: self.modulation,
+            tempo: self.tempo,
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Builds the unison stack of a voice once its chain is final (after velocity).
+    /// Allocates, so it is called on the control side when the note is created.
+    pub fn prepare_voice(&mut self) {
+        if self.unison.is_active() {
+            self.stack = UnisonStack::build(&self.oscillator, &self.unison);
+        }
+    }
 
+    /// Generates a frame of the voice chain, `increment` is the phase advance per sample in radians.
+    pub fn generate_frame(&mut self, phase: f64, increment: f64, time: f64) -> StereoFrame {
+        let mut frame = self.oscillate(phase, increment);
         for module in &mut self.modules {
-            sample = module.process(sample, time);
+            frame = module.process_frame(frame, time);
         }
+        frame
+    }
+
+    /// Oscillator (or unison stack) output only, before the voice chain.
+    /// Without unison the oscillator is mono and both channels are equal.
+    pub fn oscillate(&mut self, phase: f64, increment: f64) -> StereoFrame {
+        let [left, right] = if self.stack.is_empty() {
+            let sample = self.oscillator.next_sample(phase, increment);
+            [sample, sample]
+        } else {
+            self.stack.next_frame(increment)
+        };
+        [left * self.amplitude, right * self.amplitude]
+    }
+
+    /// Runs a block of oscillator frames through the voice chain, module after module
+    pub fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for module in &mut self.modules {
+            module.process_block(block);
+        }
+    }
 
-        sample
+    /// Passes the mix of all voices through the master bus
+    pub fn process_master(
+        &mut self,
+        frame: StereoFrame,
+        time: f64,
+        controllers: &ModControllers,
+    ) -> StereoFrame {
+        self.modulate_master(controllers, 1);
+        self.master.process_frame(frame, time)
     }
 
-    /// Déclenche note_on sur tous les modules ADSR
+    /// Block version of `process_master`, split at the control steps of the modulation
+    pub fn process_master_block(
+        &mut self,
+        block: &mut [StereoFrame],
+        controllers: &ModControllers,
+    ) {
+        let mut start = 0;
+        while start < block.len() {
+            let span = self.modulate_master(controllers, block.len() - start);
+            self.master.process_block(&mut block[start..start + span]);
+            start += span;
+        }
+    }
+
+    /// Key signal of the next master block, heard by the compressor when its sidechain is on
+    pub fn sidechain(&mut self, key: &[StereoFrame]) {
+        self.master.sidechain(key);
+    }
+
+    /// Updates the modulation of the voice when a control step is due and returns
+    /// how many of the next `frames` frames are rendered before the following one
+    pub fn modulate(&mut self, controllers: &ModControllers, frames: usize) -> usize {
+        let (span, amounts) = self.modulation.next_span(controllers, frames);
+        if let Some(amounts) = amounts {
+            for module in &mut self.modules {
+                module.modulate(&amounts);
+            }
+            for oscillator in self.oscillators_mut() {
+                oscillator.modulate(&amounts);
+            }
+        }
+        span
+    }
+
+    /// Same as `modulate` for the master bus, driven by the LFOs and the controllers only
+    fn modulate_master(&mut self, controllers: &ModControllers, frames: usize) -> usize {
+        let (span, amounts) = self.modulation.next_span(controllers, frames);
+        if let Some(amounts) = amounts {
+            for module in &mut self.master.modules {
+                module.modulate(&amounts);
+            }
+        }
+        span
+    }
+
+    /// Pitch offset of the voice in semitones, from the modulation matrix
+    pub fn pitch_offset(&self) -> f64 {
+        self.modulation.amounts().get(ModDestination::Pitch)
+    }
+
+    pub fn get_mod_matrix(&self) -> ModMatrix {
+        self.modulation.get_matrix()
+    }
+
+    pub fn set_mod_matrix(&mut self, matrix: ModMatrix) {
+        self.modulation.set_matrix(matrix);
+    }
+
+    pub fn get_pitch(&self) -> PitchSettings {
+        self.modulation.get_pitch()
+    }
+
+    pub fn set_pitch(&mut self, settings: PitchSettings) {
+        self.modulation.set_pitch(settings);
+    }
+
+    /// Slides the voice from `semitones` away from its note, over the glide time of the patch
+    pub fn glide_from(&mut self, semitones: f64) {
+        self.modulation.glide_from(semitones);
+    }
+
+    pub fn glide_offset(&self) -> f64 {
+        self.modulation.glide_offset()
+    }
+
+    /// Modulation sources of this synth, read by new voices to follow its free-running LFOs
+    pub fn modulator(&self) -> &Modulator {
+        &self.modulation
+    }
+
+    pub fn follow_free_lfos(&mut self, master: &Modulator) {
+        self.modulation.follow_free_lfos(master);
+    }
+
+    pub fn get_tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    pub fn get_sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Moves the oscillator, the modulation and every module to another sample rate.
+    /// Settings are kept, running states (delay lines, reverb tails) may start over.
+    /// Allocates, so it is called on the control side before the synth reaches the engine.
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        self.oscillator.set_sample_rate(sample_rate);
+        self.modulation.set_sample_rate(sample_rate);
+        for slot in self
+            .modules
+            .iter_mut()
+            .chain(self.master.modules.iter_mut())
+        {
+            slot.module.set_sample_rate(sample_rate);
+        }
+        self.set_tempo(self.tempo);
+    }
+
+    /// Sets the global tempo of the modulation and of every module, voice chain and master bus
+    pub fn set_tempo(&mut self, bpm: f64) {
+        self.tempo = bpm.max(1.0);
+        self.modulation.set_tempo(self.tempo);
+        for module in self
+            .modules
+            .iter_mut()
+            .chain(self.master.modules.iter_mut())
+        {
+            module.set_tempo(self.tempo);
+        }
+    }
+
+    /// Template oscillator followed by the oscillators of the unison stack
+    pub fn oscillators_mut(&mut self) -> impl Iterator<Item = &mut O> {
+        std::iter::once(&mut self.oscillator).chain(
+            self.stack
+                .oscillators
+                .iter_mut()
+                .map(|unison| &mut unison.oscillator),
+        )
+    }
+
+    pub fn set_unison(&mut self, settings: UnisonSettings) {
+        self.unison = UnisonSettings {
+            voices: settings.voices.clamp(1, MAX_UNISON),
+            detune: settings.detune.max(0.0),
+            spread: settings.spread.clamp(0.0, 1.0),
+        };
+        self.stack.update(&self.unison);
+    }
+
+    /// Déclenche note_on sur l'oscillateur, tous les modules ADSR, les enveloppes de filtre,
+    /// les LFO, le bruit et la modulation
     pub fn note_on(&mut self) {
+        self.modulation.note_on();
+        for oscillator in self.oscillators_mut() {
+            oscillator.note_on();
+        }
         for module in &mut self.modules {
             // Utilise Any pour downcaster vers ADSR si possible
-            if let Some(adsr) = module.as_any_mut().downcast_mut::<crate::synths::modules::adsr::ADSR>() {
+            let any = module.as_any_mut();
+            if let Some(adsr) = any.downcast_mut::<ADSR>() {
                 adsr.note_on();
+            } else if let Some(filter) = any.downcast_mut::<MultiModeFilter>() {
+                filter.note_on();
+            } else if let Some(lfo) = any.downcast_mut::<LFO>() {
+                lfo.note_on();
+            } else if let Some(noise) = any.downcast_mut::<Noise>() {
+                noise.note_on();
             }
         }
     }
 
-    /// Déclenche note_off sur tous les modules ADSR
+    /// Déclenche note_off sur l'oscillateur, tous les modules ADSR et les enveloppes de filtre
     pub fn note_off(&mut self) {
+        self.modulation.note_off();
+        for oscillator in self.oscillators_mut() {
+            oscillator.note_off();
+        }
         for module in &mut self.modules {
-            if let Some(adsr) = module.as_any_mut().downcast_mut::<crate::synths::modules::adsr::ADSR>() {
+            let any = module.as_any_mut();
+            if let Some(adsr) = any.downcast_mut::<ADSR>() {
                 adsr.note_off();
+            } else if let Some(filter) = any.downcast_mut::<MultiModeFilter>() {
+                filter.note_off();
             }
         }
     }
+
+    /// Tells the filters and the modulation which note the voice plays, for their key tracking
+    pub fn set_note_frequency(&mut self, frequency: f64) {
+        self.modulation.set_note_frequency(frequency);
+        for module in &mut self.modules {
+            if let Some(filter) = module.as_any_mut().downcast_mut::<MultiModeFilter>() {
+                filter.set_note_frequency(frequency);
+            }
+        }
+    }
+
+    /// Velocity of the note, as a modulation source
+    pub fn set_note_velocity(&mut self, velocity: f64) {
+        self.modulation.set_velocity(velocity);
+    }
 }
@@ -0,0 +1,577 @@
+use crate::consts::constants::{self, MOD_CONTROL_INTERVAL};
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::modules::lfo::{LFO, LfoWaveform};
+use crate::synths::pitch::{Glide, PitchSettings};
+use crate::synths::tempo::TempoDivision;
+use serde::{Deserialize, Serialize};
+
+pub const MOD_ROUTES: usize = 8; // emplacements de la matrice
+pub const MOD_LFOS: usize = 2;
+const KEY_REFERENCE: f64 = 261.63; // Do 4 : le suivi de clavier y vaut 0
+const KEY_RANGE: f64 = 5.0; // octaves de part et d'autre du Do 4 pour aller jusqu'à ±1
+
+/// Signals that can drive a parameter.
+/// LFOs, key tracking and random are bipolar (-1..1), the others go from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModSource {
+    Lfo1,
+    Lfo2,
+    Envelope,
+    Velocity,
+    KeyTracking,
+    ModWheel,
+    Aftertouch,
+    Random, // tiré une fois par note
+}
+
+impl ModSource {
+    pub const ALL: [ModSource; 8] = [
+        ModSource::Lfo1,
+        ModSource::Lfo2,
+        ModSource::Envelope,
+        ModSource::Velocity,
+        ModSource::KeyTracking,
+        ModSource::ModWheel,
+        ModSource::Aftertouch,
+        ModSource::Random,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModSource::Lfo1 => "LFO 1",
+            ModSource::Lfo2 => "LFO 2",
+            ModSource::Envelope => "Envelope",
+            ModSource::Velocity => "Velocity",
+            ModSource::KeyTracking => "Key tracking",
+            ModSource::ModWheel => "Mod wheel",
+            ModSource::Aftertouch => "Aftertouch",
+            ModSource::Random => "Random",
+        }
+    }
+}
+
+/// Parameters a route can move, each by up to `range` at full depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModDestination {
+    Cutoff,
+    Resonance,
+    Gain,
+    FmIndex,
+    Pitch,
+    Pan,
+    ReverbMix, // sur le master bus : seules les sources globales l'atteignent
+}
+
+impl ModDestination {
+    pub const COUNT: usize = 7;
+
+    pub const ALL: [ModDestination; Self::COUNT] = [
+        ModDestination::Cutoff,
+        ModDestination::Resonance,
+        ModDestination::Gain,
+        ModDestination::FmIndex,
+        ModDestination::Pitch,
+        ModDestination::Pan,
+        ModDestination::ReverbMix,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModDestination::Cutoff => "Filter cutoff",
+            ModDestination::Resonance => "Filter resonance",
+            ModDestination::Gain => "Gain",
+            ModDestination::FmIndex => "FM index",
+            ModDestination::Pitch => "Pitch",
+            ModDestination::Pan => "Pan",
+            ModDestination::ReverbMix => "Reverb mix",
+        }
+    }
+
+    /// Amount added by a source at 1 with a depth of 1
+    pub fn range(&self) -> f64 {
+        match self {
+            ModDestination::Cutoff => 5.0,    // octaves
+            ModDestination::Resonance => 2.0, // octaves de Q
+            ModDestination::Gain => 24.0,     // dB
+            ModDestination::FmIndex => 5.0,
+            ModDestination::Pitch => 12.0, // demi-tons
+            ModDestination::Pan => 1.0,
+            ModDestination::ReverbMix => 1.0,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            ModDestination::Cutoff | ModDestination::Resonance => "oct",
+            ModDestination::Gain => "dB",
+            ModDestination::Pitch => "st",
+            ModDestination::FmIndex | ModDestination::Pan | ModDestination::ReverbMix => "",
+        }
+    }
+}
+
+/// One slot of the matrix: a source moving a destination by `depth` (-1..1) of its range
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModRoute {
+    pub active: bool,
+    pub source: ModSource,
+    pub destination: ModDestination,
+    pub depth: f64,
+}
+
+impl Default for ModRoute {
+    fn default() -> Self {
+        Self {
+            active: false,
+            source: ModSource::Lfo1,
+            destination: ModDestination::Cutoff,
+            depth: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModLfoSettings {
+    pub waveform: LfoWaveform,
+    pub frequency: f64, // Hz, quand il n'est pas synchronisé
+    pub sync: bool,     // cycle d'une durée de `division` au tempo global
+    pub division: TempoDivision,
+    pub retrigger: bool, // false = phase libre, partagée par toutes les notes
+    pub delay: f64,      // secondes
+    pub fade_in: f64,    // secondes
+}
+
+impl ModLfoSettings {
+    fn default_for(index: usize) -> Self {
+        Self {
+            waveform: constants::MOD_LFO_WAVEFORM,
+            frequency: constants::MOD_LFO_FREQUENCIES[index],
+            sync: false,
+            division: TempoDivision::default(),
+            retrigger: true,
+            delay: 0.0,
+            fade_in: 0.0,
+        }
+    }
+
+    fn apply(&self, lfo: &mut LFO) {
+        lfo.set_waveform(self.waveform);
+        lfo.set_freq(self.frequency.max(0.0));
+        lfo.set_sync(self.sync.then_some(self.division));
+        lfo.set_retrigger(self.retrigger);
+        lfo.set_delay(self.delay);
+        lfo.set_fade_in(self.fade_in);
+    }
+}
+
+impl Default for ModLfoSettings {
+    fn default() -> Self {
+        Self::default_for(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModEnvelopeSettings {
+    pub attack: f64,
+    pub decay: f64,
+    pub sustain: f64,
+    pub release: f64,
+}
+
+impl Default for ModEnvelopeSettings {
+    fn default() -> Self {
+        Self {
+            attack: constants::MOD_ENV_ATTACK,
+            decay: constants::MOD_ENV_DECAY,
+            sustain: constants::MOD_ENV_SUSTAIN,
+            release: constants::MOD_ENV_RELEASE,
+        }
+    }
+}
+
+/// Routes and source settings of the modulation matrix, saved in presets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModMatrix {
+    pub routes: [ModRoute; MOD_ROUTES],
+    pub lfos: [ModLfoSettings; MOD_LFOS],
+    pub envelope: ModEnvelopeSettings,
+}
+
+impl Default for ModMatrix {
+    fn default() -> Self {
+        Self {
+            routes: [ModRoute::default(); MOD_ROUTES],
+            lfos: std::array::from_fn(ModLfoSettings::default_for),
+            envelope: ModEnvelopeSettings::default(),
+        }
+    }
+}
+
+impl ModMatrix {
+    /// True when at least one route moves a parameter
+    pub fn is_active(&self) -> bool {
+        self.routes
+            .iter()
+            .any(|route| route.active && route.depth != 0.0)
+    }
+}
+
+/// Values of the performance controllers, shared by every voice
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModControllers {
+    pub mod_wheel: f64,  // 0..1
+    pub aftertouch: f64, // 0..1
+}
+
+/// Amount of every destination, in the unit of its range
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModAmounts([f64; ModDestination::COUNT]);
+
+impl ModAmounts {
+    pub fn get(&self, destination: ModDestination) -> f64 {
+        self.0[destination as usize]
+    }
+}
+
+/// Sources of the matrix for one voice, or for the master bus.
+/// The master bus never plays a note, so its envelope, velocity, key and random stay at 0
+/// and only the LFOs and the controllers reach it.
+/// The vibrato and the glide of the voice are added to its pitch amount.
+#[derive(Clone, Copy)]
+pub struct Modulator {
+    matrix: ModMatrix,
+    pitch: PitchSettings,
+    glide: Glide,
+    lfos: [LFO; MOD_LFOS],
+    lfo_values: [f64; MOD_LFOS], // sorties au dernier pas de contrôle
+    envelope: ADSR,
+    velocity: f64,
+    key: f64,
+    random: f64,
+    amounts: ModAmounts,
+    countdown: usize, // échantillons avant la prochaine mise à jour
+    applied: bool,    // les modules portent encore des décalages à remettre à zéro
+    sample_rate: f64,
+}
+
+impl Modulator {
+    pub fn new(sample_rate: f64) -> Self {
+        let matrix = ModMatrix::default();
+        let lfos = matrix.lfos.map(|settings| {
+            let mut lfo = LFO::new(settings.waveform, settings.frequency, sample_rate);
+            lfo.set_amplitude(1.0);
+            lfo.set_bipolar(true);
+            lfo.set_phase(0.0);
+            settings.apply(&mut lfo);
+            lfo
+        });
+        let envelope = matrix.envelope;
+        Self {
+            matrix,
+            pitch: PitchSettings::default(),
+            glide: Glide::default(),
+            lfos,
+            lfo_values: [0.0; MOD_LFOS],
+            envelope: ADSR::new(
+                sample_rate,
+                envelope.attack,
+                envelope.decay,
+                envelope.sustain,
+                envelope.release,
+            ),
+            velocity: 0.0,
+            key: 0.0,
+            random: 0.0,
+            amounts: ModAmounts::default(),
+            countdown: 0,
+            applied: false,
+            sample_rate,
+        }
+    }
+
+    /// Changes the routes and sources, playing LFOs and envelopes keep their phase and stage
+    pub fn set_matrix(&mut self, matrix: ModMatrix) {
+        self.matrix = matrix;
+        for route in self.matrix.routes.iter_mut() {
+            route.depth = route.depth.clamp(-1.0, 1.0);
+        }
+        for (lfo, settings) in self.lfos.iter_mut().zip(matrix.lfos) {
+            settings.apply(lfo);
+        }
+        self.envelope.set_attack(matrix.envelope.attack);
+        self.envelope.set_decay(matrix.envelope.decay);
+        self.envelope.set_sustain(matrix.envelope.sustain);
+        self.envelope.set_release(matrix.envelope.release);
+    }
+
+    pub fn get_matrix(&self) -> ModMatrix {
+        self.matrix
+    }
+
+    /// Same sources and routes at another sample rate, LFOs and envelope start over
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        let (matrix, pitch) = (self.matrix, self.pitch);
+        *self = Self::new(sample_rate);
+        self.set_matrix(matrix);
+        self.set_pitch(pitch);
+    }
+
+    pub fn set_pitch(&mut self, settings: PitchSettings) {
+        self.pitch = settings.clamped();
+    }
+
+    pub fn get_pitch(&self) -> PitchSettings {
+        self.pitch
+    }
+
+    /// Starts a glide from `semitones` away, the pitch moves at the next sample
+    pub fn glide_from(&mut self, semitones: f64) {
+        self.glide
+            .start(semitones, self.pitch.glide, self.sample_rate);
+        self.countdown = 0;
+    }
+
+    /// Semitones left before the glide reaches the note
+    pub fn glide_offset(&self) -> f64 {
+        self.glide.offset()
+    }
+
+    /// True when the amounts move, from a route, the vibrato or a glide
+    fn is_active(&self) -> bool {
+        self.matrix.is_active() || self.pitch.vibrato != 0.0 || self.glide.is_active()
+    }
+
+    /// Starts the envelope and the LFOs and draws the random values of a new note
+    pub fn note_on(&mut self) {
+        self.envelope.note_on();
+        self.random = rand::random::<f64>() * 2.0 - 1.0;
+        for lfo in self.lfos.iter_mut() {
+            lfo.note_on();
+            lfo.set_seed(rand::random());
+        }
+    }
+
+    /// Free-running LFOs of a new voice continue those of the master bus, which never restart
+    pub fn follow_free_lfos(&mut self, master: &Modulator) {
+        for (index, lfo) in self.lfos.iter_mut().enumerate() {
+            if !self.matrix.lfos[index].retrigger {
+                lfo.follow(&master.lfos[index]);
+            }
+        }
+    }
+
+    pub fn set_tempo(&mut self, bpm: f64) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_tempo(bpm);
+        }
+    }
+
+    pub fn note_off(&mut self) {
+        self.envelope.note_off();
+    }
+
+    pub fn set_note_frequency(&mut self, frequency: f64) {
+        self.key = ((frequency.max(1.0) / KEY_REFERENCE).log2() / KEY_RANGE).clamp(-1.0, 1.0);
+    }
+
+    pub fn set_velocity(&mut self, velocity: f64) {
+        self.velocity = velocity.clamp(0.0, 1.0);
+    }
+
+    /// Amounts applied since the last control update
+    pub fn amounts(&self) -> ModAmounts {
+        self.amounts
+    }
+
+    fn source_value(&self, source: ModSource, controllers: &ModControllers) -> f64 {
+        match source {
+            ModSource::Lfo1 => self.lfo_values[0],
+            ModSource::Lfo2 => self.lfo_values[1],
+            ModSource::Envelope => self.envelope.get_level(),
+            ModSource::Velocity => self.velocity,
+            ModSource::KeyTracking => self.key,
+            ModSource::ModWheel => controllers.mod_wheel,
+            ModSource::Aftertouch => controllers.aftertouch,
+            ModSource::Random => self.random,
+        }
+    }
+
+    /// Returns how many of the next `frames` frames use the current amounts.
+    /// When a control update is due, the sources advance by `MOD_CONTROL_INTERVAL` frames
+    /// and the new amounts are returned so the caller applies them first.
+    /// Without any active route, vibrato or glide nothing is split nor applied, once the
+    /// offsets are cleared.
+    pub fn next_span(
+        &mut self,
+        controllers: &ModControllers,
+        frames: usize,
+    ) -> (usize, Option<ModAmounts>) {
+        if !self.is_active() {
+            if self.applied {
+                self.applied = false;
+                self.amounts = ModAmounts::default();
+                self.countdown = 0;
+                return (frames, Some(self.amounts));
+            }
+            return (frames, None);
+        }
+
+        let mut update = None;
+        if self.countdown == 0 {
+            self.update(controllers);
+            self.countdown = MOD_CONTROL_INTERVAL;
+            self.applied = true;
+            update = Some(self.amounts);
+        }
+        let span = frames.min(self.countdown);
+        self.countdown -= span;
+        (span, update)
+    }
+
+    fn update(&mut self, controllers: &ModControllers) {
+        // Valeurs au début de l'intervalle, puis les sources avancent jusqu'au pas suivant
+        for (value, lfo) in self.lfo_values.iter_mut().zip(self.lfos.iter_mut()) {
+            *value = lfo.advance(MOD_CONTROL_INTERVAL);
+        }
+
+        let mut amounts = [0.0; ModDestination::COUNT];
+        for route in self.matrix.routes.iter().filter(|route| route.active) {
+            amounts[route.destination as usize] += self.source_value(route.source, controllers)
+                * route.depth
+                * route.destination.range();
+        }
+        amounts[ModDestination::Pitch as usize] +=
+            self.lfo_values[self.pitch.vibrato_lfo] * self.pitch.vibrato + self.glide.offset();
+        self.amounts = ModAmounts(amounts);
+        self.glide.advance(MOD_CONTROL_INTERVAL);
+
+        for _ in 0..MOD_CONTROL_INTERVAL {
+            self.envelope.get_amplitude();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    const CONTROLLERS: ModControllers = ModControllers {
+        mod_wheel: 0.75,
+        aftertouch: 0.25,
+    };
+
+    /// Note one octave above middle C at velocity 0.5, with the given routes and no vibrato.
+    /// The envelope jumps to a sustain of 0.6.
+    fn modulator(routes: &[ModRoute]) -> Modulator {
+        let mut matrix = ModMatrix {
+            envelope: ModEnvelopeSettings {
+                attack: 0.0,
+                decay: 0.0,
+                sustain: 0.6,
+                release: 0.1,
+            },
+            ..ModMatrix::default()
+        };
+        matrix.routes[..routes.len()].copy_from_slice(routes);
+        let mut modulator = Modulator::new(SAMPLE_RATE);
+        modulator.set_matrix(matrix);
+        modulator.set_pitch(PitchSettings {
+            vibrato: 0.0,
+            ..PitchSettings::default()
+        });
+        modulator.set_velocity(0.5);
+        modulator.set_note_frequency(KEY_REFERENCE * 2.0);
+        modulator.note_on();
+        modulator
+    }
+
+    fn route(source: ModSource, destination: ModDestination, depth: f64) -> ModRoute {
+        ModRoute {
+            active: true,
+            source,
+            destination,
+            depth,
+        }
+    }
+
+    /// Amounts of the second control update, once the envelope has left its attack
+    fn second_update(modulator: &mut Modulator) -> ModAmounts {
+        let (span, _) = modulator.next_span(&CONTROLLERS, MOD_CONTROL_INTERVAL);
+        assert_eq!(span, MOD_CONTROL_INTERVAL);
+        let (_, amounts) = modulator.next_span(&CONTROLLERS, MOD_CONTROL_INTERVAL);
+        amounts.expect("no control update")
+    }
+
+    #[test]
+    fn every_route_moves_its_destination_by_source_times_depth() {
+        for source in ModSource::ALL {
+            for destination in ModDestination::ALL {
+                let depth = -0.5;
+                let mut modulator = modulator(&[route(source, destination, depth)]);
+                let amounts = second_update(&mut modulator);
+
+                let value = match source {
+                    ModSource::Envelope => 0.6,
+                    ModSource::Velocity => 0.5,
+                    ModSource::KeyTracking => 1.0 / KEY_RANGE,
+                    ModSource::ModWheel => 0.75,
+                    ModSource::Aftertouch => 0.25,
+                    // Sources qui bougent seules : elles sont lues, pas recalculées
+                    ModSource::Lfo1 => modulator.lfo_values[0],
+                    ModSource::Lfo2 => modulator.lfo_values[1],
+                    ModSource::Random => modulator.random,
+                };
+                assert!(value != 0.0, "{} is silent", source.name());
+                for other in ModDestination::ALL {
+                    let expected = if other == destination {
+                        value * depth * destination.range()
+                    } else {
+                        0.0
+                    };
+                    assert!(
+                        (amounts.get(other) - expected).abs() < 1e-12,
+                        "{} -> {}: {} moved by {}, expected {expected}",
+                        source.name(),
+                        destination.name(),
+                        other.name(),
+                        amounts.get(other)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn routes_to_the_same_destination_add_up() {
+        let mut modulator = modulator(&[
+            route(ModSource::ModWheel, ModDestination::Cutoff, 1.0),
+            route(ModSource::Velocity, ModDestination::Cutoff, -0.25),
+            route(ModSource::Aftertouch, ModDestination::Cutoff, 0.5),
+            route(ModSource::Velocity, ModDestination::Gain, 1.0),
+        ]);
+        let amounts = second_update(&mut modulator);
+        let expected = (0.75 - 0.5 * 0.25 + 0.25 * 0.5) * ModDestination::Cutoff.range();
+        assert!((amounts.get(ModDestination::Cutoff) - expected).abs() < 1e-12);
+        assert_eq!(amounts.get(ModDestination::Gain), 0.5 * 24.0);
+    }
+
+    #[test]
+    fn zero_depth_and_inactive_routes_change_nothing() {
+        let mut inactive = route(ModSource::ModWheel, ModDestination::Pitch, 1.0);
+        inactive.active = false;
+        let mut modulator = modulator(&[
+            route(ModSource::ModWheel, ModDestination::Cutoff, 0.0),
+            route(ModSource::Lfo1, ModDestination::Gain, 0.0),
+            inactive,
+        ]);
+        assert!(!modulator.get_matrix().is_active());
+        // Rien n'est découpé ni appliqué
+        assert_eq!(modulator.next_span(&CONTROLLERS, 512), (512, None));
+        assert_eq!(modulator.amounts(), ModAmounts::default());
+    }
+}
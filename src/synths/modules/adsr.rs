@@ -1,4 +1,4 @@
-use crate::synths::traits::Module;
+use crate::synths::traits::{Module, StereoFrame};
 
 #[derive(Debug, Clone, Copy)]
 pub enum EnvelopeCurve {
@@ -45,8 +45,12 @@ impl ADSR {
 
     fn calc_step(&mut self, target: f64, time: f64) {
         if time <= 0.0 {
+            // Étape instantanée : le niveau est déjà sur la cible, le pas ne le bouge plus
             self.level = target;
-            self.step = 1.0;
+            self.step = match self.curve {
+                EnvelopeCurve::Linear => 0.0,
+                EnvelopeCurve::Exponential => 1.0,
+            };
             return;
         }
         let samples = time * self.sample_rate;
@@ -75,6 +79,16 @@ impl ADSR {
         self.calc_step(1.0, self.attack);
     }
 
+    /// Restarts the attack from a given level instead of silence, so a retriggered note does not click
+    pub fn note_on_from(&mut self, level: f64) {
+        self.stage = EnvelopeStage::Attack;
+        self.level = match self.curve {
+            EnvelopeCurve::Exponential => level.clamp(1e-6, 1.0),
+            EnvelopeCurve::Linear => level.clamp(0.0, 1.0),
+        };
+        self.calc_step(1.0, self.attack);
+    }
+
     pub fn note_off(&mut self) {
         self.stage = EnvelopeStage::Release;
         self.calc_step(0.0, self.release);
@@ -217,6 +231,15 @@ impl Module for ADSR {
         input * self.level
     }
 
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.advance();
+        [frame[0] * self.level, frame[1] * self.level]
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        ADSR::set_sample_rate(self, sample_rate);
+    }
+
     fn name(&self) -> &'static str {
         "ADSR"
     }
@@ -233,3 +256,25 @@ impl Module for ADSR {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_length_stages_settle_on_their_target() {
+        let mut envelope = ADSR::new(44100.0, 0.0, 0.0, 0.5, 0.0);
+        envelope.note_on();
+        for _ in 0..100 {
+            let level = envelope.get_amplitude();
+            assert!((0.0..=1.0).contains(&level), "level {level} out of range");
+        }
+        assert!(matches!(envelope.get_stage(), EnvelopeStage::Sustain));
+        assert_eq!(envelope.get_level(), 0.5);
+
+        envelope.note_off();
+        envelope.get_amplitude();
+        assert!(matches!(envelope.get_stage(), EnvelopeStage::Idle));
+        assert_eq!(envelope.get_level(), 0.0);
+    }
+}
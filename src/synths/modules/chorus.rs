@@ -0,0 +1,106 @@
+use crate::synths::modules::modulated::{DelayLine, ModEffectSettings, Sweep};
+use crate::synths::traits::{Module, StereoFrame};
+
+const CENTER_MS: f64 = 12.0; // retard moyen des copies
+const SWEEP_MS: f64 = 6.0; // écart maximal autour du centre, à depth = 1
+const TAPS: [f64; 2] = [0.0, 0.5]; // deux copies en opposition de phase par canal
+const RIGHT_OFFSET: f64 = 0.25; // le canal droit est décalé d'un quart de cycle
+
+/// Chorus: two copies per channel read through slowly swept delays, a little out of tune
+/// with the dry signal. The right channel sweeps a quarter cycle later, which widens the sound.
+#[derive(Clone)]
+pub struct Chorus {
+    sample_rate: f64,
+    settings: ModEffectSettings,
+    sweep: Sweep,
+    lines: [DelayLine; 2],
+}
+
+impl Chorus {
+    pub fn new(sample_rate: f64, settings: ModEffectSettings) -> Self {
+        let max_ms = CENTER_MS + SWEEP_MS;
+        Self {
+            sample_rate,
+            settings: settings.clamped(),
+            sweep: Sweep::default(),
+            lines: [
+                DelayLine::new(max_ms, sample_rate),
+                DelayLine::new(max_ms, sample_rate),
+            ],
+        }
+    }
+
+    /// Average of the copies of a channel
+    #[inline]
+    fn wet(&self, channel: usize, offset: f64) -> f64 {
+        let ms_to_samples = 0.001 * self.sample_rate;
+        let mut sum = 0.0;
+        for tap in TAPS {
+            let swing = self.sweep.unipolar(tap + offset) * 2.0 - 1.0;
+            let ms = CENTER_MS + SWEEP_MS * self.settings.depth * swing;
+            sum += self.lines[channel].read(ms * ms_to_samples);
+        }
+        sum / TAPS.len() as f64
+    }
+
+    #[inline]
+    fn chorus_frame(&mut self, frame: StereoFrame) -> StereoFrame {
+        let mut output = frame;
+        for (channel, offset) in [0.0, RIGHT_OFFSET].into_iter().enumerate() {
+            let wet = self.wet(channel, offset);
+            self.lines[channel].write(frame[channel] + wet * self.settings.feedback);
+            output[channel] = self.settings.blend(frame[channel], wet);
+        }
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        output
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: ModEffectSettings) {
+        self.settings = settings.clamped();
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> ModEffectSettings {
+        self.settings
+    }
+}
+
+impl Module for Chorus {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        let wet = self.wet(0, 0.0);
+        self.lines[0].write(input + wet * self.settings.feedback);
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        self.settings.blend(input, wet)
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.chorus_frame(frame)
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for frame in block.iter_mut() {
+            *frame = self.chorus_frame(*frame);
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Chorus::new(sample_rate, self.settings);
+    }
+
+    fn name(&self) -> &'static str {
+        "Chorus"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
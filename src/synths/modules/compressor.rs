@@ -1,13 +1,76 @@
-use crate::synths::traits::Module;
+use crate::consts::constants::{self, MAX_BLOCK_SIZE};
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+
+/// How the compressor measures the level it reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompressorDetector {
+    Peak, // valeur absolue de chaque échantillon, réagit aux transitoires
+    #[default]
+    Rms, // moyenne quadratique sur 30 ms, suit le volume perçu
+}
+
+impl CompressorDetector {
+    pub const ALL: [CompressorDetector; 2] = [CompressorDetector::Peak, CompressorDetector::Rms];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressorDetector::Peak => "Peak",
+            CompressorDetector::Rms => "RMS",
+        }
+    }
+}
+
+/// Longest lookahead, the line is allocated once for this length
+pub const MAX_LOOKAHEAD_MS: f64 = 10.0;
+
+/// Parameters of the compressor, saved in the presets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressorSettings {
+    pub threshold_db: f64,
+    pub ratio: f64,
+    pub attack: f64,  // secondes
+    pub release: f64, // secondes
+    pub makeup_gain_db: f64,
+    pub knee_db: f64, // largeur du coude, 0 = coude franc
+    pub detector: CompressorDetector,
+    pub lookahead_ms: f64,
+    pub sidechain: bool, // le détecteur écoute le signal de sidechain
+}
+
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: constants::CURRENT_THRESHOLD,
+            ratio: constants::CURRENT_RATIO,
+            attack: constants::CURRENT_ATTACK,
+            release: constants::CURRENT_RELEASE,
+            makeup_gain_db: constants::CURRENT_MAKEUP_GAIN,
+            knee_db: constants::CURRENT_KNEE,
+            detector: CompressorDetector::default(),
+            lookahead_ms: constants::CURRENT_LOOKAHEAD_MS,
+            sidechain: false,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Compressor {
     threshold_db: f64,
     ratio: f64,
+    attack_sec: f64,
+    release_sec: f64,
     attack_coeff: f64,
     release_coeff: f64,
     makeup_gain_db: f64,
-    _sample_rate: f64,
+    knee_db: f64, // largeur du coude, 0 = coude franc
+    detector: CompressorDetector,
+    sample_rate: f64,
+
+    // Recalculés par les setters, pour ne pas refaire les conversions à chaque échantillon
+    knee_start: f64,  // niveau linéaire sous lequel rien n'est compressé
+    makeup_gain: f64, // make-up en linéaire
 
     // Pour calcul RMS
     rms_buffer: Vec<f64>,
@@ -15,6 +78,18 @@ pub struct Compressor {
     rms_index: usize,
     rms_window_size: usize,
 
+    // Lookahead : le signal est retardé, le détecteur l'entend en avance
+    lookahead_ms: f64,
+    lookahead_samples: usize,
+    lookahead_buffer: Vec<StereoFrame>,
+    lookahead_index: usize,
+
+    // Sidechain : le détecteur écoute un signal extérieur au lieu de l'entrée
+    sidechain: bool,
+    key: Vec<StereoFrame>, // signal du bloc en cours, fourni par `Module::sidechain`
+    key_len: usize,
+    key_index: usize,
+
     envelope_db: f64,
 }
 
@@ -27,24 +102,59 @@ impl Compressor {
         makeup_gain_db: f64,
         sample_rate: f64,
     ) -> Self {
-        let attack_coeff = (-1.0 / (attack_sec * sample_rate)).exp();
-        let release_coeff = (-1.0 / (release_sec * sample_rate)).exp();
+        let attack_coeff = Self::time_to_coeff(attack_sec, sample_rate);
+        let release_coeff = Self::time_to_coeff(release_sec, sample_rate);
 
         let rms_window_size = (0.03 * sample_rate) as usize; // 30 ms window
+        let lookahead_len = (MAX_LOOKAHEAD_MS * 0.001 * sample_rate) as usize + 1;
 
-        Self {
+        let mut compressor = Self {
             threshold_db,
             ratio,
+            attack_sec,
+            release_sec,
             attack_coeff,
             release_coeff,
             makeup_gain_db,
-            _sample_rate: sample_rate,
+            knee_db: constants::CURRENT_KNEE,
+            detector: CompressorDetector::Rms,
+            sample_rate,
+            knee_start: 0.0,
+            makeup_gain: Self::db_to_linear(makeup_gain_db),
             rms_buffer: vec![0.0; rms_window_size],
             rms_sum: 0.0,
             rms_index: 0,
             rms_window_size,
-            envelope_db: -120.0,
-        }
+            lookahead_ms: 0.0,
+            lookahead_samples: 0,
+            lookahead_buffer: vec![[0.0, 0.0]; lookahead_len],
+            lookahead_index: 0,
+            sidechain: false,
+            key: vec![[0.0, 0.0]; MAX_BLOCK_SIZE],
+            key_len: 0,
+            key_index: 0,
+            envelope_db: 0.0, // réduction de gain en dB, aucune au départ
+        };
+        compressor.set_lookahead(constants::CURRENT_LOOKAHEAD_MS);
+        compressor.update_knee_start();
+        compressor
+    }
+
+    pub fn from_settings(settings: CompressorSettings, sample_rate: f64) -> Self {
+        let mut compressor = Self::new(
+            settings.threshold_db,
+            settings.ratio,
+            settings.attack,
+            settings.release,
+            settings.makeup_gain_db,
+            sample_rate,
+        );
+        compressor.set_settings(settings);
+        compressor
+    }
+
+    fn time_to_coeff(seconds: f64, sample_rate: f64) -> f64 {
+        (-1.0 / (seconds.max(1e-5) * sample_rate)).exp()
     }
 
     fn linear_to_db(linear: f64) -> f64 {
@@ -63,51 +173,264 @@ impl Compressor {
         self.rms_buffer[self.rms_index] = sq;
         self.rms_index = (self.rms_index + 1) % self.rms_window_size;
 
-        (self.rms_sum / self.rms_window_size as f64).sqrt()
+        (self.rms_sum.max(0.0) / self.rms_window_size as f64).sqrt()
     }
 
-    fn gain_reduction(&self, input_db: f64) -> f64 {
-        if input_db > self.threshold_db {
-            (input_db - self.threshold_db) * (1.0 - 1.0 / self.ratio)
+    /// Level the detector hears for this frame: the input, or the next frame of the sidechain.
+    /// Without a key signal for this frame, the sidechain is silent.
+    fn detected_level(&mut self, frame: StereoFrame) -> f64 {
+        let key = if self.key_index < self.key_len {
+            self.key_index += 1;
+            Some(self.key[self.key_index - 1])
         } else {
+            None
+        };
+        let heard = if self.sidechain {
+            key.unwrap_or([0.0, 0.0])
+        } else {
+            frame
+        };
+        heard[0].abs().max(heard[1].abs())
+    }
+
+    /// Follows the detected level and returns the linear gain to apply
+    fn next_gain(&mut self, level: f64) -> f64 {
+        let level = match self.detector {
+            CompressorDetector::Peak => level,
+            // Calcul du niveau RMS sur la fenêtre glissante
+            CompressorDetector::Rms => self.update_rms(level),
+        };
+        self.follow(level)
+    }
+
+    /// Smooths the reduction towards the one the level asks for and returns the linear gain
+    #[inline]
+    fn follow(&mut self, level: f64) -> f64 {
+        // Sous le coude, pas besoin de passer en dB
+        let target_reduction = if level <= self.knee_start {
             0.0
+        } else {
+            self.gain_reduction(Self::linear_to_db(level))
+        };
+
+        // Attack/Release smoothing de la réduction de gain
+        if target_reduction > self.envelope_db {
+            self.envelope_db =
+                target_reduction + self.attack_coeff * (self.envelope_db - target_reduction);
+        } else {
+            self.envelope_db =
+                target_reduction + self.release_coeff * (self.envelope_db - target_reduction);
+        }
+
+        // Gain final avec make-up
+        if self.envelope_db == 0.0 {
+            self.makeup_gain
+        } else {
+            self.makeup_gain * Self::db_to_linear(-self.envelope_db)
+        }
+    }
+
+    /// Compresses a block, `detect` turning the level heard into the one the gain follows
+    fn compress_block(
+        &mut self,
+        block: &mut [StereoFrame],
+        mut detect: impl FnMut(&mut Self, f64) -> f64,
+    ) {
+        // La part du signal de sidechain qui tombe dans ce bloc, lue en une fois
+        let key_start = self.key_index;
+        let key_len = self.key_len.saturating_sub(key_start).min(block.len());
+        self.key_index += key_len;
+
+        for (n, frame) in block.iter_mut().enumerate() {
+            let heard = if !self.sidechain {
+                *frame
+            } else if n < key_len {
+                self.key[key_start + n]
+            } else {
+                [0.0, 0.0]
+            };
+            let level = detect(self, heard[0].abs().max(heard[1].abs()));
+            let delayed = self.delayed(*frame);
+            let gain = self.follow(level);
+            *frame = [delayed[0] * gain, delayed[1] * gain];
         }
     }
 
+    fn update_knee_start(&mut self) {
+        self.knee_start = Self::db_to_linear(self.threshold_db - self.knee_db / 2.0);
+    }
+
+    /// Reduction in dB for a level, with a quadratic curve across the knee
+    fn gain_reduction(&self, input_db: f64) -> f64 {
+        let slope = 1.0 - 1.0 / self.ratio;
+        let over = input_db - self.threshold_db;
+        if 2.0 * over <= -self.knee_db {
+            0.0
+        } else if 2.0 * over < self.knee_db {
+            let x = over + self.knee_db / 2.0;
+            slope * x * x / (2.0 * self.knee_db)
+        } else {
+            over * slope
+        }
+    }
+
+    /// Writes a frame into the lookahead line and returns the one to compress
+    fn delayed(&mut self, frame: StereoFrame) -> StereoFrame {
+        let len = self.lookahead_buffer.len();
+        self.lookahead_buffer[self.lookahead_index] = frame;
+        let read = (self.lookahead_index + len - self.lookahead_samples) % len;
+        self.lookahead_index = (self.lookahead_index + 1) % len;
+        self.lookahead_buffer[read]
+    }
+
     // #### Getters ####
     pub fn get_threshold(&self) -> f64 {
         self.threshold_db
     }
 
+    pub fn get_ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    pub fn get_attack(&self) -> f64 {
+        self.attack_sec
+    }
+
+    pub fn get_release(&self) -> f64 {
+        self.release_sec
+    }
+
+    pub fn get_makeup_gain(&self) -> f64 {
+        self.makeup_gain_db
+    }
+
+    pub fn get_knee(&self) -> f64 {
+        self.knee_db
+    }
+
+    pub fn get_detector(&self) -> CompressorDetector {
+        self.detector
+    }
+
+    pub fn get_lookahead(&self) -> f64 {
+        self.lookahead_ms
+    }
+
+    pub fn get_sidechain(&self) -> bool {
+        self.sidechain
+    }
+
+    pub fn get_settings(&self) -> CompressorSettings {
+        CompressorSettings {
+            threshold_db: self.threshold_db,
+            ratio: self.ratio,
+            attack: self.attack_sec,
+            release: self.release_sec,
+            makeup_gain_db: self.makeup_gain_db,
+            knee_db: self.knee_db,
+            detector: self.detector,
+            lookahead_ms: self.lookahead_ms,
+            sidechain: self.sidechain,
+        }
+    }
+
+    /// Current gain reduction in dB (positive), for the meter
+    pub fn get_gain_reduction(&self) -> f64 {
+        self.envelope_db
+    }
+
     // #### Setters ####
     pub fn set_threshold(&mut self, new_threshold: f64) {
         self.threshold_db = new_threshold;
+        self.update_knee_start();
+    }
+
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio.max(1.0);
+    }
+
+    pub fn set_attack(&mut self, seconds: f64) {
+        self.attack_sec = seconds;
+        self.attack_coeff = Self::time_to_coeff(seconds, self.sample_rate);
+    }
+
+    pub fn set_release(&mut self, seconds: f64) {
+        self.release_sec = seconds;
+        self.release_coeff = Self::time_to_coeff(seconds, self.sample_rate);
+    }
+
+    pub fn set_makeup_gain(&mut self, gain_db: f64) {
+        self.makeup_gain_db = gain_db;
+        self.makeup_gain = Self::db_to_linear(gain_db);
+    }
+
+    pub fn set_knee(&mut self, knee_db: f64) {
+        self.knee_db = knee_db.max(0.0);
+        self.update_knee_start();
+    }
+
+    pub fn set_detector(&mut self, detector: CompressorDetector) {
+        self.detector = detector;
+    }
+
+    /// Delays the signal by up to `MAX_LOOKAHEAD_MS`, so the gain is already down when a peak arrives
+    pub fn set_lookahead(&mut self, ms: f64) {
+        self.lookahead_ms = ms.clamp(0.0, MAX_LOOKAHEAD_MS);
+        let samples = (self.lookahead_ms * 0.001 * self.sample_rate).round() as usize;
+        self.lookahead_samples = samples.min(self.lookahead_buffer.len() - 1);
+    }
+
+    /// Makes the detector listen to the key signal given to `Module::sidechain`
+    pub fn set_sidechain(&mut self, sidechain: bool) {
+        self.sidechain = sidechain;
+    }
+
+    pub fn set_settings(&mut self, settings: CompressorSettings) {
+        self.set_threshold(settings.threshold_db);
+        self.set_ratio(settings.ratio);
+        self.set_attack(settings.attack);
+        self.set_release(settings.release);
+        self.set_makeup_gain(settings.makeup_gain_db);
+        self.set_knee(settings.knee_db);
+        self.set_detector(settings.detector);
+        self.set_lookahead(settings.lookahead_ms);
+        self.set_sidechain(settings.sidechain);
     }
 }
 
 impl Module for Compressor {
     fn process(&mut self, input: f64, _time: f64) -> f64 {
-        // Calcul du niveau RMS sur la fenêtre glissante
-        let rms = self.update_rms(input.abs());
-        let input_db = Self::linear_to_db(rms);
+        let level = self.detected_level([input, input]);
+        let delayed = self.delayed([input, input])[0];
+        // Applique le gain sur le signal d'entrée (préserve la phase)
+        delayed * self.next_gain(level)
+    }
 
-        let target_reduction = self.gain_reduction(input_db);
+    /// Linked stereo: the louder channel drives one gain for both, so the image does not shift
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let level = self.detected_level(frame);
+        let delayed = self.delayed(frame);
+        let gain = self.next_gain(level);
+        [delayed[0] * gain, delayed[1] * gain]
+    }
 
-        // Attack/Release smoothing de la réduction de gain
-        if target_reduction > self.envelope_db {
-            self.envelope_db =
-                target_reduction + self.attack_coeff * (self.envelope_db - target_reduction);
-        } else {
-            self.envelope_db =
-                target_reduction + self.release_coeff * (self.envelope_db - target_reduction);
+    /// The detector is chosen once for the whole block
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        match self.detector {
+            CompressorDetector::Peak => self.compress_block(block, |_, level| level),
+            CompressorDetector::Rms => self.compress_block(block, Self::update_rms),
         }
+    }
 
-        // Gain final avec make-up
-        let gain_db = -self.envelope_db + self.makeup_gain_db;
-        let gain = Self::db_to_linear(gain_db);
+    /// Keeps the key signal of the next block, read frame by frame by the detector
+    fn sidechain(&mut self, key: &[StereoFrame]) {
+        self.key_len = key.len().min(self.key.len());
+        self.key[..self.key_len].copy_from_slice(&key[..self.key_len]);
+        self.key_index = 0;
+    }
 
-        // Applique le gain sur le signal d'entrée (préserve la phase)
-        input * gain
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Compressor::from_settings(self.get_settings(), sample_rate);
     }
 
     fn name(&self) -> &'static str {
@@ -126,3 +449,161 @@ impl Module for Compressor {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    fn compressor(settings: CompressorSettings) -> Compressor {
+        Compressor::from_settings(settings, SAMPLE_RATE)
+    }
+
+    fn hard_knee() -> CompressorSettings {
+        CompressorSettings {
+            threshold_db: -20.0,
+            ratio: 4.0,
+            attack: 0.001,
+            release: 0.05,
+            makeup_gain_db: 0.0,
+            knee_db: 0.0,
+            detector: CompressorDetector::Peak,
+            lookahead_ms: 0.0,
+            sidechain: false,
+        }
+    }
+
+    #[test]
+    fn soft_knee_starts_below_the_threshold_and_meets_the_hard_knee_above_it() {
+        let hard = compressor(hard_knee());
+        let soft = compressor(CompressorSettings {
+            knee_db: 12.0,
+            ..hard_knee()
+        });
+
+        // Au seuil : rien en coude franc, déjà un peu en coude doux
+        assert_eq!(hard.gain_reduction(-20.0), 0.0);
+        assert!((soft.gain_reduction(-20.0) - 0.75 * 6.0 * 6.0 / 24.0).abs() < 1e-9);
+        // Sous le coude, aucun des deux ne compresse
+        assert_eq!(soft.gain_reduction(-27.0), 0.0);
+        assert_eq!(hard.gain_reduction(-27.0), 0.0);
+        // Au-dessus du coude, les deux suivent le ratio
+        for input_db in [-14.0, -10.0, 0.0] {
+            let expected = (input_db + 20.0) * 0.75;
+            assert!((hard.gain_reduction(input_db) - expected).abs() < 1e-9);
+            assert!((soft.gain_reduction(input_db) - expected).abs() < 1e-9);
+        }
+        // Le coude doux compresse au moins autant que le coude franc et monte sans saut
+        let mut previous = 0.0;
+        for step in 0..=120 {
+            let input_db = -26.0 + step as f64 * 0.1;
+            let reduction = soft.gain_reduction(input_db);
+            assert!(reduction + 1e-9 >= hard.gain_reduction(input_db));
+            assert!(reduction >= previous && reduction - previous < 0.1);
+            previous = reduction;
+        }
+    }
+
+    /// Gain reduction after 50 ms of a signal with a peak of 0.5 and a crest factor of 10
+    fn reduction_on_spiky_signal(detector: CompressorDetector) -> f64 {
+        let mut compressor = compressor(CompressorSettings {
+            threshold_db: -12.0,
+            detector,
+            ..hard_knee()
+        });
+        for n in 0..(0.05 * SAMPLE_RATE) as usize {
+            let sample = if n % 100 == 0 { 0.5 } else { 0.0 };
+            compressor.process_frame([sample, sample], 0.0);
+        }
+        compressor.get_gain_reduction()
+    }
+
+    #[test]
+    fn peak_detector_reacts_to_transients_the_rms_detector_ignores() {
+        let peak = reduction_on_spiky_signal(CompressorDetector::Peak);
+        let rms = reduction_on_spiky_signal(CompressorDetector::Rms);
+        // Crête à -6 dB, 6 dB au-dessus du seuil ; RMS à -26 dB, sous le seuil
+        assert!(peak > 1.0, "peak detector reduced by {peak} dB");
+        assert_eq!(rms, 0.0, "RMS detector reduced by {rms} dB");
+    }
+
+    #[test]
+    fn lookahead_lowers_the_gain_before_the_transient_comes_out() {
+        let lookahead_ms = 5.0;
+        let delay = (lookahead_ms * 0.001 * SAMPLE_RATE) as usize;
+        let mut compressor = compressor(CompressorSettings {
+            lookahead_ms,
+            ..hard_knee()
+        });
+        let mut output = Vec::new();
+        for n in 0..delay * 3 {
+            let sample = if n < delay { 0.0 } else { 1.0 };
+            output.push(compressor.process_frame([sample, sample], 0.0)[0]);
+        }
+
+        // Le silence sort pendant que le détecteur entend déjà la transitoire
+        assert!(output[..delay].iter().all(|&sample| sample == 0.0));
+        assert!(compressor.get_gain_reduction() > 0.0);
+        // Le premier échantillon fort sort déjà atténué
+        let first = output[delay * 2];
+        assert!(first > 0.0 && first < 0.5, "transient came out at {first}");
+    }
+
+    #[test]
+    fn block_processing_matches_frame_processing() {
+        for detector in CompressorDetector::ALL {
+            let settings = CompressorSettings {
+                knee_db: 6.0,
+                lookahead_ms: 2.0,
+                detector,
+                ..hard_knee()
+            };
+            let mut by_frame = compressor(settings);
+            let mut by_block = compressor(settings);
+            let input: Vec<StereoFrame> = (0..4096)
+                .map(|n| {
+                    let swell = (n as f64 / 4096.0 * std::f64::consts::PI).sin();
+                    let phase = n as f64 * 0.05;
+                    [swell * phase.sin(), swell * phase.cos() * 0.5]
+                })
+                .collect();
+
+            let expected: Vec<StereoFrame> = input
+                .iter()
+                .map(|&frame| by_frame.process_frame(frame, 0.0))
+                .collect();
+            let mut output = input.clone();
+            for block in output.chunks_mut(300) {
+                by_block.process_block(block);
+            }
+            for (got, want) in output.iter().flatten().zip(expected.iter().flatten()) {
+                assert!(
+                    (got - want).abs() < 1e-12,
+                    "{detector:?}: {got} against {want}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gain_reduction_follows_attack_and_release() {
+        let mut compressor = compressor(hard_knee());
+        assert_eq!(compressor.get_gain_reduction(), 0.0);
+
+        // 0 dBFS, 20 dB au-dessus du seuil, ratio 4 : 15 dB de réduction
+        for _ in 0..(0.02 * SAMPLE_RATE) as usize {
+            compressor.process_frame([1.0, 1.0], 0.0);
+        }
+        let reduction = compressor.get_gain_reduction();
+        assert!((reduction - 15.0).abs() < 0.01, "reduction {reduction} dB");
+        let out = compressor.process_frame([1.0, 1.0], 0.0)[0];
+        assert!((out - Compressor::db_to_linear(-reduction)).abs() < 1e-3);
+
+        // Après 10 constantes de release en silence, la réduction est retombée
+        for _ in 0..(0.5 * SAMPLE_RATE) as usize {
+            compressor.process_frame([0.0, 0.0], 0.0);
+        }
+        assert!(compressor.get_gain_reduction() < 0.01);
+    }
+}
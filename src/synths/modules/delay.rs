@@ -0,0 +1,338 @@
+use crate::consts::constants::{self, DEFAULT_TEMPO};
+use crate::synths::tempo::TempoDivision;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Longest echo, the lines are allocated once for this length
+pub const MAX_DELAY_MS: f64 = 2000.0;
+pub const MAX_DELAY_FEEDBACK: f64 = 0.95; // au-delà, les répétitions ne s'éteignent plus
+const TIME_SMOOTHING_MS: f64 = 50.0; // glissement du temps de retard, comme une bande
+
+/// Parameters of the delay, saved in the presets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DelaySettings {
+    pub time_ms: f64,            // hors synchronisation
+    pub sync: bool,              // temps pris sur le tempo global
+    pub division: TempoDivision, // temps quand synchronisé
+    pub feedback: f64,           // part de l'écho réinjectée (0..0.95)
+    pub low_cut: f64,            // Hz, passe-haut dans la boucle
+    pub high_cut: f64,           // Hz, passe-bas dans la boucle
+    pub ping_pong: bool,         // les échos alternent entre gauche et droite
+    pub dry_wet: f64,            // 0 = dry, 1 = wet
+}
+
+impl Default for DelaySettings {
+    fn default() -> Self {
+        Self {
+            time_ms: constants::CURRENT_DELAY_TIME_MS,
+            sync: false,
+            division: TempoDivision::default(),
+            feedback: constants::CURRENT_DELAY_FEEDBACK,
+            low_cut: constants::CURRENT_DELAY_LOW_CUT,
+            high_cut: constants::CURRENT_DELAY_HIGH_CUT,
+            ping_pong: false,
+            dry_wet: constants::CURRENT_DELAY_DRY_WET,
+        }
+    }
+}
+
+impl DelaySettings {
+    /// Same settings brought back into their valid ranges
+    pub fn clamped(&self) -> Self {
+        Self {
+            time_ms: self.time_ms.clamp(1.0, MAX_DELAY_MS),
+            feedback: self.feedback.clamp(0.0, MAX_DELAY_FEEDBACK),
+            low_cut: self.low_cut.clamp(20.0, 2000.0),
+            high_cut: self.high_cut.clamp(500.0, 20000.0),
+            dry_wet: self.dry_wet.clamp(0.0, 1.0),
+            ..*self
+        }
+    }
+
+    /// Time between two echoes in seconds, from the tempo when synced
+    pub fn seconds(&self, bpm: f64) -> f64 {
+        let seconds = if self.sync {
+            self.division.seconds(bpm)
+        } else {
+            self.time_ms * 0.001
+        };
+        seconds.min(MAX_DELAY_MS * 0.001)
+    }
+}
+
+/// Echo with a time in ms or in notes of the tempo.
+/// The repeats go through a low-cut and a high-cut filter, so each one is darker and thinner
+/// than the previous, and in ping-pong mode they bounce from one side to the other.
+#[derive(Clone)]
+pub struct Delay {
+    sample_rate: f64,
+    settings: DelaySettings,
+    tempo: f64, // noires par minute
+
+    lines: [Vec<f64>; 2], // une ligne par canal, le mono n'utilise que la gauche
+    index: usize,
+    delay_samples: f64, // temps actuel, rejoint la cible en douceur
+
+    high_cut_alpha: f64,
+    low_cut_alpha: f64,
+    smoothing: f64,
+    high_cut_state: [f64; 2],
+    low_cut_state: [f64; 2],
+}
+
+impl Delay {
+    pub fn new(sample_rate: f64, settings: DelaySettings) -> Self {
+        let len = (MAX_DELAY_MS * 0.001 * sample_rate) as usize + 2;
+        let mut delay = Self {
+            sample_rate,
+            settings,
+            tempo: DEFAULT_TEMPO,
+            lines: [vec![0.0; len], vec![0.0; len]],
+            index: 0,
+            delay_samples: 0.0,
+            high_cut_alpha: 1.0,
+            low_cut_alpha: 0.0,
+            smoothing: 1.0 - (-1000.0 / (TIME_SMOOTHING_MS * sample_rate)).exp(),
+            high_cut_state: [0.0; 2],
+            low_cut_state: [0.0; 2],
+        };
+        delay.set_settings(settings);
+        delay.delay_samples = delay.target_samples();
+        delay
+    }
+
+    /// Target delay in samples, kept inside the lines for the interpolation
+    fn target_samples(&self) -> f64 {
+        let max = (self.lines[0].len() - 2) as f64;
+        (self.settings.seconds(self.tempo) * self.sample_rate).clamp(1.0, max)
+    }
+
+    /// Reads a line `delay_samples` behind the write position (linear interpolation)
+    #[inline]
+    fn read(&self, channel: usize) -> f64 {
+        let line = &self.lines[channel];
+        let len = line.len();
+        let read = self.index as f64 - self.delay_samples + len as f64;
+        let index = read.floor() as usize % len;
+        let next = (index + 1) % len;
+        let frac = read.fract();
+        line[index] * (1.0 - frac) + line[next] * frac
+    }
+
+    /// Low-cut and high-cut of the feedback path, two one-pole filters
+    #[inline]
+    fn tone(&mut self, channel: usize, input: f64) -> f64 {
+        let low = &mut self.high_cut_state[channel];
+        *low += self.high_cut_alpha * (input - *low);
+        let rumble = &mut self.low_cut_state[channel];
+        *rumble += self.low_cut_alpha * (*low - *rumble);
+        *low - *rumble
+    }
+
+    /// Moves the write position and the delay time by one sample
+    #[inline]
+    fn advance(&mut self, target: f64) {
+        self.delay_samples += (target - self.delay_samples) * self.smoothing;
+        self.index = (self.index + 1) % self.lines[0].len();
+    }
+
+    #[inline]
+    fn echo_frame(&mut self, frame: StereoFrame, target: f64) -> StereoFrame {
+        let echoes = [self.read(0), self.read(1)];
+        let feedback = self.settings.feedback;
+        let repeats = [
+            self.tone(0, echoes[0]) * feedback,
+            self.tone(1, echoes[1]) * feedback,
+        ];
+
+        if self.settings.ping_pong {
+            // L'entrée part à gauche, chaque écho repasse de l'autre côté
+            self.lines[0][self.index] = (frame[0] + frame[1]) * 0.5 + repeats[1];
+            self.lines[1][self.index] = repeats[0];
+        } else {
+            self.lines[0][self.index] = frame[0] + repeats[0];
+            self.lines[1][self.index] = frame[1] + repeats[1];
+        }
+        self.advance(target);
+
+        let mix = self.settings.dry_wet;
+        [
+            (1.0 - mix) * frame[0] + mix * echoes[0],
+            (1.0 - mix) * frame[1] + mix * echoes[1],
+        ]
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: DelaySettings) {
+        self.settings = settings.clamped();
+        let alpha = |hz: f64| 1.0 - (-2.0 * PI * hz / self.sample_rate).exp();
+        self.high_cut_alpha = alpha(self.settings.high_cut);
+        self.low_cut_alpha = alpha(self.settings.low_cut);
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> DelaySettings {
+        self.settings
+    }
+}
+
+impl Module for Delay {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        let echo = self.read(0);
+        let repeat = self.tone(0, echo) * self.settings.feedback;
+        self.lines[0][self.index] = input + repeat;
+        self.advance(self.target_samples());
+
+        let mix = self.settings.dry_wet;
+        (1.0 - mix) * input + mix * echo
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.echo_frame(frame, self.target_samples())
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        let target = self.target_samples();
+        for frame in block.iter_mut() {
+            *frame = self.echo_frame(*frame, target);
+        }
+    }
+
+    fn set_tempo(&mut self, bpm: f64) {
+        self.tempo = bpm.max(1.0);
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        let tempo = self.tempo;
+        *self = Delay::new(sample_rate, self.settings);
+        self.tempo = tempo;
+        self.delay_samples = self.target_samples();
+    }
+
+    fn name(&self) -> &'static str {
+        "Delay"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::tempo::{NoteFeel, NoteLength};
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    /// Wet-only delay of an eighth note at 90 BPM, 16000 samples at 48 kHz
+    fn synced(feedback: f64, ping_pong: bool) -> Delay {
+        let mut delay = Delay::new(
+            SAMPLE_RATE,
+            DelaySettings {
+                sync: true,
+                division: TempoDivision {
+                    length: NoteLength::Eighth,
+                    feel: NoteFeel::Straight,
+                },
+                feedback,
+                low_cut: 20.0,
+                high_cut: 20000.0,
+                ping_pong,
+                dry_wet: 1.0,
+                ..DelaySettings::default()
+            },
+        );
+        Module::set_tempo(&mut delay, 90.0);
+        // Le temps glisse vers sa nouvelle valeur comme une bande
+        for _ in 0..(SAMPLE_RATE * 1.5) as usize {
+            delay.process_frame([0.0, 0.0], 0.0);
+        }
+        delay
+    }
+
+    const ECHO: usize = 16000;
+
+    /// Output for a click on both channels, over `echoes` repeats
+    fn click(delay: &mut Delay, echoes: usize) -> Vec<StereoFrame> {
+        (0..ECHO * echoes + ECHO / 2)
+            .map(|n| {
+                let sample = if n == 0 { 1.0 } else { 0.0 };
+                delay.process_frame([sample, sample], 0.0)
+            })
+            .collect()
+    }
+
+    /// Loudest sample of a channel around the `k`-th echo
+    fn echo_peak(output: &[StereoFrame], k: usize, channel: usize) -> f64 {
+        output[k * ECHO - ECHO / 4..k * ECHO + ECHO / 4]
+            .iter()
+            .fold(0.0, |peak: f64, frame| peak.max(frame[channel].abs()))
+    }
+
+    #[test]
+    fn first_echo_lands_on_the_synced_time() {
+        let mut delay = synced(0.0, false);
+        let output = click(&mut delay, 1);
+        let first = output
+            .iter()
+            .position(|frame| frame[0].abs() > 1e-6)
+            .unwrap();
+        assert_eq!(first, ECHO);
+        assert!((output[ECHO][0] - 1.0).abs() < 1e-6);
+        assert!((output[ECHO][1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn echo_keeps_its_time_and_tempo_at_another_sample_rate() {
+        let mut delay = synced(0.0, false);
+        Module::set_sample_rate(&mut delay, SAMPLE_RATE * 2.0);
+        let output = click(&mut delay, 2);
+        let first = output
+            .iter()
+            .position(|frame| frame[0].abs() > 1e-6)
+            .unwrap();
+        assert_eq!(first, ECHO * 2);
+    }
+
+    #[test]
+    fn ping_pong_echoes_alternate_sides() {
+        let mut delay = synced(0.5, true);
+        let output = click(&mut delay, 4);
+        for k in 1..=4 {
+            // Premier écho à gauche, puis de l'autre côté à chaque répétition
+            let (side, other) = if k % 2 == 1 { (0, 1) } else { (1, 0) };
+            let heard = echo_peak(&output, k, side);
+            assert!(heard > 0.01, "echo {k} missing on channel {side}");
+            assert!(
+                echo_peak(&output, k, other) < heard * 1e-6,
+                "echo {k} also on channel {other}"
+            );
+        }
+    }
+
+    #[test]
+    fn repeats_fade_out_even_at_the_highest_feedback() {
+        for feedback in [0.5, 0.9, 2.0] {
+            let mut delay = synced(feedback, false);
+            let output = click(&mut delay, 8);
+            let peaks: Vec<f64> = (1..=8).map(|k| echo_peak(&output, k, 0)).collect();
+            for pair in peaks.windows(2) {
+                assert!(
+                    pair[1] < pair[0] && pair[1] / pair[0] <= MAX_DELAY_FEEDBACK,
+                    "feedback {feedback}: echoes {peaks:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use crate::consts::constants;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Transfer curve of the waveshaper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistortionCurve {
+    Tanh,     // saturation douce et symétrique
+    HardClip, // écrêtage net à ±1
+    Foldback, // le signal se replie au-delà de ±1
+    Bitcrush, // quantification sur peu de bits
+    Tube,     // saturation asymétrique, harmoniques paires
+}
+
+impl DistortionCurve {
+    pub const ALL: [DistortionCurve; 5] = [
+        DistortionCurve::Tanh,
+        DistortionCurve::HardClip,
+        DistortionCurve::Foldback,
+        DistortionCurve::Bitcrush,
+        DistortionCurve::Tube,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DistortionCurve::Tanh => "Tanh",
+            DistortionCurve::HardClip => "Hard clip",
+            DistortionCurve::Foldback => "Foldback",
+            DistortionCurve::Bitcrush => "Bitcrush",
+            DistortionCurve::Tube => "Tube",
+        }
+    }
+
+    /// Shapes an already driven sample, `steps` is the number of levels per polarity of the bitcrush
+    #[inline]
+    fn shape(&self, x: f64, steps: f64) -> f64 {
+        match self {
+            DistortionCurve::Tanh => x.tanh(),
+            DistortionCurve::HardClip => x.clamp(-1.0, 1.0),
+            DistortionCurve::Foldback => 1.0 - ((x + 1.0).rem_euclid(4.0) - 2.0).abs(),
+            DistortionCurve::Bitcrush => (x.clamp(-1.0, 1.0) * steps).round() / steps,
+            // Le biais décale le point de fonctionnement, le continu est retiré ensuite
+            DistortionCurve::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+        }
+    }
+}
+
+/// Rate at which the waveshaper runs, to keep its harmonics from folding back below Nyquist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Oversampling {
+    Off,
+    X2,
+    X4,
+}
+
+impl Oversampling {
+    pub const ALL: [Oversampling; 3] = [Oversampling::Off, Oversampling::X2, Oversampling::X4];
+
+    pub fn factor(&self) -> usize {
+        match self {
+            Oversampling::Off => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Oversampling::Off => "Off",
+            Oversampling::X2 => "2x",
+            Oversampling::X4 => "4x",
+        }
+    }
+}
+
+pub const MAX_DRIVE_DB: f64 = 48.0;
+const TUBE_BIAS: f64 = 0.5;
+const DC_BLOCKER_HZ: f64 = 10.0;
+
+/// Parameters of the distortion, saved in the presets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DistortionSettings {
+    pub curve: DistortionCurve,
+    pub drive: f64,    // dB ajoutés avant la courbe
+    pub bits: u32,     // résolution du bitcrush (2..16)
+    pub low_cut: f64,  // Hz, passe-haut avant la courbe
+    pub high_cut: f64, // Hz, passe-bas après la courbe
+    pub oversampling: Oversampling,
+    pub mix: f64,    // 0 = dry, 1 = wet
+    pub output: f64, // dB, pour compenser le drive
+}
+
+impl Default for DistortionSettings {
+    fn default() -> Self {
+        Self {
+            curve: constants::CURRENT_DISTORTION_CURVE,
+            drive: constants::CURRENT_DISTORTION_DRIVE,
+            bits: 8,
+            low_cut: constants::CURRENT_DISTORTION_LOW_CUT,
+            high_cut: constants::CURRENT_DISTORTION_HIGH_CUT,
+            oversampling: Oversampling::X2,
+            mix: 1.0,
+            output: constants::CURRENT_DISTORTION_OUTPUT,
+        }
+    }
+}
+
+impl DistortionSettings {
+    /// Same settings brought back into their valid ranges
+    pub fn clamped(&self) -> Self {
+        Self {
+            drive: self.drive.clamp(0.0, MAX_DRIVE_DB),
+            bits: self.bits.clamp(2, 16),
+            low_cut: self.low_cut.clamp(20.0, 2000.0),
+            high_cut: self.high_cut.clamp(500.0, 20000.0),
+            mix: self.mix.clamp(0.0, 1.0),
+            output: self.output.clamp(-48.0, 12.0),
+            ..*self
+        }
+    }
+}
+
+// Demi-bande de 31 points (fenêtre de Blackman) : un coefficient sur deux est nul,
+// sauf le central qui vaut 0.5, donc seuls les 16 points pairs sont calculés
+const HALFBAND_LENGTH: usize = 31;
+const PHASE_TAPS: usize = HALFBAND_LENGTH.div_ceil(2);
+const UP_DELAY: usize = (HALFBAND_LENGTH - 3) / 4; // retard de la phase impaire en entrée
+const DOWN_DELAY: usize = HALFBAND_LENGTH.div_ceil(4); // retard de la phase impaire en sortie
+
+/// Even taps of the half-band low-pass shared by both resampling stages
+fn halfband_taps() -> [f64; PHASE_TAPS] {
+    let center = (HALFBAND_LENGTH - 1) as f64 / 2.0;
+    let last = (HALFBAND_LENGTH - 1) as f64;
+    let mut taps = [0.0; PHASE_TAPS];
+    for (k, tap) in taps.iter_mut().enumerate() {
+        let n = (2 * k) as f64;
+        let x = (n - center) / 2.0;
+        let sinc = (PI * x).sin() / (PI * x);
+        let window = 0.42 - 0.5 * (2.0 * PI * n / last).cos() + 0.08 * (4.0 * PI * n / last).cos();
+        *tap = 0.5 * sinc * window;
+    }
+    // Gain unité en continu : les points pairs font la moitié, le central l'autre
+    let sum: f64 = taps.iter().sum();
+    taps.map(|tap| tap * 0.5 / sum)
+}
+
+/// Doubles the rate: the even outputs are filtered, the odd ones are the input delayed
+#[derive(Clone, Copy)]
+struct Upsampler {
+    history: [f64; PHASE_TAPS], // entrées, la plus récente en premier
+}
+
+impl Upsampler {
+    #[inline]
+    fn process(&mut self, taps: &[f64; PHASE_TAPS], input: f64) -> [f64; 2] {
+        self.history.copy_within(0..PHASE_TAPS - 1, 1);
+        self.history[0] = input;
+        let even: f64 = taps.iter().zip(&self.history).map(|(t, x)| t * x).sum();
+        [2.0 * even, self.history[UP_DELAY]]
+    }
+}
+
+/// Halves the rate after removing what would fold back
+#[derive(Clone, Copy)]
+struct Downsampler {
+    even: [f64; PHASE_TAPS],
+    odd: [f64; PHASE_TAPS],
+}
+
+impl Downsampler {
+    #[inline]
+    fn process(&mut self, taps: &[f64; PHASE_TAPS], [even, odd]: [f64; 2]) -> f64 {
+        self.even.copy_within(0..PHASE_TAPS - 1, 1);
+        self.even[0] = even;
+        self.odd.copy_within(0..PHASE_TAPS - 1, 1);
+        self.odd[0] = odd;
+        let filtered: f64 = taps.iter().zip(&self.even).map(|(t, x)| t * x).sum();
+        filtered + 0.5 * self.odd[DOWN_DELAY]
+    }
+}
+
+/// Per-sample coefficients at the oversampled rate
+#[derive(Clone, Copy)]
+struct Coefficients {
+    gain: f64,
+    steps: f64,
+    low_cut: f64,
+    high_cut: f64,
+    dc_blocker: f64,
+    output: f64,
+}
+
+/// Resampling stages and filter states of one channel
+#[derive(Clone, Copy)]
+struct Channel {
+    up: [Upsampler; 2], // 2x puis 4x
+    down: [Downsampler; 2],
+    low_cut_state: f64,
+    high_cut_state: f64,
+    dc_input: f64,
+    dc_output: f64,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            up: [Upsampler {
+                history: [0.0; PHASE_TAPS],
+            }; 2],
+            down: [Downsampler {
+                even: [0.0; PHASE_TAPS],
+                odd: [0.0; PHASE_TAPS],
+            }; 2],
+            low_cut_state: 0.0,
+            high_cut_state: 0.0,
+            dc_input: 0.0,
+            dc_output: 0.0,
+        }
+    }
+
+    /// Drive, curve and tone filters on one oversampled sample, blended with the dry one.
+    /// Mixing at the oversampled rate keeps the dry signal aligned with the delay of the filters.
+    #[inline]
+    fn shape(&mut self, settings: &DistortionSettings, c: &Coefficients, input: f64) -> f64 {
+        self.low_cut_state += c.low_cut * (input - self.low_cut_state);
+        let driven = (input - self.low_cut_state) * c.gain;
+        let shaped = settings.curve.shape(driven, c.steps);
+
+        // Bloqueur de continu, pour le tube et le foldback
+        let blocked = shaped - self.dc_input + c.dc_blocker * self.dc_output;
+        self.dc_input = shaped;
+        self.dc_output = blocked;
+
+        self.high_cut_state += c.high_cut * (blocked - self.high_cut_state);
+        let wet = self.high_cut_state * c.output;
+        (1.0 - settings.mix) * input + settings.mix * wet
+    }
+
+    #[inline]
+    fn process(
+        &mut self,
+        settings: &DistortionSettings,
+        c: &Coefficients,
+        taps: &[f64; PHASE_TAPS],
+        input: f64,
+    ) -> f64 {
+        match settings.oversampling {
+            Oversampling::Off => self.shape(settings, c, input),
+            Oversampling::X2 => {
+                let [a, b] = self.up[0].process(taps, input);
+                let shaped = [self.shape(settings, c, a), self.shape(settings, c, b)];
+                self.down[0].process(taps, shaped)
+            }
+            Oversampling::X4 => {
+                let mut halves = [0.0; 2];
+                for (half, sample) in halves.iter_mut().zip(self.up[0].process(taps, input)) {
+                    let [a, b] = self.up[1].process(taps, sample);
+                    let shaped = [self.shape(settings, c, a), self.shape(settings, c, b)];
+                    *half = self.down[1].process(taps, shaped);
+                }
+                self.down[0].process(taps, halves)
+            }
+        }
+    }
+}
+
+/// Drive into a waveshaper between two tone filters.
+/// The curve runs at 2x or 4x the sample rate, the half-band filters around it
+/// remove the harmonics that would otherwise fold back as inharmonic aliasing.
+#[derive(Clone)]
+pub struct Distortion {
+    sample_rate: f64,
+    settings: DistortionSettings,
+    coefficients: Coefficients,
+    taps: [f64; PHASE_TAPS],
+    channels: [Channel; 2],
+}
+
+impl Distortion {
+    pub fn new(sample_rate: f64, settings: DistortionSettings) -> Self {
+        let mut distortion = Self {
+            sample_rate,
+            settings,
+            coefficients: Coefficients {
+                gain: 1.0,
+                steps: 1.0,
+                low_cut: 0.0,
+                high_cut: 1.0,
+                dc_blocker: 1.0,
+                output: 1.0,
+            },
+            taps: halfband_taps(),
+            channels: [Channel::new(); 2],
+        };
+        distortion.set_settings(settings);
+        distortion
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: DistortionSettings) {
+        let settings = settings.clamped();
+        if settings.oversampling != self.settings.oversampling {
+            // Les historiques de l'ancien facteur n'ont plus de sens
+            self.channels = [Channel::new(); 2];
+        }
+        self.settings = settings;
+
+        let rate = self.sample_rate * settings.oversampling.factor() as f64;
+        let alpha = |hz: f64| 1.0 - (-2.0 * PI * hz.min(rate * 0.45) / rate).exp();
+        let db = |db: f64| 10.0_f64.powf(db / 20.0);
+        self.coefficients = Coefficients {
+            gain: db(settings.drive),
+            steps: 2.0_f64.powi(settings.bits as i32 - 1),
+            low_cut: alpha(settings.low_cut),
+            high_cut: alpha(settings.high_cut),
+            dc_blocker: 1.0 - 2.0 * PI * DC_BLOCKER_HZ / rate,
+            output: db(settings.output),
+        };
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> DistortionSettings {
+        self.settings
+    }
+}
+
+impl Module for Distortion {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        self.channels[0].process(&self.settings, &self.coefficients, &self.taps, input)
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let [left, right] = &mut self.channels;
+        [
+            left.process(&self.settings, &self.coefficients, &self.taps, frame[0]),
+            right.process(&self.settings, &self.coefficients, &self.taps, frame[1]),
+        ]
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+        self.set_settings(self.settings);
+    }
+
+    fn name(&self) -> &'static str {
+        "Distortion"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,64 +1,316 @@
-use crate::synths::traits::Module;
+use crate::consts::constants;
+use crate::synths::modulation::{ModAmounts, ModDestination};
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
+pub const MAX_FILTER_ENV_AMOUNT: f64 = 6.0; // en octaves, dans les deux sens
+const KEY_TRACKING_REFERENCE: f64 = 261.63; // Do 4 : le suivi de clavier ne change rien à cette note
+
+/// Response of the filter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterMode {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    Peak, // cloche, utilise le gain
+    LowShelf,
+    HighShelf,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 7] = [
+        FilterMode::LowPass,
+        FilterMode::HighPass,
+        FilterMode::BandPass,
+        FilterMode::Notch,
+        FilterMode::Peak,
+        FilterMode::LowShelf,
+        FilterMode::HighShelf,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterMode::LowPass => "Low-pass",
+            FilterMode::HighPass => "High-pass",
+            FilterMode::BandPass => "Band-pass",
+            FilterMode::Notch => "Notch",
+            FilterMode::Peak => "Peak",
+            FilterMode::LowShelf => "Low shelf",
+            FilterMode::HighShelf => "High shelf",
+        }
+    }
+
+    /// Peak and shelves boost or cut by a gain instead of removing frequencies
+    pub fn uses_gain(&self) -> bool {
+        matches!(
+            self,
+            FilterMode::Peak | FilterMode::LowShelf | FilterMode::HighShelf
+        )
+    }
+}
+
+/// Number of cascaded 2-pole stages
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterSlope {
+    Db12,
+    Db24,
+}
+
+impl FilterSlope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterSlope::Db12 => "12 dB/oct",
+            FilterSlope::Db24 => "24 dB/oct",
+        }
+    }
+}
+
+/// Per-voice movement of the cutoff: its own envelope, with a bipolar amount
+/// in octaves, and keyboard tracking relative to middle C
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterModulation {
+    pub attack: f64,
+    pub decay: f64,
+    pub sustain: f64,
+    pub release: f64,
+    pub amount: f64, // octaves au sommet de l'enveloppe, négatif pour fermer le filtre
+    pub key_tracking: f64, // 0 = coupure fixe, 1 = suit la note
+}
+
+impl Default for FilterModulation {
+    fn default() -> Self {
+        Self {
+            attack: constants::FILTER_ENV_ATTACK,
+            decay: constants::FILTER_ENV_DECAY,
+            sustain: constants::FILTER_ENV_SUSTAIN,
+            release: constants::FILTER_ENV_RELEASE,
+            amount: constants::FILTER_ENV_AMOUNT,
+            key_tracking: constants::FILTER_KEY_TRACKING,
+        }
+    }
+}
+
+/// Coefficients of one stage: `a*` for the integrators, `m*` mix the input, band and low outputs
+#[derive(Clone, Copy, Default)]
+struct StageCoefficients {
+    a1: f64,
+    a2: f64,
+    a3: f64,
+    m0: f64,
+    m1: f64,
+    m2: f64,
+}
+
+impl StageCoefficients {
+    /// Trapezoidal (zero-delay feedback) state-variable filter, after Andrew Simper (Cytomic)
+    fn new(mode: FilterMode, cutoff: f64, sample_rate: f64, q: f64, gain_db: f64) -> Self {
+        let a = 10.0_f64.powf(gain_db / 40.0);
+        let g = (PI * cutoff / sample_rate).tan();
+        let k = 1.0 / q;
+
+        let (g, k, m0, m1, m2) = match mode {
+            FilterMode::LowPass => (g, k, 0.0, 0.0, 1.0),
+            FilterMode::HighPass => (g, k, 1.0, -k, -1.0),
+            FilterMode::BandPass => (g, k, 0.0, 1.0, 0.0),
+            FilterMode::Notch => (g, k, 1.0, -k, 0.0),
+            FilterMode::Peak => {
+                let k = 1.0 / (q * a);
+                (g, k, 1.0, k * (a * a - 1.0), 0.0)
+            }
+            FilterMode::LowShelf => (g / a.sqrt(), k, 1.0, k * (a - 1.0), a * a - 1.0),
+            FilterMode::HighShelf => (g * a.sqrt(), k, a * a, k * (1.0 - a) * a, 1.0 - a * a),
+        };
+
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        Self {
+            a1,
+            a2,
+            a3: g * a2,
+            m0,
+            m1,
+            m2,
+        }
+    }
+}
+
+/// Mémoire d'un étage : les deux intégrateurs
+#[derive(Clone, Copy, Default)]
+struct StageState {
+    ic1eq: f64,
+    ic2eq: f64,
+}
+
+impl StageState {
+    #[inline]
+    fn tick(&mut self, c: &StageCoefficients, v0: f64) -> f64 {
+        let v3 = v0 - self.ic2eq;
+        let v1 = c.a1 * self.ic1eq + c.a2 * v3; // bande
+        let v2 = self.ic2eq + c.a2 * self.ic1eq + c.a3 * v3; // passe-bas
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        c.m0 * v0 + c.m1 * v1 + c.m2 * v2
+    }
+}
+
+/// Multi-mode filter built on a zero-delay-feedback state-variable topology.
+/// Its integrators keep their state when the coefficients change, so the cutoff
+/// can be modulated every sample without the clicks or blow-ups of a biquad.
+/// The 24 dB slope cascades a Butterworth stage before the resonant one
+/// (for peak and shelves, two stages sharing the gain).
+/// Each voice owns a copy, whose envelope and key tracking move the cutoff for that note only.
 #[derive(Clone, Copy)]
-pub struct LowPassFilter {
+pub struct MultiModeFilter {
     sample_rate: f64,
+    mode: FilterMode,
+    slope: FilterSlope,
     cutoff_freq: f64,
-    resonance: f64, 
-    // coefficients
-    a0: f64,
-    a1: f64,
-    a2: f64,
-    b1: f64,
-    b2: f64,
-    // mémoire des échantillons
-    x1: f64, // entrée précédente
-    x2: f64, // entrée il y a 2 échantillons
-    y1: f64, // sortie précédente
-    y2: f64, // sortie il y a 2 échantillons
+    resonance: f64, // Q
+    gain_db: f64,   // peak et shelves seulement
+    modulation: FilterModulation,
+    envelope: ADSR,
+    note_frequency: f64,
+    offset: f64,           // octaves ajoutées à la coupure par l'enveloppe et le clavier
+    matrix_cutoff: f64,    // octaves ajoutées par la matrice de modulation
+    matrix_resonance: f64, // octaves de Q ajoutées par la matrice
+    coefficients: [StageCoefficients; 2],
+    states: [[StageState; 2]; 2], // [canal][étage], le mono utilise la gauche
 }
 
-impl LowPassFilter {
-    pub fn new(sample_rate: f64, cutoff_freq: f64, resonance: f64) -> Self {
-        let mut filter = LowPassFilter {
+impl MultiModeFilter {
+    pub fn new(
+        sample_rate: f64,
+        mode: FilterMode,
+        slope: FilterSlope,
+        cutoff_freq: f64,
+        resonance: f64,
+        gain_db: f64,
+    ) -> Self {
+        let modulation = FilterModulation::default();
+        let mut filter = Self {
             sample_rate,
+            mode,
+            slope,
             cutoff_freq,
-            resonance,
-            a0: 0.0,
-            a1: 0.0,
-            a2: 0.0,
-            b1: 0.0,
-            b2: 0.0,
-            x1: 0.0,
-            x2: 0.0,
-            y1: 0.0,
-            y2: 0.0,
+            resonance: resonance.max(0.01),
+            gain_db,
+            modulation,
+            envelope: ADSR::new(
+                sample_rate,
+                modulation.attack,
+                modulation.decay,
+                modulation.sustain,
+                modulation.release,
+            ),
+            note_frequency: KEY_TRACKING_REFERENCE,
+            offset: 0.0,
+            matrix_cutoff: 0.0,
+            matrix_resonance: 0.0,
+            coefficients: [StageCoefficients::default(); 2],
+            states: [[StageState::default(); 2]; 2],
         };
         filter.calc_coefficients();
         filter
     }
 
+    /// The former RBJ low-pass: same cutoff and Q, 12 dB slope
+    pub fn low_pass(sample_rate: f64, cutoff_freq: f64, resonance: f64) -> Self {
+        Self::new(
+            sample_rate,
+            FilterMode::LowPass,
+            FilterSlope::Db12,
+            cutoff_freq,
+            resonance,
+            0.0,
+        )
+    }
+
     fn calc_coefficients(&mut self) {
-        let omega = 2.0 * std::f64::consts::PI * self.cutoff_freq / self.sample_rate;
-        let alpha = omega.sin() / (2.0 * self.resonance);
-        let cos_omega = omega.cos();
+        // Sous Nyquist, sinon tan() diverge
+        let cutoff = (self.cutoff_freq * 2.0_f64.powf(self.offset + self.matrix_cutoff))
+            .clamp(10.0, self.sample_rate * 0.49);
+        let resonance = (self.resonance * 2.0_f64.powf(self.matrix_resonance)).max(0.01);
+        let resonant = |gain_db| {
+            StageCoefficients::new(self.mode, cutoff, self.sample_rate, resonance, gain_db)
+        };
 
-        let b0 = (1.0 - cos_omega) / 2.0;
-        let b1 = 1.0 - cos_omega;
-        let b2 = (1.0 - cos_omega) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
+        self.coefficients = match self.slope {
+            FilterSlope::Db12 => [resonant(self.gain_db), StageCoefficients::default()],
+            FilterSlope::Db24 if self.mode.uses_gain() => {
+                [resonant(self.gain_db * 0.5), resonant(self.gain_db * 0.5)]
+            }
+            FilterSlope::Db24 => [
+                StageCoefficients::new(self.mode, cutoff, self.sample_rate, FRAC_1_SQRT_2, 0.0),
+                resonant(0.0),
+            ],
+        };
+    }
+
+    /// Octaves added by the key tracking, constant for a note
+    fn key_offset(&self) -> f64 {
+        self.modulation.key_tracking * (self.note_frequency / KEY_TRACKING_REFERENCE).log2()
+    }
+
+    /// Whether the envelope moves the cutoff, in which case it is recomputed every sample
+    fn is_enveloped(&self) -> bool {
+        self.modulation.amount != 0.0
+    }
+
+    /// Advances the envelope by one sample and follows it with the cutoff
+    #[inline]
+    fn advance_envelope(&mut self) {
+        let level = self.envelope.get_amplitude();
+        self.offset = self.key_offset() + self.modulation.amount * level;
+        self.calc_coefficients();
+    }
+
+    /// Starts the envelope of a voice
+    pub fn note_on(&mut self) {
+        self.envelope.note_on();
+    }
 
-        self.a0 = b0 / a0;
-        self.a1 = b1 / a0;
-        self.a2 = b2 / a0;
-        self.b1 = a1 / a0;
-        self.b2 = a2 / a0;
+    pub fn note_off(&mut self) {
+        self.envelope.note_off();
+    }
+
+    /// Note played by the voice, for the key tracking
+    pub fn set_note_frequency(&mut self, frequency: f64) {
+        self.note_frequency = frequency.max(1.0);
+        self.offset = self.key_offset() + self.modulation.amount * self.envelope.get_level();
+        self.calc_coefficients();
+    }
+
+    #[inline]
+    fn filter(&self, states: &mut [StageState; 2], input: f64) -> f64 {
+        let output = states[0].tick(&self.coefficients[0], input);
+        match self.slope {
+            FilterSlope::Db12 => output,
+            FilterSlope::Db24 => states[1].tick(&self.coefficients[1], output),
+        }
     }
 
     // #### Setters ####
 
+    pub fn set_mode(&mut self, mode: FilterMode) {
+        self.mode = mode;
+        self.calc_coefficients();
+    }
+
+    pub fn set_slope(&mut self, slope: FilterSlope) {
+        if slope != self.slope {
+            // Le second étage repart de zéro
+            for channel in self.states.iter_mut() {
+                channel[1] = StageState::default();
+            }
+        }
+        self.slope = slope;
+        self.calc_coefficients();
+    }
+
     pub fn set_cutoff_freq(&mut self, cutoff_freq: f64) {
         self.cutoff_freq = cutoff_freq;
         self.calc_coefficients();
@@ -69,13 +321,43 @@ impl LowPassFilter {
         self.calc_coefficients();
     }
 
+    pub fn set_gain(&mut self, gain_db: f64) {
+        self.gain_db = gain_db;
+        self.calc_coefficients();
+    }
+
     pub fn set_sample_rate(&mut self, sample_rate: f64) {
         self.sample_rate = sample_rate;
+        self.envelope.set_sample_rate(sample_rate);
         self.calc_coefficients();
     }
 
+    /// Changes the envelope and key tracking, a playing envelope keeps its stage and level
+    pub fn set_modulation(&mut self, modulation: FilterModulation) {
+        self.modulation = FilterModulation {
+            sustain: modulation.sustain.clamp(0.0, 1.0),
+            amount: modulation
+                .amount
+                .clamp(-MAX_FILTER_ENV_AMOUNT, MAX_FILTER_ENV_AMOUNT),
+            ..modulation
+        };
+        self.envelope.set_attack(self.modulation.attack);
+        self.envelope.set_decay(self.modulation.decay);
+        self.envelope.set_sustain(self.modulation.sustain);
+        self.envelope.set_release(self.modulation.release);
+        self.set_note_frequency(self.note_frequency);
+    }
+
     // #### Getters ####
 
+    pub fn get_mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    pub fn get_slope(&self) -> FilterSlope {
+        self.slope
+    }
+
     pub fn get_cutoff_freq(&self) -> f64 {
         self.cutoff_freq
     }
@@ -84,29 +366,77 @@ impl LowPassFilter {
         self.resonance
     }
 
+    pub fn get_gain(&self) -> f64 {
+        self.gain_db
+    }
+
+    pub fn get_modulation(&self) -> FilterModulation {
+        self.modulation
+    }
+
     pub fn get_sample_rate(&self) -> f64 {
         self.sample_rate
     }
 }
 
-impl Module for LowPassFilter {
+impl Module for MultiModeFilter {
     fn process(&mut self, input: f64, _time: f64) -> f64 {
-        // Formule du filtre biquad: y[n] = a0*x[n] + a1*x[n-1] + a2*x[n-2] - b1*y[n-1] - b2*y[n-2]
-        let output = self.a0 * input + self.a1 * self.x1 + self.a2 * self.x2
-            - self.b1 * self.y1
-            - self.b2 * self.y2;
-
-        // Mise à jour de la mémoire
-        self.x2 = self.x1;
-        self.x1 = input;
-        self.y2 = self.y1;
-        self.y1 = output;
+        if self.is_enveloped() {
+            self.advance_envelope();
+        }
+        let mut states = self.states[0];
+        let output = self.filter(&mut states, input);
+        self.states[0] = states;
+        output
+    }
 
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        if self.is_enveloped() {
+            self.advance_envelope();
+        }
+        let mut states = self.states;
+        let output = [
+            self.filter(&mut states[0], frame[0]),
+            self.filter(&mut states[1], frame[1]),
+        ];
+        self.states = states;
         output
     }
 
+    /// One channel after the other, the state staying in registers for the whole block.
+    /// With an envelope the coefficients change every sample, so both channels advance together.
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        if self.is_enveloped() {
+            for frame in block.iter_mut() {
+                *frame = self.process_frame(*frame, 0.0);
+            }
+            return;
+        }
+        for channel in 0..2 {
+            let mut states = self.states[channel];
+            for frame in block.iter_mut() {
+                frame[channel] = self.filter(&mut states, frame[channel]);
+            }
+            self.states[channel] = states;
+        }
+    }
+
+    fn modulate(&mut self, amounts: &ModAmounts) {
+        let cutoff = amounts.get(ModDestination::Cutoff);
+        let resonance = amounts.get(ModDestination::Resonance);
+        if cutoff != self.matrix_cutoff || resonance != self.matrix_resonance {
+            self.matrix_cutoff = cutoff;
+            self.matrix_resonance = resonance;
+            self.calc_coefficients();
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        MultiModeFilter::set_sample_rate(self, sample_rate);
+    }
+
     fn name(&self) -> &'static str {
-        "LowPassFilter"
+        "MultiModeFilter"
     }
 
     fn clone_box(&self) -> Box<dyn Module> {
@@ -121,3 +451,234 @@ impl Module for LowPassFilter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+    const CUTOFF: f64 = 1000.0;
+
+    fn filter(
+        mode: FilterMode,
+        slope: FilterSlope,
+        resonance: f64,
+        gain_db: f64,
+    ) -> MultiModeFilter {
+        MultiModeFilter::new(SAMPLE_RATE, mode, slope, CUTOFF, resonance, gain_db)
+    }
+
+    /// Gain in dB of the filter for a sine, measured once the filter has settled
+    fn response_db(mut filter: MultiModeFilter, frequency: f64) -> f64 {
+        let length = (SAMPLE_RATE * 0.5) as usize;
+        let (mut input, mut output) = (0.0, 0.0);
+        for n in 0..length {
+            let sample = (2.0 * PI * frequency * n as f64 / SAMPLE_RATE).sin();
+            let filtered = filter.process(sample, 0.0);
+            // La seconde moitié seulement, après le transitoire
+            if n >= length / 2 {
+                input += sample * sample;
+                output += filtered * filtered;
+            }
+        }
+        10.0 * (output / input).log10()
+    }
+
+    fn assert_db(measured: f64, expected: f64, what: &str) {
+        assert!(
+            (measured - expected).abs() < 0.5,
+            "{what}: {measured:.2} dB, expected {expected:.2} dB"
+        );
+    }
+
+    #[test]
+    fn each_mode_shapes_the_spectrum_around_the_cutoff() {
+        let below = CUTOFF / 2.0;
+        let above = CUTOFF * 2.0;
+        let response = |mode, resonance, gain_db, frequency| {
+            response_db(
+                filter(mode, FilterSlope::Db12, resonance, gain_db),
+                frequency,
+            )
+        };
+
+        // Butterworth : -3 dB à la coupure, -12,3 dB une octave plus loin du côté coupé
+        let butterworth = FRAC_1_SQRT_2;
+        assert_db(
+            response(FilterMode::LowPass, butterworth, 0.0, CUTOFF),
+            -3.01,
+            "low-pass at cutoff",
+        );
+        assert_db(
+            response(FilterMode::LowPass, butterworth, 0.0, below),
+            -0.26,
+            "low-pass an octave below",
+        );
+        assert_db(
+            response(FilterMode::LowPass, butterworth, 0.0, above),
+            -12.30,
+            "low-pass an octave above",
+        );
+        assert_db(
+            response(FilterMode::HighPass, butterworth, 0.0, CUTOFF),
+            -3.01,
+            "high-pass at cutoff",
+        );
+        assert_db(
+            response(FilterMode::HighPass, butterworth, 0.0, below),
+            -12.30,
+            "high-pass an octave below",
+        );
+        assert_db(
+            response(FilterMode::HighPass, butterworth, 0.0, above),
+            -0.26,
+            "high-pass an octave above",
+        );
+
+        // Q = 2 : la bande passe avec un gain Q au centre, la coupe-bande y creuse
+        let q = 2.0;
+        let band = response(FilterMode::BandPass, q, 0.0, CUTOFF);
+        assert_db(band, 20.0 * q.log10(), "band-pass at cutoff");
+        assert!(response(FilterMode::BandPass, q, 0.0, below) < band - 9.0);
+        assert!(response(FilterMode::BandPass, q, 0.0, above) < band - 9.0);
+        assert!(response(FilterMode::Notch, q, 0.0, CUTOFF) < -30.0);
+        assert!(response(FilterMode::Notch, q, 0.0, below) > -1.0);
+        assert!(response(FilterMode::Notch, q, 0.0, above) > -1.0);
+
+        // Cloche et plateaux à +6 dB
+        assert_db(
+            response(FilterMode::Peak, q, 6.0, CUTOFF),
+            6.0,
+            "peak at cutoff",
+        );
+        assert!(response(FilterMode::Peak, q, 6.0, CUTOFF / 4.0).abs() < 0.5);
+        assert!(response(FilterMode::Peak, q, 6.0, CUTOFF * 4.0).abs() < 0.5);
+        assert_db(
+            response(FilterMode::LowShelf, butterworth, 6.0, CUTOFF / 8.0),
+            6.0,
+            "low shelf below",
+        );
+        assert_db(
+            response(FilterMode::LowShelf, butterworth, 6.0, CUTOFF),
+            3.0,
+            "low shelf at cutoff",
+        );
+        assert_db(
+            response(FilterMode::LowShelf, butterworth, 6.0, CUTOFF * 8.0),
+            0.0,
+            "low shelf above",
+        );
+        assert_db(
+            response(FilterMode::HighShelf, butterworth, 6.0, CUTOFF / 8.0),
+            0.0,
+            "high shelf below",
+        );
+        assert_db(
+            response(FilterMode::HighShelf, butterworth, 6.0, CUTOFF),
+            3.0,
+            "high shelf at cutoff",
+        );
+        assert_db(
+            response(FilterMode::HighShelf, butterworth, 6.0, CUTOFF * 8.0),
+            6.0,
+            "high shelf above",
+        );
+    }
+
+    #[test]
+    fn the_24_db_slope_cuts_twice_as_steeply() {
+        for (slope, expected) in [(FilterSlope::Db12, 12.0), (FilterSlope::Db24, 24.0)] {
+            let response = |frequency| {
+                response_db(
+                    filter(FilterMode::LowPass, slope, FRAC_1_SQRT_2, 0.0),
+                    frequency,
+                )
+            };
+            // Au-dessus de la coupure, chaque octave retire la pente entière
+            let per_octave = response(2.0 * CUTOFF) - response(4.0 * CUTOFF);
+            assert!(
+                (per_octave - expected).abs() < 1.0,
+                "{}: {per_octave:.2} dB per octave",
+                slope.name()
+            );
+        }
+    }
+
+    /// Cutoff the coefficients are computed for, with the envelope and key tracking
+    fn effective_cutoff(filter: &MultiModeFilter) -> f64 {
+        filter.cutoff_freq * 2.0_f64.powf(filter.offset + filter.matrix_cutoff)
+    }
+
+    /// Cutoff reached halfway through a 100 ms attack
+    fn cutoff_during_attack(amount: f64) -> f64 {
+        let mut filter = filter(FilterMode::LowPass, FilterSlope::Db12, FRAC_1_SQRT_2, 0.0);
+        filter.set_modulation(FilterModulation {
+            attack: 0.1,
+            amount,
+            key_tracking: 0.0,
+            ..FilterModulation::default()
+        });
+        filter.note_on();
+        for _ in 0..(SAMPLE_RATE * 0.05) as usize {
+            filter.process(0.0, 0.0);
+        }
+        effective_cutoff(&filter)
+    }
+
+    #[test]
+    fn envelope_amount_opens_or_closes_the_filter_during_the_attack() {
+        assert_eq!(cutoff_during_attack(0.0), CUTOFF);
+        let opened = cutoff_during_attack(2.0);
+        let closed = cutoff_during_attack(-2.0);
+        assert!(opened > CUTOFF * 1.5, "positive amount reached {opened} Hz");
+        assert!(closed < CUTOFF / 1.5, "negative amount reached {closed} Hz");
+        // Montée et descente symétriques en octaves
+        assert!(((opened / CUTOFF).log2() + (closed / CUTOFF).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn full_key_tracking_moves_the_cutoff_an_octave_per_octave() {
+        for (tracking, octaves_per_octave) in [(1.0, 1.0), (0.5, 0.5), (0.0, 0.0)] {
+            let mut filter = filter(FilterMode::LowPass, FilterSlope::Db12, FRAC_1_SQRT_2, 0.0);
+            filter.set_modulation(FilterModulation {
+                amount: 0.0,
+                key_tracking: tracking,
+                ..FilterModulation::default()
+            });
+            for octaves in [-2.0, -1.0, 0.0, 1.0, 2.0] {
+                filter.set_note_frequency(KEY_TRACKING_REFERENCE * 2.0_f64.powf(octaves));
+                let moved = (effective_cutoff(&filter) / CUTOFF).log2();
+                assert!(
+                    (moved - octaves * octaves_per_octave).abs() < 1e-9,
+                    "tracking {tracking}: {moved} octaves for a note {octaves} octaves away"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn audio_rate_sweeps_at_full_resonance_stay_bounded() {
+        for mode in FilterMode::ALL {
+            for slope in [FilterSlope::Db12, FilterSlope::Db24] {
+                let mut filter = filter(mode, slope, 20.0, 12.0);
+                let mut peak: f64 = 0.0;
+                for n in 0..SAMPLE_RATE as usize {
+                    let time = n as f64 / SAMPLE_RATE;
+                    // Coupure balayée de 20 Hz à 20 kHz, 500 fois par seconde, sous une dent de scie
+                    let sweep = (2.0 * PI * 500.0 * time).sin();
+                    filter.set_cutoff_freq(20.0 * 1000.0_f64.powf((sweep + 1.0) / 2.0));
+                    let input = 2.0 * (110.0 * time).fract() - 1.0;
+                    let output = filter.process_frame([input, -input], 0.0);
+                    assert!(
+                        output.iter().all(|sample| sample.is_finite()),
+                        "{mode:?} {slope:?} diverged"
+                    );
+                    peak = output
+                        .iter()
+                        .fold(peak, |peak, sample| peak.max(sample.abs()));
+                }
+                assert!(peak < 100.0, "{mode:?} {slope:?} peaked at {peak}");
+            }
+        }
+    }
+}
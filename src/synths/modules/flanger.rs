@@ -0,0 +1,99 @@
+use crate::synths::modules::modulated::{DelayLine, ModEffectSettings, Sweep};
+use crate::synths::traits::{Module, StereoFrame};
+
+const MIN_MS: f64 = 0.3; // retard au creux du balayage
+const SWEEP_MS: f64 = 5.0; // amplitude du balayage à depth = 1
+const RIGHT_OFFSET: f64 = 0.25; // le canal droit est décalé d'un quart de cycle
+
+/// Flanger: a very short swept delay mixed with the dry signal, which moves a comb of notches
+/// up and down the spectrum. The feedback sharpens the comb, a negative one hollows the lows.
+#[derive(Clone)]
+pub struct Flanger {
+    sample_rate: f64,
+    settings: ModEffectSettings,
+    sweep: Sweep,
+    lines: [DelayLine; 2],
+}
+
+impl Flanger {
+    pub fn new(sample_rate: f64, settings: ModEffectSettings) -> Self {
+        let max_ms = MIN_MS + SWEEP_MS;
+        Self {
+            sample_rate,
+            settings: settings.clamped(),
+            sweep: Sweep::default(),
+            lines: [
+                DelayLine::new(max_ms, sample_rate),
+                DelayLine::new(max_ms, sample_rate),
+            ],
+        }
+    }
+
+    /// Writes the input of a channel and returns its delayed copy
+    #[inline]
+    fn flange(&mut self, channel: usize, input: f64, offset: f64) -> f64 {
+        let ms = MIN_MS + SWEEP_MS * self.settings.depth * self.sweep.unipolar(offset);
+        let wet = self.lines[channel].read(ms * 0.001 * self.sample_rate);
+        self.lines[channel].write(input + wet * self.settings.feedback);
+        wet
+    }
+
+    #[inline]
+    fn flanger_frame(&mut self, frame: StereoFrame) -> StereoFrame {
+        let left = self.flange(0, frame[0], 0.0);
+        let right = self.flange(1, frame[1], RIGHT_OFFSET);
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        [
+            self.settings.blend(frame[0], left),
+            self.settings.blend(frame[1], right),
+        ]
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: ModEffectSettings) {
+        self.settings = settings.clamped();
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> ModEffectSettings {
+        self.settings
+    }
+}
+
+impl Module for Flanger {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        let wet = self.flange(0, input, 0.0);
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        self.settings.blend(input, wet)
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.flanger_frame(frame)
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for frame in block.iter_mut() {
+            *frame = self.flanger_frame(*frame);
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Flanger::new(sample_rate, self.settings);
+    }
+
+    fn name(&self) -> &'static str {
+        "Flanger"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,28 +1,53 @@
-use crate::synths::traits::Module;
+use crate::synths::modulation::{ModAmounts, ModDestination};
+use crate::synths::traits::{Module, StereoFrame};
 
 #[derive(Clone, Copy)]
 /// Gain struct:
-/// This module implements a simple gain control for audio signals.
+/// This module implements a simple gain control for audio signals,
+/// with a balance between the left and right channels.
 pub struct Gain {
-    gain_db: f64, // Gain factor in dB
+    gain_db: f64,        // Gain factor in dB
+    pan: f64,            // -1 = gauche, 0 = centre, 1 = droite
+    matrix_gain_db: f64, // ajouts de la matrice de modulation
+    matrix_pan: f64,
 }
 
 impl Gain {
     pub fn new(gain_db: f64) -> Self {
-        Self { gain_db }
+        Self {
+            gain_db,
+            pan: 0.0,
+            matrix_gain_db: 0.0,
+            matrix_pan: 0.0,
+        }
     }
 
     pub fn set_gain(&mut self, gain_db: f64) {
         self.gain_db = gain_db;
     }
 
+    pub fn set_pan(&mut self, pan: f64) {
+        self.pan = pan.clamp(-1.0, 1.0);
+    }
+
     pub fn get_gain(&self) -> f64 {
         self.gain_db
     }
 
+    pub fn get_pan(&self) -> f64 {
+        self.pan
+    }
+
     /// Convertit les dB en facteur linéaire pour le traitement audio
     fn db_to_linear(&self) -> f64 {
-        10.0_f64.powf(self.gain_db / 20.0)
+        10.0_f64.powf((self.gain_db + self.matrix_gain_db) / 20.0)
+    }
+
+    /// Linear gains of the left and right channels, balance included
+    fn channel_gains(&self) -> StereoFrame {
+        let gain = self.db_to_linear();
+        let pan = (self.pan + self.matrix_pan).clamp(-1.0, 1.0);
+        [gain * (1.0 - pan).min(1.0), gain * (1.0 + pan).min(1.0)]
     }
 }
 
@@ -31,6 +56,29 @@ impl Module for Gain {
         input * self.db_to_linear()
     }
 
+    /// Balance: the centre keeps both channels at unity, panning only lowers the opposite side
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let [left, right] = self.channel_gains();
+        [frame[0] * left, frame[1] * right]
+    }
+
+    /// The dB conversion is done once per block
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        let [left, right] = self.channel_gains();
+        for frame in block.iter_mut() {
+            *frame = [frame[0] * left, frame[1] * right];
+        }
+    }
+
+    fn modulate(&mut self, amounts: &ModAmounts) {
+        self.matrix_gain_db = amounts.get(ModDestination::Gain);
+        self.matrix_pan = amounts.get(ModDestination::Pan);
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: f64) {
+        // Gain et panoramique ne dépendent pas de la fréquence
+    }
+
     fn name(&self) -> &'static str {
         "Gain"
     }
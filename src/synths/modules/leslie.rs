@@ -0,0 +1,171 @@
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+/// Rotor speeds of the rotary speaker
+pub enum LeslieSpeed {
+    Slow, // chorale
+    Fast, // tremolo
+}
+
+impl LeslieSpeed {
+    /// Target rotation rates (horn, drum) in Hz
+    fn rates(&self) -> (f64, f64) {
+        match self {
+            LeslieSpeed::Slow => (0.8, 0.7),
+            LeslieSpeed::Fast => (6.8, 5.9),
+        }
+    }
+}
+
+const CROSSOVER_HZ: f64 = 800.0;
+const HORN_INERTIA: f64 = 0.7; // secondes pour changer de vitesse
+const DRUM_INERTIA: f64 = 4.0; // le tambour est plus lourd
+const HORN_DELAY_MS: f64 = 1.5;
+const HORN_DELAY_DEPTH_MS: f64 = 0.6; // effet Doppler
+const HORN_AM_DEPTH: f64 = 0.25;
+const DRUM_AM_DEPTH: f64 = 0.15;
+const HORN_SPREAD: f64 = 0.6; // écart gauche/droite entre les deux micros
+const DRUM_SPREAD: f64 = 0.3;
+
+/// Rotary speaker: the signal is split into a treble horn and a bass drum
+/// spinning at their own speed, with amplitude modulation on both and Doppler on the horn.
+/// Changing the speed ramps the rotors up or down like the real motors.
+/// In stereo the cabinet is picked up by two microphones on each side of the rotors.
+#[derive(Clone)]
+pub struct Leslie {
+    sample_rate: f64,
+    speed: LeslieSpeed,
+    horn_rate: f64,
+    drum_rate: f64,
+    horn_phase: f64, // 0..1
+    drum_phase: f64, // 0..1
+    lowpass_state: f64,
+    delay: Vec<f64>,
+    delay_index: usize,
+}
+
+impl Leslie {
+    pub fn new(sample_rate: f64, speed: LeslieSpeed) -> Self {
+        let (horn_rate, drum_rate) = speed.rates();
+        let delay_len = ((HORN_DELAY_MS + HORN_DELAY_DEPTH_MS) * 0.001 * sample_rate) as usize + 2;
+        Self {
+            sample_rate,
+            speed,
+            horn_rate,
+            drum_rate,
+            horn_phase: 0.0,
+            drum_phase: 0.25, // les rotors ne sont pas alignés
+            lowpass_state: 0.0,
+            delay: vec![0.0; delay_len],
+            delay_index: 0,
+        }
+    }
+
+    /// Per-sample smoothing coefficients (crossover, horn motor, drum motor),
+    /// which only depend on the sample rate
+    fn coefficients(&self) -> [f64; 3] {
+        let inertia = |seconds: f64| (-1.0 / (seconds * self.sample_rate)).exp();
+        [
+            1.0 - (-2.0 * PI * CROSSOVER_HZ / self.sample_rate).exp(),
+            inertia(HORN_INERTIA),
+            inertia(DRUM_INERTIA),
+        ]
+    }
+
+    /// Writes the horn signal and reads it back with a delay in samples (linear interpolation)
+    fn delayed_horn(&mut self, input: f64, delay_samples: f64) -> f64 {
+        let len = self.delay.len();
+        self.delay[self.delay_index] = input;
+
+        let read = self.delay_index as f64 - delay_samples + len as f64;
+        let index = read.floor() as usize % len;
+        let next = (index + 1) % len;
+        let frac = read.fract();
+        let output = self.delay[index] * (1.0 - frac) + self.delay[next] * frac;
+
+        self.delay_index = (self.delay_index + 1) % len;
+        output
+    }
+
+    /// Spins the rotors by one sample and returns what the left and right microphones hear
+    fn spin(&mut self, input: f64, [alpha, horn_inertia, drum_inertia]: [f64; 3]) -> StereoFrame {
+        // Séparation grave/aigu
+        self.lowpass_state += alpha * (input - self.lowpass_state);
+        let bass = self.lowpass_state;
+        let treble = input - bass;
+
+        // Les moteurs rejoignent leur vitesse cible avec de l'inertie
+        let (horn_target, drum_target) = self.speed.rates();
+        self.horn_rate = horn_target + (self.horn_rate - horn_target) * horn_inertia;
+        self.drum_rate = drum_target + (self.drum_rate - drum_target) * drum_inertia;
+        self.horn_phase = (self.horn_phase + self.horn_rate / self.sample_rate).fract();
+        self.drum_phase = (self.drum_phase + self.drum_rate / self.sample_rate).fract();
+
+        let horn_angle = (2.0 * PI * self.horn_phase).sin();
+        let drum_angle = (2.0 * PI * self.drum_phase).sin();
+        // Le rotor passe devant un micro un quart de tour après être face à l'auditeur
+        let horn_side = (2.0 * PI * self.horn_phase).cos();
+        let drum_side = (2.0 * PI * self.drum_phase).cos();
+
+        let delay_ms = HORN_DELAY_MS + HORN_DELAY_DEPTH_MS * horn_angle;
+        let horn = self.delayed_horn(treble, delay_ms * 0.001 * self.sample_rate)
+            * (1.0 - HORN_AM_DEPTH * (1.0 - horn_angle) * 0.5);
+        let drum = bass * (1.0 - DRUM_AM_DEPTH * (1.0 - drum_angle) * 0.5);
+
+        [-1.0, 1.0].map(|side| {
+            horn * (1.0 + side * HORN_SPREAD * horn_side)
+                + drum * (1.0 + side * DRUM_SPREAD * drum_side)
+        })
+    }
+
+    // #### Setters ####
+    pub fn set_speed(&mut self, speed: LeslieSpeed) {
+        self.speed = speed;
+    }
+
+    // #### Getters ####
+    pub fn get_speed(&self) -> LeslieSpeed {
+        self.speed
+    }
+}
+
+impl Module for Leslie {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        // Les deux micros mélangés donnent le son mono de la cabine
+        let [left, right] = self.spin(input, self.coefficients());
+        (left + right) * 0.5
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.spin((frame[0] + frame[1]) * 0.5, self.coefficients())
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        let coefficients = self.coefficients();
+        for frame in block.iter_mut() {
+            *frame = self.spin((frame[0] + frame[1]) * 0.5, coefficients);
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Leslie::new(sample_rate, self.speed);
+    }
+
+    fn name(&self) -> &'static str {
+        "Leslie"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
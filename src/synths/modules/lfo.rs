@@ -1,25 +1,66 @@
-use crate::synths::traits::Module;
+use crate::consts::constants::DEFAULT_TEMPO;
+use crate::synths::modules::noise::{xorshift, xorshift_state};
+use crate::synths::tempo::TempoDivision;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 
 /// Type of LFO waveforms
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum LfoWaveform {
     Sine,
     Triangle,
     Square,
     SawUp,
     SawDown,
+    SampleHold,   // une valeur aléatoire tenue pendant chaque cycle
+    SmoothRandom, // valeurs aléatoires reliées en douceur
 }
 
+impl LfoWaveform {
+    pub const ALL: [LfoWaveform; 7] = [
+        LfoWaveform::Sine,
+        LfoWaveform::Triangle,
+        LfoWaveform::Square,
+        LfoWaveform::SawUp,
+        LfoWaveform::SawDown,
+        LfoWaveform::SampleHold,
+        LfoWaveform::SmoothRandom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LfoWaveform::Sine => "Sine",
+            LfoWaveform::Triangle => "Triangle",
+            LfoWaveform::Square => "Square",
+            LfoWaveform::SawUp => "Sawtooth Up",
+            LfoWaveform::SawDown => "Sawtooth Down",
+            LfoWaveform::SampleHold => "Sample & Hold",
+            LfoWaveform::SmoothRandom => "Smooth Random",
+        }
+    }
+}
+
+/// Low-frequency oscillator, free-running or restarted by each note.
+/// Its rate is either in Hz or a division of the global tempo, and its output can
+/// wait for a delay then fade in after the note starts.
 #[derive(Clone, Copy)]
 pub struct LFO {
     waveform: LfoWaveform,
-    freq: f64,        // en Hz
-    sample_rate: f64, // échantillons par seconde
-    phase: f64,       // 0..1
-    amplitude: f64,   // amplitude de sortie
-    offset: f64,      // décalage de sortie
-    bipolar: bool,    // true = -1..1, false = 0..1
+    freq: f64,                   // en Hz, hors synchronisation
+    sync: Option<TempoDivision>, // durée d'un cycle quand synchronisé au tempo
+    tempo: f64,                  // noires par minute
+    sample_rate: f64,            // échantillons par seconde
+    phase: f64,                  // 0..1
+    amplitude: f64,              // amplitude de sortie
+    offset: f64,                 // décalage de sortie
+    bipolar: bool,               // true = -1..1, false = 0..1
+    retrigger: bool,             // la phase repart de 0 à chaque note
+    delay: f64,                  // secondes de silence après la note
+    fade_in: f64,                // secondes de montée après le délai
+    elapsed: f64,                // secondes depuis la note
+    random: [f64; 2],            // valeurs aléatoires du cycle courant et du suivant
+    seed: u64,                   // état du générateur xorshift
 }
 
 impl LFO {
@@ -27,11 +68,60 @@ impl LFO {
         Self {
             waveform,
             freq,
+            sync: None,
+            tempo: DEFAULT_TEMPO,
             sample_rate,
             phase: 0.9,
             amplitude: 2.0,
             offset: 0.0,
             bipolar: false,
+            retrigger: false,
+            delay: 0.0,
+            fade_in: 0.0,
+            elapsed: f64::INFINITY, // sans note, la sortie est déjà pleine
+            random: [0.0; 2],
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Cycles per second, from the tempo when synced
+    pub fn rate(&self) -> f64 {
+        match self.sync {
+            Some(division) => division.frequency(self.tempo),
+            None => self.freq,
+        }
+    }
+
+    /// Restarts the delay and fade-in, and the phase when retriggered
+    pub fn note_on(&mut self) {
+        self.elapsed = 0.0;
+        if self.retrigger {
+            self.phase = 0.0;
+        }
+    }
+
+    /// Takes the phase and random values of another LFO, so a free-running LFO
+    /// of a new voice continues the shared one instead of starting over
+    pub fn follow(&mut self, other: &LFO) {
+        self.phase = other.phase;
+        self.random = other.random;
+        self.seed = other.seed;
+    }
+
+    /// Next random value, from -1 to 1
+    fn next_random(&mut self) -> f64 {
+        xorshift(&mut self.seed)
+    }
+
+    /// Gain of the delay and fade-in, 0 right after the note and 1 once faded in
+    fn fade(&self) -> f64 {
+        let time = self.elapsed - self.delay;
+        if time < 0.0 {
+            0.0
+        } else if time >= self.fade_in {
+            1.0
+        } else {
+            time / self.fade_in
         }
     }
 
@@ -57,7 +147,41 @@ impl LFO {
             }
             LfoWaveform::SawUp => 2.0 * self.phase - 1.0,
             LfoWaveform::SawDown => 1.0 - 2.0 * self.phase,
+            LfoWaveform::SampleHold => self.random[0],
+            LfoWaveform::SmoothRandom => {
+                // Interpolation en cosinus, sans cassure aux changements de cycle
+                let mix = (1.0 - (self.phase * PI).cos()) * 0.5;
+                self.random[0] + (self.random[1] - self.random[0]) * mix
+            }
+        }
+    }
+
+    /// Returns the modulation of the current sample and advances the phase
+    fn next_modulation(&mut self) -> f64 {
+        self.advance(1)
+    }
+
+    /// Returns the output at the current phase, then moves the phase `samples` samples forward.
+    /// Lets the modulation matrix run the LFO at control rate.
+    pub fn advance(&mut self, samples: usize) -> f64 {
+        let mut signal = self.waveform_value();
+
+        if !self.bipolar {
+            signal = (signal + 1.0) * 0.5;
         }
+
+        // Adding offset and scaling by amplitude
+        let output = signal * self.fade() * self.amplitude + self.offset;
+
+        // Phase advancement
+        self.phase += self.rate() * samples as f64 / self.sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= self.phase.floor();
+            self.random = [self.random[1], self.next_random()];
+        }
+        self.elapsed += samples as f64 / self.sample_rate;
+
+        output
     }
 
     /// #### Setters ####
@@ -85,6 +209,37 @@ impl LFO {
         self.bipolar = bipolar;
     }
 
+    pub fn set_phase(&mut self, phase: f64) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Syncs the rate to the tempo, `None` goes back to the frequency in Hz
+    pub fn set_sync(&mut self, sync: Option<TempoDivision>) {
+        self.sync = sync;
+    }
+
+    pub fn set_tempo(&mut self, bpm: f64) {
+        self.tempo = bpm.max(1.0);
+    }
+
+    pub fn set_retrigger(&mut self, retrigger: bool) {
+        self.retrigger = retrigger;
+    }
+
+    pub fn set_delay(&mut self, seconds: f64) {
+        self.delay = seconds.max(0.0);
+    }
+
+    pub fn set_fade_in(&mut self, seconds: f64) {
+        self.fade_in = seconds.max(0.0);
+    }
+
+    /// Restarts the random sequence, e.g. so each voice gets its own
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = xorshift_state(seed);
+        self.random = [self.next_random(), self.next_random()];
+    }
+
     /// #### Getters ####
     pub fn get_waveform(&self) -> LfoWaveform {
         self.waveform
@@ -109,27 +264,49 @@ impl LFO {
     pub fn get_bipolar(&self) -> bool {
         self.bipolar
     }
+
+    pub fn get_phase(&self) -> f64 {
+        self.phase
+    }
+
+    pub fn get_sync(&self) -> Option<TempoDivision> {
+        self.sync
+    }
+
+    pub fn get_tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    pub fn get_retrigger(&self) -> bool {
+        self.retrigger
+    }
+
+    pub fn get_delay(&self) -> f64 {
+        self.delay
+    }
+
+    pub fn get_fade_in(&self) -> f64 {
+        self.fade_in
+    }
 }
 
 impl Module for LFO {
     fn process(&mut self, input: f64, _time: f64) -> f64 {
-        let mut signal = self.waveform_value();
-
-        if !self.bipolar {
-            signal = (signal + 1.0) * 0.5;
-        }
+        // Applying the LFO modulation to the input signal
+        input * (1.0 + self.next_modulation())
+    }
 
-        // Adding offset and scaling by amplitude
-        let output = signal * self.amplitude + self.offset;
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let modulation = 1.0 + self.next_modulation();
+        [frame[0] * modulation, frame[1] * modulation]
+    }
 
-        // Phase advancement
-        self.phase += self.freq / self.sample_rate;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
-        }
+    fn set_tempo(&mut self, bpm: f64) {
+        LFO::set_tempo(self, bpm);
+    }
 
-        // Applying the LFO modulation to the input signal
-        input * (1.0 + output)
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        LFO::set_sample_rate(self, sample_rate);
     }
 
     fn name(&self) -> &'static str {
@@ -139,7 +316,7 @@ impl Module for LFO {
     fn clone_box(&self) -> Box<dyn Module> {
         Box::new(*self)
     }
-    
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -148,3 +325,119 @@ impl Module for LFO {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::tempo::{NoteFeel, NoteLength};
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    /// Bipolar LFO from -1 to 1, starting at phase 0
+    fn lfo(waveform: LfoWaveform, freq: f64) -> LFO {
+        let mut lfo = LFO::new(waveform, freq, SAMPLE_RATE);
+        lfo.set_amplitude(1.0);
+        lfo.set_bipolar(true);
+        lfo.set_phase(0.0);
+        lfo
+    }
+
+    #[test]
+    fn synced_rate_follows_the_tempo_division() {
+        let mut lfo = lfo(LfoWaveform::Sine, 5.0);
+        for length in NoteLength::ALL {
+            for feel in NoteFeel::ALL {
+                let division = TempoDivision { length, feel };
+                lfo.set_sync(Some(division));
+                for bpm in [60.0, 120.0, 174.0] {
+                    lfo.set_tempo(bpm);
+                    assert_eq!(lfo.rate(), division.frequency(bpm));
+                }
+            }
+        }
+
+        // Une noire à 120 BPM : un cycle par demi-seconde
+        lfo.set_sync(Some(TempoDivision::default()));
+        lfo.set_tempo(120.0);
+        lfo.set_phase(0.0);
+        lfo.advance((SAMPLE_RATE * 0.125) as usize);
+        assert!((lfo.get_phase() - 0.25).abs() < 1e-9);
+
+        lfo.set_sync(None);
+        assert_eq!(lfo.rate(), 5.0);
+    }
+
+    #[test]
+    fn retrigger_restarts_the_phase_on_each_note() {
+        for retrigger in [true, false] {
+            let mut lfo = lfo(LfoWaveform::Sine, 3.0);
+            lfo.set_retrigger(retrigger);
+            lfo.advance(5000);
+            let phase = lfo.get_phase();
+            assert!(phase > 0.0);
+            lfo.note_on();
+            let expected = if retrigger { 0.0 } else { phase };
+            assert_eq!(lfo.get_phase(), expected, "retrigger {retrigger}");
+        }
+    }
+
+    #[test]
+    fn output_waits_for_the_delay_then_fades_in_linearly() {
+        // Carré très lent : la sortie vaut 1 et ne bouge que par le fondu
+        let mut lfo = lfo(LfoWaveform::Square, 0.01);
+        lfo.set_delay(0.1);
+        lfo.set_fade_in(0.2);
+        assert_eq!(lfo.advance(1), 1.0, "no note: already at full depth");
+
+        lfo.note_on();
+        let output: Vec<f64> = (0..(SAMPLE_RATE * 0.4) as usize)
+            .map(|_| lfo.advance(1))
+            .collect();
+        let at = |seconds: f64| output[(seconds * SAMPLE_RATE) as usize];
+        assert_eq!(at(0.0), 0.0);
+        assert_eq!(at(0.099), 0.0);
+        for (seconds, expected) in [(0.15, 0.25), (0.2, 0.5), (0.25, 0.75)] {
+            assert!(
+                (at(seconds) - expected).abs() < 1e-3,
+                "{} at {seconds} s",
+                at(seconds)
+            );
+        }
+        assert_eq!(at(0.31), 1.0);
+        assert_eq!(at(0.399), 1.0);
+    }
+
+    #[test]
+    fn random_waveforms_repeat_with_their_seed_and_stay_in_range() {
+        for waveform in [LfoWaveform::SampleHold, LfoWaveform::SmoothRandom] {
+            let render = |seed| {
+                let mut lfo = lfo(waveform, 50.0);
+                lfo.set_seed(seed);
+                (0..SAMPLE_RATE as usize)
+                    .map(|_| lfo.advance(1))
+                    .collect::<Vec<_>>()
+            };
+            let first = render(7);
+            assert_eq!(
+                first,
+                render(7),
+                "{}: same seed, other values",
+                waveform.name()
+            );
+            assert_ne!(first, render(8), "{}: the seed is ignored", waveform.name());
+            assert!(first.iter().all(|value| (-1.0..=1.0).contains(value)));
+
+            // 50 cycles, donc une cinquantaine de valeurs différentes bien réparties
+            let (min, max) = first
+                .iter()
+                .fold((1.0f64, -1.0f64), |(min, max), &v| (min.min(v), max.max(v)));
+            assert!(min < -0.5 && max > 0.5, "{}: {min}..{max}", waveform.name());
+        }
+
+        // Le sample & hold tient sa valeur pendant tout un cycle
+        let mut lfo = lfo(LfoWaveform::SampleHold, 50.0);
+        lfo.set_seed(3);
+        let cycle: Vec<f64> = (0..960).map(|_| lfo.advance(1)).collect();
+        assert!(cycle.iter().all(|&value| value == cycle[0]));
+    }
+}
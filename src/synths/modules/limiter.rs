@@ -0,0 +1,351 @@
+use crate::consts::constants;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+pub const MIN_CEILING_DB: f64 = -24.0;
+const LOOKAHEAD_MS: f64 = 1.5; // le gain est déjà baissé quand le pic sort de la ligne
+const PHASES: usize = 4; // suréchantillonnage de la détection des pics inter-échantillons
+const TAPS_PER_PHASE: usize = 12;
+const DETECTION_DELAY: usize = TAPS_PER_PHASE / 2; // retard de groupe du filtre d'interpolation
+
+/// Parameters of the limiter, saved in the presets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterSettings {
+    pub ceiling_db: f64, // niveau crête maximal en sortie, pics inter-échantillons compris
+    pub release_ms: f64, // remontée du gain après un pic
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            ceiling_db: constants::CURRENT_LIMITER_CEILING_DB,
+            release_ms: constants::CURRENT_LIMITER_RELEASE_MS,
+        }
+    }
+}
+
+impl LimiterSettings {
+    /// Same settings brought back into their valid ranges
+    pub fn clamped(&self) -> Self {
+        Self {
+            ceiling_db: self.ceiling_db.clamp(MIN_CEILING_DB, 0.0),
+            release_ms: self.release_ms.clamp(1.0, 1000.0),
+        }
+    }
+
+    pub fn ceiling(&self) -> f64 {
+        10f64.powf(self.ceiling_db / 20.0)
+    }
+}
+
+/// Minimum of the last values pushed, over a fixed window, in constant amortized time.
+/// Only the values that can still become the minimum are kept, increasing from the oldest,
+/// in a ring allocated once for the whole window.
+#[derive(Clone)]
+struct RunningMin {
+    candidates: Vec<(u64, f64)>, // (numéro d'échantillon, valeur)
+    head: usize,
+    len: usize,
+    count: u64,
+}
+
+impl RunningMin {
+    fn new(window: usize) -> Self {
+        Self {
+            candidates: vec![(0, 0.0); window.max(1)],
+            head: 0,
+            len: 0,
+            count: 0,
+        }
+    }
+
+    /// Adds a value and returns the minimum of the window ending with it
+    #[inline]
+    fn push(&mut self, value: f64) -> f64 {
+        let window = self.candidates.len();
+        // Le plus ancien sort de la fenêtre
+        if self.len > 0 && self.candidates[self.head].0 + window as u64 <= self.count {
+            self.head = (self.head + 1) % window;
+            self.len -= 1;
+        }
+        // Les candidats plus grands que la nouvelle valeur ne seront plus jamais le minimum
+        while self.len > 0 && self.candidates[(self.head + self.len - 1) % window].1 >= value {
+            self.len -= 1;
+        }
+        self.candidates[(self.head + self.len) % window] = (self.count, value);
+        self.len += 1;
+        self.count += 1;
+        self.candidates[self.head].1
+    }
+}
+
+/// Brickwall limiter, last stage of the master bus.
+/// Peaks are measured between the samples too (4x interpolation), the signal is delayed so the
+/// gain is already down when they come out, and a final clip at the ceiling catches the rest.
+#[derive(Clone)]
+pub struct Limiter {
+    sample_rate: f64,
+    settings: LimiterSettings,
+    ceiling: f64,
+    release_coeff: f64,
+
+    kernel: [[f64; TAPS_PER_PHASE]; PHASES], // une phase par position entre deux échantillons
+    history: [[f64; TAPS_PER_PHASE]; 2],     // derniers échantillons par canal, récent en tête
+
+    delay: Vec<StereoFrame>, // signal retardé de la détection plus le lookahead
+    targets: RunningMin,     // gains voulus pour chaque échantillon encore dans la ligne
+    index: usize,
+    envelope: f64, // minimum des gains voulus, avec la remontée
+
+    smoothing: Vec<f64>, // moyenne glissante sur la durée du lookahead
+    smoothing_index: usize,
+    smoothing_sum: f64,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f64, settings: LimiterSettings) -> Self {
+        let lookahead = ((LOOKAHEAD_MS * 0.001 * sample_rate).round() as usize).max(1);
+        let delay_length = lookahead + DETECTION_DELAY + 1;
+
+        let mut limiter = Self {
+            sample_rate,
+            settings,
+            ceiling: 1.0,
+            release_coeff: 0.0,
+            kernel: Self::interpolation_kernel(),
+            history: [[0.0; TAPS_PER_PHASE]; 2],
+            delay: vec![[0.0, 0.0]; delay_length],
+            targets: RunningMin::new(delay_length),
+            index: 0,
+            envelope: 1.0,
+            smoothing: vec![1.0; lookahead],
+            smoothing_index: 0,
+            smoothing_sum: lookahead as f64,
+        };
+        limiter.set_settings(settings);
+        limiter
+    }
+
+    /// Windowed sinc cut at the original Nyquist, split in phases each summing to 1.
+    /// Phase p reads the signal p/4 of a sample after the one `DETECTION_DELAY` samples back.
+    fn interpolation_kernel() -> [[f64; TAPS_PER_PHASE]; PHASES] {
+        let length = (PHASES * TAPS_PER_PHASE) as f64;
+        let mut kernel = [[0.0; TAPS_PER_PHASE]; PHASES];
+        for (phase, taps) in kernel.iter_mut().enumerate() {
+            for (tap, coefficient) in taps.iter_mut().enumerate() {
+                let offset = (tap * PHASES + phase) as f64 - length / 2.0;
+                let x = offset / PHASES as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let window = 0.42
+                    + 0.5 * (2.0 * PI * offset / length).cos()
+                    + 0.08 * (4.0 * PI * offset / length).cos();
+                *coefficient = sinc * window;
+            }
+            let sum: f64 = taps.iter().sum();
+            taps.iter_mut().for_each(|coefficient| *coefficient /= sum);
+        }
+        kernel
+    }
+
+    /// Highest absolute value of the frame and of the points interpolated just before it
+    #[inline]
+    fn true_peak(&mut self, frame: StereoFrame) -> f64 {
+        let mut peak: f64 = 0.0;
+        for (channel, &sample) in frame.iter().enumerate() {
+            let history = &mut self.history[channel];
+            history.copy_within(0..TAPS_PER_PHASE - 1, 1);
+            history[0] = sample;
+            peak = peak.max(sample.abs());
+            for taps in &self.kernel {
+                let interpolated: f64 = taps.iter().zip(history.iter()).map(|(c, x)| c * x).sum();
+                peak = peak.max(interpolated.abs());
+            }
+        }
+        peak
+    }
+
+    #[inline]
+    fn limit_frame(&mut self, frame: StereoFrame) -> StereoFrame {
+        let peak = self.true_peak(frame);
+        let target = if peak > self.ceiling {
+            self.ceiling / peak
+        } else {
+            1.0
+        };
+
+        // Écriture dans la ligne, lecture de l'échantillon le plus ancien
+        let length = self.delay.len();
+        self.delay[self.index] = frame;
+        self.index = (self.index + 1) % length;
+        let delayed = self.delay[self.index];
+
+        // Le gain descend aussitôt au minimum de la ligne et remonte avec le release
+        let held = self.targets.push(target);
+        self.envelope = if held < self.envelope {
+            held
+        } else {
+            held + (self.envelope - held) * self.release_coeff
+        };
+
+        // Lissage sur le lookahead : tous les gains moyennés ont vu le pic qui sort
+        self.smoothing_sum += self.envelope - self.smoothing[self.smoothing_index];
+        self.smoothing[self.smoothing_index] = self.envelope;
+        self.smoothing_index = (self.smoothing_index + 1) % self.smoothing.len();
+        let gain = (self.smoothing_sum / self.smoothing.len() as f64).min(1.0);
+
+        delayed.map(|sample| (sample * gain).clamp(-self.ceiling, self.ceiling))
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: LimiterSettings) {
+        self.settings = settings.clamped();
+        self.ceiling = self.settings.ceiling();
+        self.release_coeff = (-1.0 / (self.settings.release_ms * 0.001 * self.sample_rate)).exp();
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> LimiterSettings {
+        self.settings
+    }
+}
+
+impl Module for Limiter {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        self.limit_frame([input, input])[0]
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.limit_frame(frame)
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for frame in block.iter_mut() {
+            *frame = self.limit_frame(*frame);
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Limiter::new(sample_rate, self.settings);
+    }
+
+    fn name(&self) -> &'static str {
+        "Limiter"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    fn settings() -> LimiterSettings {
+        LimiterSettings {
+            ceiling_db: -1.0,
+            release_ms: 50.0,
+        }
+    }
+
+    /// Highest value of the signal read at 4x the sample rate, with the limiter's own kernel
+    fn true_peak(frames: &[StereoFrame]) -> f64 {
+        let mut meter = Limiter::new(SAMPLE_RATE, settings());
+        frames
+            .iter()
+            .map(|&frame| meter.true_peak(frame))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn running_min_matches_a_scan_of_the_window() {
+        let window = 7;
+        let mut running = RunningMin::new(window);
+        let mut state = 12345u32;
+        let mut values = Vec::new();
+        for _ in 0..2000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let value = (state % 100) as f64 / 100.0;
+            values.push(value);
+            let start = values.len().saturating_sub(window);
+            let expected = values[start..].iter().copied().fold(f64::MAX, f64::min);
+            assert_eq!(running.push(value), expected);
+        }
+    }
+
+    #[test]
+    fn inter_sample_peaks_stay_under_the_ceiling() {
+        // Sinus à fs/4 déphasé de 45° : les échantillons valent ±0,707, la crête réelle 1
+        let input: Vec<StereoFrame> = (0..4800)
+            .map(|n| {
+                let sample = (PI / 2.0 * n as f64 + PI / 4.0).sin();
+                [sample, sample]
+            })
+            .collect();
+        let sample_peak = input
+            .iter()
+            .flatten()
+            .fold(0.0, |peak: f64, s| peak.max(s.abs()));
+        assert!(
+            sample_peak < settings().ceiling(),
+            "samples already over the ceiling"
+        );
+        assert!(
+            true_peak(&input) > 0.99,
+            "the test signal has no inter-sample peak"
+        );
+
+        let mut limiter = Limiter::new(SAMPLE_RATE, settings());
+        let output: Vec<StereoFrame> = input
+            .iter()
+            .map(|&frame| limiter.process_frame(frame, 0.0))
+            .collect();
+        let peak = true_peak(&output);
+        assert!(
+            peak <= settings().ceiling() * 1.001,
+            "true peak {peak} over the ceiling {}",
+            settings().ceiling()
+        );
+    }
+
+    #[test]
+    fn gain_recovers_to_unity_after_the_release() {
+        let mut limiter = Limiter::new(SAMPLE_RATE, settings());
+        let mut output = Vec::new();
+        // Un burst à +6 dB, puis un signal calme bien sous le plafond
+        for n in 0..(SAMPLE_RATE * 0.6) as usize {
+            let level = if n < 480 { 2.0 } else { 0.25 };
+            let sample = level * (n as f64 * 0.05).sin();
+            output.push((sample, limiter.process_frame([sample, sample], 0.0)[0]));
+        }
+        let delay = limiter.delay.len() - 1;
+        let during = output[480..480 + delay].iter().map(|(_, out)| out.abs());
+        assert!(during.fold(0.0, f64::max) <= settings().ceiling());
+
+        // Dix constantes de release plus tard, la sortie est le signal d'entrée retardé
+        let recovered = (SAMPLE_RATE * 0.5) as usize;
+        for n in recovered..output.len() {
+            let (input, _) = output[n - delay];
+            let (_, out) = output[n];
+            assert!((out - input).abs() < 1e-3, "gain {} at {n}", out / input);
+        }
+    }
+}
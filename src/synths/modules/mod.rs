@@ -1,15 +1,30 @@
 pub mod adsr;
+pub mod chorus;
 pub mod compressor;
+pub mod delay;
+pub mod distortion;
 pub mod filter;
+pub mod flanger;
 pub mod gain;
+pub mod leslie;
 pub mod lfo;
+pub mod limiter;
+pub mod modulated;
 pub mod noise;
+pub mod phaser;
 pub mod reverb;
 
 pub use adsr::ADSR;
+pub use chorus::Chorus;
 pub use compressor::Compressor;
-pub use filter::LowPassFilter;
+pub use delay::Delay;
+pub use distortion::Distortion;
+pub use filter::MultiModeFilter;
+pub use flanger::Flanger;
 pub use gain::Gain;
+pub use leslie::Leslie;
 pub use lfo::LFO;
+pub use limiter::Limiter;
 pub use noise::Noise;
+pub use phaser::Phaser;
 pub use reverb::Reverb;
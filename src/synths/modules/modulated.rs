@@ -0,0 +1,238 @@
+use crate::consts::constants;
+use crate::synths::modules::{Chorus, Flanger, Phaser};
+use crate::synths::traits::Module;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Time-modulated effects of the master bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModEffectKind {
+    Chorus,
+    Flanger,
+    Phaser,
+}
+
+impl ModEffectKind {
+    pub const ALL: [ModEffectKind; 3] = [
+        ModEffectKind::Chorus,
+        ModEffectKind::Flanger,
+        ModEffectKind::Phaser,
+    ];
+
+    /// Name of the effect, also the name of its module in the chain
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModEffectKind::Chorus => "Chorus",
+            ModEffectKind::Flanger => "Flanger",
+            ModEffectKind::Phaser => "Phaser",
+        }
+    }
+
+    pub fn default_settings(&self) -> ModEffectSettings {
+        match self {
+            ModEffectKind::Chorus => constants::CURRENT_CHORUS,
+            ModEffectKind::Flanger => constants::CURRENT_FLANGER,
+            ModEffectKind::Phaser => constants::CURRENT_PHASER,
+        }
+    }
+
+    pub fn is_active_by_default(&self) -> bool {
+        match self {
+            ModEffectKind::Chorus => constants::ACTIVATION_CHORUS,
+            ModEffectKind::Flanger => constants::ACTIVATION_FLANGER,
+            ModEffectKind::Phaser => constants::ACTIVATION_PHASER,
+        }
+    }
+
+    pub fn build(&self, sample_rate: f64, settings: ModEffectSettings) -> Box<dyn Module> {
+        match self {
+            ModEffectKind::Chorus => Box::new(Chorus::new(sample_rate, settings)),
+            ModEffectKind::Flanger => Box::new(Flanger::new(sample_rate, settings)),
+            ModEffectKind::Phaser => Box::new(Phaser::new(sample_rate, settings)),
+        }
+    }
+
+    /// Reads the settings of a module of this kind, None for the other modules
+    pub fn settings_of(&self, module: &dyn Module) -> Option<ModEffectSettings> {
+        let any = module.as_any();
+        match self {
+            ModEffectKind::Chorus => any.downcast_ref::<Chorus>().map(Chorus::get_settings),
+            ModEffectKind::Flanger => any.downcast_ref::<Flanger>().map(Flanger::get_settings),
+            ModEffectKind::Phaser => any.downcast_ref::<Phaser>().map(Phaser::get_settings),
+        }
+    }
+
+    /// Sets the settings of a module of this kind, ignores the other modules
+    pub fn apply(&self, module: &mut dyn Module, settings: ModEffectSettings) {
+        let any = module.as_any_mut();
+        match self {
+            ModEffectKind::Chorus => {
+                if let Some(chorus) = any.downcast_mut::<Chorus>() {
+                    chorus.set_settings(settings);
+                }
+            }
+            ModEffectKind::Flanger => {
+                if let Some(flanger) = any.downcast_mut::<Flanger>() {
+                    flanger.set_settings(settings);
+                }
+            }
+            ModEffectKind::Phaser => {
+                if let Some(phaser) = any.downcast_mut::<Phaser>() {
+                    phaser.set_settings(settings);
+                }
+            }
+        }
+    }
+}
+
+/// Controls shared by the chorus, the flanger and the phaser
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModEffectSettings {
+    pub rate: f64,     // Hz
+    pub depth: f64,    // 0..1, amplitude du balayage
+    pub feedback: f64, // -0.95..0.95, négatif creuse les graves du flanger
+    pub mix: f64,      // 0 = dry, 1 = wet
+}
+
+impl ModEffectSettings {
+    pub const MAX_RATE: f64 = 10.0;
+    pub const MAX_FEEDBACK: f64 = 0.95;
+
+    /// Same settings brought back into their valid ranges
+    pub fn clamped(&self) -> Self {
+        Self {
+            rate: self.rate.clamp(0.01, Self::MAX_RATE),
+            depth: self.depth.clamp(0.0, 1.0),
+            feedback: self.feedback.clamp(-Self::MAX_FEEDBACK, Self::MAX_FEEDBACK),
+            mix: self.mix.clamp(0.0, 1.0),
+        }
+    }
+
+    #[inline]
+    pub fn blend(&self, dry: f64, wet: f64) -> f64 {
+        (1.0 - self.mix) * dry + self.mix * wet
+    }
+}
+
+/// Circular buffer read at a fractional distance behind the write position
+#[derive(Clone)]
+pub struct DelayLine {
+    buffer: Vec<f64>,
+    index: usize,
+}
+
+impl DelayLine {
+    /// Line able to delay by up to `max_ms`
+    pub fn new(max_ms: f64, sample_rate: f64) -> Self {
+        Self {
+            buffer: vec![0.0; (max_ms * 0.001 * sample_rate) as usize + 2],
+            index: 0,
+        }
+    }
+
+    /// Sample written `delay` samples ago (linear interpolation), `delay` from 1
+    #[inline]
+    pub fn read(&self, delay: f64) -> f64 {
+        let len = self.buffer.len();
+        let read = self.index as f64 - delay.clamp(1.0, (len - 2) as f64) + len as f64;
+        let index = read.floor() as usize % len;
+        let next = (index + 1) % len;
+        let frac = read.fract();
+        self.buffer[index] * (1.0 - frac) + self.buffer[next] * frac
+    }
+
+    #[inline]
+    pub fn write(&mut self, input: f64) {
+        self.buffer[self.index] = input;
+        self.index = (self.index + 1) % self.buffer.len();
+    }
+}
+
+/// Sine sweeping the delay time or the all-pass frequency
+#[derive(Clone, Copy, Default)]
+pub struct Sweep {
+    phase: f64, // 0..1
+}
+
+impl Sweep {
+    #[inline]
+    pub fn advance(&mut self, rate: f64, sample_rate: f64) {
+        self.phase = (self.phase + rate / sample_rate).fract();
+    }
+
+    /// Value from 0 to 1, `offset` shifts the phase by a fraction of a cycle
+    #[inline]
+    pub fn unipolar(&self, offset: f64) -> f64 {
+        0.5 + 0.5 * (2.0 * PI * (self.phase + offset)).sin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synths::traits::StereoFrame;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    /// Full-scale sawtooth on the left, a sine on the right
+    fn input(n: usize) -> StereoFrame {
+        let time = n as f64 / SAMPLE_RATE;
+        [
+            2.0 * (220.0 * time).fract() - 1.0,
+            (2.0 * PI * 1000.0 * time).sin(),
+        ]
+    }
+
+    #[test]
+    fn output_stays_bounded_at_the_highest_feedback() {
+        // Au pire, un peigne qui boucle à 0,95 amplifie 20 fois
+        let bound = 1.0 + 1.0 / (1.0 - ModEffectSettings::MAX_FEEDBACK);
+        for kind in ModEffectKind::ALL {
+            for (feedback, rate) in [(1.0, 0.1), (-1.0, 0.1), (1.0, 10.0), (-1.0, 10.0)] {
+                let settings = ModEffectSettings {
+                    rate,
+                    depth: 1.0,
+                    feedback,
+                    mix: 1.0,
+                };
+                let mut effect = kind.build(SAMPLE_RATE, settings);
+                let mut peak: f64 = 0.0;
+                for n in 0..(SAMPLE_RATE * 3.0) as usize {
+                    let output = effect.process_frame(input(n), 0.0);
+                    assert!(output.iter().all(|sample| sample.is_finite()));
+                    peak = output
+                        .iter()
+                        .fold(peak, |peak, sample| peak.max(sample.abs()));
+                }
+                assert!(
+                    peak < bound,
+                    "{} with feedback {feedback} at {rate} Hz peaked at {peak}",
+                    kind.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_mix_passes_the_dry_signal_untouched() {
+        for kind in ModEffectKind::ALL {
+            let settings = ModEffectSettings {
+                mix: 0.0,
+                ..kind.default_settings()
+            };
+            let mut by_frame = kind.build(SAMPLE_RATE, settings);
+            let mut by_block = kind.build(SAMPLE_RATE, settings);
+            let dry: Vec<StereoFrame> = (0..4096).map(input).collect();
+
+            let mut block = dry.clone();
+            for part in block.chunks_mut(512) {
+                by_block.process_block(part);
+            }
+            for (n, &frame) in dry.iter().enumerate() {
+                assert_eq!(by_frame.process_frame(frame, 0.0), frame, "{}", kind.name());
+                assert_eq!(by_frame.process(frame[0], 0.0), frame[0], "{}", kind.name());
+                assert_eq!(block[n], frame, "{} by block", kind.name());
+            }
+        }
+    }
+}
@@ -1,16 +1,177 @@
-use crate::synths::traits::Module;
-use rand::Rng;
+use crate::consts::constants;
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
+
+/// Spectrum of the noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoiseColor {
+    White,  // même énergie à toutes les fréquences
+    Pink,   // -3 dB par octave
+    Brown,  // -6 dB par octave
+    Blue,   // +3 dB par octave
+    Violet, // +6 dB par octave
+}
+
+impl NoiseColor {
+    pub const ALL: [NoiseColor; 5] = [
+        NoiseColor::White,
+        NoiseColor::Pink,
+        NoiseColor::Brown,
+        NoiseColor::Blue,
+        NoiseColor::Violet,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseColor::White => "White",
+            NoiseColor::Pink => "Pink",
+            NoiseColor::Brown => "Brown",
+            NoiseColor::Blue => "Blue",
+            NoiseColor::Violet => "Violet",
+        }
+    }
+}
+
+/// Turns any seed into a starting state for `xorshift`, never 0.
+/// Close seeds give unrelated sequences.
+pub fn xorshift_state(seed: u64) -> u64 {
+    // splitmix64
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)).max(1)
+}
+
+/// Advances a xorshift state and returns a uniform value from -1 to 1.
+/// The state lives in its owner, so generators stay `Copy` and never share a lock on the audio thread.
+#[inline]
+pub fn xorshift(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+/// Colored noise from a seeded xorshift generator.
+/// `Copy` and allocation-free, so each voice owns its generator on the audio thread.
+/// Every color peaks at about ±1.
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseGenerator {
+    color: NoiseColor,
+    state: u64,     // état du xorshift, jamais nul
+    pink: [f64; 7], // filtres du bruit rose (Paul Kellet)
+    brown: f64,     // intégrateur du bruit brun
+    previous: f64,  // échantillon précédent, dérivé pour le bleu et le violet
+}
+
+impl NoiseGenerator {
+    pub fn new(color: NoiseColor, seed: u64) -> Self {
+        let mut generator = Self {
+            color,
+            state: 1,
+            pink: [0.0; 7],
+            brown: 0.0,
+            previous: 0.0,
+        };
+        generator.set_seed(seed);
+        generator
+    }
+
+    /// Restarts the random sequence, the same seed always gives the same noise
+    pub fn set_seed(&mut self, seed: u64) {
+        self.state = xorshift_state(seed);
+    }
+
+    pub fn set_color(&mut self, color: NoiseColor) {
+        self.color = color;
+    }
+
+    pub fn get_color(&self) -> NoiseColor {
+        self.color
+    }
+
+    /// Uniform white sample from -1 to 1
+    #[inline]
+    fn white(&mut self) -> f64 {
+        xorshift(&mut self.state)
+    }
+
+    /// Pink noise from white through a sum of one-pole filters
+    #[inline]
+    fn pink(&mut self, white: f64) -> f64 {
+        let p = &mut self.pink;
+        p[0] = 0.99886 * p[0] + white * 0.0555179;
+        p[1] = 0.99332 * p[1] + white * 0.0750759;
+        p[2] = 0.96900 * p[2] + white * 0.1538520;
+        p[3] = 0.86650 * p[3] + white * 0.3104856;
+        p[4] = 0.55000 * p[4] + white * 0.5329522;
+        p[5] = -0.7616 * p[5] - white * 0.0168980;
+        let pink = p[0] + p[1] + p[2] + p[3] + p[4] + p[5] + p[6] + white * 0.5362;
+        p[6] = white * 0.115926;
+        pink * 0.13
+    }
+
+    #[inline]
+    pub fn next_sample(&mut self) -> f64 {
+        let white = self.white();
+        match self.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => self.pink(white),
+            NoiseColor::Brown => {
+                // Intégrateur qui fuit, pour ne pas dériver loin de 0
+                self.brown = (self.brown + 0.02 * white) / 1.02;
+                self.brown * 4.0
+            }
+            NoiseColor::Blue => {
+                let pink = self.pink(white);
+                let blue = pink - self.previous;
+                self.previous = pink;
+                blue * 2.6
+            }
+            NoiseColor::Violet => {
+                let violet = white - self.previous;
+                self.previous = white;
+                violet * 0.5
+            }
+        }
+    }
+}
 
 /// Noise struct
-/// This struct adds noise to an audio signal.
+/// This struct adds colored noise to an audio signal, independently on each channel.
 #[derive(Clone, Copy)]
 pub struct Noise {
-    amount: f64, // niveau de bruit à ajouter
+    amount: f64,                     // niveau de bruit à ajouter
+    generators: [NoiseGenerator; 2], // un par canal
 }
 
 impl Noise {
     pub fn new(amount: f64) -> Self {
-        Self { amount }
+        Self::with_color(amount, constants::CURRENT_NOISE_COLOR)
+    }
+
+    pub fn with_color(amount: f64, color: NoiseColor) -> Self {
+        Self {
+            amount,
+            generators: [NoiseGenerator::new(color, 0), NoiseGenerator::new(color, 1)],
+        }
+    }
+
+    #[inline]
+    fn add_noise(generator: &mut NoiseGenerator, amount: f64, input: f64) -> f64 {
+        input + generator.next_sample() * amount
+    }
+
+    /// Seeds both channels, the right one from `seed + 1`
+    pub fn set_seed(&mut self, seed: u64) {
+        for (offset, generator) in self.generators.iter_mut().enumerate() {
+            generator.set_seed(seed.wrapping_add(offset as u64));
+        }
+    }
+
+    /// Draws a new seed, so voices copied from the same patch do not play the same noise
+    pub fn note_on(&mut self) {
+        self.set_seed(rand::random());
     }
 
     pub fn set_amount(&mut self, amount: f64) {
@@ -20,14 +181,42 @@ impl Noise {
     pub fn get_amount(&self) -> f64 {
         self.amount
     }
+
+    pub fn set_color(&mut self, color: NoiseColor) {
+        for generator in self.generators.iter_mut() {
+            generator.set_color(color);
+        }
+    }
+
+    pub fn get_color(&self) -> NoiseColor {
+        self.generators[0].get_color()
+    }
 }
 
 impl Module for Noise {
     fn process(&mut self, input: f64, _time: f64) -> f64 {
-        let mut rng = rand::rng();
-        let noise: f64 = rng.random_range(-1.0..1.0) * self.amount;
-        let mixed = input + noise;
-        mixed.clamp(-1.0, 1.0) // évite la saturation
+        Self::add_noise(&mut self.generators[0], self.amount, input)
+    }
+
+    /// Independent noise on each channel, like two noisy analog paths
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let [left, right] = &mut self.generators;
+        [
+            Self::add_noise(left, self.amount, frame[0]),
+            Self::add_noise(right, self.amount, frame[1]),
+        ]
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        let [left, right] = &mut self.generators;
+        for frame in block.iter_mut() {
+            frame[0] = Self::add_noise(left, self.amount, frame[0]);
+            frame[1] = Self::add_noise(right, self.amount, frame[1]);
+        }
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: f64) {
+        // Le bruit blanc et ses filtres de couleur sont définis par échantillon
     }
 
     fn name(&self) -> &'static str {
@@ -0,0 +1,121 @@
+use crate::synths::modules::modulated::{ModEffectSettings, Sweep};
+use crate::synths::traits::{Module, StereoFrame};
+use std::f64::consts::PI;
+
+const STAGES: usize = 6; // trois creux dans le spectre
+const MIN_HZ: f64 = 200.0; // fréquence des all-pass au creux du balayage
+const SWEEP_OCTAVES: f64 = 5.0; // étendue du balayage à depth = 1
+const RIGHT_OFFSET: f64 = 0.25; // le canal droit est décalé d'un quart de cycle
+
+/// Chain of first-order all-pass filters of one channel
+#[derive(Clone, Copy, Default)]
+struct AllpassChain {
+    states: [f64; STAGES],
+    last: f64, // sortie précédente, pour le feedback
+}
+
+impl AllpassChain {
+    #[inline]
+    fn process(&mut self, input: f64, coefficient: f64, feedback: f64) -> f64 {
+        let mut x = input + self.last * feedback;
+        for state in self.states.iter_mut() {
+            let y = coefficient * x + *state;
+            *state = x - coefficient * y;
+            x = y;
+        }
+        self.last = x;
+        x
+    }
+}
+
+/// Phaser: the signal goes through a chain of all-pass filters whose frequency is swept,
+/// and their phase shift mixed with the dry signal digs moving notches.
+#[derive(Clone)]
+pub struct Phaser {
+    sample_rate: f64,
+    settings: ModEffectSettings,
+    sweep: Sweep,
+    chains: [AllpassChain; 2],
+}
+
+impl Phaser {
+    pub fn new(sample_rate: f64, settings: ModEffectSettings) -> Self {
+        Self {
+            sample_rate,
+            settings: settings.clamped(),
+            sweep: Sweep::default(),
+            chains: [AllpassChain::default(); 2],
+        }
+    }
+
+    /// All-pass coefficient at the current point of the sweep
+    #[inline]
+    fn coefficient(&self, offset: f64) -> f64 {
+        let octaves = SWEEP_OCTAVES * self.settings.depth * self.sweep.unipolar(offset);
+        let hz = (MIN_HZ * 2.0_f64.powf(octaves)).min(self.sample_rate * 0.45);
+        let t = (PI * hz / self.sample_rate).tan();
+        (t - 1.0) / (t + 1.0)
+    }
+
+    #[inline]
+    fn phase_frame(&mut self, frame: StereoFrame) -> StereoFrame {
+        let mut output = frame;
+        for (channel, offset) in [0.0, RIGHT_OFFSET].into_iter().enumerate() {
+            let coefficient = self.coefficient(offset);
+            let wet =
+                self.chains[channel].process(frame[channel], coefficient, self.settings.feedback);
+            output[channel] = self.settings.blend(frame[channel], wet);
+        }
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        output
+    }
+
+    // #### Setters ####
+    pub fn set_settings(&mut self, settings: ModEffectSettings) {
+        self.settings = settings.clamped();
+    }
+
+    // #### Getters ####
+    pub fn get_settings(&self) -> ModEffectSettings {
+        self.settings
+    }
+}
+
+impl Module for Phaser {
+    fn process(&mut self, input: f64, _time: f64) -> f64 {
+        let coefficient = self.coefficient(0.0);
+        let wet = self.chains[0].process(input, coefficient, self.settings.feedback);
+        self.sweep.advance(self.settings.rate, self.sample_rate);
+        self.settings.blend(input, wet)
+    }
+
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        self.phase_frame(frame)
+    }
+
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for frame in block.iter_mut() {
+            *frame = self.phase_frame(*frame);
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        *self = Phaser::new(sample_rate, self.settings);
+    }
+
+    fn name(&self) -> &'static str {
+        "Phaser"
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,6 +1,9 @@
-use crate::synths::traits::Module;
+use crate::consts::constants::MAX_BLOCK_SIZE;
+use crate::synths::modulation::{ModAmounts, ModDestination};
+use crate::synths::traits::{Module, StereoFrame};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 /// Types of reverb
 pub enum ReverbType {
     Hall,
@@ -44,6 +47,25 @@ impl DampedComb {
         }
         y
     }
+
+    /// Adds the output of the comb for a whole block to `output`, its state kept in registers
+    fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        let (feedback, damping) = (self.feedback, self.damping);
+        let mut lowpass_state = self.lowpass_state;
+        let mut idx = self.idx;
+        for (&x, out) in input.iter().zip(output.iter_mut()) {
+            let y = self.buf[idx];
+            lowpass_state = (1.0 - damping) * y + damping * lowpass_state;
+            self.buf[idx] = lowpass_state * feedback + x;
+            idx += 1;
+            if idx >= self.buf.len() {
+                idx = 0;
+            }
+            *out += y;
+        }
+        self.lowpass_state = lowpass_state;
+        self.idx = idx;
+    }
 }
 
 /// All-pass diffuser pour lisser/épaissir la queue
@@ -75,6 +97,86 @@ impl Allpass {
         }
         y
     }
+
+    /// Filters a whole block in place
+    fn process_block(&mut self, block: &mut [f64]) {
+        let feedback = self.feedback;
+        let mut idx = self.idx;
+        for sample in block.iter_mut() {
+            let buf_out = self.buf[idx];
+            self.buf[idx] = *sample + buf_out * feedback;
+            *sample = buf_out - *sample;
+            idx += 1;
+            if idx >= self.buf.len() {
+                idx = 0;
+            }
+        }
+        self.idx = idx;
+    }
+}
+
+/// Décalage des lignes du canal droit (23 échantillons à 44,1 kHz, comme Freeverb),
+/// assez pour décorréler les deux queues sans changer leur couleur
+const STEREO_SPREAD_MS: f64 = 0.52;
+
+/// Queue algorithmique d'un canal : combs en // puis allpass en série
+#[derive(Clone, Default)]
+struct Tail {
+    combs: Vec<DampedComb>,
+    allpasses: Vec<Allpass>,
+    comb_gain: f64, // normalisation simple par nb de combs
+}
+
+impl Tail {
+    fn new(
+        comb_ms: &[f64],
+        ap_ms: &[f64],
+        offset_ms: f64,
+        feedback: f64,
+        damping: f64,
+        sample_rate: f64,
+    ) -> Self {
+        let len = |ms: f64| (((ms + offset_ms) / 1000.0) * sample_rate).round() as usize;
+        Self {
+            comb_gain: 1.0 / comb_ms.len().max(1) as f64,
+            combs: comb_ms
+                .iter()
+                .map(|&ms| DampedComb::new(len(ms).max(1), feedback, damping))
+                .collect(),
+            allpasses: ap_ms
+                .iter()
+                .map(|&ms| Allpass::new(len(ms).max(1), 0.7))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let mut sum = 0.0;
+        for c in &mut self.combs {
+            sum += c.process(x);
+        }
+
+        let mut y = sum * self.comb_gain;
+        for ap in &mut self.allpasses {
+            y = ap.process(y);
+        }
+        y
+    }
+
+    /// Runs each line over the whole block in turn, rather than every line for each sample
+    fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        output.fill(0.0);
+        for c in &mut self.combs {
+            c.process_block(input, output);
+        }
+        for sample in output.iter_mut() {
+            *sample *= self.comb_gain;
+        }
+        for ap in &mut self.allpasses {
+            ap.process_block(output);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -83,6 +185,7 @@ pub struct Reverb {
 
     // Mix & paramètres globaux
     dry_wet: f64,      // 0 = dry, 1 = wet
+    matrix_mix: f64,   // ajouté au dry/wet par la matrice de modulation
     early_gain: f64,   // gain des early reflections (convolution)
     tail_gain: f64,    // gain de la queue (algo)
     pre_delay_ms: f64, // pré-délai avant early+tail
@@ -97,8 +200,11 @@ pub struct Reverb {
     predelay_buf: Vec<f64>,
     predelay_pos: usize,
 
-    combs: Vec<DampedComb>,  // combs en parallèle
-    allpasses: Vec<Allpass>, // allpass en série
+    // entrée et sortie des queues pour le bloc en cours, allouées une fois pour MAX_BLOCK_SIZE
+    block_input: Vec<f64>,
+    block_output: Vec<f64>,
+
+    tails: [Tail; 2], // gauche puis droite, le mono n'utilise que la gauche
 }
 
 impl Reverb {
@@ -113,6 +219,7 @@ impl Reverb {
         let mut reverb = Self {
             reverb_type,
             dry_wet, // mix de base
+            matrix_mix: 0.0,
             early_gain,
             tail_gain,
             pre_delay_ms, // pré-délai léger par défaut
@@ -125,8 +232,10 @@ impl Reverb {
             predelay_buf: vec![0.0; 1],
             predelay_pos: 0,
 
-            combs: Vec::new(),
-            allpasses: Vec::new(),
+            block_input: vec![0.0; MAX_BLOCK_SIZE],
+            block_output: vec![0.0; MAX_BLOCK_SIZE],
+
+            tails: Default::default(),
         };
         reverb.configure_by_type(reverb_type);
         reverb.rebuild_predelay();
@@ -195,19 +304,17 @@ impl Reverb {
         self.tail_gain = tail_gain;
         self.pre_delay_ms = pre_delay;
 
-        // Construire combs
-        self.combs.clear();
-        for ms in comb_ms {
-            let len = ((ms / 1000.0) * self.sample_rate).round() as usize;
-            self.combs
-                .push(DampedComb::new(len.max(1), feedback, damping));
-        }
-        // Construire allpasses
-        self.allpasses.clear();
-        for ms in ap_ms {
-            let len = ((ms / 1000.0) * self.sample_rate).round() as usize;
-            self.allpasses.push(Allpass::new(len.max(1), 0.7));
-        }
+        // Construire les deux queues, la droite légèrement plus longue
+        self.tails = [0.0, STEREO_SPREAD_MS].map(|offset| {
+            Tail::new(
+                &comb_ms,
+                &ap_ms,
+                offset,
+                feedback,
+                damping,
+                self.sample_rate,
+            )
+        });
     }
 
     fn rebuild_predelay(&mut self) {
@@ -256,25 +363,6 @@ impl Reverb {
         y
     }
 
-    /// Trajet algorithmique (queue) : combs en // puis allpass en série
-    #[inline]
-    fn algo_tail(&mut self, x: f64) -> f64 {
-        let mut sum = 0.0;
-        for c in &mut self.combs {
-            sum += c.process(x);
-        }
-        // normalisation simple par nb de combs
-        if !self.combs.is_empty() {
-            sum /= self.combs.len() as f64;
-        }
-
-        let mut y = sum;
-        for ap in &mut self.allpasses {
-            y = ap.process(y);
-        }
-        y
-    }
-
     /// #### Setters ####
     /// Charge/remplace l’IR (mono). Idéalement courte (early reflections).
     pub fn set_ir(&mut self, ir: Vec<f64>) {
@@ -292,13 +380,21 @@ impl Reverb {
     pub fn set_dry_wet(&mut self, v: f64) {
         self.dry_wet = v.clamp(0.0, 1.0);
     }
+    pub fn set_early_gain(&mut self, gain: f64) {
+        self.early_gain = gain;
+    }
+
+    pub fn set_tail_gain(&mut self, gain: f64) {
+        self.tail_gain = gain;
+    }
+
     pub fn set_pre_delay_ms(&mut self, ms: f64) {
         self.pre_delay_ms = ms.max(0.0);
         self.rebuild_predelay();
     }
 
     pub fn set_tail_damping(&mut self, d: f64) {
-        for c in &mut self.combs {
+        for c in self.tails.iter_mut().flat_map(|tail| tail.combs.iter_mut()) {
             c.damping = d.clamp(0.0, 1.0);
         }
     }
@@ -307,6 +403,27 @@ impl Reverb {
     pub fn reverb_type(&self) -> ReverbType {
         self.reverb_type
     }
+
+    pub fn get_dry_wet(&self) -> f64 {
+        self.dry_wet
+    }
+
+    /// Dry/wet with the modulation of the matrix
+    fn mix(&self) -> f64 {
+        (self.dry_wet + self.matrix_mix).clamp(0.0, 1.0)
+    }
+
+    pub fn get_early_gain(&self) -> f64 {
+        self.early_gain
+    }
+
+    pub fn get_tail_gain(&self) -> f64 {
+        self.tail_gain
+    }
+
+    pub fn get_pre_delay_ms(&self) -> f64 {
+        self.pre_delay_ms
+    }
 }
 
 impl Module for Reverb {
@@ -319,13 +436,74 @@ impl Module for Reverb {
         let early = self.convolve_early(delayed) * self.early_gain;
 
         // Queue algorithmique
-        let tail = self.algo_tail(delayed) * self.tail_gain;
+        let tail = self.tails[0].process(delayed) * self.tail_gain;
 
         // Somme des composantes de réverbe
         let wet = early + tail;
 
         // Mix dry/wet
-        (1.0 - self.dry_wet) * input + self.dry_wet * wet
+        let mix = self.mix();
+        (1.0 - mix) * input + mix * wet
+    }
+
+    /// The mixed input feeds two decorrelated tails, which gives the reverb its width
+    fn process_frame(&mut self, frame: StereoFrame, _time: f64) -> StereoFrame {
+        let delayed = self.predelay((frame[0] + frame[1]) * 0.5);
+        let early = self.convolve_early(delayed) * self.early_gain;
+
+        let mix = self.mix();
+        let mut output = frame;
+        for (channel, tail) in self.tails.iter_mut().enumerate() {
+            let wet = early + tail.process(delayed) * self.tail_gain;
+            output[channel] = (1.0 - mix) * frame[channel] + mix * wet;
+        }
+        output
+    }
+
+    /// Pre-delay and early reflections first, then each line of the tails runs through the
+    /// whole block, its state staying in registers
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        let mix = self.mix();
+        let tail_gain = mix * self.tail_gain;
+        let early_gain = mix * self.early_gain;
+        for block in block.chunks_mut(MAX_BLOCK_SIZE) {
+            let len = block.len();
+            let mut input = std::mem::take(&mut self.block_input);
+            let mut output = std::mem::take(&mut self.block_output);
+            for (frame, delayed) in block.iter_mut().zip(input.iter_mut()) {
+                *delayed = self.predelay((frame[0] + frame[1]) * 0.5);
+                let early = self.convolve_early(*delayed) * early_gain;
+                *frame = frame.map(|sample| (1.0 - mix) * sample + early);
+            }
+            for (channel, tail) in self.tails.iter_mut().enumerate() {
+                tail.process_block(&input[..len], &mut output[..len]);
+                for (frame, &wet) in block.iter_mut().zip(output.iter()) {
+                    frame[channel] += wet * tail_gain;
+                }
+            }
+            self.block_input = input;
+            self.block_output = output;
+        }
+    }
+
+    fn modulate(&mut self, amounts: &ModAmounts) {
+        self.matrix_mix = amounts.get(ModDestination::ReverbMix);
+    }
+
+    /// Lines rebuilt for the new rate, the mix, gains, pre-delay and damping are kept
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        let (dry_wet, early_gain, tail_gain) = (self.dry_wet, self.early_gain, self.tail_gain);
+        let pre_delay_ms = self.pre_delay_ms;
+        let damping = self.tails[0].combs.first().map(|comb| comb.damping);
+        self.sample_rate = sample_rate;
+        self.configure_by_type(self.reverb_type);
+        self.dry_wet = dry_wet;
+        self.early_gain = early_gain;
+        self.tail_gain = tail_gain;
+        if let Some(damping) = damping {
+            self.set_tail_damping(damping);
+        }
+        self.set_pre_delay_ms(pre_delay_ms);
     }
 
     fn name(&self) -> &'static str {
@@ -344,3 +522,65 @@ impl Module for Reverb {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_processing_matches_frame_processing() {
+        let mut by_frame = Reverb::new(48000.0, ReverbType::Hall, 0.3, 0.9, 0.95, 20.0);
+        by_frame.set_ir(vec![0.5, 0.0, 0.25, 0.0, 0.125]);
+        let mut by_block = by_frame.clone();
+        // Un clic puis du silence : la queue dure bien plus qu'un bloc
+        let input: Vec<StereoFrame> = (0..8192)
+            .map(|n| if n < 64 { [1.0, -0.5] } else { [0.0, 0.0] })
+            .collect();
+
+        let expected: Vec<StereoFrame> = input
+            .iter()
+            .map(|&frame| by_frame.process_frame(frame, 0.0))
+            .collect();
+        let mut output = input.clone();
+        for block in output.chunks_mut(MAX_BLOCK_SIZE + 100) {
+            by_block.process_block(block);
+        }
+        for (got, want) in output.iter().flatten().zip(expected.iter().flatten()) {
+            assert!((got - want).abs() < 1e-12, "{got} against {want}");
+        }
+        assert!(
+            output[4000..]
+                .iter()
+                .flatten()
+                .any(|sample| sample.abs() > 1e-4)
+        );
+    }
+
+    #[test]
+    fn sample_rate_change_keeps_the_settings() {
+        let mut reverb = Reverb::new(48000.0, ReverbType::Plate, 0.3, 0.9, 0.95, 20.0);
+        reverb.set_dry_wet(0.8);
+        reverb.set_early_gain(0.4);
+        reverb.set_tail_gain(0.6);
+        reverb.set_pre_delay_ms(33.0);
+        reverb.set_tail_damping(0.7);
+        Module::set_sample_rate(&mut reverb, 96000.0);
+
+        assert_eq!(reverb.reverb_type(), ReverbType::Plate);
+        assert_eq!(reverb.get_dry_wet(), 0.8);
+        assert_eq!(reverb.get_early_gain(), 0.4);
+        assert_eq!(reverb.get_tail_gain(), 0.6);
+        assert_eq!(reverb.get_pre_delay_ms(), 33.0);
+        assert_eq!(reverb.predelay_buf.len(), 3168);
+        for comb in reverb.tails.iter().flat_map(|tail| &tail.combs) {
+            assert_eq!(comb.damping, 0.7);
+        }
+        // Les lignes durent le même temps, en deux fois plus d'échantillons
+        let fresh = Reverb::new(96000.0, ReverbType::Plate, 0.3, 0.9, 0.95, 20.0);
+        for (got, want) in reverb.tails.iter().zip(&fresh.tails) {
+            for (comb, fresh_comb) in got.combs.iter().zip(&want.combs) {
+                assert_eq!(comb.buf.len(), fresh_comb.buf.len());
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use crate::synths::modulation::{ModAmounts, ModDestination};
 use crate::synths::traits::Oscillator;
 
 #[derive(Clone, Copy)]
 pub struct FmOscillator {
-    mod_index: f64, // Intensité modulation
-    mod_ratio: f64, // Ratio fréquence modulateur/porteur
+    mod_index: f64,    // Intensité modulation
+    mod_ratio: f64,    // Ratio fréquence modulateur/porteur
+    index_offset: f64, // ajouté à l'indice par la matrice de modulation
 }
 
 impl FmOscillator {
@@ -11,6 +13,7 @@ impl FmOscillator {
         Self {
             mod_index,
             mod_ratio,
+            index_offset: 0.0,
         }
     }
 
@@ -37,7 +40,7 @@ impl Oscillator for FmOscillator {
     fn sample(&self, phase: f64) -> f64 {
         // Module phase du carrier (pas l'amplitude)
         let modulator_phase = phase * self.mod_ratio;
-        let modulation = (modulator_phase).sin() * self.mod_index;
+        let modulation = (modulator_phase).sin() * (self.mod_index + self.index_offset).max(0.0);
 
         // La phase modulée du carrier
         let modulated_phase = phase + modulation;
@@ -57,6 +60,10 @@ impl Oscillator for FmOscillator {
         }
     }
 
+    fn modulate(&mut self, amounts: &ModAmounts) {
+        self.index_offset = amounts.get(ModDestination::FmIndex);
+    }
+
     fn name(&self) -> &'static str {
         "FM"
     }
@@ -1,30 +1,182 @@
+use crate::consts::constants;
 use crate::synths::traits::Oscillator;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 
+/// Frequency ratio of each drawbar to the played note: 16', 5 1/3', 8', 4', 2 2/3', 2', 1 3/5', 1 1/3', 1'
+pub const DRAWBAR_RATIOS: [f64; 9] = [0.5, 1.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0];
+pub const DRAWBAR_NAMES: [&str; 9] = [
+    "16'", "5 1/3'", "8'", "4'", "2 2/3'", "2'", "1 3/5'", "1 1/3'", "1'",
+];
+pub const MAX_DRAWBAR: u8 = 8;
+
+const PERCUSSION_LEVEL: f64 = 0.6;
+const KEY_CLICK_TIME: f64 = 0.003; // secondes
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PercussionHarmonic {
+    Second, // 4'
+    Third,  // 2 2/3'
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PercussionDecay {
+    Fast,
+    Slow,
+}
+
+impl PercussionDecay {
+    /// Time constant of the exponential decay, in seconds
+    pub fn time(&self) -> f64 {
+        match self {
+            PercussionDecay::Fast => 0.2,
+            PercussionDecay::Slow => 1.0,
+        }
+    }
+}
+
+/// Drawbar positions and percussion switches of the organ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HammondRegistration {
+    pub drawbars: [u8; 9], // 0..8, de 16' à 1'
+    pub percussion: bool,
+    pub percussion_harmonic: PercussionHarmonic,
+    pub percussion_decay: PercussionDecay,
+    pub key_click: f64, // 0..1
+}
+
+impl Default for HammondRegistration {
+    fn default() -> Self {
+        Self {
+            drawbars: constants::HAMMOND_DRAWBARS,
+            percussion: constants::HAMMOND_PERCUSSION,
+            percussion_harmonic: PercussionHarmonic::Third,
+            percussion_decay: PercussionDecay::Fast,
+            key_click: constants::HAMMOND_KEY_CLICK,
+        }
+    }
+}
+
+/// Tonewheel organ: one sine per drawbar, plus percussion and key click.
+/// Each voice owns a copy, so the percussion and the click start with the note.
 #[derive(Clone, Copy)]
-pub struct HammondOscillator;
+pub struct HammondOscillator {
+    registration: HammondRegistration,
+    sample_rate: f64,
+    phase: f64, // propre phase sur deux périodes, pour que le 16' et le 5 1/3' restent continus
+    percussion_level: f64,
+    click_level: f64,
+    click_seed: u32,
+}
+
+impl Default for HammondOscillator {
+    fn default() -> Self {
+        Self::new(HammondRegistration::default(), constants::SAMPLE_RATE)
+    }
+}
+
+impl HammondOscillator {
+    pub fn new(registration: HammondRegistration, sample_rate: f64) -> Self {
+        Self {
+            registration,
+            sample_rate,
+            phase: 0.0,
+            percussion_level: 1.0,
+            click_level: 1.0,
+            click_seed: 0x1234_5678,
+        }
+    }
+
+    /// Amplitude of a drawbar position, each step is about 3 dB
+    fn drawbar_gain(level: u8) -> f64 {
+        match level.min(MAX_DRAWBAR) {
+            0 => 0.0,
+            level => 10.0_f64.powf((level as f64 - 8.0) * 3.0 / 20.0),
+        }
+    }
+
+    fn drawbar_sum(&self, phase: f64) -> f64 {
+        let sample: f64 = DRAWBAR_RATIOS
+            .iter()
+            .zip(self.registration.drawbars)
+            .map(|(ratio, level)| (phase * ratio).sin() * Self::drawbar_gain(level))
+            .sum();
+        sample * 0.3 // Evite la saturation
+    }
+
+    fn next_noise(&mut self) -> f64 {
+        // Générateur congruentiel, suffisant pour un clic
+        self.click_seed = self
+            .click_seed
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        (self.click_seed >> 8) as f64 / (1u32 << 23) as f64 - 1.0
+    }
+
+    // #### Setters ####
+    pub fn set_registration(&mut self, registration: HammondRegistration) {
+        self.registration = registration;
+    }
+
+    pub fn set_drawbar(&mut self, index: usize, level: u8) {
+        if let Some(drawbar) = self.registration.drawbars.get_mut(index) {
+            *drawbar = level.min(MAX_DRAWBAR);
+        }
+    }
+
+    pub fn set_percussion(&mut self, percussion: bool) {
+        self.registration.percussion = percussion;
+    }
+
+    pub fn set_percussion_harmonic(&mut self, harmonic: PercussionHarmonic) {
+        self.registration.percussion_harmonic = harmonic;
+    }
+
+    pub fn set_percussion_decay(&mut self, decay: PercussionDecay) {
+        self.registration.percussion_decay = decay;
+    }
+
+    pub fn set_key_click(&mut self, key_click: f64) {
+        self.registration.key_click = key_click.clamp(0.0, 1.0);
+    }
+
+    // #### Getters ####
+    pub fn get_registration(&self) -> HammondRegistration {
+        self.registration
+    }
+}
 
 impl Oscillator for HammondOscillator {
+    /// Drawbars only, for a phase within one period of the note
     fn sample(&self, phase: f64) -> f64 {
-        let mut sample = 0.0;
-        // 16' - Sub-harmonic (octave plus bas)
-        sample += (phase * 0.5).sin() * 0.6;
-        // 5 1/3' - 3ème harmonique
-        sample += (phase * 3.0).sin() * 0.4;
-        // 8' - Fondamentale
-        sample += phase.sin() * 0.8;
-        // 4' - 2ème harmonique (octave plus haut)
-        sample += (phase * 2.0).sin() * 0.5;
-        // 2 2/3' - 3ème harmonique octave
-        sample += (phase * 6.0).sin() * 0.3;
-        // 2' - 4ème harmonique
-        sample += (phase * 4.0).sin() * 0.25;
-        // 1 3/5' - 5ème harmonique
-        sample += (phase * 5.0).sin() * 0.2;
-        // 1 1/3' - 6ème harmonique
-        sample += (phase * 8.0).sin() * 0.15;
-        // 1' - 8ème harmonique
-        sample += (phase * 8.0).sin() * 0.1;
-        sample * 0.3 // Evite la saturation
+        self.drawbar_sum(phase)
+    }
+
+    fn next_sample(&mut self, _phase: f64, increment: f64) -> f64 {
+        let mut sample = self.drawbar_sum(self.phase);
+
+        if self.registration.percussion && self.percussion_level > 1e-4 {
+            let ratio = match self.registration.percussion_harmonic {
+                PercussionHarmonic::Second => 2.0,
+                PercussionHarmonic::Third => 3.0,
+            };
+            sample += (self.phase * ratio).sin() * self.percussion_level * PERCUSSION_LEVEL;
+            let decay_samples = self.registration.percussion_decay.time() * self.sample_rate;
+            self.percussion_level *= (-1.0 / decay_samples).exp();
+        }
+
+        if self.registration.key_click > 0.0 && self.click_level > 1e-4 {
+            sample += self.next_noise() * self.click_level * self.registration.key_click * 0.5;
+            self.click_level *= (-1.0 / (KEY_CLICK_TIME * self.sample_rate)).exp();
+        }
+
+        self.phase = (self.phase + increment) % (4.0 * PI);
+        sample
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
     }
 
     fn name(&self) -> &'static str {
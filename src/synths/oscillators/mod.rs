@@ -1,11 +1,18 @@
-pub mod sine;
-pub mod square;
-pub mod sawtooth;
 pub mod fm;
 pub mod hammond;
+pub mod multi_fm;
+pub mod noise;
+mod polyblep;
+pub mod sawtooth;
+pub mod sine;
+pub mod square;
+pub mod wavetable;
 
-pub use sine::SineOscillator;
-pub use square::SquareOscillator;
-pub use sawtooth::SawtoothOscillator;
 pub use fm::FmOscillator;
 pub use hammond::HammondOscillator;
+pub use multi_fm::MultiFmOscillator;
+pub use noise::NoiseOscillator;
+pub use sawtooth::SawtoothOscillator;
+pub use sine::SineOscillator;
+pub use square::SquareOscillator;
+pub use wavetable::WavetableOscillator;
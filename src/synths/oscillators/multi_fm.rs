@@ -0,0 +1,282 @@
+use crate::consts::constants;
+use crate::synths::modulation::{ModAmounts, ModDestination};
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::traits::Oscillator;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+pub const OPERATOR_COUNT: usize = 4;
+pub const ALGORITHM_COUNT: u8 = 8;
+
+const MAX_MODULATION_INDEX: f64 = 5.0; // indice de modulation d'un modulateur au niveau 1
+const MAX_FEEDBACK: f64 = PI;
+
+/// Routing of the four operators (index 0 = operator 1, the bottom of the stack).
+/// `modulators[i]` has bit `j` set when operator `j + 1` modulates operator `i + 1`;
+/// only higher operators modulate lower ones, so they are computed from 4 down to 1.
+struct Algorithm {
+    modulators: [u8; OPERATOR_COUNT],
+    carriers: u8,
+}
+
+/// The eight classic 4-operator algorithms
+const ALGORITHMS: [Algorithm; ALGORITHM_COUNT as usize] = [
+    // 1 : 4 -> 3 -> 2 -> 1
+    Algorithm {
+        modulators: [0b0010, 0b0100, 0b1000, 0],
+        carriers: 0b0001,
+    },
+    // 2 : (3 + 4) -> 2 -> 1
+    Algorithm {
+        modulators: [0b0010, 0b1100, 0, 0],
+        carriers: 0b0001,
+    },
+    // 3 : (4 + (3 -> 2)) -> 1
+    Algorithm {
+        modulators: [0b1010, 0b0100, 0, 0],
+        carriers: 0b0001,
+    },
+    // 4 : (2 + (4 -> 3)) -> 1
+    Algorithm {
+        modulators: [0b0110, 0, 0b1000, 0],
+        carriers: 0b0001,
+    },
+    // 5 : 2 -> 1, 4 -> 3
+    Algorithm {
+        modulators: [0b0010, 0, 0b1000, 0],
+        carriers: 0b0101,
+    },
+    // 6 : 4 -> (1, 2, 3)
+    Algorithm {
+        modulators: [0b1000, 0b1000, 0b1000, 0],
+        carriers: 0b0111,
+    },
+    // 7 : 4 -> 3, 1 et 2 seuls
+    Algorithm {
+        modulators: [0, 0, 0b1000, 0],
+        carriers: 0b0111,
+    },
+    // 8 : quatre porteuses
+    Algorithm {
+        modulators: [0, 0, 0, 0],
+        carriers: 0b1111,
+    },
+];
+
+/// Settings of one operator
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FmOperatorSettings {
+    pub ratio: f64,    // fréquence relative à la note
+    pub detune: f64,   // en cents
+    pub level: f64,    // 0..1, volume pour une porteuse, indice de modulation sinon
+    pub feedback: f64, // 0..1, auto-modulation
+    pub attack: f64,
+    pub decay: f64,
+    pub sustain: f64,
+    pub release: f64,
+}
+
+impl Default for FmOperatorSettings {
+    fn default() -> Self {
+        Self {
+            ratio: 1.0,
+            detune: 0.0,
+            level: 1.0,
+            feedback: 0.0,
+            attack: 0.01,
+            decay: 0.3,
+            sustain: 0.8,
+            release: 0.5,
+        }
+    }
+}
+
+/// Algorithm and operators of a multi-operator FM patch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FmPatch {
+    pub algorithm: u8, // 1..=ALGORITHM_COUNT
+    pub operators: [FmOperatorSettings; OPERATOR_COUNT],
+}
+
+impl Default for FmPatch {
+    fn default() -> Self {
+        // Algorithme 1 : une pile 4 -> 3 -> 2 -> 1, modulateurs qui décroissent (son de piano électrique)
+        let modulator = |ratio: f64, level: f64, decay: f64| FmOperatorSettings {
+            ratio,
+            level,
+            decay,
+            sustain: 0.2,
+            ..FmOperatorSettings::default()
+        };
+        Self {
+            algorithm: 1,
+            operators: [
+                FmOperatorSettings::default(),
+                modulator(1.0, 0.5, 1.0),
+                modulator(14.0, 0.15, 0.2),
+                FmOperatorSettings {
+                    feedback: 0.3,
+                    ..modulator(1.0, 0.2, 0.5)
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Operator {
+    envelope: ADSR,
+    phase: f64,
+    outputs: [f64; 2], // deux dernières sorties, pour le feedback
+}
+
+/// FM engine with four operators, each with its own envelope.
+/// Each voice owns a copy, so the operator envelopes start with the note.
+#[derive(Clone, Copy)]
+pub struct MultiFmOscillator {
+    patch: FmPatch,
+    operators: [Operator; OPERATOR_COUNT],
+    index_offset: f64, // ajouté à l'indice des modulateurs par la matrice
+}
+
+impl Default for MultiFmOscillator {
+    fn default() -> Self {
+        Self::new(FmPatch::default(), constants::SAMPLE_RATE)
+    }
+}
+
+impl MultiFmOscillator {
+    pub fn new(patch: FmPatch, sample_rate: f64) -> Self {
+        let operators = patch.operators.map(|settings| {
+            let mut envelope = ADSR::new(
+                sample_rate,
+                settings.attack,
+                settings.decay,
+                settings.sustain,
+                settings.release,
+            );
+            envelope.note_on();
+            Operator {
+                envelope,
+                phase: 0.0,
+                outputs: [0.0; 2],
+            }
+        });
+        let mut oscillator = Self {
+            patch,
+            operators,
+            index_offset: 0.0,
+        };
+        oscillator.set_algorithm(patch.algorithm);
+        oscillator
+    }
+
+    fn algorithm(&self) -> &'static Algorithm {
+        &ALGORITHMS[(self.patch.algorithm - 1) as usize]
+    }
+
+    // #### Setters ####
+    pub fn set_patch(&mut self, patch: FmPatch) {
+        self.set_algorithm(patch.algorithm);
+        for (index, settings) in patch.operators.into_iter().enumerate() {
+            self.set_operator(index, settings);
+        }
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: u8) {
+        self.patch.algorithm = algorithm.clamp(1, ALGORITHM_COUNT);
+    }
+
+    /// Changes an operator, its envelope keeps its current stage and level
+    pub fn set_operator(&mut self, index: usize, settings: FmOperatorSettings) {
+        if index >= OPERATOR_COUNT {
+            return;
+        }
+        self.patch.operators[index] = settings;
+        let envelope = &mut self.operators[index].envelope;
+        envelope.set_attack(settings.attack);
+        envelope.set_decay(settings.decay);
+        envelope.set_sustain(settings.sustain);
+        envelope.set_release(settings.release);
+    }
+
+    // #### Getters ####
+    pub fn get_patch(&self) -> FmPatch {
+        self.patch
+    }
+}
+
+impl Oscillator for MultiFmOscillator {
+    /// Carriers only, without modulation nor envelopes
+    fn sample(&self, phase: f64) -> f64 {
+        let carriers = self.algorithm().carriers;
+        let sum: f64 = (0..OPERATOR_COUNT)
+            .filter(|index| carriers & (1 << index) != 0)
+            .map(|index| {
+                let settings = &self.patch.operators[index];
+                (phase * settings.ratio).sin() * settings.level
+            })
+            .sum();
+        sum / carriers.count_ones() as f64
+    }
+
+    fn next_sample(&mut self, _phase: f64, increment: f64) -> f64 {
+        let algorithm = self.algorithm();
+        let modulation_index = (MAX_MODULATION_INDEX + self.index_offset).max(0.0);
+        let mut outputs = [0.0; OPERATOR_COUNT];
+        let mut sample = 0.0;
+
+        for index in (0..OPERATOR_COUNT).rev() {
+            let settings = self.patch.operators[index];
+            let operator = &mut self.operators[index];
+
+            let mut modulation = 0.0;
+            for (source, output) in outputs.iter().enumerate() {
+                if algorithm.modulators[index] & (1 << source) != 0 {
+                    modulation += output * modulation_index;
+                }
+            }
+            let feedback = (operator.outputs[0] + operator.outputs[1]) * 0.5;
+            modulation += feedback * settings.feedback * MAX_FEEDBACK;
+
+            let output = (operator.phase + modulation).sin()
+                * settings.level
+                * operator.envelope.get_amplitude();
+            operator.outputs = [output, operator.outputs[0]];
+            outputs[index] = output;
+
+            if algorithm.carriers & (1 << index) != 0 {
+                sample += output;
+            }
+
+            let ratio = settings.ratio * 2.0_f64.powf(settings.detune / 1200.0);
+            operator.phase = (operator.phase + increment * ratio) % (2.0 * PI);
+        }
+
+        sample / algorithm.carriers.count_ones() as f64
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        // Les enveloppes recalculent leurs pas à la nouvelle fréquence
+        *self = Self {
+            index_offset: self.index_offset,
+            ..Self::new(self.patch, sample_rate)
+        };
+    }
+
+    fn note_off(&mut self) {
+        for operator in self.operators.iter_mut() {
+            operator.envelope.note_off();
+        }
+    }
+
+    fn modulate(&mut self, amounts: &ModAmounts) {
+        self.index_offset = amounts.get(ModDestination::FmIndex);
+    }
+
+    fn name(&self) -> &'static str {
+        "Multi FM"
+    }
+}
@@ -0,0 +1,51 @@
+use crate::synths::modules::noise::{NoiseColor, NoiseGenerator};
+use crate::synths::traits::Oscillator;
+
+/// Colored noise as the sound source of a voice, for drums, wind or breath.
+/// The pitch of the note is ignored: the filter and its key tracking shape the sound.
+#[derive(Clone, Copy)]
+pub struct NoiseOscillator {
+    generator: NoiseGenerator,
+}
+
+impl NoiseOscillator {
+    pub fn new(color: NoiseColor) -> Self {
+        Self {
+            generator: NoiseGenerator::new(color, 0),
+        }
+    }
+
+    // #### Setters ####
+    pub fn set_color(&mut self, color: NoiseColor) {
+        self.generator.set_color(color);
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.generator.set_seed(seed);
+    }
+
+    // #### Getters ####
+    pub fn get_color(&self) -> NoiseColor {
+        self.generator.get_color()
+    }
+}
+
+impl Oscillator for NoiseOscillator {
+    /// Noise has no waveform to read at a phase, voices only call `next_sample`
+    fn sample(&self, _phase: f64) -> f64 {
+        0.0
+    }
+
+    fn next_sample(&mut self, _phase: f64, _increment: f64) -> f64 {
+        self.generator.next_sample()
+    }
+
+    /// Each voice draws its own seed, otherwise chords would play the same noise several times
+    fn note_on(&mut self) {
+        self.generator.set_seed(rand::random());
+    }
+
+    fn name(&self) -> &'static str {
+        "Noise"
+    }
+}
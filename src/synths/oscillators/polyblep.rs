@@ -0,0 +1,22 @@
+/// Polynomial correction of a unit step placed at phase 0.
+/// `t` is the normalized phase (0..1) and `dt` the phase increment per sample,
+/// subtracting it around a discontinuity removes most of the aliasing.
+pub fn poly_blep(t: f64, dt: f64) -> f64 {
+    if dt <= 0.0 {
+        return 0.0;
+    }
+    if t < dt {
+        let t = t / dt;
+        2.0 * t - t * t - 1.0
+    } else if t > 1.0 - dt {
+        let t = (t - 1.0) / dt;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Phase in radians to a normalized phase in 0..1
+pub fn normalize_phase(phase: f64) -> f64 {
+    (phase / (2.0 * std::f64::consts::PI)).rem_euclid(1.0)
+}
@@ -1,12 +1,39 @@
+use crate::synths::oscillators::polyblep::{normalize_phase, poly_blep};
 use crate::synths::traits::Oscillator;
 
 #[derive(Clone, Copy)]
-pub struct SawtoothOscillator;
+pub struct SawtoothOscillator {
+    band_limited: bool, // PolyBLEP autour de la discontinuité
+}
+
+impl SawtoothOscillator {
+    pub fn new(band_limited: bool) -> Self {
+        Self { band_limited }
+    }
+
+    // #### Setters ####
+    pub fn set_band_limited(&mut self, band_limited: bool) {
+        self.band_limited = band_limited;
+    }
+
+    // #### Getters ####
+    pub fn is_band_limited(&self) -> bool {
+        self.band_limited
+    }
+}
 
 impl Oscillator for SawtoothOscillator {
     fn sample(&self, phase: f64) -> f64 {
-        let normalized_phase = (phase % (2.0 * std::f64::consts::PI)) / (2.0 * std::f64::consts::PI);
-        2.0 * normalized_phase - 1.0
+        2.0 * normalize_phase(phase) - 1.0
+    }
+
+    fn next_sample(&mut self, phase: f64, increment: f64) -> f64 {
+        if !self.band_limited {
+            return self.sample(phase);
+        }
+        let t = normalize_phase(phase);
+        let dt = increment / (2.0 * std::f64::consts::PI);
+        2.0 * t - 1.0 - poly_blep(t, dt)
     }
 
     fn name(&self) -> &'static str {
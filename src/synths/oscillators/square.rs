@@ -1,15 +1,44 @@
+use crate::synths::oscillators::polyblep::{normalize_phase, poly_blep};
 use crate::synths::traits::Oscillator;
 
 #[derive(Clone, Copy)]
-pub struct SquareOscillator;
+pub struct SquareOscillator {
+    band_limited: bool, // PolyBLEP autour des deux discontinuités
+}
+
+impl SquareOscillator {
+    pub fn new(band_limited: bool) -> Self {
+        Self { band_limited }
+    }
+
+    // #### Setters ####
+    pub fn set_band_limited(&mut self, band_limited: bool) {
+        self.band_limited = band_limited;
+    }
+
+    // #### Getters ####
+    pub fn is_band_limited(&self) -> bool {
+        self.band_limited
+    }
+}
 
 impl Oscillator for SquareOscillator {
     fn sample(&self, phase: f64) -> f64 {
-        if phase % (2.0 * std::f64::consts::PI) < std::f64::consts::PI { 
-            1.0 
-        } else { 
-            -1.0 
+        if normalize_phase(phase) < 0.5 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    fn next_sample(&mut self, phase: f64, increment: f64) -> f64 {
+        if !self.band_limited {
+            return self.sample(phase);
         }
+        let t = normalize_phase(phase);
+        let dt = increment / (2.0 * std::f64::consts::PI);
+        // Montée à 0, descente à 0.5
+        self.sample(phase) + poly_blep(t, dt) - poly_blep((t + 0.5).rem_euclid(1.0), dt)
     }
 
     fn name(&self) -> &'static str {
@@ -0,0 +1,372 @@
+use crate::consts::constants;
+use crate::synths::traits::Oscillator;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+pub const FRAME_SIZE: usize = 2048; // échantillons par cycle
+pub const MAX_FRAMES: usize = 256;
+const MIP_LEVELS: usize = 11; // le niveau k garde les harmoniques jusqu'à (FRAME_SIZE / 2) >> k
+const BUILTIN_FRAMES: usize = 32;
+
+/// Where the frames of a wavetable come from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WavetableSource {
+    Basic,     // sinus -> triangle -> dent de scie -> carré
+    Pulse,     // rapport cyclique de 50 % à 5 %
+    Harmonics, // ajoute une harmonique à chaque frame
+    File(String),
+}
+
+impl WavetableSource {
+    pub const BUILTIN: [WavetableSource; 3] = [
+        WavetableSource::Basic,
+        WavetableSource::Pulse,
+        WavetableSource::Harmonics,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            WavetableSource::Basic => "Basic",
+            WavetableSource::Pulse => "Pulse",
+            WavetableSource::Harmonics => "Harmonics",
+            WavetableSource::File(path) => Path::new(path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(path),
+        }
+    }
+}
+
+/// Frames of a wavetable, each stored once per mip-level.
+/// Tables are immutable and shared between the voices.
+pub struct Wavetable {
+    source: WavetableSource,
+    frame_count: usize,
+    data: Vec<f32>, // [frame][niveau][échantillon]
+}
+
+impl Wavetable {
+    /// Builds the mip-levels of single-cycle frames of FRAME_SIZE samples
+    fn new(source: WavetableSource, frames: &[Vec<f64>]) -> Self {
+        let frame_count = frames.len().clamp(1, MAX_FRAMES);
+        let mut data = Vec::with_capacity(frame_count * MIP_LEVELS * FRAME_SIZE);
+        for frame in frames.iter().take(frame_count) {
+            let spectrum = fft(frame.iter().map(|&x| (x, 0.0)).collect(), false);
+            for level in 0..MIP_LEVELS {
+                let max_harmonic = (FRAME_SIZE / 2) >> level;
+                let mut bins = spectrum.clone();
+                for (bin, value) in bins.iter_mut().enumerate() {
+                    let harmonic = bin.min(FRAME_SIZE - bin);
+                    if harmonic == 0 || harmonic >= max_harmonic {
+                        *value = (0.0, 0.0); // ni continu ni harmoniques trop hautes
+                    }
+                }
+                let samples = fft(bins, true);
+                data.extend(
+                    samples
+                        .iter()
+                        .map(|&(re, _)| (re / FRAME_SIZE as f64) as f32),
+                );
+            }
+        }
+
+        // Normalisation sur le niveau complet de toutes les frames
+        let peak = (0..frame_count)
+            .flat_map(|frame| Self::slice(&data, frame, 0))
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+        if peak > 0.0 {
+            data.iter_mut().for_each(|sample| *sample /= peak);
+        }
+
+        Self {
+            source,
+            frame_count,
+            data,
+        }
+    }
+
+    fn slice(data: &[f32], frame: usize, level: usize) -> &[f32] {
+        let start = (frame * MIP_LEVELS + level) * FRAME_SIZE;
+        &data[start..start + FRAME_SIZE]
+    }
+
+    /// Returns the table of a source, built-in tables are only computed once
+    pub fn load(source: &WavetableSource) -> Result<Arc<Wavetable>, hound::Error> {
+        static BASIC: OnceLock<Arc<Wavetable>> = OnceLock::new();
+        static PULSE: OnceLock<Arc<Wavetable>> = OnceLock::new();
+        static HARMONICS: OnceLock<Arc<Wavetable>> = OnceLock::new();
+
+        let builtin = |cell: &OnceLock<Arc<Wavetable>>, shape: fn(f64, f64) -> f64| {
+            cell.get_or_init(|| {
+                let frames: Vec<Vec<f64>> = (0..BUILTIN_FRAMES)
+                    .map(|frame| {
+                        let position = frame as f64 / (BUILTIN_FRAMES - 1) as f64;
+                        (0..FRAME_SIZE)
+                            .map(|i| shape(i as f64 / FRAME_SIZE as f64, position))
+                            .collect()
+                    })
+                    .collect();
+                Arc::new(Wavetable::new(source.clone(), &frames))
+            })
+            .clone()
+        };
+
+        match source {
+            WavetableSource::Basic => Ok(builtin(&BASIC, basic_shape)),
+            WavetableSource::Pulse => Ok(builtin(&PULSE, pulse_shape)),
+            WavetableSource::Harmonics => Ok(builtin(&HARMONICS, harmonics_shape)),
+            WavetableSource::File(path) => Self::load_wav(path).map(Arc::new),
+        }
+    }
+
+    /// Reads a WAV file (first channel). A length that is a multiple of FRAME_SIZE is
+    /// split into frames, anything else is a single cycle resampled to FRAME_SIZE.
+    fn load_wav(path: &str) -> Result<Wavetable, hound::Error> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = spec.channels as usize;
+        let samples: Vec<f64> = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .samples::<f32>()
+                .step_by(channels)
+                .map(|s| s.map(f64::from))
+                .collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample - 1)) as f64;
+                reader
+                    .samples::<i32>()
+                    .step_by(channels)
+                    .map(|s| s.map(|s| s as f64 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        if samples.len() < 2 {
+            return Err(hound::Error::FormatError("wavetable is empty"));
+        }
+
+        let frames: Vec<Vec<f64>> = if samples.len().is_multiple_of(FRAME_SIZE) {
+            samples.chunks(FRAME_SIZE).map(<[f64]>::to_vec).collect()
+        } else {
+            let len = samples.len() as f64;
+            vec![
+                (0..FRAME_SIZE)
+                    .map(|i| {
+                        let read = i as f64 * len / FRAME_SIZE as f64;
+                        let index = read.floor() as usize;
+                        let next = (index + 1) % samples.len();
+                        let frac = read.fract();
+                        samples[index] * (1.0 - frac) + samples[next] * frac
+                    })
+                    .collect(),
+            ]
+        };
+        Ok(Wavetable::new(
+            WavetableSource::File(path.to_string()),
+            &frames,
+        ))
+    }
+
+    pub fn source(&self) -> &WavetableSource {
+        &self.source
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Reads the table at a phase (0..1) and a position (0..1) between the frames
+    fn read(&self, phase: f64, position: f64, level: usize) -> f64 {
+        let frame = position.clamp(0.0, 1.0) * (self.frame_count - 1) as f64;
+        let first = frame.floor() as usize;
+        let second = (first + 1).min(self.frame_count - 1);
+        let morph = frame - first as f64;
+
+        let read = phase * FRAME_SIZE as f64;
+        let index = read.floor() as usize % FRAME_SIZE;
+        let next = (index + 1) % FRAME_SIZE;
+        let frac = read.fract();
+        let sample = |frame: usize| {
+            let table = Self::slice(&self.data, frame, level);
+            table[index] as f64 * (1.0 - frac) + table[next] as f64 * frac
+        };
+
+        sample(first) * (1.0 - morph) + sample(second) * morph
+    }
+}
+
+fn basic_shape(t: f64, position: f64) -> f64 {
+    let shapes = [
+        (2.0 * PI * t).sin(),
+        1.0 - 4.0 * ((t + 0.25).fract() - 0.5).abs(),
+        2.0 * t - 1.0,
+        if t < 0.5 { 1.0 } else { -1.0 },
+    ];
+    let scaled = position * (shapes.len() - 1) as f64;
+    let index = (scaled.floor() as usize).min(shapes.len() - 2);
+    let morph = scaled - index as f64;
+    shapes[index] * (1.0 - morph) + shapes[index + 1] * morph
+}
+
+fn pulse_shape(t: f64, position: f64) -> f64 {
+    let width = 0.5 - 0.45 * position;
+    if t < width { 1.0 } else { -1.0 }
+}
+
+fn harmonics_shape(t: f64, position: f64) -> f64 {
+    let count = 1 + (position * (BUILTIN_FRAMES - 1) as f64).round() as usize;
+    (1..=count)
+        .map(|n| (2.0 * PI * n as f64 * t).sin() / n as f64)
+        .sum()
+}
+
+/// In-place radix-2 FFT on a power of two length, unscaled
+fn fft(mut values: Vec<(f64, f64)>, inverse: bool) -> Vec<(f64, f64)> {
+    let n = values.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (wr, wi) = ((angle * k as f64).cos(), (angle * k as f64).sin());
+                let (ar, ai) = values[start + k];
+                let (br, bi) = values[start + k + len / 2];
+                let (tr, ti) = (br * wr - bi * wi, br * wi + bi * wr);
+                values[start + k] = (ar + tr, ai + ti);
+                values[start + k + len / 2] = (ar - tr, ai - ti);
+            }
+        }
+        len <<= 1;
+    }
+    values
+}
+
+/// Morph position and its built-in scanning LFO
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WavetableSettings {
+    pub position: f64,   // 0..1
+    pub scan_rate: f64,  // Hz
+    pub scan_depth: f64, // 0..1, amplitude du balayage autour de la position
+}
+
+impl Default for WavetableSettings {
+    fn default() -> Self {
+        Self {
+            position: constants::WAVETABLE_POSITION,
+            scan_rate: 0.5,
+            scan_depth: 0.0,
+        }
+    }
+}
+
+/// Reads a shared wavetable, picking the mip-level that stays below Nyquist for the played note
+#[derive(Clone)]
+pub struct WavetableOscillator {
+    table: Arc<Wavetable>,
+    settings: WavetableSettings,
+    sample_rate: f64,
+    scan_phase: f64,
+}
+
+impl Default for WavetableOscillator {
+    fn default() -> Self {
+        let table = Wavetable::load(&WavetableSource::Basic).expect("built-in wavetable");
+        Self::new(table, WavetableSettings::default(), constants::SAMPLE_RATE)
+    }
+}
+
+impl WavetableOscillator {
+    pub fn new(table: Arc<Wavetable>, settings: WavetableSettings, sample_rate: f64) -> Self {
+        Self {
+            table,
+            settings,
+            sample_rate,
+            scan_phase: 0.0,
+        }
+    }
+
+    /// Mip-level whose highest harmonic stays below Nyquist, `increment` in radians per sample
+    fn mip_level(increment: f64) -> usize {
+        let allowed_harmonics = PI / increment.max(1e-9);
+        let ratio = (FRAME_SIZE / 2) as f64 / allowed_harmonics;
+        if ratio <= 1.0 {
+            0
+        } else {
+            (ratio.log2().ceil() as usize).min(MIP_LEVELS - 1)
+        }
+    }
+
+    // #### Setters ####
+    pub fn set_table(&mut self, table: Arc<Wavetable>) {
+        self.table = table;
+    }
+
+    pub fn set_settings(&mut self, settings: WavetableSettings) {
+        self.settings = settings;
+    }
+
+    pub fn set_position(&mut self, position: f64) {
+        self.settings.position = position.clamp(0.0, 1.0);
+    }
+
+    pub fn set_scan_rate(&mut self, rate: f64) {
+        self.settings.scan_rate = rate.max(0.0);
+    }
+
+    pub fn set_scan_depth(&mut self, depth: f64) {
+        self.settings.scan_depth = depth.clamp(0.0, 1.0);
+    }
+
+    // #### Getters ####
+    pub fn get_table(&self) -> &Arc<Wavetable> {
+        &self.table
+    }
+
+    pub fn get_settings(&self) -> WavetableSettings {
+        self.settings
+    }
+}
+
+impl Oscillator for WavetableOscillator {
+    /// Full bandwidth frame at the morph position, without scanning
+    fn sample(&self, phase: f64) -> f64 {
+        self.table
+            .read(phase / (2.0 * PI), self.settings.position, 0)
+    }
+
+    fn next_sample(&mut self, phase: f64, increment: f64) -> f64 {
+        let scan = (2.0 * PI * self.scan_phase).sin() * self.settings.scan_depth;
+        self.scan_phase = (self.scan_phase + self.settings.scan_rate / self.sample_rate).fract();
+
+        let phase = (phase / (2.0 * PI)).rem_euclid(1.0);
+        self.table.read(
+            phase,
+            self.settings.position + scan,
+            Self::mip_level(increment),
+        )
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn name(&self) -> &'static str {
+        "Wavetable"
+    }
+}
@@ -0,0 +1,304 @@
+use crate::audio::voice_allocator::{RetriggerMode, StealPolicy, VoiceMode, VoiceSettings};
+use crate::synths::manager::SynthType;
+use crate::synths::modulation::{ModEnvelopeSettings, ModLfoSettings, ModRoute};
+use crate::synths::modules::adsr::ADSR;
+use crate::synths::modules::compressor::CompressorSettings;
+use crate::synths::modules::delay::DelaySettings;
+use crate::synths::modules::distortion::DistortionSettings;
+use crate::synths::modules::filter::{FilterMode, FilterModulation, FilterSlope};
+use crate::synths::modules::leslie::LeslieSpeed;
+use crate::synths::modules::lfo::LfoWaveform;
+use crate::synths::modules::limiter::LimiterSettings;
+use crate::synths::modules::modulated::{ModEffectKind, ModEffectSettings};
+use crate::synths::modules::noise::NoiseColor;
+use crate::synths::modules::reverb::ReverbType;
+use crate::synths::oscillators::hammond::{PercussionDecay, PercussionHarmonic};
+use crate::synths::oscillators::multi_fm::FmOperatorSettings;
+use crate::synths::pitch::{GlideMode, PitchSettings};
+use crate::synths::unison::UnisonSettings;
+use crate::synths::velocity::VelocitySettings;
+
+/// A single parameter change, sent from the interfaces to the audio engine.
+/// The same value is applied to the control-side patch, to every playing voice
+/// and to the master bus, so they never drift apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    // ADSR
+    Attack(f64),
+    Decay(f64),
+    Sustain(f64),
+    Release(f64),
+    // VELOCITY
+    VelocityAmplitude(f64),
+    VelocityCutoff(f64),
+    VelocityEnvelope(f64),
+    KeyboardVelocity(f64),
+    // OSCILLATOR
+    BandLimited(bool),
+    // HAMMOND
+    Drawbar(usize, u8), // index (16' = 0), position 0..8
+    Percussion(bool),
+    PercussionHarmonic(PercussionHarmonic),
+    PercussionDecay(PercussionDecay),
+    KeyClick(f64),
+    // FM
+    FmModIndex(f64),
+    FmModRatio(f64),
+    FmAlgorithm(u8),                       // 1..=8
+    FmOperator(usize, FmOperatorSettings), // index (opérateur 1 = 0)
+    // NOISE OSCILLATOR
+    NoiseSourceColor(NoiseColor),
+    // WAVETABLE
+    WavetablePosition(f64),
+    WavetableScanRate(f64),
+    WavetableScanDepth(f64),
+    // PITCH
+    PitchBendRange(f64), // demi-tons
+    Vibrato(f64),        // demi-tons
+    VibratoLfo(usize),   // index du LFO de la matrice
+    GlideTime(f64),      // secondes
+    GlideMode(GlideMode),
+    // UNISON
+    UnisonVoices(usize), // notes suivantes seulement
+    UnisonDetune(f64),
+    UnisonSpread(f64),
+    // VOICES
+    MaxVoices(usize),
+    StealPolicy(StealPolicy),
+    Retrigger(RetriggerMode),
+    VoiceMode(VoiceMode),
+    // GAIN
+    Gain(f64),
+    Pan(f64),
+    GainActive(bool),
+    // NOISE
+    Noise(f64),
+    NoiseColor(NoiseColor),
+    NoiseActive(bool),
+    // MODULATION MATRIX
+    ModRoute(usize, ModRoute), // index de l'emplacement
+    ModLfo(usize, ModLfoSettings),
+    ModEnvelope(ModEnvelopeSettings),
+    // TEMPO
+    Tempo(f64), // noires par minute
+    // LFO
+    LfoFrequency(f64),
+    LfoWaveform(LfoWaveform),
+    LfoAmplitude(f64),
+    LfoActive(bool),
+    // FILTER
+    Cutoff(f64),
+    Resonance(f64),
+    FilterMode(FilterMode),
+    FilterSlope(FilterSlope),
+    FilterGain(f64),
+    FilterModulation(FilterModulation),
+    FilterActive(bool),
+    // DISTORTION
+    Distortion(DistortionSettings),
+    DistortionActive(bool),
+    // COMPRESSOR
+    Compressor(CompressorSettings),
+    CompressorActive(bool),
+    // REVERB
+    ReverbType(ReverbType),
+    ReverbActive(bool),
+    // LESLIE
+    LeslieSpeed(LeslieSpeed),
+    LeslieActive(bool),
+    // DELAY
+    Delay(DelaySettings),
+    DelayActive(bool),
+    // LIMITER
+    Limiter(LimiterSettings),
+    LimiterActive(bool),
+    // CHORUS, FLANGER, PHASER
+    ModEffect(ModEffectKind, ModEffectSettings),
+    ModEffectActive(ModEffectKind, bool),
+}
+
+impl Param {
+    /// True when applying the change to `synth` reallocates buffers: a new reverb type, or
+    /// switching on a module the patch does not have yet.
+    /// Such changes are applied on the control side and the whole patch is swapped instead.
+    pub fn requires_rebuild(&self, synth: &SynthType) -> bool {
+        matches!(self, Param::ReverbType(_))
+            || self
+                .switched_on_module()
+                .is_some_and(|name| !synth.has_module(name))
+    }
+
+    /// Module switched on by this parameter, None for the other parameters
+    fn switched_on_module(&self) -> Option<&'static str> {
+        match *self {
+            Param::GainActive(true) => Some("Gain"),
+            Param::NoiseActive(true) => Some("NoiseEffect"),
+            Param::LfoActive(true) => Some("LFO"),
+            Param::FilterActive(true) => Some("MultiModeFilter"),
+            Param::DistortionActive(true) => Some("Distortion"),
+            Param::CompressorActive(true) => Some("SimpleRMSCompressor"),
+            Param::ReverbActive(true) => Some("Reverb"),
+            Param::LeslieActive(true) => Some("Leslie"),
+            Param::DelayActive(true) => Some("Delay"),
+            Param::LimiterActive(true) => Some("Limiter"),
+            Param::ModEffectActive(kind, true) => Some(kind.name()),
+            _ => None,
+        }
+    }
+
+    /// True for the effects of the master bus. Voices have no master bus, so the
+    /// engine only applies these to the global synthesizer.
+    pub fn is_master(&self) -> bool {
+        matches!(
+            self,
+            Param::Compressor(_)
+                | Param::CompressorActive(_)
+                | Param::ReverbType(_)
+                | Param::ReverbActive(_)
+                | Param::LeslieSpeed(_)
+                | Param::LeslieActive(_)
+                | Param::Delay(_)
+                | Param::DelayActive(_)
+                | Param::Limiter(_)
+                | Param::LimiterActive(_)
+                | Param::ModEffect(..)
+                | Param::ModEffectActive(..)
+        )
+    }
+
+    /// Applies the velocity parameters, returns false for the others.
+    /// Velocity only shapes new notes, so these never reach the audio thread.
+    pub fn apply_to_velocity(&self, velocity: &mut VelocitySettings) -> bool {
+        match *self {
+            Param::VelocityAmplitude(amount) => velocity.amplitude = amount,
+            Param::VelocityCutoff(octaves) => velocity.cutoff = octaves,
+            Param::VelocityEnvelope(amount) => velocity.envelope = amount,
+            Param::KeyboardVelocity(value) => velocity.keyboard_velocity = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Applies the voice allocation parameters, returns false for the others
+    pub fn apply_to_voices(&self, voices: &mut VoiceSettings) -> bool {
+        match *self {
+            Param::MaxVoices(count) => voices.max_voices = count,
+            Param::StealPolicy(policy) => voices.steal_policy = policy,
+            Param::Retrigger(mode) => voices.retrigger = mode,
+            Param::VoiceMode(mode) => voices.mode = mode,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Applies the envelope parameters to an ADSR, ignores the others
+    pub fn apply_to_envelope(&self, adsr: &mut ADSR) {
+        match *self {
+            Param::Attack(attack) => adsr.set_attack(attack),
+            Param::Decay(decay) => adsr.set_decay(decay),
+            Param::Sustain(sustain) => adsr.set_sustain(sustain),
+            Param::Release(release) => adsr.set_release(release),
+            _ => {}
+        }
+    }
+}
+
+impl SynthType {
+    /// Applies a parameter change to the modules of this synthesizer.
+    /// Envelope, velocity and voice parameters are handled by the notes and the
+    /// voice allocator and are ignored here.
+    pub fn apply_param(&mut self, param: Param) {
+        match param {
+            Param::Attack(_) | Param::Decay(_) | Param::Sustain(_) | Param::Release(_) => {}
+            Param::VelocityAmplitude(_)
+            | Param::VelocityCutoff(_)
+            | Param::VelocityEnvelope(_)
+            | Param::KeyboardVelocity(_) => {}
+            Param::MaxVoices(_)
+            | Param::StealPolicy(_)
+            | Param::Retrigger(_)
+            | Param::VoiceMode(_) => {}
+            Param::BandLimited(band_limited) => self.set_band_limited(band_limited),
+            Param::Drawbar(index, level) => self.set_drawbar(index, level),
+            Param::Percussion(percussion) => self.set_percussion(percussion),
+            Param::PercussionHarmonic(harmonic) => self.set_percussion_harmonic(harmonic),
+            Param::PercussionDecay(decay) => self.set_percussion_decay(decay),
+            Param::KeyClick(key_click) => self.set_key_click(key_click),
+            Param::FmModIndex(mod_index) => self.set_fm_mod_index(mod_index),
+            Param::FmModRatio(mod_ratio) => self.set_fm_mod_ratio(mod_ratio),
+            Param::FmAlgorithm(algorithm) => self.set_fm_algorithm(algorithm),
+            Param::FmOperator(index, settings) => self.set_fm_operator(index, settings),
+            Param::NoiseSourceColor(color) => self.set_noise_source_color(color),
+            Param::WavetablePosition(position) => self.set_wavetable_position(position),
+            Param::WavetableScanRate(rate) => self.set_wavetable_scan_rate(rate),
+            Param::WavetableScanDepth(depth) => self.set_wavetable_scan_depth(depth),
+            Param::PitchBendRange(bend_range) => self.set_pitch(PitchSettings {
+                bend_range,
+                ..self.get_pitch()
+            }),
+            Param::Vibrato(vibrato) => self.set_pitch(PitchSettings {
+                vibrato,
+                ..self.get_pitch()
+            }),
+            Param::VibratoLfo(vibrato_lfo) => self.set_pitch(PitchSettings {
+                vibrato_lfo,
+                ..self.get_pitch()
+            }),
+            Param::GlideTime(glide) => self.set_pitch(PitchSettings {
+                glide,
+                ..self.get_pitch()
+            }),
+            Param::GlideMode(glide_mode) => self.set_pitch(PitchSettings {
+                glide_mode,
+                ..self.get_pitch()
+            }),
+            Param::UnisonVoices(voices) => self.set_unison(UnisonSettings {
+                voices,
+                ..self.get_unison()
+            }),
+            Param::UnisonDetune(detune) => self.set_unison(UnisonSettings {
+                detune,
+                ..self.get_unison()
+            }),
+            Param::UnisonSpread(spread) => self.set_unison(UnisonSettings {
+                spread,
+                ..self.get_unison()
+            }),
+            Param::Gain(gain) => self.set_current_gain(gain),
+            Param::Pan(pan) => self.set_current_pan(pan),
+            Param::GainActive(active) => self.set_gain_activation(active),
+            Param::Noise(noise) => self.set_current_noise(noise),
+            Param::NoiseColor(color) => self.set_noise_color(color),
+            Param::NoiseActive(active) => self.set_noise_activation(active),
+            Param::ModRoute(index, route) => self.set_mod_route(index, route),
+            Param::ModLfo(index, settings) => self.set_mod_lfo(index, settings),
+            Param::ModEnvelope(settings) => self.set_mod_envelope(settings),
+            Param::Tempo(bpm) => self.set_tempo(bpm),
+            Param::LfoFrequency(frequency) => self.set_current_lfo_frequency(frequency),
+            Param::LfoWaveform(waveform) => self.set_current_lfo_waveform(waveform),
+            Param::LfoAmplitude(amplitude) => self.set_current_lfo_amplitude(amplitude),
+            Param::LfoActive(active) => self.set_lfo_activation(active),
+            Param::Cutoff(cutoff) => self.set_current_cutoff(cutoff),
+            Param::Resonance(resonance) => self.set_current_resonance(resonance),
+            Param::FilterMode(mode) => self.set_filter_mode(mode),
+            Param::FilterSlope(slope) => self.set_filter_slope(slope),
+            Param::FilterGain(gain) => self.set_filter_gain(gain),
+            Param::FilterModulation(modulation) => self.set_filter_modulation(modulation),
+            Param::FilterActive(active) => self.set_filter_activation(active),
+            Param::Distortion(settings) => self.set_distortion(settings),
+            Param::DistortionActive(active) => self.set_distortion_activation(active),
+            Param::Compressor(settings) => self.set_compressor(settings),
+            Param::CompressorActive(active) => self.set_compressor_activation(active),
+            Param::ReverbType(reverb_type) => self.set_current_reverb_type(reverb_type),
+            Param::ReverbActive(active) => self.set_reverb_activation(active),
+            Param::LeslieSpeed(speed) => self.set_leslie_speed(speed),
+            Param::LeslieActive(active) => self.set_leslie_activation(active),
+            Param::Delay(settings) => self.set_delay(settings),
+            Param::DelayActive(active) => self.set_delay_activation(active),
+            Param::Limiter(settings) => self.set_limiter(settings),
+            Param::LimiterActive(active) => self.set_limiter_activation(active),
+            Param::ModEffect(kind, settings) => self.set_mod_effect(kind, settings),
+            Param::ModEffectActive(kind, active) => self.set_mod_effect_activation(kind, active),
+        }
+    }
+}
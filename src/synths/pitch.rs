@@ -0,0 +1,109 @@
+use crate::consts::constants::{self, MAX_PITCH_BEND_RANGE};
+use crate::synths::modulation::MOD_LFOS;
+use serde::{Deserialize, Serialize};
+
+/// When a new note slides from the pitch of the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GlideMode {
+    Off,
+    Always,
+    Legato, // seulement si la note précédente est encore tenue
+}
+
+impl GlideMode {
+    pub const ALL: [GlideMode; 3] = [GlideMode::Off, GlideMode::Always, GlideMode::Legato];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GlideMode::Off => "Off",
+            GlideMode::Always => "Always",
+            GlideMode::Legato => "Legato",
+        }
+    }
+}
+
+/// Pitch bend range, vibrato and glide of a patch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PitchSettings {
+    pub bend_range: f64,    // demi-tons, vers le haut comme vers le bas
+    pub vibrato: f64,       // demi-tons au sommet du LFO
+    pub vibrato_lfo: usize, // LFO de la matrice de modulation (0 = LFO 1)
+    pub glide: f64,         // secondes pour rejoindre la nouvelle note
+    pub glide_mode: GlideMode,
+}
+
+impl Default for PitchSettings {
+    fn default() -> Self {
+        Self {
+            bend_range: constants::PITCH_BEND_RANGE,
+            vibrato: constants::VIBRATO_DEPTH,
+            vibrato_lfo: 0,
+            glide: constants::GLIDE_TIME,
+            glide_mode: GlideMode::Off,
+        }
+    }
+}
+
+impl PitchSettings {
+    /// Same settings brought back into their valid ranges
+    pub fn clamped(&self) -> Self {
+        Self {
+            bend_range: self.bend_range.clamp(0.0, MAX_PITCH_BEND_RANGE),
+            vibrato: self.vibrato.max(0.0),
+            vibrato_lfo: self.vibrato_lfo.min(MOD_LFOS - 1),
+            glide: self.glide.max(0.0),
+            glide_mode: self.glide_mode,
+        }
+    }
+
+    /// Frequency ratio of a pitch bend position (-1..1)
+    pub fn bend_ratio(&self, bend: f64) -> f64 {
+        2.0_f64.powf(bend.clamp(-1.0, 1.0) * self.bend_range / 12.0)
+    }
+
+    /// True when a note played after another slides from its pitch
+    pub fn glides(&self, legato: bool) -> bool {
+        match self.glide_mode {
+            GlideMode::Off => false,
+            GlideMode::Always => self.glide > 0.0,
+            GlideMode::Legato => legato && self.glide > 0.0,
+        }
+    }
+}
+
+/// Pitch offset of a voice sliding linearly back to 0 in semitones
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Glide {
+    offset: f64, // demi-tons restant à parcourir
+    step: f64,   // demi-tons par échantillon
+}
+
+impl Glide {
+    /// Starts `semitones` away from the note, to reach it in `time` seconds
+    pub fn start(&mut self, semitones: f64, time: f64, sample_rate: f64) {
+        if time > 0.0 && semitones.is_finite() {
+            self.offset = semitones;
+            self.step = semitones.abs() / (time * sample_rate);
+        } else {
+            self.offset = 0.0;
+        }
+    }
+
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.offset != 0.0
+    }
+
+    pub fn advance(&mut self, samples: usize) {
+        let step = self.step * samples as f64;
+        if self.offset.abs() <= step {
+            self.offset = 0.0;
+        } else {
+            self.offset -= step * self.offset.signum();
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// Note length a synced time is based on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteLength {
+    FourBars,
+    TwoBars,
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+}
+
+impl NoteLength {
+    pub const ALL: [NoteLength; 8] = [
+        NoteLength::FourBars,
+        NoteLength::TwoBars,
+        NoteLength::Whole,
+        NoteLength::Half,
+        NoteLength::Quarter,
+        NoteLength::Eighth,
+        NoteLength::Sixteenth,
+        NoteLength::ThirtySecond,
+    ];
+
+    /// Length in quarter notes (beats), for a 4/4 bar
+    pub fn beats(&self) -> f64 {
+        match self {
+            NoteLength::FourBars => 16.0,
+            NoteLength::TwoBars => 8.0,
+            NoteLength::Whole => 4.0,
+            NoteLength::Half => 2.0,
+            NoteLength::Quarter => 1.0,
+            NoteLength::Eighth => 0.5,
+            NoteLength::Sixteenth => 0.25,
+            NoteLength::ThirtySecond => 0.125,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteLength::FourBars => "4 bars",
+            NoteLength::TwoBars => "2 bars",
+            NoteLength::Whole => "1/1",
+            NoteLength::Half => "1/2",
+            NoteLength::Quarter => "1/4",
+            NoteLength::Eighth => "1/8",
+            NoteLength::Sixteenth => "1/16",
+            NoteLength::ThirtySecond => "1/32",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteFeel {
+    Straight,
+    Dotted,  // une fois et demie la durée
+    Triplet, // trois dans la durée de deux
+}
+
+impl NoteFeel {
+    pub const ALL: [NoteFeel; 3] = [NoteFeel::Straight, NoteFeel::Dotted, NoteFeel::Triplet];
+
+    fn factor(&self) -> f64 {
+        match self {
+            NoteFeel::Straight => 1.0,
+            NoteFeel::Dotted => 1.5,
+            NoteFeel::Triplet => 2.0 / 3.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteFeel::Straight => "Straight",
+            NoteFeel::Dotted => "Dotted",
+            NoteFeel::Triplet => "Triplet",
+        }
+    }
+}
+
+/// A duration expressed in notes of the global tempo, e.g. a dotted eighth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TempoDivision {
+    pub length: NoteLength,
+    pub feel: NoteFeel,
+}
+
+impl Default for TempoDivision {
+    fn default() -> Self {
+        Self {
+            length: NoteLength::Quarter,
+            feel: NoteFeel::Straight,
+        }
+    }
+}
+
+impl TempoDivision {
+    pub fn beats(&self) -> f64 {
+        self.length.beats() * self.feel.factor()
+    }
+
+    /// Duration in seconds at `bpm` quarter notes per minute
+    pub fn seconds(&self, bpm: f64) -> f64 {
+        self.beats() * 60.0 / bpm.max(1.0)
+    }
+
+    /// Rate in Hz of something repeating once per division
+    pub fn frequency(&self, bpm: f64) -> f64 {
+        1.0 / self.seconds(bpm)
+    }
+}
@@ -1,21 +1,80 @@
+use crate::synths::modulation::ModAmounts;
+
+/// Left and right samples
+pub type StereoFrame = [f64; 2];
+
 pub trait Synthesizer: Send + Sync {
     fn generate_sample(&self, phase: f64, frequency: f64) -> f64;
     fn name(&self) -> &'static str;
 }
 
-pub trait Oscillator: Send + Sync + Clone + Copy {
+pub trait Oscillator: Send + Sync + Clone {
     fn sample(&self, phase: f64) -> f64;
+
+    /// Renders the next sample of a voice, the phase advancing by `increment` radians per sample.
+    /// Called once per sample on the voice's own copy, so oscillators can band-limit
+    /// themselves or keep a state (percussion, key click).
+    fn next_sample(&mut self, phase: f64, _increment: f64) -> f64 {
+        self.sample(phase)
+    }
+
+    /// Called when the voice starts, for oscillators drawing a state of their own per voice
+    fn note_on(&mut self) {}
+
+    /// For oscillators whose timing depends on the sample rate (envelopes, percussion, scanning)
+    fn set_sample_rate(&mut self, _sample_rate: f64) {}
+
+    /// Called when the key of the voice is released, for oscillators with their own envelopes
+    fn note_off(&mut self) {}
+
+    /// Receives the amounts of the modulation matrix, oscillators pick the destinations they own
+    fn modulate(&mut self, _amounts: &ModAmounts) {}
+
     fn name(&self) -> &'static str;
 }
 
 pub trait Module: Send + Sync {
     fn process(&mut self, input: f64, time: f64) -> f64;
+
+    /// Processes a stereo frame. The default adapts mono modules: both channels are
+    /// mixed, processed once and sent to both outputs, so their state is never doubled.
+    /// Modules that can keep or create stereo width override it.
+    fn process_frame(&mut self, frame: StereoFrame, time: f64) -> StereoFrame {
+        let sample = self.process((frame[0] + frame[1]) * 0.5, time);
+        [sample, sample]
+    }
+
+    /// Processes a block of frames in place, with one virtual call for the whole block.
+    /// The default runs `process_frame` on each frame; modules override it to compute
+    /// what does not change within a block only once.
+    fn process_block(&mut self, block: &mut [StereoFrame]) {
+        for frame in block.iter_mut() {
+            *frame = self.process_frame(*frame, 0.0);
+        }
+    }
+
+    /// Receives the amounts of the modulation matrix at control rate.
+    /// They are offsets from the parameters set by the user, which stay untouched;
+    /// modules pick the destinations they own and ignore the others.
+    fn modulate(&mut self, _amounts: &ModAmounts) {}
+
+    /// Global tempo in quarter notes per minute, for the modules with synced times
+    fn set_tempo(&mut self, _bpm: f64) {}
+
+    /// Key signal of the next block, for the modules with a sidechain input
+    fn sidechain(&mut self, _key: &[StereoFrame]) {}
+
+    /// Adapts the module to another sample rate, its settings kept.
+    /// Running states may start over and delay lines are reallocated,
+    /// so it is only called on the control side.
+    fn set_sample_rate(&mut self, sample_rate: f64);
+
     fn name(&self) -> &'static str;
     fn clone_box(&self) -> Box<dyn Module>;
-    
+
     /// Pour le downcasting mutable
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
-    
+
     /// Pour le downcasting en lecture seule
     fn as_any(&self) -> &dyn std::any::Any;
 }
@@ -0,0 +1,135 @@
+use crate::consts::constants::{self, MAX_UNISON};
+use crate::synths::traits::{Oscillator, StereoFrame};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Number of stacked oscillators per note and how they are spread
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnisonSettings {
+    pub voices: usize, // 1 = pas d'unisson
+    pub detune: f64,   // écart en cents entre les deux oscillateurs extrêmes et le centre
+    pub spread: f64,   // 0 = mono, 1 = extrêmes panoramiqués à fond
+}
+
+impl Default for UnisonSettings {
+    fn default() -> Self {
+        Self {
+            voices: constants::CURRENT_UNISON_VOICES,
+            detune: constants::CURRENT_UNISON_DETUNE,
+            spread: constants::CURRENT_UNISON_SPREAD,
+        }
+    }
+}
+
+impl UnisonSettings {
+    pub fn is_active(&self) -> bool {
+        self.voices > 1
+    }
+
+    /// Position of an oscillator in the stack, from -1 to 1
+    fn offset(&self, index: usize) -> f64 {
+        if self.voices < 2 {
+            0.0
+        } else {
+            2.0 * index as f64 / (self.voices - 1) as f64 - 1.0
+        }
+    }
+
+    /// Frequency ratio of an oscillator of the stack
+    fn ratio(&self, index: usize) -> f64 {
+        2.0_f64.powf(self.detune * self.offset(index) / 1200.0)
+    }
+
+    /// Equal-power (left, right) gains, 1 for both channels in the center
+    fn pan(&self, index: usize) -> StereoFrame {
+        let pan = self.spread.clamp(0.0, 1.0) * self.offset(index);
+        let angle = (pan + 1.0) * PI / 4.0;
+        [angle.cos() * 2.0_f64.sqrt(), angle.sin() * 2.0_f64.sqrt()]
+    }
+}
+
+/// One oscillator of the stack, with its own phase, pitch offset and pan
+#[derive(Clone)]
+pub struct UnisonOscillator<O: Oscillator> {
+    pub oscillator: O,
+    phase: f64, // 0..1
+    ratio: f64,
+    gains: StereoFrame,
+}
+
+/// Copies of an oscillator stacked on one note.
+/// Built on the control side when the note starts, then only updated in place.
+#[derive(Clone)]
+pub struct UnisonStack<O: Oscillator> {
+    pub oscillators: Vec<UnisonOscillator<O>>,
+}
+
+impl<O: Oscillator> UnisonStack<O> {
+    pub fn new() -> Self {
+        Self {
+            oscillators: Vec::new(),
+        }
+    }
+
+    /// Copies the oscillator once per unison voice, each starting at a random phase
+    pub fn build(oscillator: &O, settings: &UnisonSettings) -> Self {
+        let count = settings.voices.clamp(1, MAX_UNISON);
+        let mut stack = Self {
+            oscillators: (0..count)
+                .map(|_| UnisonOscillator {
+                    oscillator: oscillator.clone(),
+                    phase: rand::random::<f64>(),
+                    ratio: 1.0,
+                    gains: [1.0, 1.0],
+                })
+                .collect(),
+        };
+        stack.update(settings);
+        stack
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.oscillators.is_empty()
+    }
+
+    /// Applies new detune and spread amounts. The number of oscillators of a playing note never changes.
+    pub fn update(&mut self, settings: &UnisonSettings) {
+        let settings = UnisonSettings {
+            voices: self.oscillators.len(),
+            ..*settings
+        };
+        for (index, unison) in self.oscillators.iter_mut().enumerate() {
+            unison.ratio = settings.ratio(index);
+            unison.gains = settings.pan(index);
+        }
+    }
+
+    /// Sums the stack into a stereo frame, `increment` being the phase advance of the note in radians
+    pub fn next_frame(&mut self, increment: f64) -> StereoFrame {
+        let mut frame = [0.0, 0.0];
+        for unison in self.oscillators.iter_mut() {
+            let increment = increment * unison.ratio;
+            let sample = unison
+                .oscillator
+                .next_sample(unison.phase * 2.0 * PI, increment);
+            frame[0] += sample * unison.gains[0];
+            frame[1] += sample * unison.gains[1];
+
+            unison.phase += increment / (2.0 * PI);
+            if unison.phase >= 1.0 {
+                unison.phase -= 1.0;
+            }
+        }
+
+        // Des phases aléatoires s'additionnent en puissance
+        let scale = 1.0 / (self.oscillators.len() as f64).sqrt();
+        [frame[0] * scale, frame[1] * scale]
+    }
+}
+
+impl<O: Oscillator> Default for UnisonStack<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
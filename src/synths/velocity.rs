@@ -0,0 +1,98 @@
+use crate::consts::constants;
+use crate::synths::manager::SynthType;
+use crate::synths::modules::adsr::ADSR;
+use serde::{Deserialize, Serialize};
+
+const MIN_ENVELOPE_TIME: f64 = 0.001; // les notes les plus fortes gardent 1 ms d'attaque et de decay
+
+/// How the velocity of a note (0..1) shapes its voice.
+/// Applied once on the control side when the note is built.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocitySettings {
+    pub amplitude: f64,         // 0 = vélocité ignorée, 1 = toute la plage
+    pub cutoff: f64,            // octaves retirées à la coupure pour une vélocité nulle
+    pub envelope: f64,          // 0..1, allonge l'attaque/decay des notes douces
+    pub keyboard_velocity: f64, // vélocité fixe du clavier d'ordinateur
+}
+
+impl Default for VelocitySettings {
+    fn default() -> Self {
+        Self {
+            amplitude: constants::VELOCITY_AMPLITUDE,
+            cutoff: constants::VELOCITY_CUTOFF,
+            envelope: constants::VELOCITY_ENVELOPE,
+            keyboard_velocity: constants::KEYBOARD_VELOCITY,
+        }
+    }
+}
+
+impl VelocitySettings {
+    /// Amplitude multiplier of a note. Squared velocity sounds more even than a linear one.
+    pub fn amplitude_gain(&self, velocity: f64) -> f64 {
+        let velocity = velocity.clamp(0.0, 1.0);
+        1.0 - self.amplitude.clamp(0.0, 1.0) * (1.0 - velocity * velocity)
+    }
+
+    /// Adjusts the filter cutoff of a voice and the times of its envelope
+    pub fn apply(&self, velocity: f64, voice: &mut SynthType, envelope: &mut ADSR) {
+        let softness = 1.0 - velocity.clamp(0.0, 1.0);
+
+        if self.cutoff > 0.0 {
+            let cutoff = voice.get_current_cutoff() * 2.0_f64.powf(-self.cutoff * softness);
+            voice.set_current_cutoff(cutoff.max(20.0));
+        }
+
+        if self.envelope > 0.0 {
+            // Notes fortes : attaque plus rapide, notes douces : plus lente
+            let scale = 1.0 + self.envelope.clamp(0.0, 1.0) * (2.0 * softness - 1.0);
+            envelope.set_attack((envelope.get_attack() * scale).max(MIN_ENVELOPE_TIME));
+            envelope.set_decay((envelope.get_decay() * scale).max(MIN_ENVELOPE_TIME));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::engine;
+    use crate::synths::param::Param;
+
+    /// Output peak of a loud note held for 0.1 s, measured before the engine clips to full scale.
+    /// The limiter is off so the raw envelope shows.
+    fn loud_note_peak(envelope_sensitivity: f64) -> f64 {
+        let (controller, mut engine) = engine::create_engine(SynthType::n_sine(), 44100.0);
+        controller.set_param(Param::LimiterActive(false));
+        controller.set_param(Param::VelocityEnvelope(envelope_sensitivity));
+        controller.note_on_with_velocity(440.0, 1.0);
+        engine.process_commands();
+
+        let mut output = vec![[0.0, 0.0]; 4410];
+        engine.render_block(&mut output);
+        engine.remove_finished_voices();
+        controller.get_output_peak()
+    }
+
+    #[test]
+    fn full_velocity_with_full_envelope_sensitivity_stays_bounded() {
+        let reference = loud_note_peak(0.0);
+        let peak = loud_note_peak(1.0);
+        assert!(
+            peak <= reference * 1.1,
+            "peak {peak} against {reference} without sensitivity"
+        );
+    }
+
+    #[test]
+    fn scaled_times_keep_a_minimum() {
+        let settings = VelocitySettings {
+            envelope: 1.0,
+            ..VelocitySettings::default()
+        };
+        let mut voice = SynthType::n_sine();
+        let mut envelope = ADSR::new(44100.0, 0.1, 0.2, 0.5, 0.3);
+        settings.apply(1.0, &mut voice, &mut envelope);
+        assert_eq!(envelope.get_attack(), MIN_ENVELOPE_TIME);
+        assert_eq!(envelope.get_decay(), MIN_ENVELOPE_TIME);
+    }
+}
@@ -0,0 +1,174 @@
+//! Compares the aliasing of the naive and band-limited (PolyBLEP, wavetable mip-levels) oscillators,
+//! and of the distortion with and without oversampling.
+//!
+//! One second of each waveform is analysed with a DFT whose bins fall exactly on 10 Hz.
+//! The energy outside the true harmonics of the note is aliasing folded back below Nyquist.
+
+use std::f64::consts::PI;
+use synthesizer_emulation::synths::modules::Distortion;
+use synthesizer_emulation::synths::modules::distortion::{
+    DistortionCurve, DistortionSettings, Oversampling,
+};
+use synthesizer_emulation::synths::oscillators::wavetable::{
+    Wavetable, WavetableSettings, WavetableSource,
+};
+use synthesizer_emulation::synths::oscillators::{
+    SawtoothOscillator, SquareOscillator, WavetableOscillator,
+};
+use synthesizer_emulation::synths::traits::{Module, Oscillator};
+
+const SAMPLE_RATE: f64 = 48000.0;
+const LENGTH: usize = 4800; // 10 Hz par bin
+const BIN_WIDTH: f64 = SAMPLE_RATE / LENGTH as f64;
+
+/// Power spectrum (bins 1..N/2) of one period-aligned block of the oscillator
+fn power_spectrum<O: Oscillator>(oscillator: &O, frequency: f64) -> Vec<f64> {
+    let mut oscillator = oscillator.clone();
+    let increment = 2.0 * PI * frequency / SAMPLE_RATE;
+    let signal: Vec<f64> = (0..LENGTH)
+        .map(|n| {
+            let phase = (n as f64 * increment).rem_euclid(2.0 * PI);
+            oscillator.next_sample(phase, increment)
+        })
+        .collect();
+    spectrum(&signal)
+}
+
+/// Power spectrum (bins 1..N/2) of a block of LENGTH samples
+fn spectrum(signal: &[f64]) -> Vec<f64> {
+    let cos: Vec<f64> = (0..LENGTH)
+        .map(|n| (2.0 * PI * n as f64 / LENGTH as f64).cos())
+        .collect();
+    let sin: Vec<f64> = (0..LENGTH)
+        .map(|n| (2.0 * PI * n as f64 / LENGTH as f64).sin())
+        .collect();
+
+    (1..LENGTH / 2)
+        .map(|bin| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (n, sample) in signal.iter().enumerate() {
+                let index = (bin * n) % LENGTH;
+                re += sample * cos[index];
+                im -= sample * sin[index];
+            }
+            re * re + im * im
+        })
+        .collect()
+}
+
+/// Aliased energy relative to the harmonic energy, in dB
+fn aliasing_db<O: Oscillator>(oscillator: &O, frequency: f64) -> f64 {
+    spectrum_aliasing_db(&power_spectrum(oscillator, frequency), frequency)
+}
+
+fn spectrum_aliasing_db(spectrum: &[f64], frequency: f64) -> f64 {
+    let harmonic_step = (frequency / BIN_WIDTH).round() as usize;
+
+    let (mut harmonic, mut aliased) = (0.0, 0.0);
+    for (index, power) in spectrum.iter().enumerate() {
+        let bin = index + 1;
+        if bin % harmonic_step == 0 {
+            harmonic += power;
+        } else {
+            aliased += power;
+        }
+    }
+    10.0 * (aliased / harmonic).log10()
+}
+
+fn assert_less_aliasing<N: Oscillator, B: Oscillator>(naive: &N, band_limited: &B, frequency: f64) {
+    let naive_db = aliasing_db(naive, frequency);
+    let band_limited_db = aliasing_db(band_limited, frequency);
+    assert!(
+        band_limited_db < naive_db - 10.0,
+        "{} at {frequency} Hz: naive {naive_db:.1} dB, band-limited {band_limited_db:.1} dB",
+        naive.name(),
+    );
+}
+
+// Fréquences multiples de 10 Hz pour tomber sur les bins, choisies pour que
+// les repliements ne coïncident pas avec les harmoniques
+const HIGH_NOTES: [f64; 3] = [1270.0, 2630.0, 4190.0];
+
+#[test]
+fn band_limited_sawtooth_aliases_less_than_naive() {
+    for frequency in HIGH_NOTES {
+        assert_less_aliasing(
+            &SawtoothOscillator::new(false),
+            &SawtoothOscillator::new(true),
+            frequency,
+        );
+    }
+}
+
+#[test]
+fn band_limited_square_aliases_less_than_naive() {
+    for frequency in HIGH_NOTES {
+        assert_less_aliasing(
+            &SquareOscillator::new(false),
+            &SquareOscillator::new(true),
+            frequency,
+        );
+    }
+}
+
+#[test]
+fn wavetable_mip_levels_alias_less_than_naive_square() {
+    // Dernière frame de la table de base : un carré
+    let table = Wavetable::load(&WavetableSource::Basic).unwrap();
+    let settings = WavetableSettings {
+        position: 1.0,
+        ..WavetableSettings::default()
+    };
+    let wavetable = WavetableOscillator::new(table, settings, SAMPLE_RATE);
+    for frequency in HIGH_NOTES {
+        assert_less_aliasing(&SquareOscillator::new(false), &wavetable, frequency);
+    }
+}
+
+#[test]
+fn band_limiting_keeps_the_harmonics() {
+    // Le fondamental garde (presque) la même énergie
+    let frequency = 1270.0;
+    let bin = (frequency / BIN_WIDTH).round() as usize - 1;
+    let naive = power_spectrum(&SawtoothOscillator::new(false), frequency)[bin];
+    let band_limited = power_spectrum(&SawtoothOscillator::new(true), frequency)[bin];
+    let difference_db = 10.0 * (band_limited / naive).log10();
+    assert!(
+        difference_db.abs() < 1.0,
+        "fundamental changed by {difference_db:.2} dB"
+    );
+}
+
+/// Sine driven hard into the clipper, analysed once the filters have settled
+fn distortion_aliasing_db(oversampling: Oversampling, frequency: f64) -> f64 {
+    let settings = DistortionSettings {
+        curve: DistortionCurve::HardClip,
+        drive: 24.0,
+        low_cut: 20.0,
+        high_cut: 20000.0,
+        oversampling,
+        mix: 1.0,
+        output: 0.0,
+        ..DistortionSettings::default()
+    };
+    let mut distortion = Distortion::new(SAMPLE_RATE, settings);
+    let increment = 2.0 * PI * frequency / SAMPLE_RATE;
+    let signal: Vec<f64> = (0..2 * LENGTH)
+        .map(|n| distortion.process((n as f64 * increment).sin() * 0.5, 0.0))
+        .skip(LENGTH)
+        .collect();
+    spectrum_aliasing_db(&spectrum(&signal), frequency)
+}
+
+#[test]
+fn oversampled_distortion_aliases_less() {
+    for frequency in HIGH_NOTES {
+        let [off_db, x2_db, x4_db] =
+            Oversampling::ALL.map(|o| distortion_aliasing_db(o, frequency));
+        assert!(
+            x2_db < off_db - 6.0 && x4_db < x2_db,
+            "{frequency} Hz: off {off_db:.1} dB, 2x {x2_db:.1} dB, 4x {x4_db:.1} dB",
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Checks the spectrum of every noise color, its level and that a seed always gives the same noise.
+//!
+//! The slope is read between two octave bands two octaves apart, from the power spectrum
+//! averaged over many Hann-windowed blocks.
+
+use std::f64::consts::PI;
+use synthesizer_emulation::synths::modules::noise::{NoiseColor, NoiseGenerator};
+
+const SAMPLE_RATE: f64 = 48000.0;
+const LENGTH: usize = 1024;
+const BLOCKS: usize = 200;
+const LOW_BAND: (f64, f64) = (500.0, 1000.0);
+const HIGH_BAND: (f64, f64) = (2000.0, 4000.0);
+
+/// Mean power of the DFT bins between `low` and `high` Hz, averaged over all the blocks
+fn band_power(blocks: &[Vec<f64>], (low, high): (f64, f64)) -> f64 {
+    let bin_width = SAMPLE_RATE / LENGTH as f64;
+    let bins = (low / bin_width).ceil() as usize..=(high / bin_width).floor() as usize;
+    let count = bins.clone().count() * blocks.len();
+    let mut total = 0.0;
+    for block in blocks {
+        for bin in bins.clone() {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (n, sample) in block.iter().enumerate() {
+                let angle = 2.0 * PI * (bin * n % LENGTH) as f64 / LENGTH as f64;
+                re += sample * angle.cos();
+                im -= sample * angle.sin();
+            }
+            total += re * re + im * im;
+        }
+    }
+    total / count as f64
+}
+
+/// Consecutive Hann-windowed blocks of the noise, after a second to let the filters settle
+fn windowed_blocks(color: NoiseColor) -> Vec<Vec<f64>> {
+    let mut generator = NoiseGenerator::new(color, 42);
+    for _ in 0..SAMPLE_RATE as usize {
+        generator.next_sample();
+    }
+    (0..BLOCKS)
+        .map(|_| {
+            (0..LENGTH)
+                .map(|n| {
+                    let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / LENGTH as f64).cos();
+                    generator.next_sample() * window
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn each_color_has_its_spectral_slope() {
+    for (color, db_per_octave) in [
+        (NoiseColor::White, 0.0),
+        (NoiseColor::Pink, -3.0),
+        (NoiseColor::Brown, -6.0),
+        (NoiseColor::Blue, 3.0),
+        (NoiseColor::Violet, 6.0),
+    ] {
+        let blocks = windowed_blocks(color);
+        let low = band_power(&blocks, LOW_BAND);
+        let high = band_power(&blocks, HIGH_BAND);
+        let slope = 10.0 * (high / low).log10() / 2.0;
+        assert!(
+            (slope - db_per_octave).abs() < 0.5,
+            "{}: {slope:.2} dB per octave, expected {db_per_octave}",
+            color.name()
+        );
+    }
+}
+
+#[test]
+fn every_color_peaks_at_about_full_scale() {
+    for color in NoiseColor::ALL {
+        let mut generator = NoiseGenerator::new(color, 7);
+        let peak = (0..10 * SAMPLE_RATE as usize)
+            .map(|_| generator.next_sample().abs())
+            .fold(0.0, f64::max);
+        assert!(
+            (0.8..=1.2).contains(&peak),
+            "{} peaks at {peak:.3}",
+            color.name()
+        );
+    }
+}
+
+#[test]
+fn the_same_seed_gives_the_same_noise() {
+    let render = |color, seed| {
+        let mut generator = NoiseGenerator::new(color, seed);
+        (0..4096)
+            .map(|_| generator.next_sample())
+            .collect::<Vec<_>>()
+    };
+    for color in NoiseColor::ALL {
+        let noise = render(color, 3);
+        assert_eq!(noise, render(color, 3), "{}", color.name());
+        assert_ne!(noise, render(color, 4), "{}", color.name());
+    }
+}
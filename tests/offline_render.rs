@@ -0,0 +1,82 @@
+//! Renders notes without an audio device, through the same engine as the real-time callback,
+//! and checks the WAV files written and that the patch sounds the same at every sample rate.
+
+use synthesizer_emulation::audio::offline_render::{self, NoteEvent, RenderSettings, WavFormat};
+use synthesizer_emulation::synths::manager::SynthType;
+use synthesizer_emulation::synths::param::Param;
+use synthesizer_emulation::synths::traits::StereoFrame;
+
+fn rms(frames: &[StereoFrame]) -> f64 {
+    let sum: f64 = frames.iter().flatten().map(|sample| sample * sample).sum();
+    (sum / (frames.len() * 2) as f64).sqrt()
+}
+
+/// RMS in the middle of a 4 kHz note, through a 2 kHz low-pass or dry.
+/// No random nor time-based effect, so only the filter can change with the sample rate.
+fn note_level(sample_rate: f64, filtered: bool) -> f64 {
+    let mut synth = SynthType::n_sine();
+    for param in [
+        Param::NoiseActive(false),
+        Param::LfoActive(false),
+        Param::CompressorActive(false),
+        Param::ReverbActive(false),
+        Param::LimiterActive(false),
+        Param::Cutoff(2000.0),
+        Param::FilterActive(filtered),
+    ] {
+        synth.apply_param(param);
+    }
+    let settings = RenderSettings {
+        sample_rate,
+        tail: 0.0,
+        ..RenderSettings::default()
+    };
+    let frames = offline_render::render(synth, &[NoteEvent::new(0.0, 0.5, 4000.0)], &settings);
+    // Loin de l'attaque et du release
+    rms(&frames[frames.len() / 4..frames.len() * 3 / 4])
+}
+
+#[test]
+fn render_writes_a_wav_of_the_expected_length() {
+    let settings = RenderSettings {
+        sample_rate: 48000.0,
+        format: WavFormat::Int24,
+        tail: 0.5,
+    };
+    let events = [
+        NoteEvent::new(0.0, 0.25, 440.0),
+        NoteEvent::new(0.25, 0.25, 660.0).with_velocity(0.5),
+    ];
+    let frames = offline_render::render(SynthType::n_sine(), &events, &settings);
+    assert_eq!(frames.len(), 48000, "0.5 s of notes and 0.5 s of tail");
+    assert!(rms(&frames) > 0.01, "render is silent");
+
+    let path = std::env::temp_dir().join(format!("offline_render_{}.wav", std::process::id()));
+    offline_render::write_wav(&path, &frames, settings.sample_rate, settings.format).unwrap();
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    let spec = reader.spec();
+    let samples: Vec<i32> = reader.samples::<i32>().map(Result::unwrap).collect();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(spec.channels, 2);
+    assert_eq!(spec.sample_rate, 48000);
+    assert_eq!(spec.bits_per_sample, 24);
+    assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+    assert_eq!(samples.len(), frames.len() * 2);
+    assert!(samples.iter().any(|&sample| sample.abs() > 1 << 16));
+}
+
+#[test]
+fn filter_sounds_the_same_at_every_sample_rate() {
+    let dry = note_level(44100.0, false);
+    let at_44100 = note_level(44100.0, true);
+    let at_96000 = note_level(96000.0, true);
+    assert!(
+        at_44100 < dry * 0.5,
+        "the low-pass does not cut: {at_44100} against {dry}"
+    );
+    assert!(
+        (at_96000 / at_44100 - 1.0).abs() < 0.1,
+        "RMS {at_44100} at 44.1 kHz against {at_96000} at 96 kHz"
+    );
+}